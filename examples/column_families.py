@@ -1,16 +1,13 @@
-from rocksdict import Rdict, Options, SliceTransform, PlainTableFactoryOptions
+from rocksdict import Rdict, Options, SliceTransform
 import random
 
 path = str("tmp")
 cf1_name = str("cf1")
 cf2_name = str("cf2")
 
-# set cf2 as a plain table
+# set cf2 with a prefix extractor
 cf2_opt = Options()
 cf2_opt.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
-p_opt = PlainTableFactoryOptions()
-p_opt.user_key_length = 200
-cf2_opt.set_plain_table_factory(p_opt)
 
 # create column families if missing
 opt = Options() # create_if_missing=True by default
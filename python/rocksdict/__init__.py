@@ -1,20 +1,29 @@
+from collections.abc import MutableMapping
+
 from .rocksdict import *
 
 __doc__ = rocksdict.__doc__
 
+# Rdict implements the whole MutableMapping interface natively,
+# register it so that `isinstance(db, MutableMapping)` holds.
+MutableMapping.register(Rdict)
+
 __all__ = ["Rdict",
            "WriteBatch",
+           "WriteBatchWithIndex",
            "SstFileWriter",
            "AccessType",
            "WriteOptions",
            "Snapshot",
            "RdictIter",
+           "WalIterator",
            "Options",
            "ReadOptions",
            "ColumnFamily",
            "IngestExternalFileOptions",
            "DBPath",
            "MemtableFactory",
+           "MergeOperator",
            "BlockBasedOptions",
            "PlainTableFactoryOptions",
            "CuckooTableOptions",
@@ -25,9 +34,14 @@ __all__ = ["Rdict",
            "BlockBasedIndexType",
            "Cache",
            "ChecksumType",
+           "FileChecksumGenFactory",
            "DBCompactionStyle",
            "DBCompressionType",
            "DBRecoveryMode",
+           "StatsLevel",
+           "LogLevel",
+           "Statistics",
+           "PerfContext",
            "Env",
            "FifoCompactOptions",
            "CompactOptions",
@@ -35,7 +49,14 @@ __all__ = ["Rdict",
            "KeyEncodingType",
            "DbClosedError",
            "WriteBufferManager",
-           "Checkpoint"]
-
-Rdict.__enter__ = lambda self: self
-Rdict.__exit__ = lambda self, exc_type, exc_val, exc_tb: self.close()
+           "RateLimiter",
+           "Checkpoint",
+           "PinnedSlice",
+           "encode_int_key",
+           "decode_int_key",
+           "encode_float_key",
+           "decode_float_key",
+           "encode_key",
+           "decode_key",
+           "encode_value",
+           "decode_value"]
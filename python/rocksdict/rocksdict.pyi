@@ -1,12 +1,14 @@
-from typing import Any, Union, List, Iterator, Tuple, Dict, overload, Callable
+from typing import Any, Union, List, Iterable, Iterator, Mapping, Tuple, Dict, overload, Callable
 
 __all__ = ["Rdict",
            "RdictIter",
+           "WalIterator",
            "Options",
            "WriteOptions",
            "ReadOptions",
            "DBPath",
            "MemtableFactory",
+           "MergeOperator",
            "BlockBasedOptions",
            "PlainTableFactoryOptions",
            "CuckooTableOptions",
@@ -17,14 +19,20 @@ __all__ = ["Rdict",
            "BlockBasedIndexType",
            "Cache",
            "ChecksumType",
+           "FileChecksumGenFactory",
            "DBCompactionStyle",
            "DBCompressionType",
            "DBRecoveryMode",
+           "StatsLevel",
+           "LogLevel",
+           "Statistics",
+           "PerfContext",
            "Env",
            "FifoCompactOptions",
            "SstFileWriter",
            "IngestExternalFileOptions",
            "WriteBatch",
+           "WriteBatchWithIndex",
            "ColumnFamily",
            "AccessType",
            "Snapshot",
@@ -33,7 +41,17 @@ __all__ = ["Rdict",
            "KeyEncodingType",
            "DbClosedError",
            "WriteBufferManager",
-           "Checkpoint"]
+           "RateLimiter",
+           "Checkpoint",
+           "PinnedSlice",
+           "encode_int_key",
+           "decode_int_key",
+           "encode_float_key",
+           "decode_float_key",
+           "encode_key",
+           "decode_key",
+           "encode_value",
+           "decode_value"]
 
 class DataBlockIndexType:
     @staticmethod
@@ -49,6 +67,10 @@ class BlockBasedIndexType:
     @staticmethod
     def two_level_index_search() -> BlockBasedIndexType: ...
 
+class FileChecksumGenFactory:
+    @staticmethod
+    def crc32c() -> FileChecksumGenFactory: ...
+
 class ChecksumType:
     @staticmethod
     def no_checksum() -> ChecksumType: ...
@@ -68,6 +90,9 @@ class BlockBasedOptions:
     def set_block_restart_interval(self, interval: int) -> None: ...
     def set_block_size(self, size: int) -> None: ...
     def set_bloom_filter(self, bits_per_key: int, block_based: bool) -> None: ...
+    def set_ribbon_filter(self, bloom_equivalent_bits_per_key: float) -> None: ...
+    def set_hybrid_ribbon_filter(self, bloom_equivalent_bits_per_key: float, bloom_before_level: int) -> None: ...
+    def set_whole_key_filtering(self, v: bool) -> None: ...
     def set_cache_index_and_filter_blocks(self, v: bool) -> None: ...
     def set_data_block_hash_ratio(self, ratio: float) -> None: ...
     def set_data_block_index_type(self, index_type: DataBlockIndexType) -> None: ...
@@ -81,7 +106,7 @@ class BlockBasedOptions:
     def set_checksum_type(self, checksum_type: ChecksumType) -> None: ...
 
 class Cache:
-    def __init__(self, capacity: int) -> None: ...
+    def __init__(self, capacity: int, num_shard_bits: int = -1) -> None: ...
     @staticmethod
     def new_hyper_clock_cache(capacity: int, estimated_entry_charge: int) -> Cache: ...
     def get_pinned_usage(self) -> int: ...
@@ -89,7 +114,12 @@ class Cache:
     def set_capacity(self, capacity: int) -> None: ...
 
 class CuckooTableOptions:
-    def __init__(self) -> None: ...
+    def __init__(self,
+                 hash_table_ratio: float = 0.9,
+                 max_search_depth: int = 100,
+                 cuckoo_block_size: int = 5,
+                 identity_as_first_hash: bool = False,
+                 use_module_hash: bool = True) -> None: ...
     def set_cuckoo_block_size(self, size: int) -> None: ...
     def set_hash_ratio(self, ratio: float) -> None: ...
     def set_identity_as_first_hash(self, flag: bool) -> None: ...
@@ -108,6 +138,8 @@ class DBCompressionType:
     @staticmethod
     def bz2() -> DBCompressionType: ...
     @staticmethod
+    def from_name(name: str) -> DBCompressionType: ...
+    @staticmethod
     def lz4() -> DBCompressionType: ...
     @staticmethod
     def lz4hc() -> DBCompressionType: ...
@@ -133,8 +165,64 @@ class DBRecoveryMode:
     @staticmethod
     def tolerate_corrupted_tail_records() -> DBRecoveryMode: ...
 
+class StatsLevel:
+    @staticmethod
+    def disable_all() -> StatsLevel: ...
+    @staticmethod
+    def except_tickers() -> StatsLevel: ...
+    @staticmethod
+    def except_histogram_or_timers() -> StatsLevel: ...
+    @staticmethod
+    def except_timers() -> StatsLevel: ...
+    @staticmethod
+    def except_detailed_timers() -> StatsLevel: ...
+    @staticmethod
+    def except_time_for_mutex() -> StatsLevel: ...
+    @staticmethod
+    def all() -> StatsLevel: ...
+
+class LogLevel:
+    @staticmethod
+    def debug() -> LogLevel: ...
+    @staticmethod
+    def info() -> LogLevel: ...
+    @staticmethod
+    def warn() -> LogLevel: ...
+    @staticmethod
+    def error() -> LogLevel: ...
+    @staticmethod
+    def fatal() -> LogLevel: ...
+    @staticmethod
+    def header() -> LogLevel: ...
+
+class Statistics:
+    def ticker(self, name: str) -> int: ...
+    def tickers(self) -> Dict[str, int]: ...
+    def histogram(self, name: str) -> Dict[str, float]: ...
+    def reset(self) -> None: ...
+    def to_string(self) -> str: ...
+    @staticmethod
+    def ticker_names() -> List[str]: ...
+    @staticmethod
+    def histogram_names() -> List[str]: ...
+
+class PerfContext:
+    def __init__(self) -> None: ...
+    @staticmethod
+    def enable(timing: bool = False) -> None: ...
+    @staticmethod
+    def disable() -> None: ...
+    def reset(self) -> None: ...
+    def metric(self, name: str) -> int: ...
+    def metrics(self) -> Dict[str, int]: ...
+    def report(self, exclude_zero_counters: bool = True) -> str: ...
+    @staticmethod
+    def metric_names() -> List[str]: ...
+
 class Env:
     def __init__(self) -> None: ...
+    @staticmethod
+    def default() -> Env: ...
     def join_all_threads(self) -> None: ...
     def lower_high_priority_thread_pool_cpu_priority(self) -> None: ...
     def lower_high_priority_thread_pool_io_priority(self) -> None: ...
@@ -152,7 +240,11 @@ class FifoCompactOptions:
     def max_table_files_size(self) -> int: ...
     @max_table_files_size.setter
     def max_table_files_size(self, v: int) -> None: ...
-    def __init__(self) -> None: ...
+    @property
+    def allow_compaction(self) -> bool: ...
+    @allow_compaction.setter
+    def allow_compaction(self, v: bool) -> None: ...
+    def __init__(self, max_table_files_size: int = 10737418240, allow_compaction: bool = False) -> None: ...
 
 class FlushOptions:
     @property
@@ -163,9 +255,9 @@ class FlushOptions:
 
 class MemtableFactory:
     @staticmethod
-    def hash_link_list() -> MemtableFactory: ...
+    def hash_link_list(bucket_count: int) -> MemtableFactory: ...
     @staticmethod
-    def hash_skip_list() -> MemtableFactory: ...
+    def hash_skip_list(bucket_count: int, height: int, branching_factor: int) -> MemtableFactory: ...
     @staticmethod
     def vector() -> MemtableFactory: ...
 
@@ -173,10 +265,24 @@ class Options:
     def __init__(self, raw_mode: bool = False) -> None: ...
     @staticmethod
     def load_latest(path: str, env: Env = Env(), ignore_unknown_options: bool = False, cache: Cache = Cache(8 * 1024 * 1024)) -> Tuple[Options, Dict[str, Options]]: ...
+    @property
+    def raw_mode(self) -> bool: ...
+    def set_ordered_numeric_keys(self, ordered: bool) -> None: ...
+    @property
+    def ordered_numeric_keys(self) -> bool: ...
+    def set_max_key_bytes(self, max_key_bytes: Union[int, None]) -> None: ...
+    def set_max_value_bytes(self, max_value_bytes: Union[int, None]) -> None: ...
+    def set_u64_timestamp_comparator(self) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    @staticmethod
+    def from_dict(options: Dict[str, Any]) -> Options: ...
+    def add_event_listener(self, listener: Any) -> None: ...
     def create_if_missing(self, create_if_missing: bool) -> None: ...
     def create_missing_column_families(self, create_missing_cfs: bool) -> None: ...
+    def set_create_missing_column_families(self, create_missing_cfs: bool) -> None: ...
     def enable_statistics(self) -> None: ...
     def get_statistics(self) -> Union[str, None]: ...
+    def set_statistics_level(self, level: StatsLevel) -> None: ...
     def increase_parallelism(self, parallelism: int) -> None: ...
     def optimize_for_point_lookup(self, cache_size: int) -> None: ...
     def optimize_level_style_compaction(self, memtable_memory_budget: int) -> None: ...
@@ -190,6 +296,10 @@ class Options:
     def set_atomic_flush(self, atomic_flush: bool) -> None: ...
     def set_block_based_table_factory(self, factory: BlockBasedOptions) -> None: ...
     def set_bloom_locality(self, v: int) -> None: ...
+    def set_bottommost_compression_options(self, w_bits: int, level: int, strategy: int, max_dict_bytes: int,
+                                           enabled: bool = True) -> None: ...
+    def set_bottommost_compression_type(self, t: DBCompressionType) -> None: ...
+    def set_bottommost_zstd_max_train_bytes(self, value: int, enabled: bool = True) -> None: ...
     def set_bytes_per_sync(self, nbytes: int) -> None: ...
     def set_compaction_readahead_size(self, compaction_readahead_size: int) -> None: ...
     def set_compaction_style(self, style: DBCompactionStyle) -> None: ...
@@ -198,7 +308,7 @@ class Options:
     def set_compression_type(self, t: DBCompressionType) -> None: ...
     def set_cuckoo_table_factory(self, factory: CuckooTableOptions) -> None: ...
     def set_db_log_dir(self, path: str) -> None: ...
-    def set_db_paths(self, paths: list) -> None: ...
+    def set_db_paths(self, paths: List[Union[DBPath, Tuple[str, int]]]) -> None: ...
     def set_db_write_buffer_size(self, size: int) -> None: ...
     def set_delete_obsolete_files_period_micros(self, micros: int) -> None: ...
     def set_disable_auto_compactions(self, disable: bool) -> None: ...
@@ -209,6 +319,7 @@ class Options:
     def set_error_if_exists(self, enabled: bool) -> None: ...
     def set_fifo_compaction_options(self, fco: FifoCompactOptions) -> None: ...
     def set_hard_pending_compaction_bytes_limit(self, limit: int) -> None: ...
+    def set_info_log_level(self, level: LogLevel) -> None: ...
     def set_inplace_update_locks(self, num: int) -> None: ...
     def set_inplace_update_support(self, enabled: bool) -> None: ...
     def set_is_fd_close_on_exec(self, enabled: bool) -> None: ...
@@ -218,8 +329,10 @@ class Options:
     def set_level_zero_slowdown_writes_trigger(self,  n_int) -> None: ...
     def set_level_zero_stop_writes_trigger(self, n: int) -> None: ...
     def set_log_file_time_to_roll(self, secs: int) -> None: ...
+    def set_logger(self, callback: Callable[[str, str], None], level: LogLevel = LogLevel.info()) -> None: ...
     def set_manifest_preallocation_size(self, size: int) -> None: ...
     def set_max_background_jobs(self, jobs: int) -> None: ...
+    def set_max_background_compactions(self, compactions: int) -> None: ...
     def set_max_bytes_for_level_base(self, size: int) -> None: ...
     def set_max_bytes_for_level_multiplier(self, mul: float) -> None: ...
     def set_max_bytes_for_level_multiplier_additional(self, level_values: list) -> None: ...
@@ -238,15 +351,20 @@ class Options:
     def set_memtable_huge_page_size(self, size: int) -> None: ...
     def set_memtable_prefix_bloom_ratio(self, ratio: float) -> None: ...
     def set_memtable_whole_key_filtering(self, whole_key_filter: bool) -> None: ...
+    def set_merge_operator(self, merge_operator: MergeOperator) -> None: ...
     def set_min_level_to_compress(self, lvl: int) -> None: ...
     def set_min_write_buffer_number(self, nbuf: int) -> None: ...
     def set_min_write_buffer_number_to_merge(self, to_merge: int) -> None: ...
     def set_num_levels(self, n: int) -> None: ...
     def set_optimize_filters_for_hits(self, optimize_for_hits: bool) -> None: ...
     def set_paranoid_checks(self, enabled: bool) -> None: ...
+    def set_paranoid_file_checks(self, enabled: bool) -> None: ...
     def set_plain_table_factory(self, options: PlainTableFactoryOptions) -> None: ...
     def set_prefix_extractor(self, prefix_extractor: SliceTransform) -> None: ...
+    def set_sst_partitioner_fixed_prefix(self, prefix_len: int) -> None: ...
+    def set_file_checksum_gen_factory(self, factory: FileChecksumGenFactory) -> None: ...
     def set_ratelimiter(self, rate_bytes_per_sec: int, refill_period_us: int, fairness: int) -> None: ...
+    def set_rate_limiter(self, rate_limiter: RateLimiter) -> None: ...
     def set_recycle_log_file_num(self, num: int) -> None: ...
     def set_report_bg_io_stats(self, enable: bool) -> None: ...
     def set_row_cache(self, cache: Cache) -> None: ...
@@ -258,6 +376,7 @@ class Options:
     def set_table_cache_num_shard_bits(self, nbits: int) -> None: ...
     def set_target_file_size_base(self, size: int) -> None: ...
     def set_target_file_size_multiplier(self, multiplier: int) -> None: ...
+    def set_ttl(self, secs: int) -> None: ...
     def set_universal_compaction_options(self, uco: UniversalCompactOptions) -> None: ...
     def set_unordered_write(self, unordered: bool) -> None: ...
     def set_use_adaptive_mutex(self, enabled: bool) -> None: ...
@@ -269,6 +388,8 @@ class Options:
     def set_wal_recovery_mode(self, mode: DBRecoveryMode) -> None: ...
     def set_wal_size_limit_mb(self, size: int) -> None: ...
     def set_wal_ttl_seconds(self, secs: int) -> None: ...
+    def set_manual_wal_flush(self, is_enabled: bool) -> None: ...
+    def set_track_and_verify_wals_in_manifest(self, v: bool) -> None: ...
     def set_writable_file_max_buffer_size(self, nbytes: int) -> None: ...
     def set_write_buffer_size(self, size: int) -> None: ...
     def set_zstd_max_train_bytes(self, value: int) -> None: ...
@@ -303,7 +424,31 @@ class PlainTableFactoryOptions:
     def user_key_length(self) -> int: ...
     @user_key_length.setter
     def user_key_length(self, v: int) -> None: ...
-    def __init__(self) -> None: ...
+    @property
+    def huge_page_tlb_size(self) -> int: ...
+    @huge_page_tlb_size.setter
+    def huge_page_tlb_size(self, v: int) -> None: ...
+    @property
+    def encoding_type(self) -> KeyEncodingType: ...
+    @encoding_type.setter
+    def encoding_type(self, v: KeyEncodingType) -> None: ...
+    @property
+    def full_scan_mode(self) -> bool: ...
+    @full_scan_mode.setter
+    def full_scan_mode(self, v: bool) -> None: ...
+    @property
+    def store_index_in_file(self) -> bool: ...
+    @store_index_in_file.setter
+    def store_index_in_file(self, v: bool) -> None: ...
+    def __init__(self,
+                 user_key_length: int = 0,
+                 bloom_bits_per_key: int = 10,
+                 hash_table_ratio: float = 0.75,
+                 index_sparseness: int = 16,
+                 huge_page_tlb_size: int = 0,
+                 encoding_type: KeyEncodingType = KeyEncodingType.plain(),
+                 full_scan_mode: bool = False,
+                 store_index_in_file: bool = False) -> None: ...
 
 class ReadOptions:
     def __init__(self) -> None: ...
@@ -320,6 +465,14 @@ class ReadOptions:
     def set_total_order_seek(self, v: bool) -> None: ...
     def set_verify_checksums(self, v: bool) -> None: ...
     def set_async_io(self, v: bool) -> None: ...
+    def set_timestamp(self, ts: Union[int, None]) -> None: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+
+class MergeOperator:
+    @staticmethod
+    def uint64_add() -> MergeOperator: ...
+    @staticmethod
+    def string_append(delimiter: Union[str, bytes, None] = None) -> MergeOperator: ...
 
 class SliceTransform:
     @staticmethod
@@ -354,13 +507,19 @@ class UniversalCompactOptions:
     def stop_style(self) -> UniversalCompactionStopStyle: ...
     @stop_style.setter
     def stop_style(self, style: UniversalCompactionStopStyle) -> None: ...
-    def __init__(self) -> None: ...
+    def __init__(self,
+                 size_ratio: int = 1,
+                 min_merge_width: int = 2,
+                 max_merge_width: int = 2147483647,
+                 max_size_amplification_percent: int = 200,
+                 compression_size_percent: int = -1,
+                 stop_style: UniversalCompactionStopStyle = ...) -> None: ...
 
 class UniversalCompactionStopStyle:
-    @classmethod
-    def __init__(cls, *args, **kwargs) -> None: ...
-    def similar(self, *args, **kwargs) -> None: ...
-    def total(self, *args, **kwargs) -> None: ...
+    @staticmethod
+    def similar() -> UniversalCompactionStopStyle: ...
+    @staticmethod
+    def total() -> UniversalCompactionStopStyle: ...
 
 class WriteOptions:
     @property
@@ -383,17 +542,30 @@ class WriteOptions:
     def sync(self) -> bool: ...
     @sync.setter
     def sync(self, v: bool) -> None: ...
-    def __init__(self) -> None: ...
-    def disable_wal(self, disable: bool) -> None: ...
+    @property
+    def disable_wal(self) -> bool: ...
+    @disable_wal.setter
+    def disable_wal(self, v: bool) -> None: ...
+    def __init__(self,
+                 sync: bool = False,
+                 disable_wal: bool = False,
+                 ignore_missing_column_families: bool = False,
+                 no_slowdown: bool = False,
+                 low_pri: bool = False,
+                 memtable_insert_hint_per_batch: bool = False) -> None: ...
+    def to_dict(self) -> Dict[str, bool]: ...
 
 class Rdict:
     def __init__(self, path: str,
                  options: Union[Options, None] = None,
                  column_families: Union[Dict[str, Options], None] = None,
-                 access_type: AccessType = AccessType.read_write()) -> None: ...
+                 access_type: AccessType = AccessType.read_write(),
+                 serializer: Union[str, Tuple[Callable[[Any], bytes], Callable[[bytes], Any]], None] = None) -> None: ...
     def __enter__(self) -> Rdict: ...
     def set_dumps(self, dumps: Callable[[Any], bytes]) -> None: ...
     def set_loads(self, dumps: Callable[[bytes], Any]) -> None: ...
+    def set_key_dumps(self, key_dumps: Callable[[Any], bytes]) -> None: ...
+    def set_key_loads(self, key_loads: Callable[[bytes], Any]) -> None: ...
     def set_read_options(self, read_opt: ReadOptions) -> None: ...
     def set_write_options(self, write_opt: WriteOptions) -> None: ...
     def __contains__(self, key: Union[str, int, float, bytes, bool]) -> bool: ...
@@ -403,35 +575,104 @@ class Rdict:
     def get(self,
             key: Union[str, int, float, bytes, bool, List[Union[str, int, float, bytes, bool]]],
             default: Any = None,
-            read_opt: Union[ReadOptions, None] = None) -> Any | None: ...
+            read_opt: Union[ReadOptions, None] = None,
+            ts: Union[int, None] = None) -> Any | None: ...
+    def get_with_stats(self,
+                       key: Union[str, int, float, bytes, bool, List[Union[str, int, float, bytes, bool]]],
+                       default: Any = None,
+                       read_opt: Union[ReadOptions, None] = None) -> Tuple[Any | None, Dict[str, int]]: ...
     def get_entity(self,
                    key: Union[str, int, float, bytes, bool, List[Union[str, int, float, bytes, bool]]],
                    default: Any = None,
                    read_opt: Union[ReadOptions, None] = None) -> List[Tuple[Any, Any]] | None: ...
+    def get_pinned(self, key: bytes, read_opt: Union[ReadOptions, None] = None) -> PinnedSlice | None: ...
+    def get_into(self, key: bytes, buffer: Any, read_opt: Union[ReadOptions, None] = None) -> int | None: ...
+    def set_raw_value_type(self, value_type: str) -> None: ...
+    def set_value_transform(self,
+                            encode: Union[Callable[[bytes], bytes], None],
+                            decode: Union[Callable[[bytes], bytes], None]) -> None: ...
     def put(self,
             key: Union[str, int, float, bytes, bool],
             value: Any,
-            write_opt: Union[WriteOptions, None] = None) -> None: ...
+            write_opt: Union[WriteOptions, None] = None,
+            ts: Union[int, None] = None) -> None: ...
+    def merge(self,
+              key: Union[str, int, float, bytes, bool],
+              value: Any,
+              write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def put_if_absent(self,
+                      key: Union[str, int, float, bytes, bool],
+                      value: Any,
+                      write_opt: Union[WriteOptions, None] = None) -> bool: ...
+    def pop(self,
+            key: Union[str, int, float, bytes, bool],
+            *default: Any,
+            write_opt: Union[WriteOptions, None] = None) -> Any: ...
+    def popitem(self, last: bool = True, write_opt: Union[WriteOptions, None] = None) -> Tuple[Any, Any]: ...
+    def setdefault(self,
+                   key: Union[str, int, float, bytes, bool],
+                   default: Any = None,
+                   write_opt: Union[WriteOptions, None] = None) -> Any: ...
     def put_entity(self,
                    key: Union[str, int, float, bytes, bool],
                    names: List[Any],
                    values: List[Any],
                    write_opt: Union[WriteOptions, None] = None) -> None: ...
-    def delete(self, key: Union[str, int, float, bytes, bool], write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def delete(self, key: Union[str, int, float, bytes, bool], write_opt: Union[WriteOptions, None] = None,
+               ts: Union[int, None] = None) -> None: ...
+    def delete_many(self, keys: Iterable[Union[str, int, float, bytes, bool]],
+                    write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def update(self, other: Union[Dict[Any, Any], Iterable[Tuple[Any, Any]], None] = None,
+               write_opt: Union[WriteOptions, None] = None,
+               atomic: bool = True,
+               **kwargs: Any) -> None: ...
     def key_may_exist(self,
                       key: Union[str, int, float, bytes, bool],
                       fetch: bool = False,
                       read_opt = None) -> Union[bool, Tuple[bool, Any]]: ...
     def iter(self, read_opt: Union[ReadOptions, None] = None) -> RdictIter: ...
+    def __len__(self) -> int: ...
+    def __bool__(self) -> bool: ...
+    def __iter__(self) -> RdictKeys: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def __or__(self, other: Mapping[Any, Any]) -> Dict[Any, Any]: ...
+    def __ror__(self, other: Mapping[Any, Any]) -> Dict[Any, Any]: ...
+    def __ior__(self, other: Mapping[Any, Any]) -> Rdict: ...
+    def get_statistics(self) -> Union[Statistics, None]: ...
+    def exact_len(self, read_opt: Union[ReadOptions, None] = None) -> int: ...
+    def count_exact(self, read_opt: Union[ReadOptions, None] = None) -> int: ...
+    def count(self, from_key: Union[str, int, float, bytes, bool, None] = None,
+              to_key: Union[str, int, float, bytes, bool, None] = None,
+              read_opt: Union[ReadOptions, None] = None) -> int: ...
+    def range(self, start: Union[str, int, float, bytes, bool, None] = None,
+              end: Union[str, int, float, bytes, bool, None] = None,
+              read_opt: Union[ReadOptions, None] = None) -> RdictItems: ...
+    def to_dict(self, from_key: Union[str, int, float, bytes, bool, None] = None,
+                to_key: Union[str, int, float, bytes, bool, None] = None,
+                read_opt: Union[ReadOptions, None] = None,
+                max_items: Union[int, None] = 1_000_000) -> Dict[Any, Any]: ...
+    def copy_to(self, other: Rdict,
+                from_key: Union[str, int, float, bytes, bool, None] = None,
+                to_key: Union[str, int, float, bytes, bool, None] = None,
+                batch_size: int = 10_000,
+                write_opt: Union[WriteOptions, None] = None) -> int: ...
     def items(self, backwards: bool = False,
               from_key: Union[str, int, float, bytes, bool, None] = None,
-              read_opt: Union[ReadOptions, None] = None) -> RdictItems: ...
+              read_opt: Union[ReadOptions, None] = None,
+              limit: Union[int, None] = None) -> RdictItemsView: ...
     def keys(self, backwards: bool = False,
              from_key: Union[str, int, float, bytes, bool, None] = None,
-             read_opt: Union[ReadOptions, None] = None) -> RdictKeys: ...
+             read_opt: Union[ReadOptions, None] = None,
+             limit: Union[int, None] = None) -> RdictKeysView: ...
     def values(self, backwards: bool = False,
                from_key: Union[str, int, float, bytes, bool, None] = None,
-               read_opt: Union[ReadOptions, None] = None) -> RdictValues: ...
+               read_opt: Union[ReadOptions, None] = None,
+               limit: Union[int, None] = None) -> RdictValuesView: ...
+    def keys_with_prefix(self, prefix: Union[str, bytes, Tuple[Any, ...]],
+                         read_opt: Union[ReadOptions, None] = None) -> RdictKeys: ...
+    def items_with_prefix(self, prefix: Union[str, bytes, Tuple[Any, ...]],
+                          read_opt: Union[ReadOptions, None] = None) -> RdictItems: ...
     def columns(self, backwards: bool = False,
                 from_key: Union[str, int, float, bytes, bool, None] = None,
                 read_opt: Union[ReadOptions, None] = None) -> RdictColumns: ...
@@ -443,27 +684,42 @@ class Rdict:
     def get_column_family_handle(self, name: str) -> ColumnFamily: ...
     def drop_column_family(self, name: str) -> None: ...
     def create_column_family(self, name: str, options: Options = Options()) -> Rdict: ...
-    def write(self, write_batch: WriteBatch, write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def write(self, write_batch: Union[WriteBatch, WriteBatchWithIndex], write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def clear(self, write_opt: Union[WriteOptions, None] = None) -> None: ...
     def delete_range(self,
                      begin: Union[str, int, float, bytes, bool],
                      end: Union[str, int, float, bytes, bool],
                      write_opt: Union[WriteOptions, None] = None) -> None: ...
+    def delete_files_in_range(self, begin: Union[str, int, float, bytes, bool],
+                              end: Union[str, int, float, bytes, bool]) -> None: ...
     def snapshot(self) -> Snapshot: ...
     def path(self) -> str: ...
     def set_options(self, options: Dict[str, str]) -> None: ...
     def property_value(self, name: str) -> Union[str, None]: ...
     def property_int_value(self, name: str) -> Union[int, None]: ...
+    def current_options(self) -> Dict[str, str]: ...
     def latest_sequence_number(self) -> int: ...
+    def get_updates_since(self, seq_number: int) -> WalIterator: ...
     def live_files(self) -> List[Dict[str, Any]]: ...
+    def verify_checksum(self) -> None: ...
+    def split_ranges(self, n: int) -> List[Tuple[Any, Any]]: ...
     def compact_range(self, begin: Union[str, int, float, bytes, bool, None],
                       end: Union[str, int, float, bytes, bool, None],
                       compact_opt: CompactOptions = CompactOptions()) -> None: ...
     def try_catch_up_with_primary(self) -> None: ...
     def cancel_all_background(self, wait: bool) -> None: ...
     def close(self) -> None: ...
-    def __exit__(self, exc_type, exc_val, exc_tb) -> None: ...
-    def flush(self, wait: bool = True) -> None: ...
+    @property
+    def is_closed(self) -> bool: ...
+    @property
+    def background_errors(self) -> int: ...
+    @property
+    def background_error(self) -> Union[str, None]: ...
+    def resume(self) -> None: ...
+    def __exit__(self, exc_type, exc_val, exc_tb) -> bool: ...
+    def flush(self, wait: bool = True, column_families: Union[List[ColumnFamily], None] = None) -> None: ...
     def flush_wal(self, sync: bool = True) -> None: ...
+    def get_sorted_wal_files(self) -> List[Dict[str, Any]]: ...
     @staticmethod
     def destroy(path: str, options: Options = Options()) -> None: ...
     @staticmethod
@@ -473,27 +729,54 @@ class Rdict:
 
 class RdictItems(Iterator[Tuple[Union[str, int, float, bytes, bool], Any]]):
     def __iter__(self) -> RdictItems: ...
+    def __length_hint__(self) -> int: ...
+    def status(self) -> None: ...
     def __next__(self) -> Tuple[Union[str, int, float, bytes, bool], Any]: ...
 
 class RdictKeys(Iterator[Union[str, int, float, bytes, bool]]):
     def __iter__(self) -> RdictKeys: ...
+    def __length_hint__(self) -> int: ...
+    def status(self) -> None: ...
     def __next__(self) -> Union[str, int, float, bytes, bool]: ...
 
 class RdictValues(Iterator[Any]):
     def __iter__(self) -> RdictValues: ...
+    def __length_hint__(self) -> int: ...
+    def status(self) -> None: ...
     def __next__(self) -> Any: ...
 
+class RdictItemsView:
+    def __iter__(self) -> RdictItems: ...
+    def __len__(self) -> int: ...
+    def __contains__(self, item: Tuple[Any, Any]) -> bool: ...
+
+class RdictKeysView:
+    def __iter__(self) -> RdictKeys: ...
+    def __len__(self) -> int: ...
+    def __contains__(self, key: Union[str, int, float, bytes, bool]) -> bool: ...
+
+class RdictValuesView:
+    def __iter__(self) -> RdictValues: ...
+    def __len__(self) -> int: ...
+    def __contains__(self, value: Any) -> bool: ...
+
 class RdictColumns(Iterator[Any]):
     def __iter__(self) -> RdictValues: ...
+    def __length_hint__(self) -> int: ...
+    def status(self) -> None: ...
     def __next__(self) -> List[Tuple[Any, Any]]: ...
 
 class RdictEntities(Iterator[Tuple[Union[str, int, float, bytes, bool], List[Tuple[Any, Any]]]]):
     def __iter__(self) -> RdictEntities: ...
+    def __length_hint__(self) -> int: ...
+    def status(self) -> None: ...
     def __next__(self) -> Tuple[Union[str, int, float, bytes, bool], List[Tuple[Any, Any]]]: ...
 
 class RdictIter:
     def valid(self) -> bool: ...
     def status(self) -> None: ...
+    def set_key_dumps(self, key_dumps: Callable[[Any], bytes]) -> None: ...
+    def set_key_loads(self, key_loads: Callable[[bytes], Any]) -> None: ...
     def seek_to_first(self) -> None: ...
     def seek_to_last(self) -> None: ...
     def seek(self, key: Union[str, int, float, bytes, bool]) -> None: ...
@@ -513,36 +796,95 @@ class IngestExternalFileOptions:
     def set_ingest_behind(self, v: bool) -> None: ...
 
 class SstFileWriter:
-    def __init__(self, options: Options = Options()) -> None: ...
+    def __init__(self, options: Options = Options(),
+                 serializer: Union[str, Tuple[Callable[[Any], bytes], Callable[[bytes], Any]], None] = None) -> None: ...
     def set_dumps(self, dumps: Callable[[Any], bytes]) -> None: ...
+    def set_key_dumps(self, key_dumps: Callable[[Any], bytes]) -> None: ...
+    def set_compression(self, compression_type: DBCompressionType, level: Union[int, None] = None) -> None: ...
+    def compression(self) -> str: ...
     def open(self, path: str) -> None: ...
     def finish(self) -> None: ...
     def file_size(self) -> int: ...
+    def num_entries(self) -> int: ...
     def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
     def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def put_with_ts(self, key: Union[str, int, float, bytes, bool], ts: int, value: Any) -> None: ...
+    def delete_with_ts(self, key: Union[str, int, float, bytes, bool], ts: int) -> None: ...
+
+class WalIterator:
+    def __iter__(self) -> WalIterator: ...
+    def __next__(self) -> Tuple[int, WriteBatch]: ...
 
 class WriteBatch:
     def __init__(self, raw_mode: bool = False) -> None: ...
+    @staticmethod
+    def from_data(data: bytes, raw_mode: bool = False) -> WriteBatch: ...
     def __len__(self) -> int: ...
     def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
     def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
     def set_dumps(self, dumps: Callable[[Any], bytes]) -> None: ...
+    def set_loads(self, loads: Callable[[bytes], Any]) -> None: ...
+    def set_key_dumps(self, key_dumps: Callable[[Any], bytes]) -> None: ...
+    def set_key_loads(self, key_loads: Callable[[bytes], Any]) -> None: ...
     def set_default_column_family(self, column_family: Union[ColumnFamily, None]) -> None: ...
     def len(self) -> int: ...
+    def count(self) -> int: ...
     def size_in_bytes(self) -> int: ...
     def is_empty(self) -> bool: ...
+    def data(self) -> bytes: ...
+    def iterate(self, decode: bool = False) -> List[Tuple[str, int, Any, Any]]: ...
     def put(self, key: Union[str, int, float, bytes, bool], value: Any,
             column_family: Union[ColumnFamily, None] = None) -> None: ...
+    def merge(self, key: Union[str, int, float, bytes, bool], value: Any,
+              column_family: Union[ColumnFamily, None] = None) -> None: ...
     def put_entity(self,
                    key: Union[str, int, float, bytes, bool],
                    names: List[Any],
                    values: List[Any]) -> None: ...
     def delete(self, key: Union[str, int, float, bytes, bool],
                column_family: Union[ColumnFamily, None] = None) -> None: ...
+    def single_delete(self, key: Union[str, int, float, bytes, bool],
+                      column_family: Union[ColumnFamily, None] = None) -> None: ...
+    def delete_range(self, begin: Union[str, int, float, bytes, bool],
+                     end: Union[str, int, float, bytes, bool],
+                     column_family: Union[ColumnFamily, None] = None) -> None: ...
+    def set_save_point(self) -> None: ...
+    def rollback_to_save_point(self) -> None: ...
+    def pop_save_point(self) -> None: ...
+    def clear(self) -> None: ...
+
+class WriteBatchWithIndex:
+    def __init__(self, raw_mode: bool = False, overwrite_key: bool = False) -> None: ...
+    def __len__(self) -> int: ...
+    def __setitem__(self, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def __delitem__(self, key: Union[str, int, float, bytes, bool]) -> None: ...
+    def set_dumps(self, dumps: Callable[[Any], bytes]) -> None: ...
+    def set_loads(self, loads: Callable[[bytes], Any]) -> None: ...
+    def set_key_dumps(self, key_dumps: Callable[[Any], bytes]) -> None: ...
+    def set_default_column_family(self, column_family: Union[ColumnFamily, None]) -> None: ...
+    def len(self) -> int: ...
+    def is_empty(self) -> bool: ...
+    def put(self, key: Union[str, int, float, bytes, bool], value: Any,
+            column_family: Union[ColumnFamily, None] = None) -> None: ...
+    def delete(self, key: Union[str, int, float, bytes, bool],
+               column_family: Union[ColumnFamily, None] = None) -> None: ...
+    def merge(self, db: Rdict, key: Union[str, int, float, bytes, bool], value: Any) -> None: ...
+    def single_delete(self, key: Union[str, int, float, bytes, bool],
+                      column_family: Union[ColumnFamily, None] = None) -> None: ...
     def delete_range(self, begin: Union[str, int, float, bytes, bool],
                      end: Union[str, int, float, bytes, bool],
                      column_family: Union[ColumnFamily, None] = None) -> None: ...
+    def set_save_point(self) -> None: ...
+    def rollback_to_save_point(self) -> None: ...
     def clear(self) -> None: ...
+    def get_from_batch(self, key: Union[str, int, float, bytes, bool],
+                       default: Any = None,
+                       column_family: Union[ColumnFamily, None] = None) -> Any: ...
+    def get_from_batch_and_db(self, db: Rdict,
+                              key: Union[str, int, float, bytes, bool],
+                              default: Any = None,
+                              read_opt: Union[ReadOptions, None] = None) -> Any: ...
+    def iter_with_base(self, db: Rdict, read_opt: Union[ReadOptions, None] = None) -> RdictIter: ...
 
 class ColumnFamily: ...
 
@@ -558,16 +900,23 @@ class AccessType:
 
 class Snapshot:
     def __getitem__(self, key: Union[str, int, float, bytes, bool]) -> Any: ...
+    def get(self, key: Union[str, int, float, bytes, bool], default: Any = None) -> Any | None: ...
     def iter(self, read_opt: Union[ReadOptions, None] = None) -> RdictIter: ...
+    def count(self, from_key: Union[str, int, float, bytes, bool, None] = None,
+              to_key: Union[str, int, float, bytes, bool, None] = None,
+              read_opt: Union[ReadOptions, None] = None) -> int: ...
     def items(self, backwards: bool = False,
               from_key: Union[str, int, float, bytes, bool, None] = None,
-              read_opt: Union[ReadOptions, None] = None) -> RdictItems: ...
+              read_opt: Union[ReadOptions, None] = None,
+              limit: Union[int, None] = None) -> RdictItems: ...
     def keys(self, backwards: bool = False,
              from_key: Union[str, int, float, bytes, bool, None] = None,
-             read_opt: Union[ReadOptions, None] = None) -> RdictKeys: ...
+             read_opt: Union[ReadOptions, None] = None,
+             limit: Union[int, None] = None) -> RdictKeys: ...
     def values(self, backwards: bool = False,
                from_key: Union[str, int, float, bytes, bool, None] = None,
-               read_opt: Union[ReadOptions, None] = None) -> RdictValues: ...
+               read_opt: Union[ReadOptions, None] = None,
+               limit: Union[int, None] = None) -> RdictValues: ...
 
 class BottommostLevelCompaction:
     @staticmethod
@@ -586,10 +935,19 @@ class CompactOptions:
     def set_change_level(self, v: bool) -> None: ...
     def set_target_level(self, lvl: int) -> None: ...
 
+class RateLimiter:
+    def __init__(self, rate_bytes_per_sec: int,
+                 refill_period_us: int = 100_000,
+                 fairness: int = 10,
+                 mode: str = "writes_only",
+                 auto_tuned: bool = False) -> None: ...
+
 class WriteBufferManager:
-    def __init__(self, buffer_size: int, allow_stall: bool) -> None: ...
+    def __init__(self, buffer_size: int, cache: Union[Cache, None] = None, allow_stall: bool = False) -> None: ...
     @staticmethod
     def new_write_buffer_manager_with_cache(buffer_size: int, allow_stall: bool, cache: Cache) -> WriteBufferManager: ...
+    def memory_usage(self) -> int: ...
+    def buffer_size(self) -> int: ...
     def get_usage(self) -> int: ...
     def get_buffer_size(self) -> int: ...
     def set_buffer_size(self, new_size: int) -> None: ...
@@ -598,7 +956,7 @@ class WriteBufferManager:
 
 class KeyEncodingType:
     @staticmethod
-    def none() -> KeyEncodingType: ...
+    def plain() -> KeyEncodingType: ...
     @staticmethod
     def prefix() -> KeyEncodingType: ...
 
@@ -606,5 +964,23 @@ class Checkpoint:
     def __init__(self, db: Rdict) -> None: ...
     def create_checkpoint(self, path: str) -> None: ...
 
+class PinnedSlice:
+    def __enter__(self) -> PinnedSlice: ...
+    def __exit__(self, exc_type, exc_val, exc_tb) -> bool: ...
+    def __len__(self) -> int: ...
+    def __buffer__(self, flags: int) -> memoryview: ...
+    def close(self) -> None: ...
+
 class DbClosedError(Exception):
     """Raised when accessing a closed database instance."""
+
+def encode_int_key(value: int, width: int = 8, signed: bool = True) -> bytes: ...
+def decode_int_key(data: bytes, signed: bool = True) -> int: ...
+def encode_float_key(value: float) -> bytes: ...
+def decode_float_key(data: bytes) -> float: ...
+def encode_key(key: Union[str, int, float, bytes, bool], raw_mode: bool = False) -> bytes: ...
+def decode_key(data: bytes, raw_mode: bool = False) -> Union[str, int, float, bytes, bool]: ...
+def encode_value(value: Any, raw_mode: bool = False,
+                 dumps: Union[Callable[[Any], bytes], None] = None) -> bytes: ...
+def decode_value(data: bytes, raw_mode: bool = False,
+                 loads: Union[Callable[[bytes], Any], None] = None) -> Any: ...
@@ -1,30 +1,91 @@
 use rocksdb::{DBWithThreadMode, MultiThreaded};
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
 /// The type of a reference to a [rocksdb::DB] that is passed around the library.
 pub(crate) type DbReference = Arc<DBWithThreadMode<MultiThreaded>>;
 
+/// All the DBs opened in this process, by canonical path.
+static OPEN_DBS: Mutex<Vec<(PathBuf, Weak<DBWithThreadMode<MultiThreaded>>)>> =
+    Mutex::new(Vec::new());
+
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Whether a DB at `path` is still alive in this process,
+/// i.e. some instances referencing it are not closed or dropped.
+pub(crate) fn is_db_open(path: &Path) -> bool {
+    let path = canonical_path(path);
+    let mut open_dbs = OPEN_DBS.lock().unwrap();
+    open_dbs.retain(|(_, db)| db.strong_count() > 0);
+    open_dbs.iter().any(|(open_path, _)| *open_path == path)
+}
+
 /// A wrapper around [DbReference] that cancels all background work when dropped.
 ///
 /// All users of [rocksdb::DB] should use this wrapper instead to avoid keeping background threads
 /// alive after the database is dropped.
+///
+/// Clones share the closed flag of the holder they are cloned from, so that the iterators and
+/// snapshots created from an instance stop working once it is closed.
 #[derive(Clone)]
 pub(crate) struct DbReferenceHolder {
     inner: Option<DbReference>,
+    /// The closed flag of this holder last, preceded by the flags of the holders it was
+    /// created from with [DbReferenceHolder::child].
+    closed: Vec<Arc<AtomicBool>>,
 }
 
 impl DbReferenceHolder {
     pub fn new(db: DBWithThreadMode<MultiThreaded>) -> Self {
+        let db = Arc::new(db);
+        OPEN_DBS
+            .lock()
+            .unwrap()
+            .push((canonical_path(db.path()), Arc::downgrade(&db)));
         Self {
-            inner: Some(Arc::new(db)),
+            inner: Some(db),
+            closed: vec![Arc::new(AtomicBool::new(false))],
         }
     }
 
+    /// A holder with its own closed flag, which is also closed when this holder is closed.
+    pub fn child(&self) -> Self {
+        let mut child = self.clone();
+        child.closed.push(Arc::new(AtomicBool::new(false)));
+        child
+    }
+
+    /// Returns `None` if this holder or one it was created from is closed.
     pub fn get(&self) -> Option<&DbReference> {
+        if self
+            .closed
+            .iter()
+            .any(|closed| closed.load(Ordering::Acquire))
+        {
+            return None;
+        }
+        self.inner.as_ref()
+    }
+
+    /// Like [DbReferenceHolder::get], but ignores the closed flags,
+    /// to release the resources created from the DB.
+    pub fn get_for_release(&self) -> Option<&DbReference> {
         self.inner.as_ref()
     }
 
+    /// Close this holder and its clones, and release this reference.
     pub fn close(&mut self) {
+        if let Some(closed) = self.closed.last() {
+            closed.store(true, Ordering::Release);
+        }
+        self.release();
+    }
+
+    /// Release this reference, without closing the clones.
+    pub fn release(&mut self) {
         if let Some(db) = self.inner.take().and_then(Arc::into_inner) {
             db.cancel_all_background_work(true);
         }
@@ -33,6 +94,6 @@ impl DbReferenceHolder {
 
 impl Drop for DbReferenceHolder {
     fn drop(&mut self) {
-        self.close();
+        self.release();
     }
 }
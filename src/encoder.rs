@@ -1,8 +1,13 @@
-use num_bigint::BigInt;
-use pyo3::exceptions::{PyException, PyKeyError, PyValueError};
+use libc::{c_char, c_int};
+use num_bigint::{BigInt, Sign};
+use pyo3::exceptions::{PyException, PyKeyError, PyOverflowError, PyTypeError, PyValueError};
+use pyo3::ffi;
 use pyo3::prelude::*;
-use pyo3::types::{PyBool, PyBytes, PyFloat, PyInt, PyString};
-use std::borrow::Cow;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyCFunction, PyDict, PyFloat, PyInt, PyString, PyTuple,
+};
+use std::ops::Deref;
+use std::pin::Pin;
 
 pub(crate) enum ValueTypes<'a, 'b> {
     Bytes(&'a [u8]),
@@ -25,16 +30,153 @@ pub(crate) fn encoding_byte(v_type: &ValueTypes) -> u8 {
     }
 }
 
+/// Encoded key or value bytes, borrowed from the python object when possible.
+pub(crate) enum EncodedBytes<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+    /// raw mode objects implementing the buffer protocol, released on drop
+    Buffer(RawBuffer),
+}
+
+impl EncodedBytes<'_> {
+    pub(crate) fn into_owned(self) -> Vec<u8> {
+        match self {
+            EncodedBytes::Owned(bytes) => bytes,
+            bytes => bytes.to_vec(),
+        }
+    }
+}
+
+impl Deref for EncodedBytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            EncodedBytes::Borrowed(bytes) => bytes,
+            EncodedBytes::Owned(bytes) => bytes,
+            EncodedBytes::Buffer(buffer) => buffer.as_bytes(),
+        }
+    }
+}
+
+impl AsRef<[u8]> for EncodedBytes<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<EncodedBytes<'_>> for Vec<u8> {
+    fn from(bytes: EncodedBytes<'_>) -> Self {
+        bytes.into_owned()
+    }
+}
+
+/// A C-contiguous buffer exported by a python object (`bytearray`,
+/// `memoryview`, `mmap`, numpy arrays, ...), read without copying.
+///
+/// The buffer is pinned since exporters may point into the `Py_buffer` itself.
+pub(crate) struct RawBuffer(Pin<Box<ffi::Py_buffer>>);
+
+// the exporter keeps the memory valid and unmoved until the buffer is released
+unsafe impl Send for RawBuffer {}
+unsafe impl Sync for RawBuffer {}
+
+impl RawBuffer {
+    /// Returns `None` if the object does not implement the buffer protocol.
+    fn get(obj: &Bound<PyAny>) -> PyResult<Option<Self>> {
+        if unsafe { ffi::PyObject_CheckBuffer(obj.as_ptr()) } == 0 {
+            return Ok(None);
+        }
+        let buffer = Self::request(obj, ffi::PyBUF_STRIDES)?;
+        if !buffer.is_contiguous() {
+            return Err(PyTypeError::new_err(
+                "raw mode only support contiguous buffers, copy it with `bytes()` first",
+            ));
+        }
+        Ok(Some(buffer))
+    }
+
+    /// A contiguous writable buffer, e.g. of a `bytearray` or a numpy array.
+    pub(crate) fn get_writable(obj: &Bound<PyAny>) -> PyResult<Self> {
+        if unsafe { ffi::PyObject_CheckBuffer(obj.as_ptr()) } == 0 {
+            return Err(PyTypeError::new_err(
+                "expected a writable buffer, such as a `bytearray`",
+            ));
+        }
+        let buffer = Self::request(obj, ffi::PyBUF_WRITABLE | ffi::PyBUF_STRIDES)?;
+        if !buffer.is_contiguous() {
+            return Err(PyTypeError::new_err("the buffer must be contiguous"));
+        }
+        Ok(buffer)
+    }
+
+    fn request(obj: &Bound<PyAny>, flags: c_int) -> PyResult<Self> {
+        let mut view = Box::pin(unsafe { std::mem::zeroed::<ffi::Py_buffer>() });
+        // without `PyBUF_FORMAT` the items are read as bytes, whatever their type
+        if unsafe { ffi::PyObject_GetBuffer(obj.as_ptr(), &mut *view, flags) } == -1 {
+            return Err(PyErr::fetch(obj.py()));
+        }
+        Ok(RawBuffer(view))
+    }
+
+    fn is_contiguous(&self) -> bool {
+        unsafe { ffi::PyBuffer_IsContiguous(&*self.0, b'C' as c_char) != 0 }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        if self.0.len == 0 {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.0.buf as *const u8, self.0.len as usize) }
+    }
+
+    /// Only for buffers obtained with `get_writable()`.
+    pub(crate) fn as_mut_bytes(&mut self) -> &mut [u8] {
+        if self.0.len == 0 {
+            return &mut [];
+        }
+        unsafe { std::slice::from_raw_parts_mut(self.0.buf as *mut u8, self.0.len as usize) }
+    }
+}
+
+impl Drop for RawBuffer {
+    fn drop(&mut self) {
+        Python::with_gil(|_| unsafe { ffi::PyBuffer_Release(&mut *self.0) });
+    }
+}
+
+/// `bytes`, or any contiguous buffer, in raw mode
+#[inline(always)]
+fn encode_raw(value: &Bound<PyAny>) -> PyResult<Option<EncodedBytes<'_>>> {
+    if let Ok(value) = value.downcast::<PyBytes>() {
+        return Ok(Some(EncodedBytes::Borrowed(value.as_bytes())));
+    }
+    Ok(RawBuffer::get(value)?.map(EncodedBytes::Buffer))
+}
+
 #[inline(always)]
-pub(crate) fn encode_key<'a>(key: &'a Bound<PyAny>, raw_mode: bool) -> PyResult<Cow<'a, [u8]>> {
+pub(crate) fn encode_key<'a>(key: &'a Bound<PyAny>, raw_mode: bool) -> PyResult<EncodedBytes<'a>> {
     if raw_mode {
-        return if let Ok(value) = key.downcast::<PyBytes>() {
-            Ok(Cow::Borrowed(value.as_bytes()))
-        } else {
-            Err(PyKeyError::new_err("raw mode only support bytes"))
+        return match encode_raw(key)? {
+            Some(bytes) => Ok(bytes),
+            None => Err(PyKeyError::new_err("raw mode only support bytes")),
         };
     }
     let bytes = py_to_value_types(key)?;
+    if let ValueTypes::Any(value) = &bytes {
+        if let Ok(tuple) = value.downcast::<PyTuple>() {
+            return Ok(EncodedBytes::Owned(encode_tuple_key(tuple)?));
+        }
+        // numpy scalars are the same keys as the equivalent python objects
+        if let Some(scalar) = numpy_scalar_item(value)? {
+            return Ok(EncodedBytes::Owned(
+                encode_key(&scalar, false)?.into_owned(),
+            ));
+        }
+        if let Some(bytes) = encode_datetime_or_uuid(value)? {
+            return Ok(EncodedBytes::Owned(bytes));
+        }
+    }
     let type_encoding = encoding_byte(&bytes);
     let owned_bytes = match bytes {
         ValueTypes::Bytes(value) => Ok(concat_type_encoding(type_encoding, value)),
@@ -52,10 +194,129 @@ pub(crate) fn encode_key<'a>(key: &'a Bound<PyAny>, raw_mode: bool) -> PyResult<
             if value { &[1u8] } else { &[0u8] },
         )),
         ValueTypes::Any(_) => Err(PyException::new_err(
-            "Only support `string`, `int`, `float`, `bool`, and `bytes` as keys",
+            "Only support `string`, `int`, `float`, `bool`, `bytes`, `datetime`, `UUID` \
+             and tuples of them as keys",
         )),
     }?;
-    Ok(Cow::Owned(owned_bytes))
+    Ok(EncodedBytes::Owned(owned_bytes))
+}
+
+/// Encode a key, converting it to bytes with `key_dumps` first if it is set.
+///
+/// The bytes returned by `key_dumps` are stored as a `bytes` key, so the keys
+/// are ordered by these bytes. `key_dumps` is ignored in raw mode.
+#[inline(always)]
+pub(crate) fn encode_key_with<'a>(
+    key: &'a Bound<PyAny>,
+    key_dumps: Option<&PyObject>,
+    raw_mode: bool,
+) -> PyResult<EncodedBytes<'a>> {
+    match key_dumps {
+        Some(key_dumps) if !raw_mode => {
+            let py = key.py();
+            let dumped = key_dumps.call1(py, (key,))?;
+            let bytes = dumped
+                .downcast_bound::<PyBytes>(py)
+                .map_err(|_| PyException::new_err("key_dumps must return bytes"))?;
+            let type_encoding = encoding_byte(&ValueTypes::Bytes(&[]));
+            Ok(EncodedBytes::Owned(concat_type_encoding(
+                type_encoding,
+                bytes.as_bytes(),
+            )))
+        }
+        _ => encode_key(key, raw_mode),
+    }
+}
+
+/// Functions transforming the encoded values of a column family, applied
+/// after `dumps` when writing and before `loads` when reading,
+/// see `Rdict.set_value_transform()`.
+#[derive(Clone)]
+pub(crate) struct ValueTransform {
+    pub(crate) encode: PyObject,
+    pub(crate) decode: PyObject,
+}
+
+impl ValueTransform {
+    pub(crate) fn encode<'py>(
+        &self,
+        py: Python<'py>,
+        bytes: &[u8],
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        Self::call(&self.encode, py, bytes)
+    }
+
+    pub(crate) fn decode<'py>(
+        &self,
+        py: Python<'py>,
+        bytes: &[u8],
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        Self::call(&self.decode, py, bytes)
+    }
+
+    fn call<'py>(f: &PyObject, py: Python<'py>, bytes: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+        f.call1(py, (PyBytes::new_bound(py, bytes),))?
+            .into_bound(py)
+            .downcast_into::<PyBytes>()
+            .map_err(|_| PyTypeError::new_err("value transform functions must return bytes"))
+    }
+}
+
+/// Encode a value, then transform it with `transform` if it is set.
+#[inline(always)]
+pub(crate) fn encode_value_with<'a>(
+    value: &'a Bound<PyAny>,
+    dumps: &PyObject,
+    transform: Option<&ValueTransform>,
+    raw_mode: bool,
+) -> PyResult<EncodedBytes<'a>> {
+    // the transform may be reversed by other readers, which cannot decode `encode_ndarray`
+    let encoded = encode_value_impl(value, dumps, raw_mode, transform.is_none())?;
+    match transform {
+        None => Ok(encoded),
+        Some(transform) => {
+            let transformed = transform.encode(value.py(), &encoded)?;
+            Ok(EncodedBytes::Owned(transformed.as_bytes().to_vec()))
+        }
+    }
+}
+
+/// Transform a value back with `transform` if it is set, then decode it.
+#[inline(always)]
+pub(crate) fn decode_value_with(
+    py: Python,
+    bytes: &[u8],
+    loads: &PyObject,
+    transform: Option<&ValueTransform>,
+    raw_mode: bool,
+) -> PyResult<PyObject> {
+    match transform {
+        None => decode_value(py, bytes, loads, raw_mode),
+        Some(transform) => {
+            let decoded = transform.decode(py, bytes)?;
+            decode_value(py, decoded.as_bytes(), loads, raw_mode)
+        }
+    }
+}
+
+/// Decode a key, converting it back with `key_loads` if it is set.
+///
+/// `key_loads` is ignored in raw mode.
+#[inline(always)]
+pub(crate) fn decode_key_with(
+    py: Python,
+    bytes: &[u8],
+    loads: &PyObject,
+    key_loads: Option<&PyObject>,
+    raw_mode: bool,
+) -> PyResult<PyObject> {
+    match key_loads {
+        Some(key_loads) if !raw_mode => match bytes.first() {
+            Some(1) => key_loads.call1(py, (PyBytes::new_bound(py, &bytes[1..]),)),
+            _ => Err(PyException::new_err("key was not encoded by key_dumps")),
+        },
+        _ => decode_value(py, bytes, loads, raw_mode),
+    }
 }
 
 ///
@@ -68,12 +329,22 @@ pub(crate) fn encode_value<'a>(
     value: &'a Bound<PyAny>,
     dumps: &PyObject,
     raw_mode: bool,
-) -> PyResult<Cow<'a, [u8]>> {
+) -> PyResult<EncodedBytes<'a>> {
+    encode_value_impl(value, dumps, raw_mode, true)
+}
+
+/// Encode a value like `encode_value`, `numpy.ndarray` values are only
+/// stored without pickle if `encode_ndarrays` is true and `dumps` is `pickle.dumps`.
+fn encode_value_impl<'a>(
+    value: &'a Bound<PyAny>,
+    dumps: &PyObject,
+    raw_mode: bool,
+    encode_ndarrays: bool,
+) -> PyResult<EncodedBytes<'a>> {
     if raw_mode {
-        if let Ok(value) = value.downcast::<PyBytes>() {
-            Ok(Cow::Borrowed(value.as_bytes()))
-        } else {
-            Err(PyValueError::new_err("raw mode only support bytes"))
+        match encode_raw(value)? {
+            Some(bytes) => Ok(bytes),
+            None => Err(PyValueError::new_err("raw mode only support bytes")),
         }
     } else {
         let bytes = py_to_value_types(value)?;
@@ -92,12 +363,18 @@ pub(crate) fn encode_value<'a>(
             }
             ValueTypes::Any(value) => {
                 let py = value.py();
+                // the values written by other serializers must be readable by them
+                if encode_ndarrays && dumps.is(&pickle_function(py, "dumps")?) {
+                    if let Some(ndarray_bytes) = encode_ndarray(value)? {
+                        return Ok(EncodedBytes::Owned(ndarray_bytes));
+                    }
+                }
                 let pickle_bytes = dumps.call1(py, (value,))?;
                 let bytes: &[u8] = pickle_bytes.downcast_bound::<PyBytes>(py)?.as_bytes();
                 concat_type_encoding(type_encoding, bytes)
             }
         };
-        Ok(Cow::Owned(owned_bytes))
+        Ok(EncodedBytes::Owned(owned_bytes))
     }
 }
 
@@ -155,11 +432,316 @@ pub(crate) fn decode_value(
             }
             5 => Ok(PyBool::new_bound(py, bytes[1] != 0).to_object(py)),
             6 => loads.call1(py, (PyBytes::new_bound(py, &bytes[1..]),)),
+            NDARRAY_ENCODING => decode_ndarray(py, &bytes[1..]),
+            DATETIME_ENCODING => decode_datetime(py, &bytes[1..]),
+            UUID_ENCODING => decode_uuid(py, &bytes[1..]),
+            TUPLE_ENCODING => decode_tuple(py, &bytes[1..], loads),
             _ => Err(PyException::new_err("Unknown value type")),
         },
     }
 }
 
+/// Type byte of `numpy.ndarray` values.
+const NDARRAY_ENCODING: u8 = 7;
+
+/// Returns the imported `numpy` module, or `None` if numpy was never imported.
+///
+/// A value cannot be an `ndarray` if numpy is not imported,
+/// so this avoids importing numpy (or requiring it to be installed).
+fn imported_numpy<'py>(py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    imported_module(py, "numpy")
+}
+
+/// Returns the module `name` if it was imported, without importing it.
+fn imported_module<'py>(py: Python<'py>, name: &str) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let modules = PyModule::import_bound(py, "sys")?.getattr("modules")?;
+    let modules = modules.downcast::<PyDict>()?;
+    modules.get_item(name)
+}
+
+/// Type byte of `datetime.datetime` keys.
+const DATETIME_ENCODING: u8 = 8;
+
+/// Type byte of `uuid.UUID` keys.
+const UUID_ENCODING: u8 = 9;
+
+/// Encode a `datetime.datetime` or `uuid.UUID` key, `None` for other types.
+///
+/// - datetimes: big endian microseconds since the epoch, with the sign bit
+///   flipped, so that the keys are in chronological order. Naive datetimes
+///   are rejected, since they do not refer to a single point in time.
+/// - UUIDs: the 16 bytes of the UUID, ordered like the UUIDs.
+fn encode_datetime_or_uuid(value: &Bound<PyAny>) -> PyResult<Option<Vec<u8>>> {
+    let py = value.py();
+    if let Some(datetime) = imported_module(py, "datetime")? {
+        if value.is_instance(&datetime.getattr("datetime")?)? {
+            if value.call_method0("utcoffset")?.is_none() {
+                return Err(PyValueError::new_err(
+                    "naive datetime keys are not supported, \
+                     set a timezone (e.g. `datetime.timezone.utc`)",
+                ));
+            }
+            let delta = value.call_method1("__sub__", (unix_epoch(py)?,))?;
+            let days: i64 = delta.getattr("days")?.extract()?;
+            let seconds: i64 = delta.getattr("seconds")?.extract()?;
+            let micros: i64 = delta.getattr("microseconds")?.extract()?;
+            let micros = (days * 86_400 + seconds) * 1_000_000 + micros;
+            let mut output = vec![DATETIME_ENCODING];
+            output.extend_from_slice(&((micros as u64) ^ (1 << 63)).to_be_bytes());
+            return Ok(Some(output));
+        }
+    }
+    if let Some(uuid) = imported_module(py, "uuid")? {
+        if value.is_instance(&uuid.getattr("UUID")?)? {
+            let bytes = value.getattr("bytes")?;
+            let mut output = vec![UUID_ENCODING];
+            output.extend_from_slice(bytes.downcast::<PyBytes>()?.as_bytes());
+            return Ok(Some(output));
+        }
+    }
+    Ok(None)
+}
+
+/// `datetime(1970, 1, 1, tzinfo=timezone.utc)`
+fn unix_epoch(py: Python) -> PyResult<Bound<PyAny>> {
+    let datetime = PyModule::import_bound(py, "datetime")?;
+    let utc = datetime.getattr("timezone")?.getattr("utc")?;
+    datetime
+        .getattr("datetime")?
+        .call1((1970, 1, 1, 0, 0, 0, 0, utc))
+}
+
+/// Decode a datetime key as a UTC datetime.
+fn decode_datetime(py: Python, bytes: &[u8]) -> PyResult<PyObject> {
+    let bytes = bytes
+        .try_into()
+        .map_err(|_| PyException::new_err("datetime keys must be 8 bytes"))?;
+    let micros = (u64::from_be_bytes(bytes) ^ (1 << 63)) as i64;
+    let datetime = PyModule::import_bound(py, "datetime")?;
+    let kwargs = PyDict::new_bound(py);
+    kwargs.set_item("microseconds", micros)?;
+    let delta = datetime.getattr("timedelta")?.call((), Some(&kwargs))?;
+    Ok(unix_epoch(py)?.call_method1("__add__", (delta,))?.unbind())
+}
+
+fn decode_uuid(py: Python, bytes: &[u8]) -> PyResult<PyObject> {
+    let kwargs = PyDict::new_bound(py);
+    kwargs.set_item("bytes", PyBytes::new_bound(py, bytes))?;
+    let uuid = PyModule::import_bound(py, "uuid")?.getattr("UUID")?;
+    Ok(uuid.call((), Some(&kwargs))?.unbind())
+}
+
+/// Type byte of tuple keys.
+const TUPLE_ENCODING: u8 = 10;
+
+/// Encode a tuple key so that the keys are ordered like the tuples:
+/// element by element, a tuple sorting before the longer tuples it starts.
+///
+/// Each element is encoded as a key (ints and floats with an order preserving
+/// encoding), then escaped (`0x00` becomes `0x00 0xFF`) and terminated by
+/// `0x00 0x01`. The encoded tuple is thus a byte prefix of the encoded tuples
+/// starting with the same elements, e.g. `(tenant,)` of `(tenant, id)`.
+fn encode_tuple_key(tuple: &Bound<PyTuple>) -> PyResult<Vec<u8>> {
+    let mut output = vec![TUPLE_ENCODING];
+    for element in tuple.iter() {
+        for byte in encode_tuple_element(&element)? {
+            output.push(byte);
+            if byte == 0 {
+                output.push(0xFF);
+            }
+        }
+        output.extend_from_slice(&[0, 1]);
+    }
+    Ok(output)
+}
+
+fn encode_tuple_element(element: &Bound<PyAny>) -> PyResult<Vec<u8>> {
+    let element = numpy_scalar_item(element)?.unwrap_or_else(|| element.clone());
+    match py_to_value_types(&element)? {
+        ValueTypes::Int(value) => Ok(encode_ordered_int(&value)),
+        ValueTypes::Float(value) => Ok(concat_type_encoding(
+            4,
+            &ordered_float_bits(value).to_be_bytes(),
+        )),
+        _ => Ok(encode_key(&element, false)?.into_owned()),
+    }
+}
+
+/// Decode a tuple key encoded by `encode_tuple_key` (without the type byte).
+fn decode_tuple(py: Python, bytes: &[u8], loads: &PyObject) -> PyResult<PyObject> {
+    let corrupted = || PyException::new_err("corrupted tuple key encoding");
+    let mut elements = Vec::new();
+    let mut element = Vec::new();
+    let mut bytes = bytes.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte != 0 {
+            element.push(byte);
+            continue;
+        }
+        match bytes.next() {
+            Some(0xFF) => element.push(0),
+            Some(1) => {
+                elements.push(decode_tuple_element(py, &element, loads)?);
+                element.clear();
+            }
+            _ => return Err(corrupted()),
+        }
+    }
+    if !element.is_empty() {
+        return Err(corrupted());
+    }
+    Ok(PyTuple::new_bound(py, elements).to_object(py))
+}
+
+fn decode_tuple_element(py: Python, bytes: &[u8], loads: &PyObject) -> PyResult<PyObject> {
+    match bytes.split_first() {
+        Some((3, int_bytes)) => Ok(decode_ordered_int(int_bytes)?.to_object(py)),
+        Some((4, float_bytes)) => {
+            let bits = float_bytes
+                .try_into()
+                .map_err(|_| PyException::new_err("float keys must be 8 bytes"))?;
+            Ok(float_from_ordered_bits(u64::from_be_bytes(bits)).into_py(py))
+        }
+        _ => decode_value(py, bytes, loads, false),
+    }
+}
+
+/// Encode an int of any size so that the encodings are in numeric order:
+///
+/// - non negative ints: `[3][1][length: u32 big-endian][big-endian magnitude]`
+/// - negative ints: the same with all the bytes after `[3][0]` inverted,
+///   so that larger magnitudes sort first.
+fn encode_ordered_int(value: &BigInt) -> Vec<u8> {
+    let (sign, magnitude) = value.to_bytes_be();
+    let length = (magnitude.len() as u32).to_be_bytes();
+    let mut output = Vec::with_capacity(6 + magnitude.len());
+    output.push(3);
+    if sign == Sign::Minus {
+        output.push(0);
+        output.extend(length.iter().chain(magnitude.iter()).map(|byte| !byte));
+    } else {
+        output.push(1);
+        output.extend_from_slice(&length);
+        output.extend_from_slice(&magnitude);
+    }
+    output
+}
+
+/// Decode an int encoded by `encode_ordered_int` (without the type byte).
+fn decode_ordered_int(bytes: &[u8]) -> PyResult<BigInt> {
+    let corrupted = || PyException::new_err("corrupted int key encoding");
+    let (&sign, rest) = bytes.split_first().ok_or_else(corrupted)?;
+    if rest.len() < 4 {
+        return Err(corrupted());
+    }
+    let mut rest = rest.to_vec();
+    if sign == 0 {
+        rest.iter_mut().for_each(|byte| *byte = !*byte);
+    }
+    let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+    let magnitude = &rest[4..];
+    if magnitude.len() != length {
+        return Err(corrupted());
+    }
+    let value = BigInt::from_bytes_be(Sign::Plus, magnitude);
+    Ok(if sign == 0 { -value } else { value })
+}
+
+/// Convert a numpy integer, floating or bool scalar to the equivalent python object.
+///
+/// Returns `None` if the value is not such a scalar.
+fn numpy_scalar_item<'py>(value: &Bound<'py, PyAny>) -> PyResult<Option<Bound<'py, PyAny>>> {
+    let numpy = match imported_numpy(value.py())? {
+        None => return Ok(None),
+        Some(numpy) => numpy,
+    };
+    for scalar_type in ["integer", "floating", "bool_"] {
+        if value.is_instance(&numpy.getattr(scalar_type)?)? {
+            return Ok(Some(value.call_method0("item")?));
+        }
+    }
+    Ok(None)
+}
+
+/// Encode a `numpy.ndarray` without pickle:
+///
+/// `[7][dtype length: u8][dtype str][ndim: u8][shape: u64 big-endian * ndim][C-order data]`
+///
+/// Returns `None` if the value is not an exact `ndarray`, or if its dtype
+/// cannot be represented by a dtype string (object and structured dtypes),
+/// in which case the value should be pickled.
+fn encode_ndarray(value: &Bound<PyAny>) -> PyResult<Option<Vec<u8>>> {
+    let py = value.py();
+    let numpy = match imported_numpy(py)? {
+        None => return Ok(None),
+        Some(numpy) => numpy,
+    };
+    if !value.get_type().is(&numpy.getattr("ndarray")?) {
+        return Ok(None);
+    }
+    let dtype = value.getattr("dtype")?;
+    if dtype.getattr("hasobject")?.is_truthy()? || !dtype.getattr("fields")?.is_none() {
+        return Ok(None);
+    }
+    let dtype_str: String = dtype.getattr("str")?.extract()?;
+    let shape: Vec<u64> = value.getattr("shape")?.extract()?;
+    if dtype_str.len() > u8::MAX as usize || shape.len() > u8::MAX as usize {
+        return Ok(None);
+    }
+    // tobytes() always returns C-order data, copying non-contiguous arrays
+    let data = value.call_method0("tobytes")?;
+    let data = data.downcast::<PyBytes>()?.as_bytes();
+    let mut output = Vec::with_capacity(3 + dtype_str.len() + 8 * shape.len() + data.len());
+    output.push(NDARRAY_ENCODING);
+    output.push(dtype_str.len() as u8);
+    output.extend_from_slice(dtype_str.as_bytes());
+    output.push(shape.len() as u8);
+    for dim in shape.iter() {
+        output.extend_from_slice(&dim.to_be_bytes());
+    }
+    output.extend_from_slice(data);
+    Ok(Some(output))
+}
+
+/// Decode a `numpy.ndarray` encoded by `encode_ndarray` (without the type byte).
+///
+/// The array is backed by a writable `bytearray` holding a copy of the data.
+fn decode_ndarray(py: Python, bytes: &[u8]) -> PyResult<PyObject> {
+    let corrupted = || PyException::new_err("corrupted numpy array encoding");
+    let numpy = PyModule::import_bound(py, "numpy")?;
+    let dtype_len = *bytes.first().ok_or_else(corrupted)? as usize;
+    let dtype_str = bytes.get(1..1 + dtype_len).ok_or_else(corrupted)?;
+    let dtype_str = std::str::from_utf8(dtype_str).map_err(|_| corrupted())?;
+    let mut offset = 1 + dtype_len;
+    let ndim = *bytes.get(offset).ok_or_else(corrupted)? as usize;
+    offset += 1;
+    let mut shape = Vec::with_capacity(ndim);
+    for _ in 0..ndim {
+        let dim = bytes.get(offset..offset + 8).ok_or_else(corrupted)?;
+        shape.push(u64::from_be_bytes(dim.try_into().unwrap()));
+        offset += 8;
+    }
+    let itemsize: u64 = numpy
+        .call_method1("dtype", (dtype_str,))?
+        .getattr("itemsize")?
+        .extract()?;
+    let data_len = shape
+        .iter()
+        .try_fold(itemsize, |len, dim| len.checked_mul(*dim))
+        .ok_or_else(corrupted)?;
+    let data = &bytes[offset..];
+    if data.len() as u64 != data_len {
+        return Err(corrupted());
+    }
+    let shape = PyTuple::new_bound(py, shape);
+    if data.is_empty() {
+        return Ok(numpy.call_method1("zeros", (shape, dtype_str))?.unbind());
+    }
+    let buffer = PyByteArray::new_bound(py, data);
+    let array = numpy.call_method1("frombuffer", (buffer, dtype_str))?;
+    Ok(array.call_method1("reshape", (shape,))?.unbind())
+}
+
 #[inline(always)]
 fn concat_type_encoding(encoding: u8, payload: &[u8]) -> Vec<u8> {
     let mut output = Vec::with_capacity(payload.len() + 1);
@@ -167,3 +749,245 @@ fn concat_type_encoding(encoding: u8, payload: &[u8]) -> Vec<u8> {
     output.extend_from_slice(payload);
     output
 }
+
+/// Encode an integer as fixed-width big-endian bytes that sort in numeric order.
+///
+/// This is meant for `raw_mode`, where keys are compared as bytes: unlike
+/// `int.to_bytes()`, the encoded keys of negative integers sort before
+/// positive ones (the sign bit is flipped when `signed=True`).
+/// The result can be used with `Rdict`, `WriteBatch` and `SstFileWriter`.
+///
+/// Examples:
+///     ::
+///
+///         from rocksdict import Rdict, Options, encode_int_key, decode_int_key
+///
+///         db = Rdict("./tmp", Options(raw_mode=True))
+///         for i in range(-10, 10):
+///             db[encode_int_key(i)] = b""
+///         assert [decode_int_key(k) for k in db.keys()] == list(range(-10, 10))
+///
+/// Args:
+///     value: the integer to encode.
+///     width: number of bytes of the encoding.
+///     signed: whether negative integers are allowed.
+///
+/// Raises:
+///     OverflowError: if the integer does not fit in `width` bytes.
+#[pyfunction]
+#[pyo3(signature = (value, width = 8, signed = true))]
+pub(crate) fn encode_int_key(
+    py: Python,
+    value: BigInt,
+    width: usize,
+    signed: bool,
+) -> PyResult<PyObject> {
+    if width == 0 {
+        return Err(PyValueError::new_err("width must be positive"));
+    }
+    let biased = if signed {
+        value + (BigInt::from(1) << (8 * width - 1))
+    } else {
+        value
+    };
+    let (sign, magnitude) = biased.to_bytes_be();
+    if sign == Sign::Minus || magnitude.len() > width {
+        return Err(PyOverflowError::new_err(format!(
+            "int too big to encode in {width} bytes (signed={signed})"
+        )));
+    }
+    let mut output = vec![0u8; width];
+    output[width - magnitude.len()..].copy_from_slice(&magnitude);
+    Ok(PyBytes::new_bound(py, &output).to_object(py))
+}
+
+/// Decode an integer encoded by `encode_int_key`.
+///
+/// Args:
+///     data: the encoded bytes.
+///     signed: must be the same as the `signed` argument used for encoding.
+#[pyfunction]
+#[pyo3(signature = (data, signed = true))]
+pub(crate) fn decode_int_key(data: &[u8], signed: bool) -> PyResult<BigInt> {
+    if data.is_empty() {
+        return Err(PyValueError::new_err("cannot decode empty bytes"));
+    }
+    let unsigned = BigInt::from_bytes_be(Sign::Plus, data);
+    if signed {
+        Ok(unsigned - (BigInt::from(1) << (8 * data.len() - 1)))
+    } else {
+        Ok(unsigned)
+    }
+}
+
+/// Encode a float as 8 bytes that sort in numeric order.
+///
+/// Uses the IEEE-754 total order: negative numbers sort before positive ones,
+/// `-0.0` sorts just before `0.0`, and NaNs sort at the extremes.
+#[pyfunction]
+pub(crate) fn encode_float_key(py: Python, value: f64) -> PyObject {
+    PyBytes::new_bound(py, &ordered_float_bits(value).to_be_bytes()).to_object(py)
+}
+
+/// Decode a float encoded by `encode_float_key`.
+#[pyfunction]
+pub(crate) fn decode_float_key(data: &[u8]) -> PyResult<f64> {
+    let ordered = u64::from_be_bytes(
+        data.try_into()
+            .map_err(|_| PyValueError::new_err("float keys must be 8 bytes"))?,
+    );
+    Ok(float_from_ordered_bits(ordered))
+}
+
+/// The bits of a float, in the IEEE-754 total order when compared as unsigned ints.
+fn ordered_float_bits(value: f64) -> u64 {
+    let bits = value.to_bits();
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | (1 << 63)
+    }
+}
+
+fn float_from_ordered_bits(ordered: u64) -> f64 {
+    let bits = if ordered >> 63 == 1 {
+        ordered & !(1 << 63)
+    } else {
+        !ordered
+    };
+    f64::from_bits(bits)
+}
+
+fn pickle_function(py: Python, name: &str) -> PyResult<PyObject> {
+    Ok(PyModule::import_bound(py, "pickle")?
+        .getattr(name)?
+        .to_object(py))
+}
+
+/// Name saved in the rocksdict config for a `(dumps, loads)` serializer.
+pub(crate) const CUSTOM_SERIALIZER: &str = "custom";
+
+/// Resolve a serializer, `"pickle"`, `"msgpack"`, `"json"` or a `(dumps, loads)`
+/// tuple, into its name and its `(dumps, loads)` functions.
+pub(crate) fn resolve_serializer(
+    py: Python,
+    serializer: &Bound<PyAny>,
+) -> PyResult<(String, PyObject, PyObject)> {
+    if let Ok(functions) = serializer.downcast::<PyTuple>() {
+        let (dumps, loads): (PyObject, PyObject) = functions.extract()?;
+        return Ok((CUSTOM_SERIALIZER.to_string(), dumps, loads));
+    }
+    let name: String = serializer
+        .extract()
+        .map_err(|_| PyTypeError::new_err("serializer must be a name or a (dumps, loads) tuple"))?;
+    let (dumps, loads) = named_serializer(py, &name)?;
+    Ok((name, dumps, loads))
+}
+
+/// `(dumps, loads)` functions of the serializer `"pickle"`, `"msgpack"` or `"json"`.
+pub(crate) fn named_serializer(py: Python, name: &str) -> PyResult<(PyObject, PyObject)> {
+    match name {
+        "pickle" => Ok((pickle_function(py, "dumps")?, pickle_function(py, "loads")?)),
+        "msgpack" => {
+            let msgpack = PyModule::import_bound(py, "msgpack")?;
+            Ok((
+                msgpack.getattr("packb")?.unbind(),
+                msgpack.getattr("unpackb")?.unbind(),
+            ))
+        }
+        "json" => {
+            let json = PyModule::import_bound(py, "json")?;
+            let json_dumps = json.getattr("dumps")?.unbind();
+            // `json.dumps` returns `str`, `json.loads` also accepts utf-8 `bytes`
+            let dumps = PyCFunction::new_closure_bound(py, None, None, move |args, kwargs| {
+                let string = json_dumps.bind(args.py()).call(args.clone(), kwargs)?;
+                Ok::<_, PyErr>(string.call_method1("encode", ("utf-8",))?.unbind())
+            })?;
+            Ok((dumps.into_any().unbind(), json.getattr("loads")?.unbind()))
+        }
+        _ => Err(PyValueError::new_err(format!(
+            "unknown serializer `{name}`, must be `pickle`, `msgpack`, `json` \
+             or a (dumps, loads) tuple"
+        ))),
+    }
+}
+
+/// Encode a key into the exact bytes stored by `Rdict`.
+///
+/// This is useful to inspect the ordering of keys, for example when the
+/// keys written to an `SstFileWriter` are reported as out of order.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import encode_key, decode_key
+///
+///         assert encode_key("a") == b"\x02a"
+///         assert encode_key(1) == b"\x03\x01"
+///         assert decode_key(encode_key(1.5)) == 1.5
+///
+/// Args:
+///     key: `int, float, bool, str, bytes, datetime` or `UUID` key, a numpy scalar,
+///         or a tuple of them.
+///     raw_mode: must be the same as the `raw_mode` of the Rdict.
+#[pyfunction(name = "encode_key")]
+#[pyo3(signature = (key, raw_mode = false))]
+pub(crate) fn py_encode_key(py: Python, key: &Bound<PyAny>, raw_mode: bool) -> PyResult<PyObject> {
+    let bytes = encode_key(key, raw_mode)?;
+    Ok(PyBytes::new_bound(py, &bytes).to_object(py))
+}
+
+/// Decode a key encoded by `encode_key`.
+///
+/// Args:
+///     data: the encoded bytes.
+///     raw_mode: must be the same as the `raw_mode` used for encoding.
+#[pyfunction(name = "decode_key")]
+#[pyo3(signature = (data, raw_mode = false))]
+pub(crate) fn py_decode_key(py: Python, data: &[u8], raw_mode: bool) -> PyResult<PyObject> {
+    // keys are never pickled, `loads` is only passed for completeness
+    decode_value(py, data, &pickle_function(py, "loads")?, raw_mode)
+}
+
+/// Encode a value into the exact bytes stored by `Rdict`.
+///
+/// Args:
+///     value: any value.
+///     raw_mode: must be the same as the `raw_mode` of the Rdict.
+///     dumps: the function set by `Rdict.set_dumps()`, `pickle.dumps` if None.
+#[pyfunction(name = "encode_value")]
+#[pyo3(signature = (value, raw_mode = false, dumps = None))]
+pub(crate) fn py_encode_value(
+    py: Python,
+    value: &Bound<PyAny>,
+    raw_mode: bool,
+    dumps: Option<PyObject>,
+) -> PyResult<PyObject> {
+    let dumps = match dumps {
+        None => pickle_function(py, "dumps")?,
+        Some(dumps) => dumps,
+    };
+    let bytes = encode_value(value, &dumps, raw_mode)?;
+    Ok(PyBytes::new_bound(py, &bytes).to_object(py))
+}
+
+/// Decode a value encoded by `encode_value`.
+///
+/// Args:
+///     data: the encoded bytes.
+///     raw_mode: must be the same as the `raw_mode` used for encoding.
+///     loads: the function set by `Rdict.set_loads()`, `pickle.loads` if None.
+#[pyfunction(name = "decode_value")]
+#[pyo3(signature = (data, raw_mode = false, loads = None))]
+pub(crate) fn py_decode_value(
+    py: Python,
+    data: &[u8],
+    raw_mode: bool,
+    loads: Option<PyObject>,
+) -> PyResult<PyObject> {
+    let loads = match loads {
+        None => pickle_function(py, "loads")?,
+        Some(loads) => loads,
+    };
+    decode_value(py, data, &loads, raw_mode)
+}
@@ -0,0 +1,365 @@
+use crate::util::{error_message, run_with_gil_later};
+use core::slice;
+use libc::{c_char, c_void, size_t};
+use librocksdb_sys::{
+    rocksdb_compactionjobinfo_t, rocksdb_externalfileingestioninfo_t, rocksdb_flushjobinfo_t,
+    rocksdb_memtableinfo_t, rocksdb_status_ptr_t, rocksdb_subcompactionjobinfo_t, rocksdb_t,
+    rocksdb_writestallinfo_t,
+};
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ptr::null_mut;
+use std::sync::{Arc, Mutex};
+
+/// methods of the python listener that can be called
+const LISTENER_METHODS: [&str; 5] = [
+    "on_flush_begin",
+    "on_flush_completed",
+    "on_compaction_begin",
+    "on_compaction_completed",
+    "on_background_error",
+];
+
+struct ListenerState {
+    /// shared with the pending notifications, cloned without the GIL
+    listener: Arc<PyObject>,
+    /// `LISTENER_METHODS` implemented by the listener
+    methods: Vec<&'static str>,
+    /// the status of the last background error is saved here, if set
+    last_background_error: Option<LastBackgroundError>,
+}
+
+/// The status of the last background error of a db, see `Rdict.background_error`.
+pub(crate) type LastBackgroundError = Arc<Mutex<Option<String>>>;
+
+/// Owned copy of `FlushJobInfo`, which is only valid during the callback.
+struct FlushJobInfo {
+    cf_name: String,
+    file_path: String,
+    smallest_seqno: u64,
+    largest_seqno: u64,
+    triggered_writes_slowdown: bool,
+    triggered_writes_stop: bool,
+}
+
+/// Owned copy of `CompactionJobInfo`, which is only valid during the callback.
+struct CompactionJobInfo {
+    cf_name: String,
+    status: Option<String>,
+    input_files: Vec<String>,
+    output_files: Vec<String>,
+    base_input_level: i32,
+    output_level: i32,
+    elapsed_micros: u64,
+    num_corrupt_keys: u64,
+    input_records: u64,
+    output_records: u64,
+    total_input_bytes: u64,
+    total_output_bytes: u64,
+}
+
+/// A background error, with the name of the `BackgroundErrorReason`.
+struct BackgroundErrorInfo {
+    reason: &'static str,
+    status: Option<String>,
+}
+
+trait ToPyDict {
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>>;
+}
+
+unsafe fn to_string(ptr: *const c_char, len: size_t) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    String::from_utf8_lossy(slice::from_raw_parts(ptr as *const u8, len)).into_owned()
+}
+
+impl FlushJobInfo {
+    unsafe fn from_c(info: *const rocksdb_flushjobinfo_t) -> Self {
+        let mut cf_name_len: size_t = 0;
+        let cf_name = librocksdb_sys::rocksdb_flushjobinfo_cf_name(info, &mut cf_name_len);
+        let mut file_path_len: size_t = 0;
+        let file_path = librocksdb_sys::rocksdb_flushjobinfo_file_path(info, &mut file_path_len);
+        let slowdown = librocksdb_sys::rocksdb_flushjobinfo_triggered_writes_slowdown(info);
+        let stop = librocksdb_sys::rocksdb_flushjobinfo_triggered_writes_stop(info);
+        FlushJobInfo {
+            cf_name: to_string(cf_name, cf_name_len),
+            file_path: to_string(file_path, file_path_len),
+            smallest_seqno: librocksdb_sys::rocksdb_flushjobinfo_smallest_seqno(info),
+            largest_seqno: librocksdb_sys::rocksdb_flushjobinfo_largest_seqno(info),
+            triggered_writes_slowdown: slowdown != 0,
+            triggered_writes_stop: stop != 0,
+        }
+    }
+}
+
+impl ToPyDict for FlushJobInfo {
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let result = PyDict::new_bound(py);
+        result.set_item("cf_name", &self.cf_name)?;
+        result.set_item("file_path", &self.file_path)?;
+        result.set_item("smallest_seqno", self.smallest_seqno)?;
+        result.set_item("largest_seqno", self.largest_seqno)?;
+        result.set_item("triggered_writes_slowdown", self.triggered_writes_slowdown)?;
+        result.set_item("triggered_writes_stop", self.triggered_writes_stop)?;
+        Ok(result)
+    }
+}
+
+impl CompactionJobInfo {
+    unsafe fn from_c(info: *const rocksdb_compactionjobinfo_t) -> Self {
+        let mut cf_name_len: size_t = 0;
+        let cf_name = librocksdb_sys::rocksdb_compactionjobinfo_cf_name(info, &mut cf_name_len);
+        let mut err: *mut c_char = null_mut();
+        librocksdb_sys::rocksdb_compactionjobinfo_status(info, &mut err);
+        let status = if err.is_null() {
+            None
+        } else {
+            Some(error_message(err))
+        };
+        let input_files = (0..librocksdb_sys::rocksdb_compactionjobinfo_input_files_count(info))
+            .map(|i| {
+                let mut len: size_t = 0;
+                let file =
+                    librocksdb_sys::rocksdb_compactionjobinfo_input_file_at(info, i, &mut len);
+                to_string(file, len)
+            })
+            .collect();
+        let output_files = (0..librocksdb_sys::rocksdb_compactionjobinfo_output_files_count(info))
+            .map(|i| {
+                let mut len: size_t = 0;
+                let file =
+                    librocksdb_sys::rocksdb_compactionjobinfo_output_file_at(info, i, &mut len);
+                to_string(file, len)
+            })
+            .collect();
+        CompactionJobInfo {
+            cf_name: to_string(cf_name, cf_name_len),
+            status,
+            input_files,
+            output_files,
+            base_input_level: librocksdb_sys::rocksdb_compactionjobinfo_base_input_level(info),
+            output_level: librocksdb_sys::rocksdb_compactionjobinfo_output_level(info),
+            elapsed_micros: librocksdb_sys::rocksdb_compactionjobinfo_elapsed_micros(info),
+            num_corrupt_keys: librocksdb_sys::rocksdb_compactionjobinfo_num_corrupt_keys(info),
+            input_records: librocksdb_sys::rocksdb_compactionjobinfo_input_records(info),
+            output_records: librocksdb_sys::rocksdb_compactionjobinfo_output_records(info),
+            total_input_bytes: librocksdb_sys::rocksdb_compactionjobinfo_total_input_bytes(info),
+            total_output_bytes: librocksdb_sys::rocksdb_compactionjobinfo_total_output_bytes(info),
+        }
+    }
+}
+
+impl ToPyDict for CompactionJobInfo {
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let result = PyDict::new_bound(py);
+        result.set_item("cf_name", &self.cf_name)?;
+        result.set_item("status", &self.status)?;
+        result.set_item("input_files", &self.input_files)?;
+        result.set_item("output_files", &self.output_files)?;
+        result.set_item("base_input_level", self.base_input_level)?;
+        result.set_item("output_level", self.output_level)?;
+        result.set_item("elapsed_micros", self.elapsed_micros)?;
+        result.set_item("num_corrupt_keys", self.num_corrupt_keys)?;
+        result.set_item("input_records", self.input_records)?;
+        result.set_item("output_records", self.output_records)?;
+        result.set_item("total_input_bytes", self.total_input_bytes)?;
+        result.set_item("total_output_bytes", self.total_output_bytes)?;
+        Ok(result)
+    }
+}
+
+impl BackgroundErrorInfo {
+    unsafe fn from_c(reason: u32, status: *mut rocksdb_status_ptr_t) -> Self {
+        let mut err: *mut c_char = null_mut();
+        librocksdb_sys::rocksdb_status_ptr_get_error(status, &mut err);
+        let status = if err.is_null() {
+            None
+        } else {
+            Some(error_message(err))
+        };
+        BackgroundErrorInfo {
+            reason: match reason {
+                0 => "flush",
+                1 => "compaction",
+                2 => "write_callback",
+                3 => "memtable",
+                4 => "manifest_write",
+                5 => "flush_no_wal",
+                6 => "manifest_write_no_wal",
+                _ => "unknown",
+            },
+            status,
+        }
+    }
+}
+
+impl ToPyDict for BackgroundErrorInfo {
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let result = PyDict::new_bound(py);
+        result.set_item("reason", self.reason)?;
+        result.set_item("status", &self.status)?;
+        Ok(result)
+    }
+}
+
+/// Call `method(info)` of the python listener later, without waiting for the GIL.
+unsafe fn notify<T: ToPyDict + Send + 'static>(state: *mut c_void, method: &'static str, info: T) {
+    let state = &*(state as *const ListenerState);
+    if !state.methods.contains(&method) {
+        return;
+    }
+    let listener = state.listener.clone();
+    run_with_gil_later(Box::new(move |py| {
+        let listener = listener.bind(py);
+        let result = info
+            .to_py_dict(py)
+            .and_then(|info| listener.call_method1(method, (info,)));
+        if let Err(e) = result {
+            e.write_unraisable_bound(py, Some(listener));
+        }
+    }));
+}
+
+unsafe extern "C" fn destructor(state: *mut c_void) {
+    drop(Box::from_raw(state as *mut ListenerState));
+}
+
+unsafe extern "C" fn on_flush_begin(
+    state: *mut c_void,
+    _db: *mut rocksdb_t,
+    info: *const rocksdb_flushjobinfo_t,
+) {
+    notify(state, "on_flush_begin", FlushJobInfo::from_c(info))
+}
+
+unsafe extern "C" fn on_flush_completed(
+    state: *mut c_void,
+    _db: *mut rocksdb_t,
+    info: *const rocksdb_flushjobinfo_t,
+) {
+    notify(state, "on_flush_completed", FlushJobInfo::from_c(info))
+}
+
+unsafe extern "C" fn on_compaction_begin(
+    state: *mut c_void,
+    _db: *mut rocksdb_t,
+    info: *const rocksdb_compactionjobinfo_t,
+) {
+    notify(
+        state,
+        "on_compaction_begin",
+        CompactionJobInfo::from_c(info),
+    )
+}
+
+unsafe extern "C" fn on_compaction_completed(
+    state: *mut c_void,
+    _db: *mut rocksdb_t,
+    info: *const rocksdb_compactionjobinfo_t,
+) {
+    notify(
+        state,
+        "on_compaction_completed",
+        CompactionJobInfo::from_c(info),
+    )
+}
+
+unsafe extern "C" fn on_background_error(
+    state: *mut c_void,
+    reason: u32,
+    status: *mut rocksdb_status_ptr_t,
+) {
+    let info = BackgroundErrorInfo::from_c(reason, status);
+    // saved synchronously, before the failed operation returns its error
+    if let Some(last_error) = &(*(state as *const ListenerState)).last_background_error {
+        *last_error.lock().unwrap() = info.status.clone();
+    }
+    notify(state, "on_background_error", info)
+}
+
+// the C API calls every callback, the other events are ignored
+
+unsafe extern "C" fn on_subcompaction(
+    _state: *mut c_void,
+    _info: *const rocksdb_subcompactionjobinfo_t,
+) {
+}
+
+unsafe extern "C" fn on_external_file_ingested(
+    _state: *mut c_void,
+    _db: *mut rocksdb_t,
+    _info: *const rocksdb_externalfileingestioninfo_t,
+) {
+}
+
+unsafe extern "C" fn on_stall_conditions_changed(
+    _state: *mut c_void,
+    _info: *const rocksdb_writestallinfo_t,
+) {
+}
+
+unsafe extern "C" fn on_memtable_sealed(_state: *mut c_void, _info: *const rocksdb_memtableinfo_t) {
+}
+
+/// Create a rocksdb event listener calling the methods of `listener`.
+///
+/// The returned listener is owned by the options it is added to.
+pub(crate) fn create_event_listener(
+    listener: &Bound<PyAny>,
+) -> PyResult<*mut librocksdb_sys::rocksdb_eventlistener_t> {
+    let mut methods = Vec::new();
+    for method in LISTENER_METHODS {
+        if listener.hasattr(method)? {
+            methods.push(method);
+        }
+    }
+    if methods.is_empty() {
+        return Err(PyException::new_err(format!(
+            "event listener must implement at least one of: {}",
+            LISTENER_METHODS.join(", ")
+        )));
+    }
+    Ok(new_event_listener(ListenerState {
+        listener: Arc::new(listener.clone().unbind()),
+        methods,
+        last_background_error: None,
+    }))
+}
+
+/// Create a rocksdb event listener saving the status of the background errors
+/// in `last_error`, without calling python.
+///
+/// The returned listener is owned by the options it is added to.
+pub(crate) fn create_background_error_listener(
+    py: Python,
+    last_error: &LastBackgroundError,
+) -> *mut librocksdb_sys::rocksdb_eventlistener_t {
+    new_event_listener(ListenerState {
+        listener: Arc::new(py.None()),
+        methods: Vec::new(),
+        last_background_error: Some(last_error.clone()),
+    })
+}
+
+fn new_event_listener(state: ListenerState) -> *mut librocksdb_sys::rocksdb_eventlistener_t {
+    let state = Box::into_raw(Box::new(state));
+    unsafe {
+        librocksdb_sys::rocksdb_eventlistener_create(
+            state as *mut c_void,
+            Some(destructor),
+            Some(on_flush_begin),
+            Some(on_flush_completed),
+            Some(on_compaction_begin),
+            Some(on_compaction_completed),
+            Some(on_subcompaction),
+            Some(on_subcompaction),
+            Some(on_external_file_ingested),
+            Some(on_background_error),
+            Some(on_stall_conditions_changed),
+            Some(on_memtable_sealed),
+        )
+    }
+}
@@ -1,7 +1,10 @@
 use crate::db_reference::DbReferenceHolder;
-use crate::encoder::{decode_value, encode_key};
+use crate::encoder::{
+    decode_key_with, decode_value, decode_value_with, encode_key_with, ValueTransform,
+};
 use crate::exceptions::DbClosedError;
 use crate::util::error_message;
+use crate::write_batch_with_index::BatchIteratorRef;
 use crate::{ReadOpt, ReadOptionsPy};
 use core::slice;
 use libc::{c_char, c_uchar, size_t};
@@ -15,6 +18,9 @@ use std::sync::Arc;
 #[pyclass]
 #[allow(dead_code)]
 pub(crate) struct RdictIter {
+    /// column family of this iterator, used for estimating its length.
+    pub(crate) column_family: Option<Arc<UnboundColumnFamily>>,
+
     /// iterator must keep a reference count of DB to keep DB alive.
     pub(crate) db: DbReferenceHolder,
 
@@ -28,37 +34,68 @@ pub(crate) struct RdictIter {
     /// use pickle loads to convert bytes to pyobjects
     pub(crate) loads: PyObject,
 
+    /// key encoding hooks, see `Rdict.set_key_dumps()`
+    pub(crate) key_dumps: Option<PyObject>,
+    pub(crate) key_loads: Option<PyObject>,
+
+    /// see `Rdict.set_value_transform()`
+    pub(crate) value_transform: Option<ValueTransform>,
+
     pub(crate) raw_mode: bool,
+
+    /// keeps the WriteBatchWithIndex alive for iterators created by `iter_with_base()`,
+    /// dropped after the iterator is destroyed.
+    pub(crate) write_batch: Option<BatchIteratorRef>,
 }
 
 #[pyclass]
 pub(crate) struct RdictItems {
     inner: RdictIter,
     backwards: bool,
+    /// number of items left to yield, if limited
+    limit: Option<usize>,
+    /// the iteration does not cover the whole column family
+    bounded: bool,
 }
 
 #[pyclass]
 pub(crate) struct RdictKeys {
     inner: RdictIter,
     backwards: bool,
+    /// number of items left to yield, if limited
+    limit: Option<usize>,
+    /// the iteration does not cover the whole column family
+    bounded: bool,
 }
 
 #[pyclass]
 pub(crate) struct RdictValues {
     inner: RdictIter,
     backwards: bool,
+    /// number of items left to yield, if limited
+    limit: Option<usize>,
+    /// the iteration does not cover the whole column family
+    bounded: bool,
 }
 
 #[pyclass]
 pub(crate) struct RdictColumns {
     inner: RdictIter,
     backwards: bool,
+    /// number of items left to yield, if limited
+    limit: Option<usize>,
+    /// the iteration does not cover the whole column family
+    bounded: bool,
 }
 
 #[pyclass]
 pub(crate) struct RdictEntities {
     inner: RdictIter,
     backwards: bool,
+    /// number of items left to yield, if limited
+    limit: Option<usize>,
+    /// the iteration does not cover the whole column family
+    bounded: bool,
 }
 
 impl RdictIter {
@@ -67,31 +104,174 @@ impl RdictIter {
         cf: &Option<Arc<UnboundColumnFamily>>,
         readopts: ReadOptionsPy,
         pickle_loads: &PyObject,
+        key_dumps: Option<&PyObject>,
+        key_loads: Option<&PyObject>,
         raw_mode: bool,
         py: Python,
     ) -> PyResult<Self> {
-        let readopts = readopts.to_read_opt(raw_mode, py)?;
+        let readopts = readopts.to_read_opt(raw_mode, key_dumps, py)?;
+        Self::from_read_opt(
+            db,
+            cf,
+            readopts,
+            pickle_loads,
+            key_dumps,
+            key_loads,
+            raw_mode,
+        )
+    }
 
+    pub(crate) fn from_read_opt(
+        db: &DbReferenceHolder,
+        cf: &Option<Arc<UnboundColumnFamily>>,
+        readopts: ReadOpt,
+        pickle_loads: &PyObject,
+        key_dumps: Option<&PyObject>,
+        key_loads: Option<&PyObject>,
+        raw_mode: bool,
+    ) -> PyResult<Self> {
         let db_inner = db
             .get()
             .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?
             .inner();
 
         Ok(RdictIter {
+            column_family: cf.clone(),
             db: db.clone(),
             inner: unsafe {
                 match cf {
-                    None => librocksdb_sys::rocksdb_create_iterator(db_inner, readopts.0),
-                    Some(cf) => {
-                        librocksdb_sys::rocksdb_create_iterator_cf(db_inner, readopts.0, cf.inner())
-                    }
+                    None => librocksdb_sys::rocksdb_create_iterator(db_inner, readopts.inner),
+                    Some(cf) => librocksdb_sys::rocksdb_create_iterator_cf(
+                        db_inner,
+                        readopts.inner,
+                        cf.inner(),
+                    ),
                 }
             },
             readopts,
             loads: pickle_loads.clone(),
+            key_dumps: key_dumps.cloned(),
+            key_loads: key_loads.cloned(),
+            value_transform: None,
             raw_mode,
+            write_batch: None,
         })
     }
+
+    /// Transform the values back with `value_transform` before decoding them.
+    pub(crate) fn with_value_transform(mut self, value_transform: Option<ValueTransform>) -> Self {
+        self.value_transform = value_transform;
+        self
+    }
+
+    // The moves of the iterator may read from disk, the methods exposed
+    // to python release the GIL around these.
+
+    fn raw_seek_to_first(&mut self) {
+        unsafe {
+            librocksdb_sys::rocksdb_iter_seek_to_first(self.inner);
+        }
+    }
+
+    fn raw_seek_to_last(&mut self) {
+        unsafe {
+            librocksdb_sys::rocksdb_iter_seek_to_last(self.inner);
+        }
+    }
+
+    fn raw_seek(&mut self, key: &[u8]) {
+        unsafe {
+            librocksdb_sys::rocksdb_iter_seek(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+        }
+    }
+
+    fn raw_seek_for_prev(&mut self, key: &[u8]) {
+        unsafe {
+            librocksdb_sys::rocksdb_iter_seek_for_prev(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+            );
+        }
+    }
+
+    fn raw_next(&mut self) {
+        unsafe {
+            librocksdb_sys::rocksdb_iter_next(self.inner);
+        }
+    }
+
+    fn raw_prev(&mut self) {
+        unsafe {
+            librocksdb_sys::rocksdb_iter_prev(self.inner);
+        }
+    }
+
+    /// Count the remaining keys by advancing the iterator to the end.
+    ///
+    /// Does not need the GIL.
+    pub(crate) fn count_remaining(&mut self) -> PyResult<u64> {
+        let mut count = 0;
+        while self.valid() {
+            count += 1;
+            self.raw_next();
+        }
+        self.status()?;
+        Ok(count)
+    }
+
+    /// Copy up to `n` encoded `(key, value)` pairs and advance past them.
+    ///
+    /// Does not need the GIL.
+    pub(crate) fn next_raw_pairs(&mut self, n: usize) -> PyResult<Vec<(Box<[u8]>, Box<[u8]>)>> {
+        let mut pairs = Vec::with_capacity(n.min(1024));
+        while pairs.len() < n && self.valid() {
+            // Safety Note: the buffers are copied before the iterator moves.
+            unsafe {
+                let mut key_len: size_t = 0;
+                let key_ptr =
+                    librocksdb_sys::rocksdb_iter_key(self.inner, &mut key_len) as *const c_uchar;
+                let mut val_len: size_t = 0;
+                let val_ptr =
+                    librocksdb_sys::rocksdb_iter_value(self.inner, &mut val_len) as *const c_uchar;
+                pairs.push((
+                    slice::from_raw_parts(key_ptr, key_len).into(),
+                    slice::from_raw_parts(val_ptr, val_len).into(),
+                ));
+            }
+            self.raw_next();
+        }
+        self.status()?;
+        Ok(pairs)
+    }
+
+    /// Raises `DbClosedError` if the instance this iterator was created from is closed.
+    fn check_open(&self) -> PyResult<()> {
+        match self.db.get() {
+            None => Err(DbClosedError::new_err("DB instance already closed")),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Estimated number of keys in the column family of this iterator.
+    pub(crate) fn estimate_num_keys(&self) -> usize {
+        let db = match self.db.get() {
+            None => return 0,
+            Some(db) => db,
+        };
+        let estimate = match &self.column_family {
+            None => db.property_int_value("rocksdb.estimate-num-keys"),
+            Some(cf) => db.property_int_value_cf(cf, "rocksdb.estimate-num-keys"),
+        };
+        match estimate {
+            Ok(Some(n)) => n as usize,
+            _ => 0,
+        }
+    }
 }
 
 #[pymethods]
@@ -100,19 +280,24 @@ impl RdictIter {
     /// it reaches the end of its defined range, or when it encounters an error.
     ///
     /// To check whether the iterator encountered an error after `valid` has
-    /// returned `false`, use the [`status`](DBRawIteratorWithThreadMode::status) method. `status` will never
-    /// return an error when `valid` is `true`.
+    /// returned `false`, use the `status` method. `status` will never
+    /// raise an error when `valid` is `true`.
+    ///
+    /// Once the `Rdict` this iterator was created from is closed, `valid`
+    /// returns `false` and the other methods raise `DbClosedError`.
     #[inline]
     pub fn valid(&self) -> bool {
-        unsafe { librocksdb_sys::rocksdb_iter_valid(self.inner) != 0 }
+        self.db.get().is_some() && unsafe { librocksdb_sys::rocksdb_iter_valid(self.inner) != 0 }
     }
 
-    /// Returns an error `Result` if the iterator has encountered an error
-    /// during operation. When an error is encountered, the iterator is
-    /// invalidated and [`valid`](DBRawIteratorWithThreadMode::valid) will return `false` when called.
+    /// Raises an exception if the iterator has encountered an error
+    /// during operation, e.g. a checksum mismatch or an I/O error.
+    /// When an error is encountered, the iterator is invalidated
+    /// and `valid` will return `false` when called.
     ///
     /// Performing a seek will discard the current status.
     pub fn status(&self) -> PyResult<()> {
+        self.check_open()?;
         let mut err: *mut c_char = null_mut();
         unsafe {
             librocksdb_sys::rocksdb_iter_get_error(self.inner, &mut err);
@@ -124,6 +309,23 @@ impl RdictIter {
         }
     }
 
+    /// set custom key dumps function, used by `seek` and `seek_for_prev`
+    ///
+    /// Notes:
+    ///     Iterators created by `Rdict` inherit the key dumps function of
+    ///     the `Rdict`, which was also used to encode the iterate bounds.
+    pub fn set_key_dumps(&mut self, key_dumps: PyObject) {
+        self.key_dumps = Some(key_dumps)
+    }
+
+    /// set custom key loads function, used by `key`
+    ///
+    /// Notes:
+    ///     Iterators created by `Rdict` inherit the key loads function of the `Rdict`.
+    pub fn set_key_loads(&mut self, key_loads: PyObject) {
+        self.key_loads = Some(key_loads)
+    }
+
     /// Seeks to the first key in the database.
     ///
     /// Example:
@@ -148,10 +350,10 @@ impl RdictIter {
     ///
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
-    pub fn seek_to_first(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_seek_to_first(self.inner);
-        }
+    pub fn seek_to_first(&mut self, py: Python) -> PyResult<()> {
+        self.check_open()?;
+        py.allow_threads(|| self.raw_seek_to_first());
+        Ok(())
     }
 
     /// Seeks to the last key in the database.
@@ -178,10 +380,10 @@ impl RdictIter {
     ///
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
-    pub fn seek_to_last(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_seek_to_last(self.inner);
-        }
+    pub fn seek_to_last(&mut self, py: Python) -> PyResult<()> {
+        self.check_open()?;
+        py.allow_threads(|| self.raw_seek_to_last());
+        Ok(())
     }
 
     /// Seeks to the specified key or the first key that lexicographically follows it.
@@ -204,15 +406,10 @@ impl RdictIter {
     ///
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
-    pub fn seek(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
-        unsafe {
-            librocksdb_sys::rocksdb_iter_seek(
-                self.inner,
-                key.as_ptr() as *const c_char,
-                key.len() as size_t,
-            );
-        }
+    pub fn seek(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.check_open()?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        py.allow_threads(|| self.raw_seek(&key));
         Ok(())
     }
 
@@ -237,34 +434,30 @@ impl RdictIter {
     ///
     ///         del iter, db
     ///         Rdict.destroy(path, Options())
-    pub fn seek_for_prev(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
-        unsafe {
-            librocksdb_sys::rocksdb_iter_seek_for_prev(
-                self.inner,
-                key.as_ptr() as *const c_char,
-                key.len() as size_t,
-            );
-        }
+    pub fn seek_for_prev(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.check_open()?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        py.allow_threads(|| self.raw_seek_for_prev(&key));
         Ok(())
     }
 
     /// Seeks to the next key.
-    pub fn next(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_next(self.inner);
-        }
+    pub fn next(&mut self, py: Python) -> PyResult<()> {
+        self.check_open()?;
+        py.allow_threads(|| self.raw_next());
+        Ok(())
     }
 
     /// Seeks to the previous key.
-    pub fn prev(&mut self) {
-        unsafe {
-            librocksdb_sys::rocksdb_iter_prev(self.inner);
-        }
+    pub fn prev(&mut self, py: Python) -> PyResult<()> {
+        self.check_open()?;
+        py.allow_threads(|| self.raw_prev());
+        Ok(())
     }
 
     /// Returns the current key.
     pub fn key(&self, py: Python) -> PyResult<PyObject> {
+        self.check_open()?;
         if self.valid() {
             // Safety Note: This is safe as all methods that may invalidate the buffer returned
             // take `&mut self`, so borrow checker will prevent use of buffer after seek.
@@ -274,7 +467,7 @@ impl RdictIter {
                 let key_ptr =
                     librocksdb_sys::rocksdb_iter_key(self.inner, key_len_ptr) as *const c_uchar;
                 let key = slice::from_raw_parts(key_ptr, key_len);
-                Ok(decode_value(py, key, &self.loads, self.raw_mode)?)
+                decode_key_with(py, key, &self.loads, self.key_loads.as_ref(), self.raw_mode)
             }
         } else {
             Ok(py.None())
@@ -283,6 +476,7 @@ impl RdictIter {
 
     /// Returns the current value.
     pub fn value(&self, py: Python) -> PyResult<PyObject> {
+        self.check_open()?;
         if self.valid() {
             // Safety Note: This is safe as all methods that may invalidate the buffer returned
             // take `&mut self`, so borrow checker will prevent use of buffer after seek.
@@ -292,7 +486,13 @@ impl RdictIter {
                 let val_ptr =
                     librocksdb_sys::rocksdb_iter_value(self.inner, val_len_ptr) as *const c_uchar;
                 let value = slice::from_raw_parts(val_ptr, val_len);
-                Ok(decode_value(py, value, &self.loads, self.raw_mode)?)
+                decode_value_with(
+                    py,
+                    value,
+                    &self.loads,
+                    self.value_transform.as_ref(),
+                    self.raw_mode,
+                )
             }
         } else {
             Ok(py.None())
@@ -307,6 +507,7 @@ impl RdictIter {
     ///    with default column name (empty bytes/string).
     ///    None or default value if the key does not exist.
     pub fn columns(&self, py: Python) -> PyResult<PyObject> {
+        self.check_open()?;
         if self.valid() {
             let columns = unsafe {
                 rocksdb::WideColumns::from_c(librocksdb_sys::rocksdb_iter_columns(self.inner))
@@ -314,7 +515,13 @@ impl RdictIter {
             let result = PyList::empty_bound(py);
             for column in columns.iter() {
                 let name = decode_value(py, column.name, &self.loads, self.raw_mode)?;
-                let value = decode_value(py, column.value, &self.loads, self.raw_mode)?;
+                let value = decode_value_with(
+                    py,
+                    column.value,
+                    &self.loads,
+                    self.value_transform.as_ref(),
+                    self.raw_mode,
+                )?;
                 result.append(PyTuple::new_bound(py, [name, value]))?;
             }
             Ok(result.to_object(py))
@@ -342,42 +549,115 @@ macro_rules! impl_iter {
                 slf
             }
 
+            /// Estimated number of items, based on `rocksdb.estimate-num-keys`
+            /// if the iteration covers the whole column family, or else the
+            /// limit of the iteration, if any.
+            fn __length_hint__(&self, py: Python) -> PyObject {
+                if self.bounded {
+                    return match self.limit {
+                        Some(limit) => limit.into_py(py),
+                        None => py.NotImplemented(),
+                    };
+                }
+                let estimate = self.inner.estimate_num_keys();
+                self.limit
+                    .map_or(estimate, |limit| estimate.min(limit))
+                    .into_py(py)
+            }
+
+            /// Raises an exception if the iterator has encountered an error,
+            /// e.g. a corrupted table file or an I/O error.
+            ///
+            /// Iterating raises this exception too instead of stopping, so
+            /// the end of an iteration means that all items were read.
+            fn status(&self) -> PyResult<()> {
+                self.inner.status()
+            }
+
             fn __next__(mut slf: PyRefMut<Self>, py: Python) -> PyResult<Option<PyObject>> {
+                if slf.limit == Some(0) {
+                    return Ok(None);
+                }
                 if slf.inner.valid() {
                     $(let $field = slf.inner.$field(py)?;)*
-                    if slf.backwards {
-                        slf.inner.prev();
-                    } else {
-                        slf.inner.next();
+                    let backwards = slf.backwards;
+                    let inner = &mut slf.inner;
+                    // advancing may read from disk, let other threads run
+                    py.allow_threads(|| {
+                        if backwards {
+                            inner.raw_prev();
+                        } else {
+                            inner.raw_next();
+                        }
+                    });
+                    if let Some(limit) = slf.limit.as_mut() {
+                        *limit -= 1;
                     }
                     Ok(Some(($($field),*).to_object(py)))
                 } else {
+                    // do not end the iteration silently on errors
+                    slf.inner.status()?;
                     Ok(None)
                 }
             }
         }
 
         impl $iter_name {
-            pub(crate) fn new(inner: RdictIter, backwards: bool, from_key: Option<&Bound<PyAny>>) -> PyResult<Self> {
+            pub(crate) fn new(
+                inner: RdictIter,
+                backwards: bool,
+                from_key: Option<&Bound<PyAny>>,
+                py: Python,
+            ) -> PyResult<Self> {
                 let mut inner = inner;
+                let bounded = from_key.is_some() || inner.readopts.is_bounded();
                 if let Some(from_key) = from_key {
                     if backwards {
-                        inner.seek_for_prev(from_key)?;
+                        inner.seek_for_prev(from_key, py)?;
                     } else {
-                        inner.seek(from_key)?;
+                        inner.seek(from_key, py)?;
                     }
                 } else {
                     if backwards {
-                        inner.seek_to_last();
+                        inner.seek_to_last(py)?;
                     } else {
-                        inner.seek_to_first();
+                        inner.seek_to_first(py)?;
                     }
                 }
                 Ok(Self {
                     inner,
                     backwards,
+                    limit: None,
+                    bounded,
                 })
             }
+
+            /// Count the items left to yield by advancing to the end,
+            /// without holding the GIL.
+            pub(crate) fn count_remaining(&mut self, py: Python) -> PyResult<usize> {
+                let backwards = self.backwards;
+                let limit = self.limit;
+                let inner = &mut self.inner;
+                py.allow_threads(|| {
+                    let mut count = 0;
+                    while limit.map_or(true, |limit| count < limit) && inner.valid() {
+                        count += 1;
+                        if backwards {
+                            inner.raw_prev();
+                        } else {
+                            inner.raw_next();
+                        }
+                    }
+                    inner.status()?;
+                    Ok(count)
+                })
+            }
+
+            /// Stop the iteration after `limit` items.
+            pub(crate) fn with_limit(mut self, limit: Option<usize>) -> Self {
+                self.limit = limit;
+                self
+            }
         }
     };
 }
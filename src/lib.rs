@@ -1,22 +1,41 @@
 mod checkpoints;
 mod db_reference;
 mod encoder;
+mod event_listener;
 mod exceptions;
 mod iter;
+mod logger;
 mod options;
+mod perf_context;
+mod pinned_slice;
 mod rdict;
 mod snapshot;
 mod sst_file_writer;
+mod statistics;
 mod util;
+mod views;
+mod wal_iter;
 mod write_batch;
+mod write_batch_with_index;
 
+use crate::encoder::{
+    decode_float_key, decode_int_key, encode_float_key, encode_int_key, py_decode_key,
+    py_decode_value, py_encode_key, py_encode_value,
+};
 use crate::exceptions::*;
 use crate::iter::*;
+use crate::logger::LogLevelPy;
 use crate::options::*;
+use crate::perf_context::PerfContextPy;
+use crate::pinned_slice::PinnedSlicePy;
 use crate::rdict::*;
 use crate::snapshot::Snapshot;
 use crate::sst_file_writer::*;
+use crate::statistics::StatisticsPy;
+use crate::views::*;
+use crate::wal_iter::WalIteratorPy;
 use crate::write_batch::*;
+use crate::write_batch_with_index::WriteBatchWithIndexPy;
 use checkpoints::CheckpointPy;
 use pyo3::prelude::*;
 
@@ -104,14 +123,16 @@ use pyo3::prelude::*;
 /// Supported types:
 ///
 /// - key: `int, float, bool, str, bytes`
-/// - value: `int, float, bool, str, bytes` and anything that
-///     supports `pickle`.
+/// - value: `int, float, bool, str, bytes`, `numpy.ndarray`
+///     (stored as dtype, shape and raw data without pickle, except for
+///     object and structured dtypes) and anything that supports `pickle`.
 ///
 #[pymodule]
 fn rocksdict(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<Rdict>()?;
     m.add_class::<OptionsPy>()?;
     m.add_class::<MemtableFactoryPy>()?;
+    m.add_class::<MergeOperatorPy>()?;
     m.add_class::<BlockBasedOptionsPy>()?;
     m.add_class::<CuckooTableOptionsPy>()?;
     m.add_class::<PlainTableFactoryOptionsPy>()?;
@@ -126,6 +147,8 @@ fn rocksdict(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<DBCompressionTypePy>()?;
     m.add_class::<DBCompactionStylePy>()?;
     m.add_class::<DBRecoveryModePy>()?;
+    m.add_class::<StatsLevelPy>()?;
+    m.add_class::<LogLevelPy>()?;
     m.add_class::<UniversalCompactOptionsPy>()?;
     m.add_class::<UniversalCompactionStopStylePy>()?;
     m.add_class::<EnvPy>()?;
@@ -136,18 +159,37 @@ fn rocksdict(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     m.add_class::<RdictKeys>()?;
     m.add_class::<RdictColumns>()?;
     m.add_class::<RdictEntities>()?;
+    m.add_class::<RdictKeysView>()?;
+    m.add_class::<RdictValuesView>()?;
+    m.add_class::<RdictItemsView>()?;
     m.add_class::<IngestExternalFileOptionsPy>()?;
     m.add_class::<SstFileWriterPy>()?;
     m.add_class::<WriteBatchPy>()?;
+    m.add_class::<WriteBatchWithIndexPy>()?;
     m.add_class::<ColumnFamilyPy>()?;
     m.add_class::<AccessType>()?;
     m.add_class::<Snapshot>()?;
     m.add_class::<CompactOptionsPy>()?;
     m.add_class::<BottommostLevelCompactionPy>()?;
     m.add_class::<ChecksumTypePy>()?;
+    m.add_class::<FileChecksumGenFactoryPy>()?;
     m.add_class::<KeyEncodingTypePy>()?;
     m.add_class::<WriteBufferManagerPy>()?;
+    m.add_class::<RateLimiterPy>()?;
     m.add_class::<CheckpointPy>()?;
+    m.add_class::<PinnedSlicePy>()?;
+    m.add_class::<StatisticsPy>()?;
+    m.add_class::<PerfContextPy>()?;
+    m.add_class::<WalIteratorPy>()?;
+
+    m.add_function(wrap_pyfunction!(encode_int_key, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_int_key, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_float_key, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_float_key, m)?)?;
+    m.add_function(wrap_pyfunction!(py_encode_key, m)?)?;
+    m.add_function(wrap_pyfunction!(py_decode_key, m)?)?;
+    m.add_function(wrap_pyfunction!(py_encode_value, m)?)?;
+    m.add_function(wrap_pyfunction!(py_decode_value, m)?)?;
 
     m.add("DbClosedError", py.get_type_bound::<DbClosedError>())?;
 
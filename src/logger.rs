@@ -0,0 +1,100 @@
+use crate::util::run_with_gil_later;
+use core::slice;
+use libc::{c_char, c_int, c_uint, c_void, size_t};
+use pyo3::prelude::*;
+use rocksdb::LogLevel;
+use std::sync::Arc;
+
+/// Level of the messages of the rocksdb info log.
+///
+/// This is to be treated as an enum, from the most to the least verbose:
+/// - Debug
+/// - Info
+/// - Warn
+/// - Error
+/// - Fatal
+/// - Header
+///
+/// Example:
+///     ::
+///
+///         opt = Options()
+///         opt.set_info_log_level(LogLevel.warn())
+#[pyclass(name = "LogLevel")]
+#[derive(Clone)]
+pub(crate) struct LogLevelPy(pub(crate) LogLevel);
+
+#[pymethods]
+impl LogLevelPy {
+    #[staticmethod]
+    pub fn debug() -> Self {
+        LogLevelPy(LogLevel::Debug)
+    }
+
+    #[staticmethod]
+    pub fn info() -> Self {
+        LogLevelPy(LogLevel::Info)
+    }
+
+    #[staticmethod]
+    pub fn warn() -> Self {
+        LogLevelPy(LogLevel::Warn)
+    }
+
+    #[staticmethod]
+    pub fn error() -> Self {
+        LogLevelPy(LogLevel::Error)
+    }
+
+    #[staticmethod]
+    pub fn fatal() -> Self {
+        LogLevelPy(LogLevel::Fatal)
+    }
+
+    #[staticmethod]
+    pub fn header() -> Self {
+        LogLevelPy(LogLevel::Header)
+    }
+}
+
+/// level names passed to the python callbacks, indexed by rocksdb log level
+const LOG_LEVEL_NAMES: [&str; 6] = ["debug", "info", "warn", "error", "fatal", "header"];
+
+unsafe extern "C" fn log_callback(
+    callback: *mut c_void,
+    level: c_uint,
+    message: *mut c_char,
+    len: size_t,
+) {
+    let callback = &*(callback as *const Arc<PyObject>);
+    let message = slice::from_raw_parts(message as *const u8, len);
+    let message = String::from_utf8_lossy(message).trim_end().to_string();
+    let level = LOG_LEVEL_NAMES
+        .get(level as usize)
+        .copied()
+        .unwrap_or("header");
+    let callback = callback.clone();
+    run_with_gil_later(Box::new(move |py| {
+        if let Err(e) = callback.call1(py, (level, message)) {
+            e.write_unraisable_bound(py, Some(callback.bind(py)));
+        }
+    }));
+}
+
+/// Create a rocksdb logger calling `callback(level, message)` for each log line.
+///
+/// The C API does not release the callback data together with the logger,
+/// so the callback is kept alive until the process exits.
+pub(crate) fn create_callback_logger(
+    callback: PyObject,
+    level: LogLevel,
+) -> *mut librocksdb_sys::rocksdb_logger_t {
+    let callback = Box::into_raw(Box::new(Arc::new(callback)));
+    unsafe {
+        librocksdb_sys::rocksdb_logger_create_callback_logger(
+            level as c_int,
+            Some(log_callback),
+            callback as *mut c_void,
+        )
+    }
+}
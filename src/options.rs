@@ -1,17 +1,40 @@
-use crate::encoder::encode_key;
+use crate::encoder::encode_key_with;
+use crate::event_listener::create_event_listener;
+use crate::logger::{create_callback_logger, LogLevelPy};
 use crate::rdict::{RocksDictConfig, ROCKSDICT_CONFIG_FILE};
+use crate::util::{error_message, unknown_name_error};
 use libc::{c_char, c_uchar, size_t};
-use num_bigint::BigInt;
-use pyo3::exceptions::PyException;
+use num_bigint::{BigInt, ToBigInt};
+use pyo3::exceptions::{PyException, PyTypeError};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList, PyTuple};
+use pyo3::types::{PyBytes, PyDict, PyList, PyString, PyTuple};
 use rocksdb::*;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::ffi::c_double;
+use std::ffi::{c_double, CString};
 use std::os::raw::{c_int, c_uint};
 use std::path::{Path, PathBuf};
 
+/// Read options with the getters of the C API into a python dict.
+///
+/// `bool` options have getters returning an integer, the other values are
+/// converted as they are.
+macro_rules! options_to_dict {
+    (
+        $dict:expr,
+        $opt:expr,
+        bool { $($bool_name:ident => $bool_getter:ident,)* }
+        value { $($name:ident => $getter:ident,)* }
+    ) => {
+        $($dict.set_item(
+            stringify!($bool_name),
+            unsafe { librocksdb_sys::$bool_getter($opt) } != 0,
+        )?;)*
+        $($dict.set_item(stringify!($name), unsafe { librocksdb_sys::$getter($opt) })?;)*
+    };
+}
+
 /// Database-wide options around performance and behavior.
 ///
 /// Please read the official tuning [guide](https://github.com/facebook/rocksdb/wiki/RocksDB-Tuning-Guide)
@@ -47,14 +70,27 @@ use std::path::{Path, PathBuf};
 /// Args:
 ///     raw_mode (bool): set this to True to operate in raw mode (i.e.
 ///         it will only allow bytes as key-value pairs, and is compatible
-///         with other RockDB database).
+///         with other RockDB database). Any contiguous buffer (`bytearray`,
+///         `memoryview`, `mmap`, numpy arrays) can also be written or used
+///         as a key without copying, and is read back as bytes.
 ///
 #[pyclass(name = "Options")]
 #[derive(Clone)]
 pub(crate) struct OptionsPy {
     pub(crate) inner_opt: Options,
     pub(crate) raw_mode: bool,
+    /// ints and floats keys are compared by value, see `set_ordered_numeric_keys`
+    pub(crate) ordered_numeric_keys: bool,
+    /// size of the user-defined timestamps of the keys, 0 if none
+    pub(crate) timestamp_size: usize,
     pub(crate) prefix_extractor: Option<SliceTransformType>,
+    pub(crate) merge_operator: Option<MergeOperatorType>,
+    /// uses `Env.mem_env()`, nothing is written to disk
+    pub(crate) in_memory: bool,
+    /// encoded keys longer than this are rejected, see `set_max_key_bytes`
+    pub(crate) max_key_bytes: Option<usize>,
+    /// encoded values longer than this are rejected, see `set_max_value_bytes`
+    pub(crate) max_value_bytes: Option<usize>,
 }
 
 /// Optionally disable WAL or sync for this write.
@@ -136,6 +172,43 @@ pub(crate) struct WriteOptionsPy {
     memtable_insert_hint_per_batch: bool,
 }
 
+/// Write options for the C API, used where `rocksdb::WriteOptions` cannot be used.
+pub(crate) struct WriteOpt(pub(crate) *mut librocksdb_sys::rocksdb_writeoptions_t);
+
+impl From<&WriteOptionsPy> for WriteOpt {
+    fn from(w_opt: &WriteOptionsPy) -> Self {
+        let opt = WriteOpt(unsafe { librocksdb_sys::rocksdb_writeoptions_create() });
+        unsafe {
+            librocksdb_sys::rocksdb_writeoptions_set_sync(opt.0, w_opt.sync as c_uchar);
+            librocksdb_sys::rocksdb_writeoptions_disable_WAL(opt.0, w_opt.disable_wal as c_int);
+            librocksdb_sys::rocksdb_writeoptions_set_ignore_missing_column_families(
+                opt.0,
+                w_opt.ignore_missing_column_families as c_uchar,
+            );
+            librocksdb_sys::rocksdb_writeoptions_set_low_pri(opt.0, w_opt.low_pri as c_uchar);
+            librocksdb_sys::rocksdb_writeoptions_set_memtable_insert_hint_per_batch(
+                opt.0,
+                w_opt.memtable_insert_hint_per_batch as c_uchar,
+            );
+            librocksdb_sys::rocksdb_writeoptions_set_no_slowdown(
+                opt.0,
+                w_opt.no_slowdown as c_uchar,
+            );
+        }
+        opt
+    }
+}
+
+unsafe impl Send for WriteOpt {}
+
+unsafe impl Sync for WriteOpt {}
+
+impl Drop for WriteOpt {
+    fn drop(&mut self) {
+        unsafe { librocksdb_sys::rocksdb_writeoptions_destroy(self.0) }
+    }
+}
+
 /// Optionally wait for the memtable flush to be performed.
 ///
 /// Example:
@@ -191,9 +264,18 @@ pub(crate) struct ReadOptionsPy {
     tailing: bool,
     pin_data: bool,
     async_io: bool,
+    timestamp: Option<u64>,
 }
 
-pub(crate) struct ReadOpt(pub(crate) *mut librocksdb_sys::rocksdb_readoptions_t);
+pub(crate) struct ReadOpt {
+    pub(crate) inner: *mut librocksdb_sys::rocksdb_readoptions_t,
+    // the C read options only keep pointers to the iterate bounds
+    // and to the timestamp, they must live as long as the read options.
+    _iterate_lower_bound: Option<Vec<u8>>,
+    _iterate_upper_bound: Option<Vec<u8>>,
+    _timestamp: Option<Vec<u8>>,
+    prefix_same_as_start: bool,
+}
 
 /// Defines the underlying memtable implementation.
 /// See official [wiki](https://github.com/facebook/rocksdb/wiki/MemTable) for more information.
@@ -205,6 +287,13 @@ pub(crate) struct MemtableFactoryPy(MemtableFactory);
 pub(crate) struct BlockBasedOptionsPy(BlockBasedOptions);
 
 /// Configuration of cuckoo-based storage.
+///
+/// Args:
+///     hash_table_ratio: utilization of the hash tables, see `set_hash_ratio()`.
+///     max_search_depth: see `set_max_search_depth()`.
+///     cuckoo_block_size: see `set_cuckoo_block_size()`.
+///     identity_as_first_hash: see `set_identity_as_first_hash()`.
+///     use_module_hash: see `set_use_module_hash()`.
 #[pyclass(name = "CuckooTableOptions")]
 pub(crate) struct CuckooTableOptionsPy(CuckooTableOptions);
 
@@ -233,12 +322,18 @@ impl KeyEncodingTypePy {
 /// See official [wiki](https://github.com/facebook/rocksdb/wiki/PlainTable-Format) for more
 /// information.
 ///
-/// Defaults:
-///  user_key_length: 0 (variable length)
-///  bloom_bits_per_key: 10
-///  hash_table_ratio: 0.75
-///  index_sparseness: 16
-///
+/// Args:
+///     user_key_length: length of the keys, 0 for variable length keys.
+///     bloom_bits_per_key: bits per key of the prefix bloom filter,
+///         0 to disable the bloom filter.
+///     hash_table_ratio: ratio of prefixes to buckets of the hash index,
+///         0 to use binary search instead of a hash index.
+///     index_sparseness: number of keys per index record within a prefix.
+///     huge_page_tlb_size: allocate the indexes from huge pages of this size,
+///         0 to disable.
+///     encoding_type: `KeyEncodingType.plain()` or `KeyEncodingType.prefix()`.
+///     full_scan_mode: only support iterating the whole file, without index.
+///     store_index_in_file: store the index in the file instead of building it on open.
 #[pyclass(name = "PlainTableFactoryOptions")]
 pub(crate) struct PlainTableFactoryOptionsPy {
     #[pyo3(get, set)]
@@ -286,6 +381,45 @@ pub enum SliceTransformType {
     Noop,
 }
 
+/// Built-in merge operators, used with `Options.set_merge_operator`
+/// and `Rdict.merge`.
+///
+/// The merge operator is saved in the rocksdict config file of the db,
+/// reopening the db with `Rdict(path)` restores it.
+///
+/// `Rdict.merge()` raises `TypeError` for values that the merge operator
+/// cannot merge. Such values may still reach the merge operator through
+/// `WriteBatch.merge()` or `put()`, they are then ignored like with the
+/// `UInt64AddOperator` of rocksdb (counted as 0, or not appended), so that
+/// flushes and compactions never fail.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options, MergeOperator
+///
+///         opt = Options()
+///         opt.set_merge_operator(MergeOperator.uint64_add())
+///         db = Rdict("./counters", opt)
+///         db.merge("visits", 1)
+///         db.merge("visits", 2)
+///         assert db["visits"] == 3
+///
+///         opt = Options()
+///         opt.set_merge_operator(MergeOperator.string_append(","))
+///         db = Rdict("./lists", opt)
+///         db.merge("tags", "a")
+///         db.merge("tags", "b")
+///         assert db["tags"] == "a,b"
+#[pyclass(name = "MergeOperator")]
+pub(crate) struct MergeOperatorPy(MergeOperatorType);
+
+#[derive(Deserialize, Serialize, Clone)]
+pub enum MergeOperatorType {
+    UInt64Add,
+    StringAppend(Vec<u8>),
+}
+
 #[pyclass(name = "DBPath")]
 pub(crate) struct DBPathPy {
     path: PathBuf,
@@ -313,7 +447,7 @@ pub(crate) struct DBPathPy {
 ///         opt.set_compression_type(DBCompressionType.snappy())
 ///
 #[pyclass(name = "DBCompressionType")]
-pub(crate) struct DBCompressionTypePy(DBCompressionType);
+pub(crate) struct DBCompressionTypePy(pub(crate) DBCompressionType);
 
 /// This is to be treated as an enum.
 ///
@@ -361,13 +495,71 @@ pub(crate) struct DBCompactionStylePy(DBCompactionStyle);
 /// The total memory is counted as total memory allocated in the arena,
 /// even if some of that may not yet be used by memtable.
 ///
-/// buffer_size: the memory limit in bytes.
-/// allow_stall: If set true, it will enable stalling of all writers when
-/// memory usage exceeds buffer_size (soft limit).
-///             It will wait for flush to complete and memory usage to drop down
+/// A WriteBufferManager can be shared by the options of several DBs, it
+/// is kept alive by every `Options` and `Rdict` using it.
+///
+/// Args:
+///     buffer_size: the memory limit in bytes.
+///     cache: if given, the memtable memory is charged against this block
+///         cache, so that memtables and block cache share a single limit.
+///     allow_stall: If set true, it will enable stalling of all writers when
+///         memory usage exceeds buffer_size (soft limit). It will wait for
+///         flush to complete and memory usage to drop down.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options, WriteBufferManager, Cache
+///
+///         # 64 MB of memtables for both databases, charged to the block cache
+///         cache = Cache(256 * 1024 * 1024)
+///         wbm = WriteBufferManager(64 * 1024 * 1024, cache=cache)
+///         opt = Options()
+///         opt.set_write_buffer_manager(wbm)
+///         db1 = Rdict("./tmp1", opt)
+///         db2 = Rdict("./tmp2", opt)
+///         print(wbm.memory_usage(), wbm.buffer_size())
 #[pyclass(name = "WriteBufferManager")]
 pub(crate) struct WriteBufferManagerPy(WriteBufferManager);
 
+/// Controls the IO rate of flush and compaction, see `Options.set_rate_limiter`.
+///
+/// A RateLimiter can be shared by the options of several DBs, the rate
+/// budget is then global to all of them.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options, RateLimiter
+///
+///         # 10 MB/s shared by the two databases
+///         limiter = RateLimiter(10 * 1024 * 1024)
+///         opt = Options()
+///         opt.set_rate_limiter(limiter)
+///         db1 = Rdict("./tmp1", opt)
+///         db2 = Rdict("./tmp2", opt)
+///
+/// Notes:
+///     The limiter is kept alive by the options (and the DBs) using it,
+///     even after the `RateLimiter` object is deleted. The rate limit
+///     cannot be adjusted after creation, since the RocksDB C API does not
+///     expose `SetBytesPerSecond`.
+///
+/// Args:
+///     rate_bytes_per_sec: the rate limit in bytes per second.
+///     refill_period_us: how often tokens are refilled, in microseconds.
+///         A smaller value smooths out the IO but increases the CPU overhead.
+///     fairness: the chance (1 / fairness) that low priority requests
+///         are granted before high priority requests.
+///     mode: which IO to limit, one of `"reads_only"`, `"writes_only"` and `"all_io"`.
+///     auto_tuned: dynamically adjust the rate limit within
+///         `[rate_bytes_per_sec / 20, rate_bytes_per_sec]`, according to demand.
+#[pyclass(name = "RateLimiter")]
+pub(crate) struct RateLimiterPy(*mut librocksdb_sys::rocksdb_ratelimiter_t);
+
+unsafe impl Send for RateLimiterPy {}
+unsafe impl Sync for RateLimiterPy {}
+
 /// Used by BlockBasedOptions::set_checksum_type.
 ///
 /// Call the corresponding functions of each
@@ -381,6 +573,17 @@ pub(crate) struct WriteBufferManagerPy(WriteBufferManager);
 #[pyclass(name = "ChecksumType")]
 pub(crate) struct ChecksumTypePy(ChecksumType);
 
+/// Used by Options::set_file_checksum_gen_factory, to compute a checksum
+/// of every table file written by the db.
+///
+/// Call the corresponding functions of each
+/// to get one of the following.
+/// - CRC32c
+///
+#[pyclass(name = "FileChecksumGenFactory")]
+#[derive(Clone)]
+pub(crate) struct FileChecksumGenFactoryPy(&'static str);
+
 impl Clone for ChecksumTypePy {
     fn clone(&self) -> Self {
         match self.0 {
@@ -413,10 +616,54 @@ impl Clone for ChecksumTypePy {
 #[pyclass(name = "DBRecoveryMode")]
 pub(crate) struct DBRecoveryModePy(DBRecoveryMode);
 
+/// This is to be treated as an enum.
+///
+/// Calling the corresponding functions of each
+/// to get one of the following, from the fewest to the most statistics.
+/// - DisableAll
+/// - ExceptTickers
+/// - ExceptHistogramOrTimers
+/// - ExceptTimers
+/// - ExceptDetailedTimers
+/// - ExceptTimeForMutex
+/// - All
+///
+/// Below is an example to collect all statistics.
+///
+/// Example:
+///     ::
+///
+///         opt = Options()
+///         opt.enable_statistics()
+///         opt.set_statistics_level(StatsLevel.all())
+///
+#[pyclass(name = "StatsLevel")]
+pub(crate) struct StatsLevelPy(statistics::StatsLevel);
+
 #[pyclass(name = "Env")]
 #[derive(Clone)]
-pub(crate) struct EnvPy(Env);
+pub(crate) struct EnvPy {
+    inner: Env,
+    /// created by `Env.mem_env()`
+    in_memory: bool,
+}
 
+/// Options of the universal compaction style, see
+/// `Options.set_universal_compaction_options`.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options, DBCompactionStyle, UniversalCompactOptions
+///
+///         opt = Options()
+///         opt.set_compaction_style(DBCompactionStyle.universal())
+///         opt.set_universal_compaction_options(
+///             UniversalCompactOptions(size_ratio=10, max_size_amplification_percent=100)
+///         )
+///         opt.set_num_levels(1)
+///         opt.set_level_zero_file_num_compaction_trigger(8)
+///         db = Rdict("./tmp", opt)
 #[pyclass(name = "UniversalCompactOptions")]
 pub(crate) struct UniversalCompactOptionsPy {
     /// Sets the percentage flexibility while comparing file size.
@@ -478,7 +725,7 @@ pub(crate) struct UniversalCompactOptionsPy {
 
     /// Sets the algorithm used to stop picking files into a single compaction run.
     ///
-    /// Default: ::Total
+    /// Default: UniversalCompactionStopStyle.total()
     #[pyo3(get, set)]
     stop_style: UniversalCompactionStopStylePy,
 }
@@ -487,6 +734,28 @@ pub(crate) struct UniversalCompactOptionsPy {
 #[derive(Copy, Clone)]
 pub(crate) struct UniversalCompactionStopStylePy(UniversalCompactionStopStyle);
 
+/// Options of the FIFO compaction style, see `Options.set_fifo_compaction_options`.
+///
+/// With FIFO compaction the oldest table files are dropped once their total
+/// size exceeds `max_table_files_size`, or once they are older than the
+/// ttl (see `Options.set_ttl`), which makes rocksdb a bounded event buffer.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options, DBCompactionStyle, FifoCompactOptions
+///
+///         fifo = FifoCompactOptions(max_table_files_size=64 * 1024 * 1024)
+///         opt = Options()
+///         opt.set_compaction_style(DBCompactionStyle.fifo())
+///         opt.set_fifo_compaction_options(fifo)
+///         # also drop files older than a day
+///         opt.set_ttl(24 * 60 * 60)
+///         db = Rdict("./tmp", opt)
+///
+/// Notes:
+///     `age_for_warm` and `file_temperature_age_thresholds` are not
+///     available in the rocksdb C API, and cannot be set.
 #[pyclass(name = "FifoCompactOptions")]
 pub(crate) struct FifoCompactOptionsPy {
     /// Sets the max table file size.
@@ -494,11 +763,52 @@ pub(crate) struct FifoCompactOptionsPy {
     /// Once the total sum of table files reaches this, we will delete the oldest
     /// table file
     ///
-    /// Default: 1GB
+    /// Default: 10GB
     #[pyo3(get, set)]
     max_table_files_size: u64,
+    /// If true, try to do compaction to compact smaller files into larger ones.
+    /// Minimum files to compact follows options.level0_file_num_compaction_trigger
+    /// and compaction won't trigger if average compact bytes per del file is
+    /// larger than options.write_buffer_size.
+    ///
+    /// Default: false
+    #[pyo3(get, set)]
+    allow_compaction: bool,
 }
 
+/// Options of `Rdict.ingest_external_file()`.
+///
+/// Notes:
+///     By default, ingested keys overlapping existing keys supersede them:
+///     the files are assigned a sequence number newer than the existing data.
+///     With `set_ingest_behind(True)`, the files are ingested at the bottom
+///     level behind the existing data instead, so existing keys are kept.
+///
+///     The sequence number assigned to the files is recorded in the MANIFEST
+///     only, the files themselves are not modified (`write_global_seqno` is
+///     not available in the C API and stays false).
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import (Rdict, Options, SstFileWriter,
+///                                IngestExternalFileOptions, DBCompactionStyle)
+///
+///         opt = Options()
+///         opt.set_compaction_style(DBCompactionStyle.universal())
+///         opt.set_allow_ingest_behind(True)
+///         db = Rdict("./tmp", opt)
+///         db["user"] = "live"
+///
+///         writer = SstFileWriter(opt)
+///         writer.open("./history.sst")
+///         writer["user"] = "historical"
+///         writer.finish()
+///
+///         ingest_opt = IngestExternalFileOptions()
+///         ingest_opt.set_ingest_behind(True)
+///         db.ingest_external_file(["./history.sst"], ingest_opt)
+///         assert db["user"] == "live"
 #[pyclass(name = "IngestExternalFileOptions")]
 pub(crate) struct IngestExternalFileOptionsPy(pub(crate) IngestExternalFileOptions);
 
@@ -513,6 +823,23 @@ unsafe impl Send for CompactOptionsPy {}
 unsafe impl Sync for CompactOptionsPy {}
 
 impl OptionsPy {
+    /// Set options given as a rocksdb options string, for the options
+    /// without a setter in the C API.
+    fn set_options_from_string(&mut self, opts_str: &str) -> PyResult<()> {
+        let opts_str = CString::new(opts_str).unwrap();
+        let opt = self.inner_opt.inner() as *mut _;
+        unsafe {
+            let mut err: *mut c_char = std::ptr::null_mut();
+            // new options are built from a copy of the base options,
+            // so the options can be updated in place
+            librocksdb_sys::rocksdb_get_options_from_string(opt, opts_str.as_ptr(), opt, &mut err);
+            if !err.is_null() {
+                return Err(PyException::new_err(error_message(err)));
+            }
+        }
+        Ok(())
+    }
+
     /// function that sets prefix extractor according to slice transform type
     fn set_prefix_extractor_inner(
         options: &mut Options,
@@ -534,6 +861,41 @@ impl OptionsPy {
         Ok(())
     }
 
+    /// function that sets merge operator according to merge operator type,
+    /// values are rocksdict encoded if not `raw_mode`
+    fn set_merge_operator_inner(
+        options: &mut Options,
+        merge_operator: &MergeOperatorType,
+        raw_mode: bool,
+    ) {
+        match (merge_operator, raw_mode) {
+            (MergeOperatorType::UInt64Add, true) => {
+                options.set_merge_operator_associative("rocksdict.uint64_add", uint64_add_raw)
+            }
+            (MergeOperatorType::UInt64Add, false) => {
+                options.set_merge_operator_associative("rocksdict.uint64_add", int_add_encoded)
+            }
+            (MergeOperatorType::StringAppend(delimiter), true) => {
+                let delimiter = delimiter.clone();
+                options.set_merge_operator_associative(
+                    "rocksdict.string_append",
+                    move |_key: &[u8], existing: Option<&[u8]>, operands: &MergeOperands| {
+                        append_raw(existing, operands, &delimiter)
+                    },
+                )
+            }
+            (MergeOperatorType::StringAppend(delimiter), false) => {
+                let delimiter = delimiter.clone();
+                options.set_merge_operator_associative(
+                    "rocksdict.string_append",
+                    move |_key: &[u8], existing: Option<&[u8]>, operands: &MergeOperands| {
+                        append_encoded(existing, operands, &delimiter)
+                    },
+                )
+            }
+        }
+    }
+
     /// load latest options from OPTIONS files and config files
     pub fn load_latest_inner(
         path: &str,
@@ -545,8 +907,11 @@ impl OptionsPy {
         config_path.push(ROCKSDICT_CONFIG_FILE);
         let rocksdict_config = RocksDictConfig::load(config_path).unwrap_or_default();
         let raw_mode = rocksdict_config.raw_mode;
+        let ordered_numeric_keys = rocksdict_config.ordered_numeric_keys;
+        let timestamp_size = rocksdict_config.timestamp_size;
         let slice_transforms = rocksdict_config.prefix_extractors;
-        let load_result = Options::load_latest(path, env.0, ignore_unknown_options, cache.0);
+        let merge_operators = rocksdict_config.merge_operators;
+        let load_result = Options::load_latest(path, env.inner, ignore_unknown_options, cache.0);
         let (options, column_families) = match load_result {
             Ok(d) => d,
             Err(e) => return Err(PyException::new_err(e.to_string())),
@@ -554,7 +919,10 @@ impl OptionsPy {
         let options = OptionsPy::compose_options_py(
             options,
             raw_mode,
+            ordered_numeric_keys,
+            timestamp_size,
             slice_transforms.get(DEFAULT_COLUMN_FAMILY_NAME).cloned(),
+            merge_operators.get(DEFAULT_COLUMN_FAMILY_NAME).cloned(),
         )?;
         let column_families: PyResult<HashMap<_, _>> = column_families
             .into_iter()
@@ -562,7 +930,10 @@ impl OptionsPy {
                 let opt = OptionsPy::compose_options_py(
                     c.options,
                     raw_mode,
+                    ordered_numeric_keys,
+                    timestamp_size,
                     slice_transforms.get(&c.name).cloned(),
+                    merge_operators.get(&c.name).cloned(),
                 );
                 match opt {
                     Ok(opt) => Ok((c.name, opt)),
@@ -573,39 +944,49 @@ impl OptionsPy {
         Ok((options, column_families?))
     }
 
-    /// convert `Options` into `OptionsPy` based on `raw_mode` and `prefix_extractor`
+    /// convert `Options` into `OptionsPy` based on `raw_mode`, `ordered_numeric_keys`,
+    /// `timestamp_size`, `prefix_extractor` and `merge_operator`
     fn compose_options_py(
         opt: Options,
         raw_mode: bool,
+        ordered_numeric_keys: bool,
+        timestamp_size: usize,
         prefix_extractor: Option<SliceTransformType>,
+        merge_operator: Option<MergeOperatorType>,
     ) -> PyResult<OptionsPy> {
         let mut opt = opt;
         if !raw_mode {
-            OptionsPy::set_rocksdict_comparator(&mut opt);
+            OptionsPy::set_rocksdict_comparator(&mut opt, ordered_numeric_keys);
         }
         if let Some(slice_transform) = &prefix_extractor {
             OptionsPy::set_prefix_extractor_inner(&mut opt, slice_transform)?
         }
-        let options = OptionsPy {
+        if let Some(merge_operator) = &merge_operator {
+            OptionsPy::set_merge_operator_inner(&mut opt, merge_operator, raw_mode)
+        }
+        let mut options = OptionsPy {
             inner_opt: opt,
             raw_mode,
+            ordered_numeric_keys,
+            timestamp_size: 0,
             prefix_extractor,
+            merge_operator,
+            in_memory: false,
+            max_key_bytes: None,
+            max_value_bytes: None,
         };
+        if timestamp_size == U64_TIMESTAMP_SIZE {
+            options.set_u64_timestamp_comparator()?;
+        }
         Ok(options)
     }
 
-    fn set_rocksdict_comparator(opt: &mut Options) {
-        opt.set_comparator(
-            "rocksdict",
-            Box::new(|v1, v2| {
-                if let (Some(3), Some(3)) = (v1.first(), v2.first()) {
-                    BigInt::from_signed_bytes_be(&v1[1..])
-                        .cmp(&BigInt::from_signed_bytes_be(&v2[1..]))
-                } else {
-                    v1.cmp(v2)
-                }
-            }),
-        );
+    fn set_rocksdict_comparator(opt: &mut Options, ordered_numeric_keys: bool) {
+        if ordered_numeric_keys {
+            opt.set_comparator("rocksdict_numeric", Box::new(rocksdict_numeric_compare));
+        } else {
+            opt.set_comparator("rocksdict", Box::new(rocksdict_compare));
+        }
     }
 }
 
@@ -618,12 +999,18 @@ impl OptionsPy {
         opt.create_if_missing(true);
         // if not raw_mode change default comparator
         if !raw_mode {
-            OptionsPy::set_rocksdict_comparator(&mut opt);
+            OptionsPy::set_rocksdict_comparator(&mut opt, false);
         }
         OptionsPy {
             inner_opt: opt,
             raw_mode,
+            ordered_numeric_keys: false,
+            timestamp_size: 0,
             prefix_extractor: None,
+            merge_operator: None,
+            in_memory: false,
+            max_key_bytes: None,
+            max_value_bytes: None,
         }
     }
 
@@ -631,6 +1018,24 @@ impl OptionsPy {
     ///
     /// Returns a tuple, where the first item is `Options`
     /// and the second item is a `Dict` of column families.
+    ///
+    /// Notes:
+    ///     rocksdb writes the options of a db to an `OPTIONS-*` file
+    ///     in the db directory every time it is opened, this is the
+    ///     serialized form of `Options` read by this method. Options
+    ///     of rocksdict (`raw_mode`, `ordered_numeric_keys`, prefix
+    ///     extractors and merge operators) are read from
+    ///     `rocksdict-config.json`. Serializing `Options` to a string or
+    ///     comparing two `Options` is not available, since the rocksdb
+    ///     C API does not expose it.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt, column_families = Options.load_latest("./tmp")
+    ///         db = Rdict("./tmp", opt, column_families=column_families)
     #[staticmethod]
     #[pyo3(signature = (
         path,
@@ -656,6 +1061,295 @@ impl OptionsPy {
         Ok(returned_tuple.to_object(py))
     }
 
+    /// Whether the options are in raw mode, see `Options(raw_mode=True)`.
+    #[getter]
+    pub fn raw_mode(&self) -> bool {
+        self.raw_mode
+    }
+
+    /// Compare int and float keys by value, so that they iterate in
+    /// numeric order and range queries on numbers behave as expected.
+    ///
+    /// By default ints are compared by value, but all ints sort before
+    /// all floats and negative floats sort in reverse order.
+    ///
+    /// Keys of different types sort by type: bytes, then str, then
+    /// numbers (ints and floats mixed), then bool, datetime, UUID and tuple.
+    /// Ints and floats are not mixed within tuple keys.
+    /// An int sorts just before an equal float, `-0.0` just before `0.0`,
+    /// and `nan` after `inf` (or before `-inf` for a negative `nan`).
+    ///
+    /// Notes:
+    ///     This changes the comparator of the db, which can not be changed
+    ///     after the db is created. The setting is saved in
+    ///     `rocksdict-config.json`, and `Rdict(path)` restores it. It is
+    ///     not available in `raw_mode`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_ordered_numeric_keys(True)
+    ///         db = Rdict("./numbers", opt)
+    ///         for key in [2, -1.5, 0, 0.5, -3]:
+    ///             db[key] = key
+    ///         assert list(db.keys()) == [-3, -1.5, 0, 0.5, 2]
+    ///         assert list(db.keys(from_key=0.25)) == [0.5, 2]
+    pub fn set_ordered_numeric_keys(&mut self, ordered: bool) -> PyResult<()> {
+        if self.raw_mode && ordered {
+            return Err(PyException::new_err(
+                "ordered numeric keys are not available in raw_mode",
+            ));
+        }
+        self.ordered_numeric_keys = ordered;
+        if !self.raw_mode {
+            OptionsPy::set_rocksdict_comparator(&mut self.inner_opt, ordered);
+        }
+        Ok(())
+    }
+
+    /// Whether int and float keys are compared by value,
+    /// see `Options.set_ordered_numeric_keys()`.
+    #[getter]
+    pub fn ordered_numeric_keys(&self) -> bool {
+        self.ordered_numeric_keys
+    }
+
+    /// Reject keys longer than `max_key_bytes` once encoded, to fail fast
+    /// instead of writing a runaway key. `None` (the default) is unlimited.
+    ///
+    /// Notes:
+    ///     The limit is checked on every key encoded by `Rdict` (and its
+    ///     column families), for reads and writes, raising an exception.
+    ///     Keys written with `WriteBatch` or `SstFileWriter` are not checked.
+    ///     It is not saved in the rocksdict config, so it must be set again
+    ///     when the db is reopened.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_max_key_bytes(1024)
+    ///         opt.set_max_value_bytes(64 * 1024 * 1024)
+    ///         db = Rdict("./tmp", opt)
+    #[pyo3(signature = (max_key_bytes))]
+    pub fn set_max_key_bytes(&mut self, max_key_bytes: Option<usize>) {
+        self.max_key_bytes = max_key_bytes;
+    }
+
+    /// Reject values longer than `max_value_bytes` once encoded (after the
+    /// serialization and the value transform), to fail fast instead of
+    /// writing a runaway value. `None` (the default) is unlimited.
+    ///
+    /// Notes:
+    ///     The limit is checked when `Rdict` (and its column families) write
+    ///     values, raising an exception before anything is written.
+    ///     Values written with `WriteBatch` or `SstFileWriter` are not checked.
+    ///     It is not saved in the rocksdict config, see `set_max_key_bytes()`.
+    #[pyo3(signature = (max_value_bytes))]
+    pub fn set_max_value_bytes(&mut self, max_value_bytes: Option<usize>) {
+        self.max_value_bytes = max_value_bytes;
+    }
+
+    /// Use a bytewise comparator with 8 bytes user-defined timestamps,
+    /// the same as `BytewiseComparatorWithU64Ts` of rocksdb.
+    ///
+    /// Each key is followed by a timestamp (encoded as a little endian u64),
+    /// entries of the same key are ordered from the newest to the oldest
+    /// timestamp. The timestamps are passed to `Rdict.put()`, `Rdict.delete()`
+    /// and `Rdict.get()` (or `ReadOptions.set_timestamp()`) with `ts=`,
+    /// and to `SstFileWriter.put_with_ts()`.
+    ///
+    /// Notes:
+    ///     Only available in `raw_mode`. The comparator of the db can not be
+    ///     changed after the db is created: it is saved in the rocksdict config,
+    ///     so `Rdict(path)` reopens the db with it, and opening it with
+    ///     `Options` that do not match raises an exception.
+    pub fn set_u64_timestamp_comparator(&mut self) -> PyResult<()> {
+        if !self.raw_mode {
+            return Err(PyException::new_err(
+                "timestamp comparator requires raw_mode, use Options(raw_mode=True)",
+            ));
+        }
+        self.inner_opt.set_comparator_with_ts(
+            "leveldb.BytewiseComparator.u64ts",
+            U64_TIMESTAMP_SIZE,
+            Box::new(compare_with_u64_ts),
+            Box::new(compare_u64_ts),
+            Box::new(compare_without_u64_ts),
+        );
+        self.timestamp_size = U64_TIMESTAMP_SIZE;
+        Ok(())
+    }
+
+    /// Current values of the options as a dict from option name to value,
+    /// to inspect the options or to store them as JSON or YAML.
+    ///
+    /// The names are the setters without `set_`, the options can be loaded
+    /// back with `Options.from_dict()`.
+    ///
+    /// Notes:
+    ///     Only the options with a plain value are included, options set
+    ///     with rocksdict objects (caches, table factories, prefix
+    ///     extractors, ...) or not readable with the rocksdb C API are not.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         import json
+    ///         from rocksdict import Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_max_background_jobs(4)
+    ///         config = json.dumps(opt.to_dict())
+    ///         opt = Options.from_dict(json.loads(config))
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let opt = self.inner_opt.inner() as *mut librocksdb_sys::rocksdb_options_t;
+        let result = PyDict::new_bound(py);
+        result.set_item("raw_mode", self.raw_mode)?;
+        result.set_item("ordered_numeric_keys", self.ordered_numeric_keys)?;
+        options_to_dict!(
+            result,
+            opt,
+            bool {
+            create_if_missing => rocksdb_options_get_create_if_missing,
+            create_missing_column_families => rocksdb_options_get_create_missing_column_families,
+            error_if_exists => rocksdb_options_get_error_if_exists,
+            paranoid_checks => rocksdb_options_get_paranoid_checks,
+            level_compaction_dynamic_level_bytes =>
+                rocksdb_options_get_level_compaction_dynamic_level_bytes,
+            optimize_filters_for_hits => rocksdb_options_get_optimize_filters_for_hits,
+            use_fsync => rocksdb_options_get_use_fsync,
+            allow_concurrent_memtable_write => rocksdb_options_get_allow_concurrent_memtable_write,
+            enable_write_thread_adaptive_yield =>
+                rocksdb_options_get_enable_write_thread_adaptive_yield,
+            use_direct_reads => rocksdb_options_get_use_direct_reads,
+            use_direct_io_for_flush_and_compaction =>
+                rocksdb_options_get_use_direct_io_for_flush_and_compaction,
+            is_fd_close_on_exec => rocksdb_options_get_is_fd_close_on_exec,
+            unordered_write => rocksdb_options_get_unordered_write,
+            disable_auto_compactions => rocksdb_options_get_disable_auto_compactions,
+            inplace_update_support => rocksdb_options_get_inplace_update_support,
+            skip_checking_sst_file_sizes_on_db_open =>
+                rocksdb_options_get_skip_checking_sst_file_sizes_on_db_open,
+            enable_pipelined_write => rocksdb_options_get_enable_pipelined_write,
+            report_bg_io_stats => rocksdb_options_get_report_bg_io_stats,
+            advise_random_on_open => rocksdb_options_get_advise_random_on_open,
+            use_adaptive_mutex => rocksdb_options_get_use_adaptive_mutex,
+            skip_stats_update_on_db_open => rocksdb_options_get_skip_stats_update_on_db_open,
+            allow_mmap_writes => rocksdb_options_get_allow_mmap_writes,
+            allow_mmap_reads => rocksdb_options_get_allow_mmap_reads,
+            atomic_flush => rocksdb_options_get_atomic_flush,
+            manual_wal_flush => rocksdb_options_get_manual_wal_flush,
+            enable_blob_files => rocksdb_options_get_enable_blob_files,
+            enable_blob_gc => rocksdb_options_get_enable_blob_gc,
+            avoid_unnecessary_blocking_io => rocksdb_options_get_avoid_unnecessary_blocking_io,
+            }
+            value {
+            compaction_readahead_size => rocksdb_options_get_compaction_readahead_size,
+            delete_obsolete_files_period_micros =>
+                rocksdb_options_get_delete_obsolete_files_period_micros,
+            max_open_files => rocksdb_options_get_max_open_files,
+            max_file_opening_threads => rocksdb_options_get_max_file_opening_threads,
+            bytes_per_sync => rocksdb_options_get_bytes_per_sync,
+            wal_bytes_per_sync => rocksdb_options_get_wal_bytes_per_sync,
+            writable_file_max_buffer_size => rocksdb_options_get_writable_file_max_buffer_size,
+            max_sequential_skip_in_iterations =>
+                rocksdb_options_get_max_sequential_skip_in_iterations,
+            table_cache_num_shard_bits => rocksdb_options_get_table_cache_numshardbits,
+            target_file_size_multiplier => rocksdb_options_get_target_file_size_multiplier,
+            max_write_buffer_number => rocksdb_options_get_max_write_buffer_number,
+            write_buffer_size => rocksdb_options_get_write_buffer_size,
+            db_write_buffer_size => rocksdb_options_get_db_write_buffer_size,
+            max_bytes_for_level_base => rocksdb_options_get_max_bytes_for_level_base,
+            max_bytes_for_level_multiplier => rocksdb_options_get_max_bytes_for_level_multiplier,
+            max_manifest_file_size => rocksdb_options_get_max_manifest_file_size,
+            target_file_size_base => rocksdb_options_get_target_file_size_base,
+            min_write_buffer_number_to_merge =>
+                rocksdb_options_get_min_write_buffer_number_to_merge,
+            level_zero_file_num_compaction_trigger =>
+                rocksdb_options_get_level0_file_num_compaction_trigger,
+            level_zero_slowdown_writes_trigger =>
+                rocksdb_options_get_level0_slowdown_writes_trigger,
+            level_zero_stop_writes_trigger => rocksdb_options_get_level0_stop_writes_trigger,
+            ttl => rocksdb_options_get_ttl,
+            max_subcompactions => rocksdb_options_get_max_subcompactions,
+            max_background_jobs => rocksdb_options_get_max_background_jobs,
+            max_background_compactions => rocksdb_options_get_max_background_compactions,
+            memtable_huge_page_size => rocksdb_options_get_memtable_huge_page_size,
+            max_successive_merges => rocksdb_options_get_max_successive_merges,
+            bloom_locality => rocksdb_options_get_bloom_locality,
+            inplace_update_locks => rocksdb_options_get_inplace_update_num_locks,
+            max_write_buffer_size_to_maintain =>
+                rocksdb_options_get_max_write_buffer_size_to_maintain,
+            max_total_wal_size => rocksdb_options_get_max_total_wal_size,
+            stats_dump_period_sec => rocksdb_options_get_stats_dump_period_sec,
+            stats_persist_period_sec => rocksdb_options_get_stats_persist_period_sec,
+            num_levels => rocksdb_options_get_num_levels,
+            memtable_prefix_bloom_ratio => rocksdb_options_get_memtable_prefix_bloom_size_ratio,
+            max_compaction_bytes => rocksdb_options_get_max_compaction_bytes,
+            wal_ttl_seconds => rocksdb_options_get_WAL_ttl_seconds,
+            wal_size_limit_mb => rocksdb_options_get_WAL_size_limit_MB,
+            manifest_preallocation_size => rocksdb_options_get_manifest_preallocation_size,
+            keep_log_file_num => rocksdb_options_get_keep_log_file_num,
+            max_log_file_size => rocksdb_options_get_max_log_file_size,
+            log_file_time_to_roll => rocksdb_options_get_log_file_time_to_roll,
+            recycle_log_file_num => rocksdb_options_get_recycle_log_file_num,
+            soft_pending_compaction_bytes_limit =>
+                rocksdb_options_get_soft_pending_compaction_bytes_limit,
+            hard_pending_compaction_bytes_limit =>
+                rocksdb_options_get_hard_pending_compaction_bytes_limit,
+            arena_block_size => rocksdb_options_get_arena_block_size,
+            min_blob_size => rocksdb_options_get_min_blob_size,
+            blob_file_size => rocksdb_options_get_blob_file_size,
+            blob_gc_age_cutoff => rocksdb_options_get_blob_gc_age_cutoff,
+            blob_gc_force_threshold => rocksdb_options_get_blob_gc_force_threshold,
+            blob_compaction_readahead_size => rocksdb_options_get_blob_compaction_readahead_size,
+            }
+        );
+        Ok(result)
+    }
+
+    /// Create options from a dict, as returned by `Options.to_dict()`.
+    ///
+    /// Options missing from the dict keep their default value.
+    ///
+    /// Args:
+    ///     options: dict from option name to value, raises an exception
+    ///         listing similar names for unknown names.
+    #[staticmethod]
+    pub fn from_dict(options: &Bound<PyDict>, py: Python) -> PyResult<Py<OptionsPy>> {
+        let raw_mode = match options.get_item("raw_mode")? {
+            None => false,
+            Some(raw_mode) => raw_mode.extract()?,
+        };
+        let opt = Bound::new(py, OptionsPy::new(raw_mode))?;
+        let known = opt.borrow().to_dict(py)?;
+        for (name, value) in options {
+            let name: String = name.extract()?;
+            if name == "raw_mode" {
+                continue;
+            }
+            if !known.contains(&name)? {
+                let known: Vec<String> = known.keys().extract()?;
+                let known = known.iter().map(String::as_str).collect();
+                return Err(unknown_name_error("option", &name, known));
+            }
+            let setter = format!("set_{name}");
+            let setter = if opt.hasattr(setter.as_str())? {
+                setter
+            } else {
+                name
+            };
+            opt.call_method1(setter.as_str(), (value,))?;
+        }
+        Ok(opt.unbind())
+    }
+
     /// By default, RocksDB uses only one background thread for flush and
     /// compaction. Calling this function will set it up such that total of
     /// `total_threads` is used. Good value for `total_threads` is the number of
@@ -724,6 +1418,26 @@ impl OptionsPy {
             .create_missing_column_families(create_missing_cfs)
     }
 
+    /// If true, the column families passed to `Rdict(column_families=...)`
+    /// that do not exist yet are created when opening the database.
+    ///
+    /// Same as `create_missing_column_families()`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_create_missing_column_families(True)
+    ///         db = Rdict("./tmp", opt, column_families={"users": Options(), "orders": Options()})
+    ///         users = db.get_column_family("users")
+    ///
+    /// Default: `false`
+    pub fn set_create_missing_column_families(&mut self, create_missing_cfs: bool) {
+        self.create_missing_column_families(create_missing_cfs)
+    }
+
     /// Specifies whether an error should be raised if the database already exists.
     ///
     /// Default: false
@@ -742,11 +1456,30 @@ impl OptionsPy {
     /// the database will switch to read-only mode and fail all other
     /// Write operations.
     ///
-    /// Default: false
+    /// When opening the db, the sizes of the table files are checked
+    /// against the MANIFEST (see `set_skip_checking_sst_file_sizes_on_db_open`),
+    /// so a truncated or appended table file makes the open fail.
+    /// Set it to false to open a damaged db and salvage what is readable.
+    ///
+    /// Default: true
     pub fn set_paranoid_checks(&mut self, enabled: bool) {
         self.inner_opt.set_paranoid_checks(enabled)
     }
 
+    /// If true, every table file written by a flush or a compaction is
+    /// read back after it is written, to verify its keys and checksums
+    /// before it is added to the db. Writes become more expensive, but a
+    /// corruption is caught when the file is created instead of when it is read.
+    ///
+    /// Notes:
+    ///     This option is not exposed by the rocksdb C API,
+    ///     it is set with a rocksdb options string.
+    ///
+    /// Default: false
+    pub fn set_paranoid_file_checks(&mut self, enabled: bool) -> PyResult<()> {
+        self.set_options_from_string(&format!("paranoid_file_checks={enabled}"))
+    }
+
     /// A list of paths where SST files can be put into, with its target size.
     /// Newer data is placed into paths specified earlier in the vector while
     /// older data gradually moves to paths specified later in the vector.
@@ -771,24 +1504,40 @@ impl OptionsPy {
     /// If left empty, only one path will be used, which is `path` passed when
     /// opening the DB.
     ///
+    /// The paths are created when opening the DB if they are missing.
+    /// Pass the same options to `Rdict.destroy()` to delete the files in all the paths.
+    ///
+    /// Notes:
+    ///     Per column family paths (`cf_paths`) are not available
+    ///     in the rocksdb C API, and cannot be set.
+    ///
     /// Default: empty
     ///
-    ///     from rocksdict import Options, DBPath
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options, DBPath
+    ///
+    ///         opt = Options()
+    ///         flash_path = DBPath("/flash_path", 10 * 1024 * 1024 * 1024) # 10 GB
+    ///         hard_drive = DBPath("/hard_drive", 2 * 1024 * 1024 * 1024 * 1024) # 2 TB
+    ///         opt.set_db_paths([flash_path, hard_drive])
     ///
-    ///     opt = Options()
-    ///     flash_path = DBPath("/flash_path", 10 * 1024 * 1024 * 1024) # 10 GB
-    ///     hard_drive = DBPath("/hard_drive", 2 * 1024 * 1024 * 1024 * 1024) # 2 TB
-    ///     opt.set_db_paths([flash_path, hard_drive])
+    ///         # or with (path, target_size) tuples
+    ///         opt.set_db_paths([("/flash_path", 10 * 1024 ** 3), ("/hard_drive", 2 * 1024 ** 4)])
     pub fn set_db_paths(&mut self, paths: &Bound<PyList>) -> PyResult<()> {
         let mut db_paths = Vec::with_capacity(paths.len());
         for p in paths.iter() {
-            let path: &Bound<DBPathPy> = p.downcast()?;
-            db_paths.push(
-                match DBPath::new(&path.borrow().path, path.borrow().target_size) {
-                    Ok(p) => p,
-                    Err(e) => return Err(PyException::new_err(e.into_string())),
-                },
-            );
+            let (path, target_size) = match p.downcast::<DBPathPy>() {
+                Ok(path) => (path.borrow().path.clone(), path.borrow().target_size),
+                Err(_) => p.extract::<(PathBuf, u64)>().map_err(|_| {
+                    PyException::new_err("db paths must be `DBPath` or `(path, target_size)`")
+                })?,
+            };
+            db_paths.push(match DBPath::new(&path, target_size) {
+                Ok(p) => p,
+                Err(e) => return Err(PyException::new_err(e.into_string())),
+            });
         }
         self.inner_opt.set_db_paths(&db_paths);
         Ok(())
@@ -798,8 +1547,13 @@ impl OptionsPy {
     /// e.g. to read/write files, schedule background work, etc. In the near
     /// future, support for doing storage operations such as read/write files
     /// through env will be deprecated in favor of file_system.
+    ///
+    /// Notes:
+    ///     With `Env.mem_env()`, `Rdict` does not create anything on disk,
+    ///     the data is lost once the last object using the env is deleted.
     pub fn set_env(&mut self, env: &EnvPy) {
-        self.inner_opt.set_env(&env.0)
+        self.inner_opt.set_env(&env.inner);
+        self.in_memory = env.in_memory;
     }
 
     /// Sets the compression algorithm that will be used for compressing blocks.
@@ -887,6 +1641,74 @@ impl OptionsPy {
         self.inner_opt.set_zstd_max_train_bytes(value)
     }
 
+    /// Sets the compression algorithm of the bottommost level, which
+    /// usually holds most of the data. It overrides `set_compression_type`
+    /// and `set_compression_per_level` for that level.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options, DBCompressionType
+    ///
+    ///         # hot levels uncompressed, bottommost level with zstd
+    ///         opts = Options()
+    ///         opts.set_compression_type(DBCompressionType.none())
+    ///         opts.set_bottommost_compression_type(DBCompressionType.zstd())
+    ///         opts.set_bottommost_compression_options(-14, 3, 0, 16 * 1024)
+    ///
+    /// Default: the compression of the level is used
+    pub fn set_bottommost_compression_type(&mut self, t: &DBCompressionTypePy) {
+        self.inner_opt.set_bottommost_compression_type(t.0)
+    }
+
+    /// Same as `set_compression_options`, for the bottommost level only.
+    ///
+    /// Args:
+    ///     enabled: if False, the bottommost level uses the options of
+    ///         `set_compression_options` instead.
+    #[pyo3(signature = (w_bits, level, strategy, max_dict_bytes, enabled = true))]
+    pub fn set_bottommost_compression_options(
+        &mut self,
+        w_bits: c_int,
+        level: c_int,
+        strategy: c_int,
+        max_dict_bytes: c_int,
+        enabled: bool,
+    ) {
+        self.inner_opt.set_bottommost_compression_options(
+            w_bits,
+            level,
+            strategy,
+            max_dict_bytes,
+            enabled,
+        )
+    }
+
+    /// Same as `set_zstd_max_train_bytes`, for the bottommost level only.
+    ///
+    /// Training a zstd dictionary on the bottommost level, where most of
+    /// the data lives, requires `max_dict_bytes` to be set with
+    /// `set_bottommost_compression_options`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options, DBCompressionType
+    ///
+    ///         opts = Options()
+    ///         opts.set_bottommost_compression_type(DBCompressionType.zstd())
+    ///         opts.set_bottommost_compression_options(-14, 3, 0, 16 * 1024)
+    ///         opts.set_bottommost_zstd_max_train_bytes(100 * 16 * 1024)
+    ///
+    /// Args:
+    ///     enabled: if False, the bottommost level uses the value of
+    ///         `set_zstd_max_train_bytes` instead.
+    #[pyo3(signature = (value, enabled = true))]
+    pub fn set_bottommost_zstd_max_train_bytes(&mut self, value: c_int, enabled: bool) {
+        self.inner_opt
+            .set_bottommost_zstd_max_train_bytes(value, enabled)
+    }
+
     /// If non-zero, we perform bigger reads when doing compaction. If you're
     /// running RocksDB on spinning disks, you should set this to at least 2MB.
     /// That way RocksDB's compaction is doing sequential instead of random reads.
@@ -951,6 +1773,75 @@ impl OptionsPy {
         Ok(())
     }
 
+    /// Cut the table files written by compactions at every change of the
+    /// first `prefix_len` bytes of the keys, so that no table file spans
+    /// two prefixes.
+    ///
+    /// With one prefix per tenant, all the files of a tenant can then be
+    /// dropped cheaply with `Rdict.delete_files_in_range()`.
+    ///
+    /// Notes:
+    ///     The prefix is taken from the encoded keys, use
+    ///     `Options(raw_mode=True)` to control the key bytes. Flushes
+    ///     are not partitioned, only compactions. Custom partitioners
+    ///     (e.g. a python callback) are not supported by the rocksdb C API.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options(raw_mode=True)
+    ///         opt.set_sst_partitioner_fixed_prefix(4)
+    ///         db = Rdict("./tmp", opt)
+    ///         db[b"ten1key1"] = b"v"
+    ///         db[b"ten2key1"] = b"v"
+    ///         db.flush()
+    ///         db.compact_range(None, None)
+    ///
+    /// Args:
+    ///     prefix_len: length of the prefixes in bytes.
+    pub fn set_sst_partitioner_fixed_prefix(&mut self, prefix_len: usize) -> PyResult<()> {
+        if prefix_len == 0 {
+            return Err(PyException::new_err("prefix_len must be positive"));
+        }
+        self.set_options_from_string(&format!(
+            "sst_partitioner_factory={{id=SstPartitionerFixedPrefixFactory;length={prefix_len}}}"
+        ))
+    }
+
+    /// Compute a checksum of every table file written by the db, stored
+    /// in the MANIFEST, to detect the corruption of whole files
+    /// (e.g. when copying them).
+    ///
+    /// Notes:
+    ///     The file checksums are verified by rocksdb tools (e.g.
+    ///     `ldb file_checksum_dump`) and when ingesting files. The rocksdb
+    ///     C API does not expose `VerifyFileChecksums()`, use
+    ///     `Rdict.verify_checksum()` to verify the block checksums.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Options, FileChecksumGenFactory
+    ///
+    ///         opt = Options()
+    ///         opt.set_file_checksum_gen_factory(FileChecksumGenFactory.crc32c())
+    pub fn set_file_checksum_gen_factory(
+        &mut self,
+        factory: &FileChecksumGenFactoryPy,
+    ) -> PyResult<()> {
+        self.set_options_from_string(&format!("file_checksum_gen_factory={{id={}}}", factory.0))
+    }
+
+    /// Sets a built-in merge operator, used by `Rdict.merge` and `WriteBatch.merge`.
+    ///
+    /// See `MergeOperator` for the available operators.
+    pub fn set_merge_operator(&mut self, merge_operator: &MergeOperatorPy) {
+        OptionsPy::set_merge_operator_inner(&mut self.inner_opt, &merge_operator.0, self.raw_mode);
+        self.merge_operator = Some(merge_operator.0.clone());
+    }
+
     // pub fn add_comparator(&mut self, name: &str, compare_fn: CompareFn) {
     //     self.0.add_comparator(name, compare_fn)
     // }
@@ -994,13 +1885,20 @@ impl OptionsPy {
     /// on target_file_size_base and target_file_size_multiplier for level-based
     /// compaction. For universal-style compaction, you can usually set it to `-1`.
     ///
+    /// A positive value caps the file descriptors used for SST files: the
+    /// table cache then closes the least recently used files, at the cost of
+    /// reopening them on a later read. Other file descriptors (WAL, MANIFEST,
+    /// LOCK, ...) are not counted, so leave some headroom below the process limit.
+    ///
     /// Default: `-1`
     pub fn set_max_open_files(&mut self, nfiles: c_int) {
         self.inner_opt.set_max_open_files(nfiles)
     }
 
     /// If max_open_files is -1, DB will open all files on DB::Open(). You can
-    /// use this option to increase the number of threads used to open the files.
+    /// use this option to increase the number of threads used to open the files,
+    /// which reduces the open latency of a db with many SST files.
+    ///
     /// Default: 16
     pub fn set_max_file_opening_threads(&mut self, nthreads: c_int) {
         self.inner_opt.set_max_file_opening_threads(nthreads)
@@ -1075,9 +1973,24 @@ impl OptionsPy {
     /// It is strongly recommended to set enable_write_thread_adaptive_yield
     /// if you are going to use this feature.
     ///
+    /// Notes:
+    ///     Raises an exception when enabled together with
+    ///     `set_inplace_update_support(True)`.
+    ///
     /// Default: true
-    pub fn set_allow_concurrent_memtable_write(&mut self, allow: bool) {
-        self.inner_opt.set_allow_concurrent_memtable_write(allow)
+    pub fn set_allow_concurrent_memtable_write(&mut self, allow: bool) -> PyResult<()> {
+        let inplace_update_support = unsafe {
+            librocksdb_sys::rocksdb_options_get_inplace_update_support(
+                self.inner_opt.inner() as *mut _
+            )
+        };
+        if allow && inplace_update_support != 0 {
+            return Err(PyException::new_err(
+                "allow_concurrent_memtable_write is not compatible with inplace_update_support",
+            ));
+        }
+        self.inner_opt.set_allow_concurrent_memtable_write(allow);
+        Ok(())
     }
 
     /// If true, threads synchronizing with the write batch group leader will wait for up to
@@ -1109,6 +2022,15 @@ impl OptionsPy {
     /// buffered. The hardware buffer of the devices may however still
     /// be used. Memory mapped files are not impacted by these parameters.
     ///
+    /// Notes:
+    ///     Direct reads bypass the OS page cache, so the block cache is
+    ///     the only cache of the data and should be sized accordingly
+    ///     (see `BlockBasedOptions.set_block_cache()`). The file system
+    ///     must support `O_DIRECT` (e.g. tmpfs does not), reads are then
+    ///     aligned to the logical sector size of the device, and
+    ///     `set_allow_mmap_reads(True)` cannot be used together with
+    ///     direct reads, opening the db would fail.
+    ///
     /// Default: false
     pub fn set_use_direct_reads(&mut self, enabled: bool) {
         self.inner_opt.set_use_direct_reads(enabled)
@@ -1122,6 +2044,12 @@ impl OptionsPy {
     /// be used. Memory mapped files are not impacted by these parameters.
     /// they may or may not improve performance depending on the use case
     ///
+    /// Notes:
+    ///     Writes are buffered and aligned to the logical sector size,
+    ///     see `set_writable_file_max_buffer_size()`. The file system must
+    ///     support `O_DIRECT`, and `set_allow_mmap_writes(True)` cannot
+    ///     be used together with this option.
+    ///
     /// Default: false
     pub fn set_use_direct_io_for_flush_and_compaction(&mut self, enabled: bool) {
         self.inner_opt
@@ -1226,7 +2154,14 @@ impl OptionsPy {
         self.inner_opt.set_max_bytes_for_level_base(size)
     }
 
+    /// Sets the ratio between the maximum total data sizes of two successive
+    /// levels, see `set_max_bytes_for_level_base()`. A smaller multiplier
+    /// lowers the write amplification, at the cost of more levels (and thus
+    /// a higher read and space amplification).
+    ///
     /// Default: `10`
+    ///
+    /// Dynamically changeable through SetOptions() API
     pub fn set_max_bytes_for_level_multiplier(&mut self, mul: f64) {
         self.inner_opt.set_max_bytes_for_level_multiplier(mul)
     }
@@ -1300,19 +2235,57 @@ impl OptionsPy {
 
     /// Sets the compaction style.
     ///
+    /// The compaction style is set per column family: for instance a time
+    /// series column family can use FIFO compaction (see `FifoCompactOptions`)
+    /// by passing these options to `Rdict.create_column_family()`, or in the
+    /// `column_families` of `Rdict`, while the other ones stay leveled.
+    ///
     /// Default: DBCompactionStyle.level()
     pub fn set_compaction_style(&mut self, style: &DBCompactionStylePy) {
         self.inner_opt.set_compaction_style(style.0)
     }
 
     /// Sets the options needed to support Universal Style compactions.
+    ///
+    /// Notes:
+    ///     Only used with `set_compaction_style(DBCompactionStyle.universal())`.
     pub fn set_universal_compaction_options(&mut self, uco: &UniversalCompactOptionsPy) {
         self.inner_opt.set_universal_compaction_options(&uco.into())
     }
 
     /// Sets the options for FIFO compaction style.
+    ///
+    /// Notes:
+    ///     Only used with `set_compaction_style(DBCompactionStyle.fifo())`.
     pub fn set_fifo_compaction_options(&mut self, fco: &FifoCompactOptionsPy) {
-        self.inner_opt.set_fifo_compaction_options(&fco.into())
+        unsafe {
+            let fifo = librocksdb_sys::rocksdb_fifo_compaction_options_create();
+            librocksdb_sys::rocksdb_fifo_compaction_options_set_max_table_files_size(
+                fifo,
+                fco.max_table_files_size,
+            );
+            librocksdb_sys::rocksdb_fifo_compaction_options_set_allow_compaction(
+                fifo,
+                fco.allow_compaction as c_uchar,
+            );
+            // the options are copied
+            librocksdb_sys::rocksdb_options_set_fifo_compaction_options(
+                self.inner_opt.inner() as *mut _,
+                fifo,
+            );
+            librocksdb_sys::rocksdb_fifo_compaction_options_destroy(fifo);
+        }
+    }
+
+    /// Sets the ttl in seconds of the data in table files.
+    ///
+    /// With FIFO compaction, table files older than the ttl are deleted.
+    /// With level compaction, files containing data older than the ttl
+    /// go through compaction.
+    ///
+    /// Default: 0 (disabled for FIFO compaction, 30 days for level compaction)
+    pub fn set_ttl(&mut self, secs: u64) {
+        unsafe { librocksdb_sys::rocksdb_options_set_ttl(self.inner_opt.inner() as *mut _, secs) }
     }
 
     /// Sets unordered_write to true trades higher write throughput with
@@ -1338,15 +2311,39 @@ impl OptionsPy {
     /// number) that will change the state visible to the snapshot after they are
     /// landed to the memtable.
     ///
+    /// Notes:
+    ///     Raises an exception when enabled together with
+    ///     `set_enable_pipelined_write(True)`.
+    ///
     /// Default: false
-    pub fn set_unordered_write(&mut self, unordered: bool) {
-        self.inner_opt.set_unordered_write(unordered)
+    pub fn set_unordered_write(&mut self, unordered: bool) -> PyResult<()> {
+        let pipelined_write = unsafe {
+            librocksdb_sys::rocksdb_options_get_enable_pipelined_write(
+                self.inner_opt.inner() as *mut _
+            )
+        };
+        if unordered && pipelined_write != 0 {
+            return Err(PyException::new_err(
+                "unordered_write is not compatible with enable_pipelined_write",
+            ));
+        }
+        self.inner_opt.set_unordered_write(unordered);
+        Ok(())
     }
 
     /// Sets maximum number of threads that will
     /// concurrently perform a compaction job by breaking it into multiple,
     /// smaller ones that are run simultaneously.
     ///
+    /// A large compaction (e.g. of level 0 after a bulk load) otherwise runs
+    /// on a single thread. The subcompactions are run by the threads of
+    /// `set_max_background_jobs()`.
+    ///
+    /// Notes:
+    ///     `compaction_pri` is not exposed by the rocksdb C API, it can be
+    ///     changed on an open db with
+    ///     `db.set_options({"compaction_pri": "kMinOverlappingRatio"})`.
+    ///
     /// Default: 1 (i.e. no subcompactions)
     pub fn set_max_subcompactions(&mut self, num: u32) {
         self.inner_opt.set_max_subcompactions(num)
@@ -1362,6 +2359,23 @@ impl OptionsPy {
         self.inner_opt.set_max_background_jobs(jobs)
     }
 
+    /// Sets maximum number of concurrent background compaction jobs.
+    ///
+    /// rocksdb derives it from `set_max_background_jobs()` (about three
+    /// quarters of the jobs are compactions), which should be preferred.
+    ///
+    /// Default: -1 (derived from `max_background_jobs`)
+    ///
+    /// Dynamically changeable through SetDBOptions() API.
+    pub fn set_max_background_compactions(&mut self, compactions: c_int) {
+        unsafe {
+            librocksdb_sys::rocksdb_options_set_max_background_compactions(
+                self.inner_opt.inner() as *mut _,
+                compactions,
+            )
+        }
+    }
+
     /// Disables automatic compactions. Manual compactions can still
     /// be issued on this column family
     ///
@@ -1422,8 +2436,15 @@ impl OptionsPy {
     /// * new sizeof(new_value) <= sizeof(old_value)
     /// * old_value for that key is a put i.e. kTypeValue
     ///
+    /// Notes:
+    ///     Inplace updates are not compatible with concurrent memtable writes,
+    ///     so enabling them disables `allow_concurrent_memtable_write`.
+    ///
     /// Default: false.
     pub fn set_inplace_update_support(&mut self, enabled: bool) {
+        if enabled {
+            self.inner_opt.set_allow_concurrent_memtable_write(false);
+        }
         self.inner_opt.set_inplace_update_support(enabled)
     }
 
@@ -1505,27 +2526,55 @@ impl OptionsPy {
     /// write throughput and reduce latency of the prepare phase of two-phase
     /// commit.
     ///
+    /// Notes:
+    ///     Raises an exception when enabled together with
+    ///     `set_unordered_write(True)`.
+    ///
     /// Default: false
-    pub fn set_enable_pipelined_write(&mut self, value: bool) {
-        self.inner_opt.set_enable_pipelined_write(value)
+    pub fn set_enable_pipelined_write(&mut self, value: bool) -> PyResult<()> {
+        let unordered_write = unsafe {
+            librocksdb_sys::rocksdb_options_get_unordered_write(self.inner_opt.inner() as *mut _)
+        };
+        if value && unordered_write != 0 {
+            return Err(PyException::new_err(
+                "enable_pipelined_write is not compatible with unordered_write",
+            ));
+        }
+        self.inner_opt.set_enable_pipelined_write(value);
+        Ok(())
     }
 
     /// Defines the underlying memtable implementation.
     /// See official [wiki](https://github.com/facebook/rocksdb/wiki/MemTable) for more information.
     /// Defaults to using a skiplist.
     ///
+    /// Notes:
+    ///     The hash based memtables (`hash_skip_list` and `hash_link_list`)
+    ///     require a prefix extractor, call `set_prefix_extractor` first.
+    ///     Only the default skiplist supports concurrent memtable writes,
+    ///     so this method disables `allow_concurrent_memtable_write`.
+    ///     The reserved size of the vector memtable is not exposed by the
+    ///     rocksdb C API.
+    ///
     /// Example:
     ///     ::
     ///
-    ///         from rocksdict import Options, MemtableFactory
+    ///         from rocksdict import Options, MemtableFactory, SliceTransform
     ///         opts = Options()
     ///         factory = MemtableFactory.hash_skip_list(bucket_count=1_000_000,
     ///                                                  height=4,
     ///                                                  branching_factor=4)
     ///
-    ///         opts.set_allow_concurrent_memtable_write(false)
+    ///         opts.set_prefix_extractor(SliceTransform.create_fixed_prefix(4))
     ///         opts.set_memtable_factory(factory)
-    pub fn set_memtable_factory(&mut self, factory: &MemtableFactoryPy) {
+    pub fn set_memtable_factory(&mut self, factory: &MemtableFactoryPy) -> PyResult<()> {
+        let is_hash_memtable = !matches!(factory.0, MemtableFactory::Vector);
+        if is_hash_memtable && self.prefix_extractor.is_none() {
+            return Err(PyException::new_err(
+                "hash memtables require a prefix extractor, call set_prefix_extractor first",
+            ));
+        }
+        self.inner_opt.set_allow_concurrent_memtable_write(false);
         self.inner_opt.set_memtable_factory(match factory.0 {
             MemtableFactory::Vector => MemtableFactory::Vector,
             MemtableFactory::HashSkipList {
@@ -1540,7 +2589,8 @@ impl OptionsPy {
             MemtableFactory::HashLinkList { bucket_count } => {
                 MemtableFactory::HashLinkList { bucket_count }
             }
-        })
+        });
+        Ok(())
     }
 
     pub fn set_block_based_table_factory(&mut self, factory: &BlockBasedOptionsPy) {
@@ -1552,18 +2602,26 @@ impl OptionsPy {
     /// implementation of TableBuilder and TableReader with default
     /// BlockBasedTableOptions).
     /// See official [wiki](https://github.com/facebook/rocksdb/wiki/CuckooTable-Format) for more information on this table format.
+    ///
+    /// Notes:
+    ///     Cuckoo tables are meant for point lookups: all keys must have
+    ///     the same length and all values must have the same length,
+    ///     otherwise `flush()` raises an exception. mmap reads are
+    ///     required (`set_allow_mmap_reads(True)`). Iteration is slow,
+    ///     since a table file is sorted in memory on first use, and
+    ///     errors of unsupported operations are raised by the iterators.
+    ///
     /// Example:
     ///     ::
     ///
     ///         from rocksdict import Options, CuckooTableOptions
     ///
-    ///         opts = Options()
-    ///         factory_opts = CuckooTableOptions()
-    ///         factory_opts.set_hash_ratio(0.8)
-    ///         factory_opts.set_max_search_depth(20)
+    ///         opts = Options(raw_mode=True)
+    ///         opts.set_allow_mmap_reads(True)
+    ///         factory_opts = CuckooTableOptions(hash_table_ratio=0.8, max_search_depth=20)
     ///         factory_opts.set_cuckoo_block_size(10)
-    ///         factory_opts.set_identity_as_first_hash(true)
-    ///         factory_opts.set_use_module_hash(false)
+    ///         factory_opts.set_identity_as_first_hash(True)
+    ///         factory_opts.set_use_module_hash(False)
     ///
     ///         opts.set_cuckoo_table_factory(factory_opts)
     pub fn set_cuckoo_table_factory(&mut self, factory: &CuckooTableOptionsPy) {
@@ -1578,22 +2636,50 @@ impl OptionsPy {
     /// See official [wiki](https://github.com/facebook/rocksdb/wiki/PlainTable-Format) for more
     /// information.
     ///
+    /// Notes:
+    ///     Plain tables are only supported in `raw_mode`: the prefix
+    ///     hash index and `user_key_length` operate on the stored keys,
+    ///     which in non-raw mode carry a type byte and are ordered by the
+    ///     rocksdict comparator rather than bytewise.
+    ///     A prefix extractor and mmap reads are required, set them
+    ///     with `set_prefix_extractor()` and `set_allow_mmap_reads(True)`
+    ///     before calling this method.
+    ///
     /// Example:
     ///     ::
     ///
-    ///         from rocksdict import Options, PlainTableFactoryOptions
+    ///         from rocksdict import Options, PlainTableFactoryOptions, SliceTransform
     ///
-    ///         opts = Options()
-    ///         factory_opts = PlainTableFactoryOptions()
-    ///         factory_opts.user_key_length = 0
-    ///         factory_opts.bloom_bits_per_key = 20
-    ///         factory_opts.hash_table_ratio = 0.75
-    ///         factory_opts.index_sparseness = 16
+    ///         opts = Options(raw_mode=True)
+    ///         opts.set_prefix_extractor(SliceTransform.create_fixed_prefix(4))
+    ///         opts.set_allow_mmap_reads(True)
+    ///         factory_opts = PlainTableFactoryOptions(user_key_length=8, bloom_bits_per_key=20)
     ///
     ///         opts.set_plain_table_factory(factory_opts)
-    pub fn set_plain_table_factory(&mut self, options: &PlainTableFactoryOptionsPy) {
-        self.inner_opt
-            .set_plain_table_factory(&options.to_opt(self.raw_mode))
+    pub fn set_plain_table_factory(
+        &mut self,
+        options: &PlainTableFactoryOptionsPy,
+    ) -> PyResult<()> {
+        if !self.raw_mode {
+            return Err(PyException::new_err(
+                "plain table requires raw_mode, use Options(raw_mode=True)",
+            ));
+        }
+        if self.prefix_extractor.is_none() {
+            return Err(PyException::new_err(
+                "plain table requires a prefix extractor, call set_prefix_extractor first",
+            ));
+        }
+        let allow_mmap_reads = unsafe {
+            librocksdb_sys::rocksdb_options_get_allow_mmap_reads(self.inner_opt.inner() as *mut _)
+        };
+        if allow_mmap_reads == 0 {
+            return Err(PyException::new_err(
+                "plain table requires mmap reads, call set_allow_mmap_reads(True) first",
+            ));
+        }
+        self.inner_opt.set_plain_table_factory(&options.to_opt());
+        Ok(())
     }
 
     /// Sets the start level to use compression.
@@ -1619,6 +2705,26 @@ impl OptionsPy {
 
     /// Recovery mode to control the consistency while replaying WAL.
     ///
+    /// - `tolerate_corrupted_tail_records`: ignore an incomplete record at
+    ///   the end of the WAL (a write interrupted by a crash), fail on any
+    ///   other corruption.
+    /// - `absolute_consistency`: fail to open on any corruption of the WAL,
+    ///   including an incomplete last record.
+    /// - `point_in_time`: stop replaying at the first corrupted record, so the
+    ///   db is recovered to a consistent point in time, losing the writes after
+    ///   the corruption. This is what gets a crashed db back online.
+    /// - `skip_any_corrupted_record`: skip the corrupted records and keep
+    ///   replaying, to salvage as much data as possible (consistency is lost).
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, DBRecoveryMode
+    ///
+    ///         opt = Options()
+    ///         opt.set_wal_recovery_mode(DBRecoveryMode.point_in_time())
+    ///         db = Rdict("./crashed_db", opt)
+    ///
     /// Default: DBRecoveryMode::PointInTime
     pub fn set_wal_recovery_mode(&mut self, mode: &DBRecoveryModePy) {
         self.inner_opt.set_wal_recovery_mode(mode.0)
@@ -1632,6 +2738,126 @@ impl OptionsPy {
         self.inner_opt.get_statistics()
     }
 
+    /// Level of the statistics collected, call `enable_statistics()` first.
+    ///
+    /// Default: `StatsLevel.except_detailed_timers()`
+    pub fn set_statistics_level(&mut self, level: &StatsLevelPy) {
+        self.inner_opt.set_statistics_level(level.0)
+    }
+
+    /// Sets the level of the messages written to the info log (the `LOG`
+    /// file in the db directory).
+    ///
+    /// Default: `LogLevel.info()`
+    pub fn set_info_log_level(&mut self, level: &LogLevelPy) {
+        self.inner_opt.set_log_level(level.0)
+    }
+
+    /// Send the info log to a python function instead of the `LOG` file.
+    ///
+    /// `callback(level, message)` is called for each message of at least
+    /// `level`, where `level` is one of `"debug"`, `"info"`, `"warn"`,
+    /// `"error"`, `"fatal"` and `"header"`.
+    ///
+    /// Notes:
+    ///     The callback is called from a dedicated thread, not from the
+    ///     thread that logged the message, since rocksdb background threads
+    ///     must not wait for the GIL. Messages are thus delivered
+    ///     asynchronously. Exceptions raised by the callback are printed and
+    ///     ignored. The callback is never released, and the logger is not
+    ///     restored when reopening the db without options.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         import logging
+    ///         from rocksdict import Rdict, Options, LogLevel
+    ///
+    ///         logger = logging.getLogger("rocksdb")
+    ///         levels = {"debug": logging.DEBUG, "info": logging.INFO,
+    ///                   "warn": logging.WARNING, "error": logging.ERROR,
+    ///                   "fatal": logging.CRITICAL, "header": logging.INFO}
+    ///
+    ///         opt = Options()
+    ///         opt.set_logger(lambda level, msg: logger.log(levels[level], msg),
+    ///                        LogLevel.warn())
+    ///         db = Rdict("./tmp", opt)
+    ///
+    /// Args:
+    ///     callback: a function taking the level and the message.
+    ///     level: the minimum level of the messages passed to the callback.
+    #[pyo3(signature = (callback, level = LogLevelPy::info()))]
+    pub fn set_logger(&mut self, callback: &Bound<PyAny>, level: LogLevelPy) -> PyResult<()> {
+        if !callback.is_callable() {
+            return Err(PyException::new_err("logger callback must be callable"));
+        }
+        let logger = create_callback_logger(callback.clone().unbind(), level.0);
+        unsafe {
+            librocksdb_sys::rocksdb_options_set_info_log(self.inner_opt.inner() as *mut _, logger);
+            // the options keep their own reference to the logger
+            librocksdb_sys::rocksdb_logger_destroy(logger);
+        }
+        Ok(())
+    }
+
+    /// Add a listener notified of the flushes, compactions and background
+    /// errors of the db.
+    ///
+    /// The listener is any object implementing some of the methods
+    /// `on_flush_begin(info)`, `on_flush_completed(info)`,
+    /// `on_compaction_begin(info)`, `on_compaction_completed(info)` and
+    /// `on_background_error(info)`, where `info` is a dict.
+    ///
+    /// Flush infos contain `cf_name`, `file_path`, `smallest_seqno`,
+    /// `largest_seqno`, `triggered_writes_slowdown` and `triggered_writes_stop`.
+    ///
+    /// Compaction infos contain `cf_name`, `status` (None or the error message),
+    /// `input_files`, `output_files`, `base_input_level`, `output_level`,
+    /// `elapsed_micros`, `num_corrupt_keys`, `input_records`, `output_records`,
+    /// `total_input_bytes` and `total_output_bytes`.
+    ///
+    /// Background error infos contain `reason` (`"flush"`, `"compaction"`,
+    /// `"write_callback"`, `"memtable"`, `"manifest_write"`, `"flush_no_wal"`
+    /// or `"manifest_write_no_wal"`) and `status` (the error message).
+    /// After a background error the db may stop accepting writes,
+    /// see `Rdict.background_errors`.
+    ///
+    /// Notes:
+    ///     Like `set_logger()`, the methods are called asynchronously from
+    ///     a dedicated thread, so that rocksdb background threads never wait
+    ///     for the GIL. Exceptions raised by the methods are printed and ignored.
+    ///     Listeners are not restored when reopening the db without options.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         class Listener:
+    ///             def on_flush_completed(self, info):
+    ///                 print("flushed", info["file_path"])
+    ///
+    ///             def on_compaction_completed(self, info):
+    ///                 print("compacted in", info["elapsed_micros"], "us")
+    ///
+    ///         opt = Options()
+    ///         opt.add_event_listener(Listener())
+    ///         db = Rdict("./tmp", opt)
+    ///
+    /// Args:
+    ///     listener: an object implementing at least one of the methods.
+    pub fn add_event_listener(&mut self, listener: &Bound<PyAny>) -> PyResult<()> {
+        let listener = create_event_listener(listener)?;
+        unsafe {
+            // the options take the ownership of the listener
+            librocksdb_sys::rocksdb_options_add_eventlistener(
+                self.inner_opt.inner() as *mut _,
+                listener,
+            );
+        }
+        Ok(())
+    }
+
     /// If not zero, dump `rocksdb.stats` to LOG every `stats_dump_period_sec`.
     ///
     /// Default: `600` (10 mins)
@@ -1698,6 +2924,10 @@ impl OptionsPy {
     /// Specifies the absolute path of the directory the
     /// write-ahead log (WAL) should be written to.
     ///
+    /// Notes:
+    ///     Pass the same options to `Rdict.destroy()` so that the
+    ///     WAL files in this directory are deleted as well.
+    ///
     /// Default: same directory as the database
     pub fn set_wal_dir(&mut self, path: &str) {
         self.inner_opt.set_wal_dir(Path::new(path))
@@ -1718,6 +2948,9 @@ impl OptionsPy {
     /// 4. If both are not 0, WAL files will be checked every 10 min and both
     ///    checks will be performed with ttl being first.
     ///
+    /// The archived logs are what `Rdict.get_updates_since()` reads once the
+    /// memtables are flushed, so these options control how far back it can reach.
+    ///
     /// Default: 0
     pub fn set_wal_ttl_seconds(&mut self, secs: u64) {
         self.inner_opt.set_wal_ttl_seconds(secs)
@@ -1727,12 +2960,36 @@ impl OptionsPy {
     ///
     /// If total size of WAL files is greater then wal_size_limit_mb,
     /// they will be deleted starting with the earliest until size_limit is met.
+    /// See `set_wal_ttl_seconds` for how the two options interact.
     ///
     /// Default: 0
     pub fn set_wal_size_limit_mb(&mut self, size: u64) {
         self.inner_opt.set_wal_size_limit_mb(size)
     }
 
+    /// If true, writes are buffered in memory and the WAL is only written
+    /// by `Rdict.flush_wal()` or when the buffer is full, which reduces
+    /// the write overhead of the WAL. Buffered writes are lost if the
+    /// process crashes, see `Rdict.flush_wal()`.
+    ///
+    /// Default: false
+    pub fn set_manual_wal_flush(&mut self, is_enabled: bool) {
+        self.inner_opt.set_manual_wal_flush(is_enabled)
+    }
+
+    /// If true, the MANIFEST tracks the synced WAL files and their sizes,
+    /// so that a missing or truncated WAL is detected when the db is opened,
+    /// instead of silently losing the writes.
+    ///
+    /// Notes:
+    ///     This option is not exposed by the rocksdb C API,
+    ///     it is set with a rocksdb options string.
+    ///
+    /// Default: false
+    pub fn set_track_and_verify_wals_in_manifest(&mut self, v: bool) -> PyResult<()> {
+        self.set_options_from_string(&format!("track_and_verify_wals_in_manifest={v}"))
+    }
+
     /// Sets the number of bytes to preallocate (via fallocate) the manifest files.
     ///
     /// Default is 4MB, which is reasonable to reduce random IO
@@ -1784,10 +3041,37 @@ impl OptionsPy {
         self.inner_opt.set_atomic_flush(atomic_flush)
     }
 
-    /// Sets global cache for table-level rows. Cache must outlive DB instance which uses it.
+    /// Sets global cache for table-level rows, so that point lookups of hot
+    /// keys do not decode their data block again.
+    ///
+    /// Notes:
+    ///     The cache can be shared with the block cache or with other
+    ///     databases (see `Cache`), it is kept alive by the options and
+    ///     the db. Only reads from table files are cached, keys still in
+    ///     the memtables are not. Hits and misses are counted by the
+    ///     `rocksdb.row.cache.hit` and `rocksdb.row.cache.miss` tickers
+    ///     (see `Options.enable_statistics()`).
+
+    ///     The row cache is a db option: it is shared by all the column
+    ///     families of the db, and is ignored in the options passed to
+    ///     `Rdict.create_column_family()`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, Cache
+    ///
+    ///         opt = Options()
+    ///         opt.set_row_cache(Cache(64 * 1024 * 1024))
+    ///         opt.enable_statistics()
+    ///         db = Rdict("./tmp", opt)
+    ///         db["hot"] = 1
+    ///         db.flush()
+    ///         for _ in range(10):
+    ///             db["hot"]
+    ///         print(db.get_statistics().ticker("rocksdb.row.cache.hit"))
     ///
     /// Default: null (disabled)
-    /// Not supported in ROCKSDB_LITE mode!
     pub fn set_row_cache(&mut self, cache: &CachePy) {
         self.inner_opt.set_row_cache(&cache.0)
     }
@@ -1808,6 +3092,21 @@ impl OptionsPy {
             .set_ratelimiter(rate_bytes_per_sec, refill_period_us, fairness)
     }
 
+    /// Use a `RateLimiter` to control the write rate of flush and compaction.
+    ///
+    /// Unlike `set_ratelimiter`, the same limiter can be shared by
+    /// several Options, so that all the DBs share a single rate budget.
+    ///
+    /// Default: disable
+    pub fn set_rate_limiter(&mut self, rate_limiter: &RateLimiterPy) {
+        unsafe {
+            librocksdb_sys::rocksdb_options_set_ratelimiter(
+                self.inner_opt.inner() as *mut _,
+                rate_limiter.0,
+            )
+        }
+    }
+
     /// Sets the maximal size of the info log file.
     ///
     /// If the log file is larger than `max_log_file_size`, a new info log file
@@ -1908,6 +3207,25 @@ impl OptionsPy {
     ///
     /// More details can be found here: [Integrated BlobDB](http://rocksdb.org/blog/2021/05/26/integrated-blob-db.html).
     ///
+    /// Values stored in blob files are read back transparently by `get`
+    /// and the iterators.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options
+    ///
+    ///         opt = Options()
+    ///         opt.set_enable_blob_files(True)
+    ///         opt.set_min_blob_size(4096)
+    ///         opt.set_enable_blob_gc(True)
+    ///         db = Rdict("./tmp", opt)
+    ///
+    ///         # the blob options can be changed at runtime
+    ///         db.set_options({"min_blob_size": "1024", "blob_garbage_collection_age_cutoff": "0.5"})
+    ///         print(db.property_int_value("rocksdb.num-blob-files"))
+    ///         print(db.property_value("rocksdb.blob-stats"))
+    ///
     /// Default: false (disable)
     ///
     /// Dynamically changeable through SetOptions() API
@@ -2048,17 +3366,65 @@ impl OptionsPy {
 
 #[pymethods]
 impl WriteOptionsPy {
+    /// All the fields can be set as keyword arguments, which is handy
+    /// to override the options of a single call:
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         db.put("durable", 1, write_opt=WriteOptions(sync=True))
     #[new]
-    pub fn new() -> Self {
+    #[pyo3(signature = (
+        sync = false,
+        disable_wal = false,
+        ignore_missing_column_families = false,
+        no_slowdown = false,
+        low_pri = false,
+        memtable_insert_hint_per_batch = false
+    ))]
+    pub fn new(
+        sync: bool,
+        disable_wal: bool,
+        ignore_missing_column_families: bool,
+        no_slowdown: bool,
+        low_pri: bool,
+        memtable_insert_hint_per_batch: bool,
+    ) -> Self {
         WriteOptionsPy {
-            sync: false,
-            disable_wal: false,
-            ignore_missing_column_families: false,
-            no_slowdown: false,
-            low_pri: false,
-            memtable_insert_hint_per_batch: false,
+            sync,
+            disable_wal,
+            ignore_missing_column_families,
+            no_slowdown,
+            low_pri,
+            memtable_insert_hint_per_batch,
         }
     }
+
+    /// The values of the write options, as a dict from option name to value.
+    ///
+    /// The dict can be passed back as keyword arguments: `WriteOptions(**d)`.
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let result = PyDict::new_bound(py);
+        result.set_item("sync", self.sync)?;
+        result.set_item("disable_wal", self.disable_wal)?;
+        result.set_item(
+            "ignore_missing_column_families",
+            self.ignore_missing_column_families,
+        )?;
+        result.set_item("no_slowdown", self.no_slowdown)?;
+        result.set_item("low_pri", self.low_pri)?;
+        result.set_item(
+            "memtable_insert_hint_per_batch",
+            self.memtable_insert_hint_per_batch,
+        )?;
+        Ok(result)
+    }
+}
+
+impl Default for WriteOptionsPy {
+    fn default() -> Self {
+        WriteOptionsPy::new(false, false, false, false, false, false)
+    }
 }
 
 impl From<&WriteOptionsPy> for WriteOptions {
@@ -2122,6 +3488,7 @@ impl ReadOptionsPy {
             tailing: false,
             pin_data: false,
             async_io: false,
+            timestamp: None,
         })
     }
 
@@ -2142,7 +3509,7 @@ impl ReadOptionsPy {
 
     /// Sets the lower bound for an iterator.
     pub fn set_iterate_lower_bound(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
-        self.iterate_upper_bound = key.to_object(py);
+        self.iterate_lower_bound = key.to_object(py);
         Ok(())
     }
 
@@ -2243,18 +3610,62 @@ impl ReadOptionsPy {
     pub fn set_async_io(&mut self, v: bool) {
         self.async_io = v
     }
+
+    /// Read the data as of this user-defined timestamp: the newest version
+    /// of each key with a timestamp not newer than `ts`.
+    ///
+    /// The db must be opened with `Options.set_u64_timestamp_comparator()`.
+    ///
+    /// Default: `None`, which is invalid for a db with user-defined timestamps
+    pub fn set_timestamp(&mut self, ts: Option<u64>) {
+        self.timestamp = ts
+    }
+
+    /// The values of the read options, as a dict from option name to value.
+    pub fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let result = PyDict::new_bound(py);
+        result.set_item("fill_cache", self.fill_cache)?;
+        result.set_item("iterate_upper_bound", &self.iterate_upper_bound)?;
+        result.set_item("iterate_lower_bound", &self.iterate_lower_bound)?;
+        result.set_item("prefix_same_as_start", self.prefix_same_as_start)?;
+        result.set_item("total_order_seek", self.total_order_seek)?;
+        result.set_item(
+            "max_skippable_internal_keys",
+            self.max_skippable_internal_keys,
+        )?;
+        result.set_item(
+            "background_purge_on_iterator_cleanup",
+            self.background_purge_on_iterator_cleanup,
+        )?;
+        result.set_item("ignore_range_deletions", self.ignore_range_deletions)?;
+        result.set_item("verify_checksums", self.verify_checksums)?;
+        result.set_item("readahead_size", self.readahead_size)?;
+        result.set_item("tailing", self.tailing)?;
+        result.set_item("pin_data", self.pin_data)?;
+        result.set_item("async_io", self.async_io)?;
+        result.set_item("timestamp", self.timestamp)?;
+        Ok(result)
+    }
 }
 
 impl ReadOptionsPy {
-    pub(crate) fn to_read_options(&self, raw_mode: bool, py: Python) -> PyResult<ReadOptions> {
+    /// `key_dumps` is the key encoding hook used for the iterate bounds.
+    pub(crate) fn to_read_options(
+        &self,
+        raw_mode: bool,
+        key_dumps: Option<&PyObject>,
+        py: Python,
+    ) -> PyResult<ReadOptions> {
         let mut opt = ReadOptions::default();
         opt.fill_cache(self.fill_cache);
         if !self.iterate_lower_bound.is_none(py) {
-            let lower_bound = encode_key(self.iterate_lower_bound.bind(py), raw_mode)?;
+            let lower_bound =
+                encode_key_with(self.iterate_lower_bound.bind(py), key_dumps, raw_mode)?;
             opt.set_iterate_lower_bound(lower_bound);
         }
         if !self.iterate_upper_bound.is_none(py) {
-            let upper_bound = encode_key(self.iterate_upper_bound.bind(py), raw_mode)?;
+            let upper_bound =
+                encode_key_with(self.iterate_upper_bound.bind(py), key_dumps, raw_mode)?;
             opt.set_iterate_upper_bound(upper_bound);
         }
         opt.set_prefix_same_as_start(self.prefix_same_as_start);
@@ -2267,87 +3678,154 @@ impl ReadOptionsPy {
         opt.set_tailing(self.tailing);
         opt.set_pin_data(self.pin_data);
         opt.set_async_io(self.async_io);
+        if let Some(ts) = self.timestamp {
+            opt.set_timestamp(ts.to_le_bytes());
+        }
         Ok(opt)
     }
 
-    pub(crate) fn to_read_opt(&self, raw_mode: bool, py: Python) -> PyResult<ReadOpt> {
-        let opt = unsafe { ReadOpt(librocksdb_sys::rocksdb_readoptions_create()) };
+    /// `key_dumps` is the key encoding hook used for the iterate bounds.
+    pub(crate) fn to_read_opt(
+        &self,
+        raw_mode: bool,
+        key_dumps: Option<&PyObject>,
+        py: Python,
+    ) -> PyResult<ReadOpt> {
+        let mut opt = ReadOpt {
+            inner: unsafe { librocksdb_sys::rocksdb_readoptions_create() },
+            _iterate_lower_bound: None,
+            _iterate_upper_bound: None,
+            _timestamp: None,
+            prefix_same_as_start: self.prefix_same_as_start,
+        };
         if !self.iterate_lower_bound.is_none(py) {
-            let lower_bound = encode_key(self.iterate_lower_bound.bind(py), raw_mode)?;
+            let lower_bound =
+                encode_key_with(self.iterate_lower_bound.bind(py), key_dumps, raw_mode)?
+                    .into_owned();
 
             unsafe {
                 librocksdb_sys::rocksdb_readoptions_set_iterate_lower_bound(
-                    opt.0,
+                    opt.inner,
                     lower_bound.as_ptr() as *const c_char,
                     lower_bound.len() as size_t,
                 );
             }
+            opt._iterate_lower_bound = Some(lower_bound);
         }
         if !self.iterate_upper_bound.is_none(py) {
-            let upper_bound = encode_key(self.iterate_upper_bound.bind(py), raw_mode)?;
+            let upper_bound =
+                encode_key_with(self.iterate_upper_bound.bind(py), key_dumps, raw_mode)?
+                    .into_owned();
 
             unsafe {
                 librocksdb_sys::rocksdb_readoptions_set_iterate_upper_bound(
-                    opt.0,
+                    opt.inner,
                     upper_bound.as_ptr() as *const c_char,
                     upper_bound.len() as size_t,
                 );
             }
+            opt._iterate_upper_bound = Some(upper_bound);
+        }
+        if let Some(ts) = self.timestamp {
+            let ts = ts.to_le_bytes().to_vec();
+            unsafe {
+                librocksdb_sys::rocksdb_readoptions_set_timestamp(
+                    opt.inner,
+                    ts.as_ptr() as *const c_char,
+                    ts.len() as size_t,
+                );
+            }
+            opt._timestamp = Some(ts);
         }
         unsafe {
-            librocksdb_sys::rocksdb_readoptions_set_fill_cache(opt.0, self.fill_cache as c_uchar);
+            librocksdb_sys::rocksdb_readoptions_set_fill_cache(
+                opt.inner,
+                self.fill_cache as c_uchar,
+            );
             librocksdb_sys::rocksdb_readoptions_set_prefix_same_as_start(
-                opt.0,
+                opt.inner,
                 self.prefix_same_as_start as c_uchar,
             );
             librocksdb_sys::rocksdb_readoptions_set_total_order_seek(
-                opt.0,
+                opt.inner,
                 self.total_order_seek as c_uchar,
             );
             librocksdb_sys::rocksdb_readoptions_set_max_skippable_internal_keys(
-                opt.0,
+                opt.inner,
                 self.max_skippable_internal_keys,
             );
             librocksdb_sys::rocksdb_readoptions_set_background_purge_on_iterator_cleanup(
-                opt.0,
+                opt.inner,
                 self.background_purge_on_iterator_cleanup as c_uchar,
             );
             librocksdb_sys::rocksdb_readoptions_set_ignore_range_deletions(
-                opt.0,
+                opt.inner,
                 self.ignore_range_deletions as c_uchar,
             );
             librocksdb_sys::rocksdb_readoptions_set_verify_checksums(
-                opt.0,
+                opt.inner,
                 self.verify_checksums as c_uchar,
             );
             librocksdb_sys::rocksdb_readoptions_set_readahead_size(
-                opt.0,
+                opt.inner,
                 self.readahead_size as size_t,
             );
-            librocksdb_sys::rocksdb_readoptions_set_tailing(opt.0, self.tailing as c_uchar);
-            librocksdb_sys::rocksdb_readoptions_set_pin_data(opt.0, self.pin_data as c_uchar);
+            librocksdb_sys::rocksdb_readoptions_set_tailing(opt.inner, self.tailing as c_uchar);
+            librocksdb_sys::rocksdb_readoptions_set_pin_data(opt.inner, self.pin_data as c_uchar);
         }
         Ok(opt)
     }
 }
 
+impl ReadOpt {
+    /// Set an iterate upper bound that is already encoded.
+    pub(crate) fn set_encoded_iterate_upper_bound(&mut self, upper_bound: Vec<u8>) {
+        unsafe {
+            librocksdb_sys::rocksdb_readoptions_set_iterate_upper_bound(
+                self.inner,
+                upper_bound.as_ptr() as *const c_char,
+                upper_bound.len() as size_t,
+            );
+        }
+        self._iterate_upper_bound = Some(upper_bound);
+    }
+
+    /// Whether the iterators created with these options only see part of
+    /// the column family.
+    pub(crate) fn is_bounded(&self) -> bool {
+        self._iterate_lower_bound.is_some()
+            || self._iterate_upper_bound.is_some()
+            || self.prefix_same_as_start
+    }
+}
+
 unsafe impl Send for ReadOpt {}
 
 unsafe impl Sync for ReadOpt {}
 
 impl Drop for ReadOpt {
     fn drop(&mut self) {
-        unsafe { librocksdb_sys::rocksdb_readoptions_destroy(self.0) }
+        unsafe { librocksdb_sys::rocksdb_readoptions_destroy(self.inner) }
     }
 }
 
 #[pymethods]
 impl MemtableFactoryPy {
+    /// Vector memtable, fast to insert into but slow to read from,
+    /// suited to bulk loading followed by a flush.
     #[staticmethod]
     pub fn vector() -> Self {
         MemtableFactoryPy(MemtableFactory::Vector)
     }
 
+    /// Hash table of skip lists, one per key prefix.
+    /// Requires a prefix extractor.
+    ///
+    /// Args:
+    ///     bucket_count: number of buckets of the hash table.
+    ///     height: max height of the skip lists.
+    ///     branching_factor: probabilistic size ratio between adjacent
+    ///         levels of the skip lists.
     #[staticmethod]
     pub fn hash_skip_list(bucket_count: usize, height: i32, branching_factor: i32) -> Self {
         MemtableFactoryPy(MemtableFactory::HashSkipList {
@@ -2357,6 +3835,11 @@ impl MemtableFactoryPy {
         })
     }
 
+    /// Hash table of sorted linked lists, one per key prefix.
+    /// Requires a prefix extractor.
+    ///
+    /// Args:
+    ///     bucket_count: number of buckets of the hash table.
     #[staticmethod]
     pub fn hash_link_list(bucket_count: usize) -> Self {
         MemtableFactoryPy(MemtableFactory::HashLinkList { bucket_count })
@@ -2395,6 +3878,24 @@ impl BlockBasedOptionsPy {
     ///
     /// Use partitioned full filters for each SST file. This option is
     /// incompatible with block-based filters.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import BlockBasedOptions, BlockBasedIndexType, Options
+    ///
+    ///         block_opts = BlockBasedOptions()
+    ///         block_opts.set_index_type(BlockBasedIndexType.two_level_index_search())
+    ///         block_opts.set_bloom_filter(10, False)
+    ///         block_opts.set_partition_filters(True)
+    ///         block_opts.set_metadata_block_size(4096)
+    ///         # only the top level index and filter blocks stay pinned,
+    ///         # the partitions are cached like data blocks
+    ///         block_opts.set_cache_index_and_filter_blocks(True)
+    ///         block_opts.set_pin_top_level_index_and_filter(True)
+    ///         block_opts.set_pin_l0_filter_and_index_blocks_in_cache(True)
+    ///         opts = Options()
+    ///         opts.set_block_based_table_factory(block_opts)
     pub fn set_partition_filters(&mut self, size: bool) {
         self.0.set_partition_filters(size)
     }
@@ -2418,6 +3919,71 @@ impl BlockBasedOptionsPy {
         self.0.set_bloom_filter(bits_per_key, block_based)
     }
 
+    /// Sets a Ribbon filter policy, using about 30% less memory than a Bloom
+    /// filter with the same false positive rate, at the cost of more CPU time
+    /// to build the filters.
+    ///
+    /// Notes:
+    ///     The filter policy name is not part of the aggregated table
+    ///     properties, but its size is, see
+    ///     `Rdict.property_value("rocksdb.aggregated-table-properties")`
+    ///     (`filter block size`).
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import BlockBasedOptions, Options
+    ///
+    ///         block_opts = BlockBasedOptions()
+    ///         block_opts.set_ribbon_filter(10)
+    ///         opts = Options()
+    ///         opts.set_block_based_table_factory(block_opts)
+    ///
+    /// Args:
+    ///     bloom_equivalent_bits_per_key: bits per key of a Bloom filter
+    ///         with the same false positive rate.
+    pub fn set_ribbon_filter(&mut self, bloom_equivalent_bits_per_key: c_double) {
+        self.0.set_ribbon_filter(bloom_equivalent_bits_per_key)
+    }
+
+    /// Sets a hybrid filter policy: Bloom filters for the levels before
+    /// `bloom_before_level`, which are faster to build for the frequently
+    /// compacted levels, and Ribbon filters for the other levels.
+    ///
+    /// Args:
+    ///     bloom_equivalent_bits_per_key: bits per key of a Bloom filter
+    ///         with the same false positive rate.
+    ///     bloom_before_level: first level using Ribbon filters, the
+    ///         memtable flushes count as level -1.
+    pub fn set_hybrid_ribbon_filter(
+        &mut self,
+        bloom_equivalent_bits_per_key: c_double,
+        bloom_before_level: c_int,
+    ) {
+        self.0
+            .set_hybrid_ribbon_filter(bloom_equivalent_bits_per_key, bloom_before_level)
+    }
+
+    /// If true, place whole keys in the filter (not just prefixes).
+    /// Set it to false with a prefix extractor to build prefix-only filters,
+    /// which are smaller but do not help point lookups.
+    ///
+    /// Default: true.
+    pub fn set_whole_key_filtering(&mut self, v: bool) {
+        self.0.set_whole_key_filtering(v)
+    }
+
+    /// Whether to put index and filter blocks in the block cache, instead of
+    /// keeping them in memory for the lifetime of the table readers.
+    ///
+    /// Notes:
+    ///     Cached index and filter blocks are inserted with high priority
+    ///     (`cache_index_and_filter_blocks_with_high_priority` defaults to
+    ///     true and is not exposed by the rust binding). Their cache hits and
+    ///     misses are counted by the `rocksdb.block.cache.index.*` and
+    ///     `rocksdb.block.cache.filter.*` tickers, see `Statistics`.
+    ///
+    /// Default: false.
     pub fn set_cache_index_and_filter_blocks(&mut self, v: bool) {
         self.0.set_cache_index_and_filter_blocks(v)
     }
@@ -2540,8 +4106,27 @@ impl BlockBasedOptionsPy {
 #[pymethods]
 impl CuckooTableOptionsPy {
     #[new]
-    pub fn default() -> Self {
-        CuckooTableOptionsPy(CuckooTableOptions::default())
+    #[pyo3(signature = (
+        hash_table_ratio = 0.9,
+        max_search_depth = 100,
+        cuckoo_block_size = 5,
+        identity_as_first_hash = false,
+        use_module_hash = true
+    ))]
+    pub fn new(
+        hash_table_ratio: f64,
+        max_search_depth: u32,
+        cuckoo_block_size: u32,
+        identity_as_first_hash: bool,
+        use_module_hash: bool,
+    ) -> Self {
+        let mut options = CuckooTableOptions::default();
+        options.set_hash_ratio(hash_table_ratio);
+        options.set_max_search_depth(max_search_depth);
+        options.set_cuckoo_block_size(cuckoo_block_size);
+        options.set_identity_as_first_hash(identity_as_first_hash);
+        options.set_use_module_hash(use_module_hash);
+        CuckooTableOptionsPy(options)
     }
 
     /// Determines the utilization of hash tables. Smaller values
@@ -2593,31 +4178,44 @@ impl CuckooTableOptionsPy {
 #[pymethods]
 impl PlainTableFactoryOptionsPy {
     #[new]
-    pub fn default() -> Self {
+    #[pyo3(signature = (
+        user_key_length = 0,
+        bloom_bits_per_key = 10,
+        hash_table_ratio = 0.75,
+        index_sparseness = 16,
+        huge_page_tlb_size = 0,
+        encoding_type = KeyEncodingTypePy::plain(),
+        full_scan_mode = false,
+        store_index_in_file = false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        user_key_length: u32,
+        bloom_bits_per_key: i32,
+        hash_table_ratio: f64,
+        index_sparseness: usize,
+        huge_page_tlb_size: usize,
+        encoding_type: KeyEncodingTypePy,
+        full_scan_mode: bool,
+        store_index_in_file: bool,
+    ) -> Self {
         PlainTableFactoryOptionsPy {
-            user_key_length: 0,
-            bloom_bits_per_key: 10,
-            hash_table_ratio: 0.75,
-            index_sparseness: 16,
-            huge_page_tlb_size: 0,
-            encoding_type: KeyEncodingTypePy(KeyEncodingType::Plain),
-            full_scan_mode: false,
-            store_index_in_file: false,
+            user_key_length,
+            bloom_bits_per_key,
+            hash_table_ratio,
+            index_sparseness,
+            huge_page_tlb_size,
+            encoding_type,
+            full_scan_mode,
+            store_index_in_file,
         }
     }
 }
 
 impl PlainTableFactoryOptionsPy {
-    fn to_opt(&self, raw_mode: bool) -> PlainTableFactoryOptions {
+    fn to_opt(&self) -> PlainTableFactoryOptions {
         PlainTableFactoryOptions {
-            // One extra byte for python object type
-            user_key_length: if raw_mode {
-                self.user_key_length
-            } else if self.user_key_length > 0 {
-                self.user_key_length + 1
-            } else {
-                0
-            },
+            user_key_length: self.user_key_length,
             bloom_bits_per_key: self.bloom_bits_per_key,
             hash_table_ratio: self.hash_table_ratio,
             index_sparseness: self.index_sparseness,
@@ -2632,9 +4230,41 @@ impl PlainTableFactoryOptionsPy {
 #[pymethods]
 impl CachePy {
     /// Create a lru cache with capacity
+    ///
+    /// The same cache can be passed to the options of several databases
+    /// (with `BlockBasedOptions.set_block_cache` or `Options.set_row_cache`)
+    /// so that they share a single memory budget. The cache is kept alive by
+    /// the databases using it, even if this object is deleted.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, BlockBasedOptions, Cache
+    ///
+    ///         cache = Cache(64 * 1024 * 1024, num_shard_bits=4)
+    ///         table_opt = BlockBasedOptions()
+    ///         table_opt.set_block_cache(cache)
+    ///         opt = Options()
+    ///         opt.set_block_based_table_factory(table_opt)
+    ///
+    ///         db1 = Rdict("./tmp1", opt)
+    ///         db2 = Rdict("./tmp2", opt)
+    ///         print(cache.get_usage())
+    ///
+    /// Args:
+    ///     capacity: capacity of the cache in bytes.
+    ///     num_shard_bits: the cache is sharded into `2^num_shard_bits` shards,
+    ///         by hash of the key. Negative to let rocksdb choose (default).
     #[new]
-    pub fn new_lru_cache(capacity: size_t) -> CachePy {
-        CachePy(Cache::new_lru_cache(capacity))
+    #[pyo3(signature = (capacity, num_shard_bits = -1))]
+    pub fn new(capacity: size_t, num_shard_bits: c_int) -> CachePy {
+        if num_shard_bits < 0 {
+            return CachePy::new_lru_cache(capacity);
+        }
+        let mut opts = LruCacheOptions::default();
+        opts.set_capacity(capacity);
+        opts.set_num_shard_bits(num_shard_bits);
+        CachePy(Cache::new_lru_cache_opts(&opts))
     }
 
     /// Creates a HyperClockCache with capacity in bytes.
@@ -2681,6 +4311,12 @@ impl CachePy {
     }
 }
 
+impl CachePy {
+    pub(crate) fn new_lru_cache(capacity: size_t) -> CachePy {
+        CachePy(Cache::new_lru_cache(capacity))
+    }
+}
+
 #[pymethods]
 impl BlockBasedIndexTypePy {
     /// A space efficient index block that is optimized for
@@ -2722,6 +4358,46 @@ impl DataBlockIndexTypePy {
     }
 }
 
+#[pymethods]
+impl MergeOperatorPy {
+    /// Add integers.
+    ///
+    /// In raw mode, the values are unsigned 64 bits integers in little
+    /// endian (e.g. `(1).to_bytes(8, "little")`), as the `UInt64AddOperator`
+    /// of rocksdb, and the additions wrap around on overflow.
+    /// Otherwise the values are python `int` and cannot overflow.
+    #[staticmethod]
+    pub fn uint64_add() -> Self {
+        MergeOperatorPy(MergeOperatorType::UInt64Add)
+    }
+
+    /// Append values, separated by `delimiter`.
+    ///
+    /// In raw mode, the values and delimiter are `bytes`. Otherwise the
+    /// values are either all `str` or all `bytes`, and `delimiter`
+    /// is `str` or `bytes`.
+    ///
+    /// Args:
+    ///     delimiter: inserted between the appended values, empty by default.
+    #[staticmethod]
+    #[pyo3(signature = (delimiter = None))]
+    pub fn string_append(delimiter: Option<&Bound<PyAny>>) -> PyResult<Self> {
+        let delimiter = match delimiter {
+            None => Vec::new(),
+            Some(d) => {
+                if let Ok(d) = d.downcast::<PyString>() {
+                    d.to_str()?.as_bytes().to_vec()
+                } else if let Ok(d) = d.downcast::<PyBytes>() {
+                    d.as_bytes().to_vec()
+                } else {
+                    return Err(PyException::new_err("delimiter must be `str` or `bytes`"));
+                }
+            }
+        };
+        Ok(MergeOperatorPy(MergeOperatorType::StringAppend(delimiter)))
+    }
+}
+
 #[pymethods]
 impl SliceTransformPy {
     #[staticmethod]
@@ -2792,6 +4468,48 @@ impl DBCompressionTypePy {
     pub fn zstd() -> Self {
         DBCompressionTypePy(DBCompressionType::Zstd)
     }
+
+    /// Get a compression type from its name, one of `"none"`, `"snappy"`,
+    /// `"zlib"`, `"bz2"`, `"lz4"`, `"lz4hc"` or `"zstd"`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         opt = Options()
+    ///         opt.set_compression_type(DBCompressionType.from_name("zstd"))
+    #[staticmethod]
+    pub fn from_name(name: &str) -> PyResult<Self> {
+        match COMPRESSION_TYPES.iter().find(|(n, _)| *n == name) {
+            Some((_, t)) => Ok(DBCompressionTypePy(*t)),
+            None => Err(PyException::new_err(format!(
+                "unknown compression type `{name}`, supported types are: {}",
+                COMPRESSION_TYPES
+                    .iter()
+                    .map(|(n, _)| *n)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+}
+
+/// Names of the compression types, all of them are compiled into rocksdict.
+const COMPRESSION_TYPES: [(&str, DBCompressionType); 7] = [
+    ("none", DBCompressionType::None),
+    ("snappy", DBCompressionType::Snappy),
+    ("zlib", DBCompressionType::Zlib),
+    ("bz2", DBCompressionType::Bz2),
+    ("lz4", DBCompressionType::Lz4),
+    ("lz4hc", DBCompressionType::Lz4hc),
+    ("zstd", DBCompressionType::Zstd),
+];
+
+/// Name of a compression type returned by the rocksdb C API.
+pub(crate) fn compression_type_name(raw: c_int) -> Option<&'static str> {
+    COMPRESSION_TYPES
+        .iter()
+        .find(|(_, t)| *t as c_int == raw)
+        .map(|(name, _)| *name)
 }
 
 #[pymethods]
@@ -2840,6 +4558,14 @@ impl ChecksumTypePy {
     }
 }
 
+#[pymethods]
+impl FileChecksumGenFactoryPy {
+    #[staticmethod]
+    pub fn crc32c() -> Self {
+        FileChecksumGenFactoryPy("FileChecksumGenCrc32cFactory")
+    }
+}
+
 #[pymethods]
 impl DBRecoveryModePy {
     #[staticmethod]
@@ -2863,23 +4589,85 @@ impl DBRecoveryModePy {
     }
 }
 
+#[pymethods]
+impl StatsLevelPy {
+    #[staticmethod]
+    pub fn disable_all() -> Self {
+        StatsLevelPy(statistics::StatsLevel::DisableAll)
+    }
+
+    #[staticmethod]
+    pub fn except_tickers() -> Self {
+        StatsLevelPy(statistics::StatsLevel::ExceptTickers)
+    }
+
+    #[staticmethod]
+    pub fn except_histogram_or_timers() -> Self {
+        StatsLevelPy(statistics::StatsLevel::ExceptHistogramOrTimers)
+    }
+
+    #[staticmethod]
+    pub fn except_timers() -> Self {
+        StatsLevelPy(statistics::StatsLevel::ExceptTimers)
+    }
+
+    #[staticmethod]
+    pub fn except_detailed_timers() -> Self {
+        StatsLevelPy(statistics::StatsLevel::ExceptDetailedTimers)
+    }
+
+    #[staticmethod]
+    pub fn except_time_for_mutex() -> Self {
+        StatsLevelPy(statistics::StatsLevel::ExceptTimeForMutex)
+    }
+
+    #[staticmethod]
+    pub fn all() -> Self {
+        StatsLevelPy(statistics::StatsLevel::All)
+    }
+}
+
 #[pymethods]
 impl EnvPy {
     /// Returns default env
     #[new]
     pub fn default() -> PyResult<Self> {
         match Env::new() {
-            Ok(env) => Ok(EnvPy(env)),
+            Ok(env) => Ok(EnvPy {
+                inner: env,
+                in_memory: false,
+            }),
             Err(e) => Err(PyException::new_err(e.into_string())),
         }
     }
 
+    /// Returns default env, same as `Env()`
+    #[staticmethod]
+    #[pyo3(name = "default")]
+    pub fn py_default() -> PyResult<Self> {
+        Self::default()
+    }
+
     /// Returns a new environment that stores its data in memory and delegates
     /// all non-file-storage tasks to base_env.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, Env
+    ///
+    ///         # nothing is written to "./tmp"
+    ///         opt = Options()
+    ///         opt.set_env(Env.mem_env())
+    ///         db = Rdict("./tmp", opt)
+    ///         db["a"] = 1
     #[staticmethod]
     pub fn mem_env() -> PyResult<Self> {
         match Env::mem_env() {
-            Ok(env) => Ok(EnvPy(env)),
+            Ok(env) => Ok(EnvPy {
+                inner: env,
+                in_memory: true,
+            }),
             Err(e) => Err(PyException::new_err(e.into_string())),
         }
     }
@@ -2889,64 +4677,79 @@ impl EnvPy {
     ///
     /// Default: 1
     pub fn set_background_threads(&mut self, num_threads: c_int) {
-        self.0.set_background_threads(num_threads)
+        self.inner.set_background_threads(num_threads)
     }
 
     /// Sets the size of the high priority thread pool that can be used to
     /// prevent compactions from stalling memtable flushes.
     pub fn set_high_priority_background_threads(&mut self, n: c_int) {
-        self.0.set_high_priority_background_threads(n)
+        self.inner.set_high_priority_background_threads(n)
     }
 
     /// Sets the size of the low priority thread pool that can be used to
     /// prevent compactions from stalling memtable flushes.
     pub fn set_low_priority_background_threads(&mut self, n: c_int) {
-        self.0.set_low_priority_background_threads(n)
+        self.inner.set_low_priority_background_threads(n)
     }
 
     /// Sets the size of the bottom priority thread pool that can be used to
     /// prevent compactions from stalling memtable flushes.
     pub fn set_bottom_priority_background_threads(&mut self, n: c_int) {
-        self.0.set_bottom_priority_background_threads(n)
+        self.inner.set_bottom_priority_background_threads(n)
     }
 
     /// Wait for all threads started by StartThread to terminate.
     pub fn join_all_threads(&mut self) {
-        self.0.join_all_threads()
+        self.inner.join_all_threads()
     }
 
     /// Lowering IO priority for threads from the specified pool.
     pub fn lower_thread_pool_io_priority(&mut self) {
-        self.0.lower_thread_pool_io_priority()
+        self.inner.lower_thread_pool_io_priority()
     }
 
     /// Lowering IO priority for high priority thread pool.
     pub fn lower_high_priority_thread_pool_io_priority(&mut self) {
-        self.0.lower_high_priority_thread_pool_io_priority()
+        self.inner.lower_high_priority_thread_pool_io_priority()
     }
 
     /// Lowering CPU priority for threads from the specified pool.
     pub fn lower_thread_pool_cpu_priority(&mut self) {
-        self.0.lower_thread_pool_cpu_priority()
+        self.inner.lower_thread_pool_cpu_priority()
     }
 
     /// Lowering CPU priority for high priority thread pool.
     pub fn lower_high_priority_thread_pool_cpu_priority(&mut self) {
-        self.0.lower_high_priority_thread_pool_cpu_priority()
+        self.inner.lower_high_priority_thread_pool_cpu_priority()
     }
 }
 
 #[pymethods]
 impl UniversalCompactOptionsPy {
     #[new]
-    pub fn default() -> Self {
+    #[pyo3(signature = (
+        size_ratio = 1,
+        min_merge_width = 2,
+        max_merge_width = c_int::MAX,
+        max_size_amplification_percent = 200,
+        compression_size_percent = -1,
+        stop_style = UniversalCompactionStopStylePy::total()
+    ))]
+    pub fn new(
+        size_ratio: c_int,
+        min_merge_width: c_int,
+        max_merge_width: c_int,
+        max_size_amplification_percent: c_int,
+        compression_size_percent: c_int,
+        stop_style: UniversalCompactionStopStylePy,
+    ) -> Self {
         UniversalCompactOptionsPy {
-            size_ratio: 1,
-            min_merge_width: 2,
-            max_merge_width: c_int::MAX,
-            max_size_amplification_percent: 200,
-            compression_size_percent: -1,
-            stop_style: UniversalCompactionStopStylePy(UniversalCompactionStopStyle::Total),
+            size_ratio,
+            min_merge_width,
+            max_merge_width,
+            max_size_amplification_percent,
+            compression_size_percent,
+            stop_style,
         }
     }
 }
@@ -2980,21 +4783,15 @@ impl UniversalCompactionStopStylePy {
 #[pymethods]
 impl FifoCompactOptionsPy {
     #[new]
-    pub fn new() -> Self {
+    #[pyo3(signature = (max_table_files_size = 0x280000000, allow_compaction = false))]
+    pub fn new(max_table_files_size: u64, allow_compaction: bool) -> Self {
         FifoCompactOptionsPy {
-            max_table_files_size: 0x280000000,
+            max_table_files_size,
+            allow_compaction,
         }
     }
 }
 
-impl From<&FifoCompactOptionsPy> for FifoCompactOptions {
-    fn from(f_opt: &FifoCompactOptionsPy) -> Self {
-        let mut opt = FifoCompactOptions::default();
-        opt.set_max_table_files_size(f_opt.max_table_files_size);
-        opt
-    }
-}
-
 #[pymethods]
 impl IngestExternalFileOptionsPy {
     #[new]
@@ -3030,7 +4827,8 @@ impl IngestExternalFileOptionsPy {
     /// Usecase: back-fill of some historical data in the database without
     /// over-writing existing newer version of data.
     /// This option could only be used if the DB has been running
-    /// with allow_ingest_behind=true since the dawn of time.
+    /// with allow_ingest_behind=true since the dawn of time
+    /// (see `Options.set_allow_ingest_behind`), otherwise the ingestion fails.
     /// All files will be ingested at the bottommost level with seqno=0.
     pub fn set_ingest_behind(&mut self, v: bool) {
         self.0.set_ingest_behind(v)
@@ -3103,11 +4901,17 @@ impl CompactOptionsPy {
 #[pymethods]
 impl WriteBufferManagerPy {
     #[new]
-    pub fn new_write_buffer_manager(buffer_size: size_t, allow_stall: bool) -> Self {
-        Self(WriteBufferManager::new_write_buffer_manager(
-            buffer_size,
-            allow_stall,
-        ))
+    #[pyo3(signature = (buffer_size, cache = None, allow_stall = false))]
+    pub fn new(buffer_size: size_t, cache: Option<CachePy>, allow_stall: bool) -> Self {
+        match cache {
+            None => Self(WriteBufferManager::new_write_buffer_manager(
+                buffer_size,
+                allow_stall,
+            )),
+            Some(cache) => {
+                Self::new_write_buffer_manager_with_cache(buffer_size, allow_stall, cache)
+            }
+        }
     }
 
     /// Users can set up RocksDB to cost memory used by memtables to block cache.
@@ -3131,17 +4935,28 @@ impl WriteBufferManagerPy {
         ))
     }
 
-    /// Returns the WriteBufferManager memory usage in bytes.
-    pub fn get_usage(&self) -> usize {
+    /// Returns the memory used by the memtables in bytes.
+    pub fn memory_usage(&self) -> usize {
         self.0.get_usage()
     }
 
     /// Returns the current buffer size in bytes.
+    pub fn buffer_size(&self) -> usize {
+        self.0.get_buffer_size()
+    }
+
+    /// Same as `memory_usage()`.
+    pub fn get_usage(&self) -> usize {
+        self.0.get_usage()
+    }
+
+    /// Same as `buffer_size()`.
     pub fn get_buffer_size(&self) -> usize {
         self.0.get_buffer_size()
     }
 
-    /// Set the buffer size in bytes.
+    /// Set the buffer size in bytes, takes effect immediately for all the
+    /// DBs sharing this WriteBufferManager.
     pub fn set_buffer_size(&self, new_size: usize) {
         self.0.set_buffer_size(new_size)
     }
@@ -3157,6 +4972,267 @@ impl WriteBufferManagerPy {
     }
 }
 
+#[pymethods]
+impl RateLimiterPy {
+    #[new]
+    #[pyo3(signature = (
+        rate_bytes_per_sec,
+        refill_period_us = 100_000,
+        fairness = 10,
+        mode = "writes_only",
+        auto_tuned = false
+    ))]
+    pub fn new(
+        rate_bytes_per_sec: i64,
+        refill_period_us: i64,
+        fairness: i32,
+        mode: &str,
+        auto_tuned: bool,
+    ) -> PyResult<Self> {
+        if rate_bytes_per_sec <= 0 || refill_period_us <= 0 || fairness <= 0 {
+            return Err(PyException::new_err(
+                "rate_bytes_per_sec, refill_period_us and fairness must be positive",
+            ));
+        }
+        // values of `RateLimiter::Mode`
+        let mode = match mode {
+            "reads_only" => 0,
+            "writes_only" => 1,
+            "all_io" => 2,
+            _ => {
+                return Err(PyException::new_err(format!(
+                    "unknown mode `{mode}`, must be one of `reads_only`, `writes_only`, `all_io`"
+                )))
+            }
+        };
+        Ok(RateLimiterPy(unsafe {
+            librocksdb_sys::rocksdb_ratelimiter_create_with_mode(
+                rate_bytes_per_sec,
+                refill_period_us,
+                fairness,
+                mode,
+                auto_tuned,
+            )
+        }))
+    }
+}
+
+impl Drop for RateLimiterPy {
+    fn drop(&mut self) {
+        // the options keep their own reference to the limiter
+        unsafe { librocksdb_sys::rocksdb_ratelimiter_destroy(self.0) }
+    }
+}
+
+/// order of the encoded keys of a db which is not in `raw_mode`,
+/// integers are compared by value
+pub(crate) fn rocksdict_compare(v1: &[u8], v2: &[u8]) -> Ordering {
+    if let (Some(3), Some(3)) = (v1.first(), v2.first()) {
+        BigInt::from_signed_bytes_be(&v1[1..]).cmp(&BigInt::from_signed_bytes_be(&v2[1..]))
+    } else {
+        v1.cmp(v2)
+    }
+}
+
+/// order of the encoded keys with `Options.set_ordered_numeric_keys(True)`,
+/// ints and floats are compared by value, other keys as in `rocksdict_compare`
+pub(crate) fn rocksdict_numeric_compare(v1: &[u8], v2: &[u8]) -> Ordering {
+    match (numeric_key(v1), numeric_key(v2)) {
+        (Some(NumericKey::Int(i1)), Some(NumericKey::Int(i2))) => i1.cmp(&i2),
+        (Some(NumericKey::Float(f1)), Some(NumericKey::Float(f2))) => f1.total_cmp(&f2),
+        (Some(NumericKey::Int(i)), Some(NumericKey::Float(f))) => cmp_int_float(&i, f),
+        (Some(NumericKey::Float(f)), Some(NumericKey::Int(i))) => cmp_int_float(&i, f).reverse(),
+        // type bytes: bytes (1) < str (2) < int (3), float (4) < bool (5) < datetime (8)
+        // < uuid (9) < tuple (10), tuples are ordered by their encoding
+        _ => v1.cmp(v2),
+    }
+}
+
+enum NumericKey {
+    Int(BigInt),
+    Float(f64),
+}
+
+fn numeric_key(v: &[u8]) -> Option<NumericKey> {
+    match v.split_first() {
+        Some((3, int_bytes)) => Some(NumericKey::Int(BigInt::from_signed_bytes_be(int_bytes))),
+        Some((4, float_bytes)) => {
+            let float_bytes = float_bytes.try_into().ok()?;
+            Some(NumericKey::Float(f64::from_be_bytes(float_bytes)))
+        }
+        _ => None,
+    }
+}
+
+/// never `Equal`, an int sorts just before the equal float
+fn cmp_int_float(i: &BigInt, f: f64) -> Ordering {
+    if f.is_nan() || f.is_infinite() {
+        // same order as `f64::total_cmp`
+        return if f.is_sign_negative() {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        };
+    }
+    // finite floats always convert
+    let truncated = f.trunc().to_bigint().unwrap();
+    match i.cmp(&truncated) {
+        Ordering::Equal if f.fract() < 0.0 => Ordering::Greater,
+        Ordering::Equal => Ordering::Less,
+        ordering => ordering,
+    }
+}
+
+pub(crate) const U64_TIMESTAMP_SIZE: usize = 8;
+
+fn compare_u64_ts(ts1: &[u8], ts2: &[u8]) -> Ordering {
+    let decode = |ts: &[u8]| ts.try_into().map(u64::from_le_bytes).unwrap_or_default();
+    decode(ts1).cmp(&decode(ts2))
+}
+
+fn compare_without_u64_ts(k1: &[u8], k1_has_ts: bool, k2: &[u8], k2_has_ts: bool) -> Ordering {
+    let strip = |k: &[u8], has_ts: bool| {
+        if has_ts {
+            &k[..k.len().saturating_sub(U64_TIMESTAMP_SIZE)]
+        } else {
+            k
+        }
+    };
+    strip(k1, k1_has_ts).cmp(strip(k2, k2_has_ts))
+}
+
+/// keys in bytewise order, then timestamps from the newest to the oldest
+fn compare_with_u64_ts(k1: &[u8], k2: &[u8]) -> Ordering {
+    compare_without_u64_ts(k1, true, k2, true).then_with(|| {
+        let ts = |k: &[u8]| &k[k.len().saturating_sub(U64_TIMESTAMP_SIZE)..];
+        compare_u64_ts(ts(k2), ts(k1))
+    })
+}
+
+/// Raise if an encoded operand of `Rdict.merge()` cannot be merged by
+/// `merge_operator`, so that it never reaches a flush or compaction.
+pub(crate) fn check_merge_operand(
+    merge_operator: Option<&MergeOperatorType>,
+    raw_mode: bool,
+    operand: &[u8],
+) -> PyResult<()> {
+    let error = match (merge_operator, raw_mode) {
+        (None, _) => {
+            return Err(PyException::new_err(
+                "merge() requires a merge operator, see Options.set_merge_operator()",
+            ))
+        }
+        (Some(MergeOperatorType::UInt64Add), true) if decode_u64(operand).is_none() => {
+            "uint64_add operands must be 8 bytes in raw mode"
+        }
+        (Some(MergeOperatorType::UInt64Add), false) if decode_int(operand).is_none() => {
+            "uint64_add operands must be `int`"
+        }
+        (Some(MergeOperatorType::StringAppend(_)), false) if !is_bytes_or_str(operand) => {
+            "string_append operands must be `str` or `bytes`"
+        }
+        _ => return Ok(()),
+    };
+    Err(PyTypeError::new_err(error))
+}
+
+fn decode_u64(v: &[u8]) -> Option<u64> {
+    v.try_into().ok().map(u64::from_le_bytes)
+}
+
+/// rocksdict encoded ints: type byte `3` followed by big endian signed bytes
+fn decode_int(v: &[u8]) -> Option<BigInt> {
+    match v.split_first() {
+        Some((3, int_bytes)) => Some(BigInt::from_signed_bytes_be(int_bytes)),
+        _ => None,
+    }
+}
+
+/// rocksdict encoded bytes (type byte `1`) or str (type byte `2`)
+fn is_bytes_or_str(v: &[u8]) -> bool {
+    matches!(v.first(), Some(1) | Some(2))
+}
+
+// The merge operators must not fail: a failed merge during a flush or a
+// compaction is a background error, which stops the writes to the db.
+// `Rdict.merge()` rejects invalid operands, but `WriteBatch.merge()` or a
+// `put()` of another type cannot be checked, so like the `UInt64AddOperator`
+// of rocksdb, invalid values are ignored.
+
+/// invalid values count as 0
+fn uint64_add_raw(
+    _key: &[u8],
+    existing: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    let mut sum = existing.and_then(decode_u64).unwrap_or(0);
+    for operand in operands.iter() {
+        sum = sum.wrapping_add(decode_u64(operand).unwrap_or(0));
+    }
+    Some(sum.to_le_bytes().to_vec())
+}
+
+/// invalid values count as 0
+fn int_add_encoded(
+    _key: &[u8],
+    existing: Option<&[u8]>,
+    operands: &MergeOperands,
+) -> Option<Vec<u8>> {
+    let mut sum = existing.and_then(decode_int).unwrap_or_default();
+    for operand in operands.iter() {
+        if let Some(operand) = decode_int(operand) {
+            sum += operand;
+        }
+    }
+    let mut result = vec![3u8];
+    result.extend_from_slice(&sum.to_signed_bytes_be());
+    Some(result)
+}
+
+fn append_raw(
+    existing: Option<&[u8]>,
+    operands: &MergeOperands,
+    delimiter: &[u8],
+) -> Option<Vec<u8>> {
+    let mut result = existing.map(|v| v.to_vec());
+    for operand in operands.iter() {
+        match &mut result {
+            None => result = Some(operand.to_vec()),
+            Some(r) => {
+                r.extend_from_slice(delimiter);
+                r.extend_from_slice(operand);
+            }
+        }
+    }
+    result
+}
+
+/// the operands that are not of the type of the existing value
+/// (or of the first valid operand) are ignored, and an existing value
+/// that is neither `bytes` nor `str` is kept as is
+fn append_encoded(
+    existing: Option<&[u8]>,
+    operands: &MergeOperands,
+    delimiter: &[u8],
+) -> Option<Vec<u8>> {
+    let mut result = existing.map(|v| v.to_vec());
+    for operand in operands.iter() {
+        if !is_bytes_or_str(operand) {
+            continue;
+        }
+        match &mut result {
+            None => result = Some(operand.to_vec()),
+            Some(r) if r.first() == operand.first() => {
+                r.extend_from_slice(delimiter);
+                r.extend_from_slice(&operand[1..]);
+            }
+            Some(_) => {}
+        }
+    }
+    // only invalid operands and no existing value
+    result.or_else(|| operands.iter().next().map(|v| v.to_vec()))
+}
+
 #[macro_export]
 macro_rules! implement_max_len_transform {
     ($($len:literal),*) => {
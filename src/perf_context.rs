@@ -0,0 +1,244 @@
+use crate::util::unknown_name_error;
+use pyo3::prelude::*;
+use rocksdb::perf::{set_perf_stats, PerfContext, PerfMetric, PerfStatsLevel};
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// The perf counters exposed by name, named like the fields of
+/// `rocksdb::PerfContext`. All counters are included in `report()`.
+const METRICS: [(&str, PerfMetric); 23] = [
+    (
+        "user_key_comparison_count",
+        PerfMetric::UserKeyComparisonCount,
+    ),
+    ("block_cache_hit_count", PerfMetric::BlockCacheHitCount),
+    ("block_read_count", PerfMetric::BlockReadCount),
+    ("block_read_byte", PerfMetric::BlockReadByte),
+    ("block_read_time", PerfMetric::BlockReadTime),
+    ("block_checksum_time", PerfMetric::BlockChecksumTime),
+    ("block_decompress_time", PerfMetric::BlockDecompressTime),
+    ("get_read_bytes", PerfMetric::GetReadBytes),
+    ("multiget_read_bytes", PerfMetric::MultigetReadBytes),
+    ("iter_read_bytes", PerfMetric::IterReadBytes),
+    (
+        "internal_key_skipped_count",
+        PerfMetric::InternalKeySkippedCount,
+    ),
+    (
+        "internal_delete_skipped_count",
+        PerfMetric::InternalDeleteSkippedCount,
+    ),
+    (
+        "internal_recent_skipped_count",
+        PerfMetric::InternalRecentSkippedCount,
+    ),
+    ("internal_merge_count", PerfMetric::InternalMergeCount),
+    ("get_snapshot_time", PerfMetric::GetSnapshotTime),
+    ("get_from_memtable_time", PerfMetric::GetFromMemtableTime),
+    ("get_from_memtable_count", PerfMetric::GetFromMemtableCount),
+    ("get_post_process_time", PerfMetric::GetPostProcessTime),
+    (
+        "get_from_output_files_time",
+        PerfMetric::GetFromOutputFilesTime,
+    ),
+    (
+        "bloom_memtable_hit_count",
+        PerfMetric::BloomMemtableHitCount,
+    ),
+    (
+        "bloom_memtable_miss_count",
+        PerfMetric::BloomMemtableMissCount,
+    ),
+    ("bloom_sst_hit_count", PerfMetric::BloomSstHitCount),
+    ("bloom_sst_miss_count", PerfMetric::BloomSstMissCount),
+];
+
+/// The counters returned by `Rdict.get_with_stats()`.
+const GET_METRICS: [&str; 6] = [
+    "block_cache_hit_count",
+    "block_read_count",
+    "block_read_byte",
+    "get_read_bytes",
+    "get_from_memtable_count",
+    "bloom_sst_miss_count",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum PerfLevel {
+    Disable,
+    Count,
+    Time,
+}
+
+impl PerfLevel {
+    fn apply(self) {
+        set_perf_stats(match self {
+            PerfLevel::Disable => PerfStatsLevel::Disable,
+            PerfLevel::Count => PerfStatsLevel::EnableCount,
+            PerfLevel::Time => PerfStatsLevel::EnableTimeExceptForMutex,
+        });
+    }
+}
+
+thread_local! {
+    /// rocksdb has no getter for the perf level, so track it here.
+    /// The rocksdb default is to enable counts.
+    static PERF_LEVEL: Cell<PerfLevel> = const { Cell::new(PerfLevel::Count) };
+}
+
+fn set_level(level: PerfLevel) {
+    level.apply();
+    PERF_LEVEL.with(|l| l.set(level));
+}
+
+/// Run `f` and return the perf counters it incremented on this thread,
+/// see `Rdict.get_with_stats()`.
+///
+/// Counts are enabled during `f` if they are disabled, and the
+/// perf context is not reset, so `PerfContext` objects are not affected.
+pub(crate) fn with_perf_counters<T>(
+    f: impl FnOnce() -> PyResult<T>,
+) -> PyResult<(T, HashMap<&'static str, u64>)> {
+    let level = PERF_LEVEL.with(|l| l.get());
+    if level == PerfLevel::Disable {
+        PerfLevel::Count.apply();
+    }
+    let context = PerfContext::default();
+    let metric = |name: &str| {
+        let (_, metric) = METRICS.into_iter().find(|(n, _)| *n == name).unwrap();
+        context.metric(metric)
+    };
+    let before: Vec<u64> = GET_METRICS.iter().map(|name| metric(name)).collect();
+    let result = f();
+    let counters = GET_METRICS
+        .iter()
+        .zip(before)
+        .map(|(name, before)| (*name, metric(name).saturating_sub(before)))
+        .collect();
+    if level == PerfLevel::Disable {
+        level.apply();
+    }
+    Ok((result?, counters))
+}
+
+/// The perf context of the current thread, which counts the work done
+/// by the operations of this thread (block cache hits, blocks read from
+/// disk, bytes read, comparisons, ...).
+///
+/// Unlike `Statistics`, which aggregates all operations of a db,
+/// a perf context can profile a single operation. The counters are
+/// per thread, shared by all `PerfContext` objects and all dbs of the
+/// thread, so a `PerfContext` cannot be used from another thread.
+///
+/// Notes:
+///     Counts are enabled by default. `PerfContext.enable(timing=True)`
+///     also measures the time spent (the `*_time` counters, in nanoseconds),
+///     which is more expensive.
+///
+///     The IO stats context of rocksdb (bytes read and written to files
+///     by the thread) is not exposed by the rocksdb C API, so it is not
+///     available. To attribute file IO to operations, use the
+///     `block_read_byte` counter for reads, and the difference of the
+///     `Statistics` tickers `"rocksdb.bytes.written"` and `"rocksdb.wal.bytes"`
+///     around an operation for writes, which are per db instead of per thread.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, PerfContext
+///
+///         db = Rdict("./tmp")
+///         db["a"] = 1
+///         db.flush()
+///
+///         ctx = PerfContext()
+///         ctx.reset()
+///         db["a"]
+///         hit = ctx.metric("block_cache_hit_count")
+///         miss = ctx.metric("block_read_count")
+///         print(ctx.report())
+///
+///         db.close()
+///         Rdict.destroy("./tmp")
+#[pyclass(name = "PerfContext", unsendable)]
+pub(crate) struct PerfContextPy {
+    inner: PerfContext,
+}
+
+#[pymethods]
+impl PerfContextPy {
+    #[new]
+    pub fn new() -> Self {
+        PerfContextPy {
+            inner: PerfContext::default(),
+        }
+    }
+
+    /// Enable the perf counters of the current thread.
+    ///
+    /// Args:
+    ///     timing: also measure the time spent, except for mutex waits.
+    #[staticmethod]
+    #[pyo3(signature = (timing = false))]
+    pub fn enable(timing: bool) {
+        set_level(if timing {
+            PerfLevel::Time
+        } else {
+            PerfLevel::Count
+        });
+    }
+
+    /// Disable the perf counters of the current thread.
+    #[staticmethod]
+    pub fn disable() {
+        set_level(PerfLevel::Disable);
+    }
+
+    /// Reset all the counters of the current thread to zero.
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// Get the value of a counter since the last `reset()`.
+    ///
+    /// Args:
+    ///     name: name of the counter, e.g. `"block_cache_hit_count"`,
+    ///         see `PerfContext.metric_names()`.
+    pub fn metric(&self, name: &str) -> PyResult<u64> {
+        match METRICS.into_iter().find(|(n, _)| *n == name) {
+            Some((_, metric)) => Ok(self.inner.metric(metric)),
+            None => Err(unknown_name_error(
+                "perf metric",
+                name,
+                METRICS.iter().map(|(n, _)| *n).collect(),
+            )),
+        }
+    }
+
+    /// Get the values of all the named counters, as a dict from name to value.
+    pub fn metrics(&self) -> HashMap<&'static str, u64> {
+        METRICS
+            .into_iter()
+            .map(|(name, metric)| (name, self.inner.metric(metric)))
+            .collect()
+    }
+
+    /// Human readable dump of all the counters.
+    ///
+    /// Args:
+    ///     exclude_zero_counters: omit the counters that are zero.
+    #[pyo3(signature = (exclude_zero_counters = true))]
+    pub fn report(&self, exclude_zero_counters: bool) -> String {
+        self.inner.report(exclude_zero_counters)
+    }
+
+    fn __str__(&self) -> String {
+        self.report(true)
+    }
+
+    /// Names of the counters accepted by `PerfContext.metric()`.
+    #[staticmethod]
+    pub fn metric_names() -> Vec<&'static str> {
+        METRICS.iter().map(|(n, _)| *n).collect()
+    }
+}
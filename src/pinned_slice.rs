@@ -0,0 +1,250 @@
+use crate::db_reference::DbReferenceHolder;
+use crate::exceptions::DbClosedError;
+use crate::util::error_message;
+use crate::{ffi_try, ffi_try_impl};
+use libc::{c_char, c_int, c_void, size_t};
+use pyo3::exceptions::{PyBufferError, PyException};
+use pyo3::ffi;
+use pyo3::prelude::*;
+use pyo3::types::PyMemoryView;
+use rocksdb::{AsColumnFamilyRef, ReadOptions, UnboundColumnFamily};
+use std::ptr::null_mut;
+use std::sync::Arc;
+
+/// A value pinned in RocksDB memory (memtable or block cache),
+/// exposed through the Python buffer protocol without copying.
+///
+/// Only available in raw mode. Obtain one with `Rdict.get_pinned()`.
+///
+/// Notes:
+///     The underlying block stays pinned as long as this object is alive.
+///     Call `close()` (or `del`) to release it as soon as you are done,
+///     otherwise the pinned blocks cannot be evicted from the cache.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options
+///
+///         db = Rdict("./tmp", Options(raw_mode=True))
+///         db[b"key"] = b"a very large value"
+///
+///         pinned = db.get_pinned(b"key")
+///         view = memoryview(pinned)
+///         assert view[:6] == b"a very"
+///
+///         # release the view first and then the pinned slice
+///         view.release()
+///         pinned.close()
+///
+///         # or release both at the end of the `with` blocks
+///         with db.get_pinned(b"key") as pinned, memoryview(pinned) as view:
+///             assert view[:6] == b"a very"
+///         del db
+///         Rdict.destroy("./tmp")
+#[pyclass(name = "PinnedSlice")]
+pub(crate) struct PinnedSlicePy {
+    inner: *mut librocksdb_sys::rocksdb_pinnableslice_t,
+    /// number of buffers currently exported to python
+    exports: usize,
+    // drop DB last
+    db: DbReferenceHolder,
+}
+
+unsafe impl Send for PinnedSlicePy {}
+
+impl PinnedSlicePy {
+    /// Returns `None` if the key is not found.
+    pub(crate) fn new(
+        db: &DbReferenceHolder,
+        cf: &Arc<UnboundColumnFamily>,
+        key: &[u8],
+        read_opt: &ReadOptions,
+        py: Python,
+    ) -> PyResult<Option<Self>> {
+        let db_inner = db
+            .get()
+            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?;
+        py.allow_threads(|| {
+            let inner = unsafe {
+                ffi_try!(librocksdb_sys::rocksdb_get_pinned_cf(
+                    db_inner.inner(),
+                    read_opt.inner(),
+                    cf.inner(),
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                ))
+            };
+            if inner.is_null() {
+                return Ok(None);
+            }
+            Ok(Some(PinnedSlicePy {
+                inner,
+                exports: 0,
+                db: db.clone(),
+            }))
+        })
+    }
+
+    fn as_bytes(&self) -> PyResult<&[u8]> {
+        if self.inner.is_null() {
+            return Err(PyException::new_err("PinnedSlice already closed"));
+        }
+        if self.db.get().is_none() {
+            return Err(DbClosedError::new_err("DB instance already closed"));
+        }
+        unsafe {
+            let mut val_len: size_t = 0;
+            let val_ptr = librocksdb_sys::rocksdb_pinnableslice_value(self.inner, &mut val_len);
+            if val_ptr.is_null() {
+                Ok(&[])
+            } else {
+                Ok(std::slice::from_raw_parts(val_ptr as *const u8, val_len))
+            }
+        }
+    }
+
+    fn release(&mut self) {
+        if !self.inner.is_null() {
+            unsafe {
+                librocksdb_sys::rocksdb_pinnableslice_destroy(self.inner);
+            }
+            self.inner = null_mut();
+        }
+    }
+}
+
+#[pymethods]
+impl PinnedSlicePy {
+    /// Unpin the value.
+    ///
+    /// Raises an exception if there are still `memoryview` objects
+    /// referencing this slice. Calling `close()` twice is a no-op.
+    fn close(&mut self) -> PyResult<()> {
+        if self.exports > 0 {
+            return Err(PyBufferError::new_err(
+                "cannot close PinnedSlice while memoryviews of it are alive",
+            ));
+        }
+        self.release();
+        self.db.release();
+        Ok(())
+    }
+
+    fn __enter__(slf: Bound<Self>) -> Bound<Self> {
+        slf
+    }
+
+    /// Unpin the value when leaving the `with` block, see `close()`.
+    fn __exit__(
+        &mut self,
+        _exc_type: &Bound<PyAny>,
+        _exc_val: &Bound<PyAny>,
+        _exc_tb: &Bound<PyAny>,
+    ) -> PyResult<bool> {
+        self.close()?;
+        // do not suppress exceptions
+        Ok(false)
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.as_bytes()?.len())
+    }
+
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+        if (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("PinnedSlice is read-only"));
+        }
+        let mut this = slf.borrow_mut();
+        fill_read_only_view(view, slf.as_any(), this.as_bytes()?, flags);
+        this.exports += 1;
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&mut self, _view: *mut ffi::Py_buffer) {
+        self.exports -= 1;
+    }
+}
+
+impl Drop for PinnedSlicePy {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// An owned copy of a raw mode value, exposed through the buffer protocol.
+///
+/// Returned wrapped in a `memoryview`, see `Rdict.set_raw_value_type()`.
+#[pyclass(frozen)]
+pub(crate) struct ValueBuffer(Vec<u8>);
+
+impl ValueBuffer {
+    /// A read-only `memoryview` of a copy of `value`.
+    pub(crate) fn memoryview(py: Python, value: &[u8]) -> PyResult<PyObject> {
+        let buffer = Bound::new(py, ValueBuffer(value.to_vec()))?;
+        Ok(PyMemoryView::from_bound(buffer.as_any())?
+            .into_any()
+            .unbind())
+    }
+}
+
+#[pymethods]
+impl ValueBuffer {
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    unsafe fn __getbuffer__(
+        slf: Bound<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+        if (flags & ffi::PyBUF_WRITABLE) == ffi::PyBUF_WRITABLE {
+            return Err(PyBufferError::new_err("value buffers are read-only"));
+        }
+        fill_read_only_view(view, slf.as_any(), &slf.get().0, flags);
+        Ok(())
+    }
+}
+
+/// Export `bytes`, owned by `obj`, as a read-only buffer of unsigned bytes.
+unsafe fn fill_read_only_view(
+    view: *mut ffi::Py_buffer,
+    obj: &Bound<PyAny>,
+    bytes: &[u8],
+    flags: c_int,
+) {
+    (*view).buf = bytes.as_ptr() as *mut c_void;
+    (*view).len = bytes.len() as isize;
+    (*view).obj = obj.clone().into_ptr();
+    (*view).readonly = 1;
+    (*view).itemsize = 1;
+    (*view).format = if (flags & ffi::PyBUF_FORMAT) == ffi::PyBUF_FORMAT {
+        b"B\0".as_ptr() as *mut c_char
+    } else {
+        null_mut()
+    };
+    (*view).ndim = 1;
+    (*view).shape = if (flags & ffi::PyBUF_ND) == ffi::PyBUF_ND {
+        &mut (*view).len
+    } else {
+        null_mut()
+    };
+    (*view).strides = if (flags & ffi::PyBUF_STRIDES) == ffi::PyBUF_STRIDES {
+        &mut (*view).itemsize
+    } else {
+        null_mut()
+    };
+    (*view).suboffsets = null_mut();
+    (*view).internal = null_mut();
+}
@@ -1,25 +1,43 @@
-use crate::db_reference::{DbReference, DbReferenceHolder};
-use crate::encoder::{decode_value, encode_key, encode_value};
+use crate::db_reference::{is_db_open, DbReference, DbReferenceHolder};
+use crate::encoder::{
+    decode_key_with, decode_value, decode_value_with, encode_key, encode_key_with, encode_value,
+    encode_value_with, named_serializer, resolve_serializer, EncodedBytes, RawBuffer,
+    ValueTransform, CUSTOM_SERIALIZER,
+};
+use crate::event_listener::{create_background_error_listener, LastBackgroundError};
 use crate::exceptions::DbClosedError;
-use crate::iter::{RdictItems, RdictKeys, RdictValues};
-use crate::options::{CachePy, EnvPy, SliceTransformType};
+use crate::iter::{RdictItems, RdictKeys};
+use crate::options::{
+    check_merge_operand, rocksdict_compare, rocksdict_numeric_compare, CachePy, EnvPy,
+    MergeOperatorType, SliceTransformType, U64_TIMESTAMP_SIZE,
+};
+use crate::perf_context::with_perf_counters;
+use crate::pinned_slice::{PinnedSlicePy, ValueBuffer};
+use crate::statistics::StatisticsPy;
+use crate::util::error_message;
+use crate::views::{RdictItemsView, RdictKeysView, RdictValuesView, ViewArgs};
+use crate::wal_iter::WalIteratorPy;
+use crate::write_batch_with_index::WriteBatchWithIndexPy;
+use crate::{ffi_try, ffi_try_impl};
 use crate::{
     CompactOptionsPy, FlushOptionsPy, IngestExternalFileOptionsPy, OptionsPy, RdictColumns,
-    RdictEntities, RdictIter, ReadOptionsPy, Snapshot, WriteBatchPy, WriteOptionsPy,
+    RdictEntities, RdictIter, ReadOptionsPy, Snapshot, WriteBatchPy, WriteOpt, WriteOptionsPy,
 };
-use pyo3::exceptions::{PyException, PyKeyError};
+use libc::{c_char, c_int, c_uchar};
+use pyo3::exceptions::{PyException, PyKeyError, PyTypeError, PyUserWarning, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList, PyTuple};
+use pyo3::types::{PyBytes, PyDict, PyList, PyMapping, PyString, PyTuple};
 use rocksdb::{
-    ColumnFamilyDescriptor, FlushOptions, Iterable as _, LiveFile, ReadOptions,
-    UnboundColumnFamily, WriteOptions, DEFAULT_COLUMN_FAMILY_NAME,
+    AsColumnFamilyRef, ColumnFamilyDescriptor, FlushOptions, Iterable as _, LiveFile, ReadOptions,
+    UnboundColumnFamily, WriteBatch, WriteOptions, DEFAULT_COLUMN_FAMILY_NAME,
 };
 use serde::{Deserialize, Serialize};
-use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
+use std::io::Read;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
@@ -38,6 +56,25 @@ type DB = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
 ///
 /// A persistent on-disk dictionary. Supports string, int, float, bytes as key, values.
 ///
+/// ints are not limited to 64 bits, any python int is stored exactly
+/// (as signed big endian bytes) and keys compare by value.
+///
+/// Timezone-aware `datetime.datetime` keys are ordered chronologically and
+/// read back as UTC datetimes (naive datetimes are rejected), `uuid.UUID`
+/// keys are stored as their 16 bytes. Keys of different types are ordered
+/// by type: bytes, str, int, float, bool, datetime, UUID, then tuple.
+///
+/// Tuples of these types (including tuples) are composite keys, ordered
+/// element by element: `(a,)` sorts before `(a, b)`, and `keys_with_prefix((a,))`
+/// iterates all the keys starting with `a`. Within tuples, ints sort before
+/// floats, regardless of `Options.set_ordered_numeric_keys()`.
+///
+/// numpy integer, floating and bool scalars are accepted as keys, as the
+/// same keys as their python equivalents (`db[np.int64(1)]` is `db[1]`), and
+/// numpy arrays are stored without pickle (non-contiguous arrays are copied),
+/// unless another serializer or a value transform is set.
+/// numpy is only used if it was imported by the program.
+///
 /// Example:
 ///     ::
 ///
@@ -65,6 +102,12 @@ type DB = rocksdb::DBWithThreadMode<rocksdb::MultiThreaded>;
 ///     access_type (AccessType): there are four access types:
 ///         ReadWrite, ReadOnly, WithTTL, and Secondary, use
 ///         AccessType class to create.
+///     serializer: how values that are not `str`, `int`, `float`, `bool`
+///         or `bytes` are stored: `"pickle"` (default), `"msgpack"` (requires
+///         the msgpack package), `"json"`, or a `(dumps, loads)` tuple.
+///         It is saved in the db, so `Rdict(path)` reads the values with
+///         the same serializer, and opening it with another one raises.
+///         `set_dumps()` and `set_loads()` override it.
 #[pyclass(name = "Rdict")]
 pub(crate) struct Rdict {
     pub(crate) write_opt: WriteOptions,
@@ -72,12 +115,24 @@ pub(crate) struct Rdict {
     pub(crate) read_opt: ReadOptions,
     pub(crate) loads: PyObject,
     pub(crate) dumps: PyObject,
+    pub(crate) key_loads: Option<PyObject>,
+    pub(crate) key_dumps: Option<PyObject>,
     pub(crate) write_opt_py: WriteOptionsPy,
     pub(crate) read_opt_py: ReadOptionsPy,
     pub(crate) column_family: Option<Arc<UnboundColumnFamily>>,
+    pub(crate) column_family_name: String,
     pub(crate) opt_py: OptionsPy,
     pub(crate) access_type: AccessType,
     pub(crate) slice_transforms: Arc<RwLock<HashMap<String, SliceTransformType>>>,
+    pub(crate) merge_operators: Arc<RwLock<HashMap<String, MergeOperatorType>>>,
+    /// name of the serializer saved in the rocksdict config
+    pub(crate) serializer: String,
+    /// raw mode values are returned as `memoryview`, see `set_raw_value_type()`
+    pub(crate) memoryview_values: bool,
+    /// see `set_value_transform()`
+    pub(crate) value_transform: Option<ValueTransform>,
+    /// see `background_error`
+    pub(crate) background_error: LastBackgroundError,
     // drop DB last
     pub(crate) db: DbReferenceHolder,
 }
@@ -112,15 +167,30 @@ pub(crate) struct AccessType(AccessTypeInner);
 #[derive(Serialize, Deserialize)]
 pub struct RocksDictConfig {
     pub raw_mode: bool,
+    #[serde(default)]
+    pub ordered_numeric_keys: bool,
     // mapping from column families to SliceTransformType
     pub prefix_extractors: HashMap<String, SliceTransformType>,
+    // mapping from column families to MergeOperatorType
+    #[serde(default)]
+    pub merge_operators: HashMap<String, MergeOperatorType>,
+    // `None` for the dbs created before the serializer was saved
+    #[serde(default)]
+    pub serializer: Option<String>,
+    // size of the user-defined timestamps, 0 if the keys have no timestamp
+    #[serde(default)]
+    pub timestamp_size: usize,
 }
 
 impl Default for RocksDictConfig {
     fn default() -> Self {
         Self {
             raw_mode: true,
+            ordered_numeric_keys: false,
             prefix_extractors: Default::default(),
+            merge_operators: Default::default(),
+            serializer: None,
+            timestamp_size: 0,
         }
     }
 }
@@ -156,11 +226,18 @@ impl Rdict {
     pub(crate) fn config(&self) -> RocksDictConfig {
         RocksDictConfig {
             raw_mode: self.opt_py.raw_mode,
+            ordered_numeric_keys: self.opt_py.ordered_numeric_keys,
             prefix_extractors: self.slice_transforms.read().unwrap().clone(),
+            merge_operators: self.merge_operators.read().unwrap().clone(),
+            serializer: Some(self.serializer.clone()),
+            timestamp_size: self.opt_py.timestamp_size,
         }
     }
 
     fn dump_config(&self) -> PyResult<()> {
+        if self.opt_py.in_memory {
+            return Ok(());
+        }
         self.config().save_to_dir(&self.path()?)
     }
 
@@ -170,6 +247,281 @@ impl Rdict {
             .get()
             .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))
     }
+
+    /// Encode a key with the key dumps function of this `Rdict`.
+    #[inline]
+    pub(crate) fn encode_key<'a>(&self, key: &'a Bound<PyAny>) -> PyResult<EncodedBytes<'a>> {
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.opt_py.raw_mode)?;
+        check_encoded_size("key", key.len(), self.opt_py.max_key_bytes)?;
+        Ok(key)
+    }
+
+    /// Convert read options, encoding their bounds like the keys of this `Rdict`.
+    #[inline]
+    pub(crate) fn to_read_options(
+        &self,
+        read_opt: &ReadOptionsPy,
+        py: Python,
+    ) -> PyResult<ReadOptions> {
+        read_opt.to_read_options(self.opt_py.raw_mode, self.key_dumps.as_ref(), py)
+    }
+
+    /// The user-defined timestamp of a read or a write, as little endian bytes.
+    fn encode_timestamp(&self, ts: u64) -> PyResult<[u8; U64_TIMESTAMP_SIZE]> {
+        if self.opt_py.timestamp_size != U64_TIMESTAMP_SIZE {
+            return Err(PyException::new_err(
+                "the db has no user-defined timestamps, \
+                 open it with Options.set_u64_timestamp_comparator()",
+            ));
+        }
+        Ok(ts.to_le_bytes())
+    }
+
+    /// Decode a value returned by `get()`, see `set_raw_value_type()`.
+    #[inline]
+    fn decode_got_value(&self, py: Python, value: &[u8]) -> PyResult<PyObject> {
+        match (&self.value_transform, self.memoryview_values) {
+            (None, true) => ValueBuffer::memoryview(py, value),
+            (Some(transform), true) => {
+                ValueBuffer::memoryview(py, transform.decode(py, value)?.as_bytes())
+            }
+            (transform, false) => decode_value_with(
+                py,
+                value,
+                &self.loads,
+                transform.as_ref(),
+                self.opt_py.raw_mode,
+            ),
+        }
+    }
+
+    /// Read an encoded key like `get()`, `None` only if the key does not exist.
+    pub(crate) fn get_existing(&self, key: &[u8], py: Python) -> PyResult<Option<PyObject>> {
+        let db = self.get_db()?;
+        let value_result = py.allow_threads(|| match &self.column_family {
+            Some(cf) => db.get_pinned_cf_opt(cf, key, &self.read_opt),
+            None => db.get_pinned_opt(key, &self.read_opt),
+        });
+        match value_result {
+            Ok(None) => Ok(None),
+            Ok(Some(slice)) => Ok(Some(self.decode_got_value(py, slice.as_ref())?)),
+            Err(e) => Err(PyException::new_err(e.to_string())),
+        }
+    }
+
+    /// Write an encoded key and value, see `put()`.
+    fn put_encoded(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        write_opt: Option<&WriteOptionsPy>,
+        ts: Option<[u8; U64_TIMESTAMP_SIZE]>,
+        py: Python,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        py.allow_threads(|| match (&self.column_family, ts) {
+            (Some(cf), None) => db.put_cf_opt(cf, key, value, write_opt),
+            (None, None) => db.put_opt(key, value, write_opt),
+            (Some(cf), Some(ts)) => db.put_cf_with_ts_opt(cf, key, ts, value, write_opt),
+            (None, Some(ts)) => db.put_with_ts_opt(key, ts, value, write_opt),
+        })
+        .map_err(|e| PyException::new_err(e.to_string()))
+    }
+
+    /// Delete an encoded key, see `delete()`.
+    fn delete_encoded(
+        &self,
+        key: &[u8],
+        write_opt: Option<&WriteOptionsPy>,
+        ts: Option<[u8; U64_TIMESTAMP_SIZE]>,
+        py: Python,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        py.allow_threads(|| match (&self.column_family, ts) {
+            (Some(cf), None) => db.delete_cf_opt(cf, key, write_opt),
+            (None, None) => db.delete_opt(key, write_opt),
+            (Some(cf), Some(ts)) => db.delete_cf_with_ts_opt(cf, key, ts, write_opt),
+            (None, Some(ts)) => db.delete_with_ts_opt(key, ts, write_opt),
+        })
+        .map_err(|e| PyException::new_err(e.to_string()))
+    }
+
+    /// An iterator positioned at `from_key`, bounded by `to_key` (excluded).
+    fn range_iter(
+        &self,
+        from_key: Option<&Bound<PyAny>>,
+        to_key: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<RdictIter> {
+        let mut read_opt: ReadOptionsPy = match read_opt {
+            None => ReadOptionsPy::default(py)?,
+            Some(opt) => opt.clone(),
+        };
+        if let Some(to_key) = to_key {
+            read_opt.set_iterate_upper_bound(to_key, py)?;
+        }
+        let mut iter = self.iter(Some(&read_opt), py)?;
+        match from_key {
+            None => iter.seek_to_first(py)?,
+            Some(from_key) => iter.seek(from_key, py)?,
+        }
+        Ok(iter)
+    }
+
+    /// Decode a `(key, value)` pair read by an iterator.
+    fn decode_pair(&self, py: Python, key: &[u8], value: &[u8]) -> PyResult<(PyObject, PyObject)> {
+        let raw_mode = self.opt_py.raw_mode;
+        Ok((
+            decode_key_with(py, key, &self.loads, self.key_loads.as_ref(), raw_mode)?,
+            decode_value_with(
+                py,
+                value,
+                &self.loads,
+                self.value_transform.as_ref(),
+                raw_mode,
+            )?,
+        ))
+    }
+
+    /// Encode a value, see `set_value_transform()`.
+    #[inline]
+    fn encode_value<'a>(&self, value: &'a Bound<PyAny>) -> PyResult<EncodedBytes<'a>> {
+        let transform = self.value_transform.as_ref();
+        let value = encode_value_with(value, &self.dumps, transform, self.opt_py.raw_mode)?;
+        check_encoded_size("value", value.len(), self.opt_py.max_value_bytes)?;
+        Ok(value)
+    }
+
+    /// Decode a value, see `set_value_transform()`.
+    #[inline]
+    fn decode_value(&self, py: Python, value: &[u8]) -> PyResult<PyObject> {
+        let transform = self.value_transform.as_ref();
+        decode_value_with(py, value, &self.loads, transform, self.opt_py.raw_mode)
+    }
+
+    fn check_write_batch_raw_mode(&self, raw_mode: bool) -> PyResult<()> {
+        if self.opt_py.raw_mode != raw_mode {
+            return if self.opt_py.raw_mode {
+                Err(PyException::new_err(
+                    "must set raw_mode=True for WriteBatch",
+                ))
+            } else {
+                Err(PyException::new_err(
+                    "must set raw_mode=False for WriteBatch",
+                ))
+            };
+        }
+        Ok(())
+    }
+
+    /// Create an iterator bounded to keys starting with `prefix`.
+    fn prefix_iter(
+        &self,
+        prefix: &Bound<PyAny>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<RdictIter> {
+        if !self.opt_py.raw_mode
+            && !(prefix.is_instance_of::<PyString>()
+                || prefix.is_instance_of::<PyBytes>()
+                || prefix.is_instance_of::<PyTuple>())
+        {
+            return Err(PyException::new_err(
+                "prefix must be `str`, `bytes` or `tuple`",
+            ));
+        }
+        let dumps_keys = self.key_dumps.is_some() && !self.opt_py.raw_mode;
+        if dumps_keys && !prefix.is_instance_of::<PyBytes>() {
+            return Err(PyException::new_err(
+                "prefix must be `bytes` when a key dumps function is set",
+            ));
+        }
+        // a `bytes` prefix is encoded like the beginning of the dumped keys
+        let prefix = encode_key(prefix, self.opt_py.raw_mode)?;
+        let mut read_opt: ReadOptionsPy = match read_opt {
+            None => ReadOptionsPy::default(py)?,
+            Some(opt) => opt.clone(),
+        };
+        let extractor_len = match self
+            .slice_transforms
+            .read()
+            .unwrap()
+            .get(&self.column_family_name)
+        {
+            Some(SliceTransformType::Fixed(len)) => Some(*len),
+            Some(SliceTransformType::MaxLen(len)) => Some(*len),
+            Some(SliceTransformType::Noop) | None => None,
+        };
+        if let Some(extractor_len) = extractor_len {
+            if prefix.len() >= extractor_len {
+                read_opt.set_prefix_same_as_start(true);
+            } else {
+                PyErr::warn_bound(
+                    py,
+                    py.get_type_bound::<PyUserWarning>().as_any(),
+                    &format!(
+                        "encoded prefix length {} is shorter than the prefix extractor \
+                        length {extractor_len}, falling back to total order seek",
+                        prefix.len()
+                    ),
+                    1,
+                )?;
+                read_opt.set_total_order_seek(true);
+            }
+        }
+        let mut readopts =
+            read_opt.to_read_opt(self.opt_py.raw_mode, self.key_dumps.as_ref(), py)?;
+        if let Some(upper_bound) = prefix_upper_bound(&prefix) {
+            readopts.set_encoded_iterate_upper_bound(upper_bound);
+        }
+        // seeking to the prefix must not call the key dumps function
+        RdictIter::from_read_opt(
+            &self.db,
+            &self.column_family,
+            readopts,
+            &self.loads,
+            None,
+            self.key_loads.as_ref(),
+            self.opt_py.raw_mode,
+        )
+        .map(|iter| iter.with_value_transform(self.value_transform.clone()))
+    }
+}
+
+fn check_not_open(path: &str, operation: &str) -> PyResult<()> {
+    if is_db_open(Path::new(path)) {
+        return Err(PyException::new_err(format!(
+            "cannot {operation} `{path}` while it is open in this process, \
+            close all the instances using it first"
+        )));
+    }
+    Ok(())
+}
+
+/// The smallest byte string greater than all keys starting with `prefix`.
+///
+/// Returns `None` if there is no such bound (the prefix is empty or all `0xFF`),
+/// in which case all keys following the prefix share the prefix.
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper_bound = prefix.to_vec();
+    while let Some(last) = upper_bound.pop() {
+        if last < u8::MAX {
+            upper_bound.push(last + 1);
+            return Some(upper_bound);
+        }
+    }
+    None
 }
 
 #[pymethods]
@@ -179,63 +531,161 @@ impl Rdict {
     /// If Options are not provided:
     /// - first, attempt to read from the path
     /// - if failed to read from the path, use default
+    ///
+    /// Existing column families missing from `column_families` are opened
+    /// with their latest options. Column families of `column_families` that
+    /// do not exist are created if `Options.set_create_missing_column_families(True)`
+    /// is set, otherwise opening raises an exception.
     #[new]
     #[pyo3(signature = (
         path,
         options = None,
         column_families = None,
-        access_type = AccessType::read_write()
+        access_type = AccessType::read_write(),
+        serializer = None
     ))]
     fn new(
         path: &str,
         options: Option<OptionsPy>,
         column_families: Option<HashMap<String, OptionsPy>>,
         access_type: AccessType,
+        serializer: Option<&Bound<PyAny>>,
         py: Python,
     ) -> PyResult<Self> {
-        let pickle = PyModule::import_bound(py, "pickle")?.to_object(py);
+        // nothing is stored on disk with `Env.mem_env()`
+        let in_memory = options.as_ref().is_some_and(|opt| opt.in_memory);
         // create db path if missing
-        fs::create_dir_all(path).map_err(|e| PyException::new_err(e.to_string()))?;
+        if !in_memory {
+            fs::create_dir_all(path).map_err(|e| PyException::new_err(e.to_string()))?;
+        }
+        // the comparator of the db depends on raw_mode
+        if let (Some(opt), Ok(config)) = (&options, RocksDictConfig::load(config_file(path))) {
+            if opt.raw_mode != config.raw_mode {
+                let raw_mode = if config.raw_mode { "True" } else { "False" };
+                return Err(PyException::new_err(format!(
+                    "the db at `{path}` was created with raw_mode={raw_mode}, \
+                     open it with Options(raw_mode={raw_mode})"
+                )));
+            }
+            if opt.ordered_numeric_keys != config.ordered_numeric_keys {
+                let ordered = if config.ordered_numeric_keys {
+                    "True"
+                } else {
+                    "False"
+                };
+                return Err(PyException::new_err(format!(
+                    "the db at `{path}` was created with ordered_numeric_keys={ordered}, \
+                     open it with Options.set_ordered_numeric_keys({ordered})"
+                )));
+            }
+            // rocksdb itself rejects a comparator not matching the db
+            if config.timestamp_size != 0 && opt.timestamp_size != config.timestamp_size {
+                return Err(PyException::new_err(format!(
+                    "the db at `{path}` was created with user-defined timestamps, \
+                     open it with Options.set_u64_timestamp_comparator()"
+                )));
+            }
+        }
+        // values are read with the serializer they were written with
+        let saved_serializer = RocksDictConfig::load(config_file(path))
+            .ok()
+            .and_then(|config| config.serializer);
+        let (serializer, dumps, loads) = match (serializer, saved_serializer) {
+            (Some(serializer), saved) => {
+                let (name, dumps, loads) = resolve_serializer(py, serializer)?;
+                if let Some(saved) = saved.filter(|saved| *saved != name) {
+                    return Err(PyException::new_err(format!(
+                        "the db at `{path}` was created with serializer `{saved}`, \
+                         not `{name}`"
+                    )));
+                }
+                (name, dumps, loads)
+            }
+            (None, Some(saved)) if saved == CUSTOM_SERIALIZER => {
+                return Err(PyException::new_err(format!(
+                    "the db at `{path}` was created with a custom serializer, \
+                     open it with serializer=(dumps, loads)"
+                )));
+            }
+            (None, saved) => {
+                let name = saved.unwrap_or_else(|| "pickle".to_string());
+                let (dumps, loads) = named_serializer(py, &name)?;
+                (name, dumps, loads)
+            }
+        };
         // load options
-        let options_loaded = OptionsPy::load_latest_inner(
-            path,
-            EnvPy::default()?,
-            false,
-            CachePy::new_lru_cache(DEFAULT_LRU_CACHE_SIZE),
-        );
-        // prioritize passed options over loaded options
+        let options_loaded = if in_memory {
+            Err(PyException::new_err("in-memory db has no options file"))
+        } else {
+            OptionsPy::load_latest_inner(
+                path,
+                EnvPy::default()?,
+                false,
+                CachePy::new_lru_cache(DEFAULT_LRU_CACHE_SIZE),
+            )
+        };
+        // prioritize passed options over loaded options,
+        // all the existing column families must be opened
         let (options, column_families) = match (options_loaded, options, column_families) {
-            (Ok((opt_loaded, cols_loaded)), opt, cols) => match (opt, cols) {
-                (Some(opt), Some(cols)) => (opt, Some(cols)),
-                (Some(opt), None) => (opt, Some(cols_loaded)),
-                (None, Some(cols)) => (opt_loaded, Some(cols)),
-                (None, None) => (opt_loaded, Some(cols_loaded)),
-            },
+            (Ok((opt_loaded, mut cols_loaded)), opt, cols) => {
+                if let Some(cols) = cols {
+                    // unless passed, the default column family uses the db options
+                    cols_loaded.remove(DEFAULT_COLUMN_FAMILY_NAME);
+                    cols_loaded.extend(cols);
+                }
+                (opt.unwrap_or(opt_loaded), Some(cols_loaded))
+            }
             (Err(_), Some(opt), cols) => (opt, cols),
             (Err(_), None, cols) => (OptionsPy::new(false), cols),
         };
         // save slice transforms types in rocksdict config
         let config_path = config_file(path);
         let mut prefix_extractors = HashMap::new();
+        let mut merge_operators = HashMap::new();
         if let Some(slice_transform) = &options.prefix_extractor {
             prefix_extractors.insert(
                 DEFAULT_COLUMN_FAMILY_NAME.to_string(),
                 slice_transform.clone(),
             );
         }
+        if let Some(merge_operator) = &options.merge_operator {
+            merge_operators.insert(
+                DEFAULT_COLUMN_FAMILY_NAME.to_string(),
+                merge_operator.clone(),
+            );
+        }
         if let Some(cf) = &column_families {
             for (name, opt) in cf.iter() {
                 if let Some(slice_transform) = &opt.prefix_extractor {
                     prefix_extractors.insert(name.clone(), slice_transform.clone());
                 }
+                if let Some(merge_operator) = &opt.merge_operator {
+                    merge_operators.insert(name.clone(), merge_operator.clone());
+                }
             }
         }
         let rocksdict_config = RocksDictConfig {
             raw_mode: options.raw_mode,
+            ordered_numeric_keys: options.ordered_numeric_keys,
             prefix_extractors: prefix_extractors.clone(),
+            merge_operators: merge_operators.clone(),
+            serializer: Some(serializer.clone()),
+            timestamp_size: options.timestamp_size,
         };
-        rocksdict_config.save(config_path)?;
-        let opt_inner = &options.inner_opt;
+        if !in_memory {
+            rocksdict_config.save(config_path)?;
+        }
+        // record the status of the background errors, see `background_error`
+        let background_error = LastBackgroundError::default();
+        let opt_inner = options.inner_opt.clone();
+        unsafe {
+            // the options take the ownership of the listener
+            librocksdb_sys::rocksdb_options_add_eventlistener(
+                opt_inner.inner() as *mut _,
+                create_background_error_listener(py, &background_error),
+            );
+        }
+        let opt_inner = &opt_inner;
         // define column families
         let cfs = match column_families {
             None => {
@@ -249,6 +699,14 @@ impl Rdict {
                 // check options_raw_mode for column families
                 for (cf_name, cf_opt) in cf.iter_mut() {
                     cf_opt.raw_mode = options.raw_mode;
+                    if cf_opt.ordered_numeric_keys != options.ordered_numeric_keys {
+                        cf_opt.set_ordered_numeric_keys(options.ordered_numeric_keys)?;
+                    }
+                    if cf_opt.timestamp_size != options.timestamp_size
+                        && options.timestamp_size == U64_TIMESTAMP_SIZE
+                    {
+                        cf_opt.set_u64_timestamp_comparator()?;
+                    }
                     if cf_name.as_str() == DEFAULT_COLUMN_FAMILY_NAME {
                         has_default_cf = true;
                     }
@@ -280,35 +738,111 @@ impl Rdict {
                 DB::open_cf_descriptors_with_ttl(opt_inner, path, cfs, *ttl)
             }
         }
-        .map_err(|e| PyException::new_err(e.to_string()))?;
+        .map_err(|e| {
+            let e = e.into_string();
+            if e.contains("Column family not found") {
+                PyException::new_err(format!(
+                    "{e}, call Options.set_create_missing_column_families(True) \
+                     to create the missing column families"
+                ))
+            } else {
+                PyException::new_err(e)
+            }
+        })?;
         let r_opt = ReadOptionsPy::default(py)?;
-        let w_opt = WriteOptionsPy::new();
+        let w_opt = WriteOptionsPy::default();
         Ok(Rdict {
             db: DbReferenceHolder::new(db),
             write_opt: (&w_opt).into(),
             flush_opt: FlushOptionsPy::new(),
-            read_opt: r_opt.to_read_options(options.raw_mode, py)?,
-            loads: pickle.getattr(py, "loads")?,
-            dumps: pickle.getattr(py, "dumps")?,
+            read_opt: r_opt.to_read_options(options.raw_mode, None, py)?,
+            loads,
+            dumps,
+            key_loads: None,
+            key_dumps: None,
             write_opt_py: w_opt,
             read_opt_py: r_opt,
             column_family: None,
+            column_family_name: DEFAULT_COLUMN_FAMILY_NAME.to_string(),
             opt_py: options.clone(),
             access_type,
             slice_transforms: Arc::new(RwLock::new(prefix_extractors)),
+            merge_operators: Arc::new(RwLock::new(merge_operators)),
+            serializer,
+            memoryview_values: false,
+            value_transform: None,
+            background_error,
         })
     }
 
     /// set custom dumps function
+    ///
+    /// Notes:
+    ///     Column family `Rdict` instances obtained afterwards
+    ///     through `get_column_family()` or `create_column_family()`
+    ///     inherit this function, existing ones are not affected.
     fn set_dumps(&mut self, dumps: PyObject) {
         self.dumps = dumps
     }
 
     /// set custom loads function
+    ///
+    /// Notes:
+    ///     This function is used by all read paths: `get`, batch get,
+    ///     `key_may_exist(fetch=True)`, iterators, and snapshots or column family
+    ///     `Rdict` instances created afterwards (existing ones are not affected).
     fn set_loads(&mut self, loads: PyObject) {
         self.loads = loads
     }
 
+    /// set custom key dumps function, converting keys to `bytes`
+    ///
+    /// The keys are ordered by the bytes returned by this function, so an
+    /// order-preserving encoding allows structured keys (e.g. tuples) with
+    /// meaningful range scans. It is ignored in raw mode.
+    ///
+    /// Notes:
+    ///     This function is used by all the paths encoding a key: `get`, `put`,
+    ///     `delete`, `merge`, `delete_range`, `compact_range`, iterator seeks and the
+    ///     iterate bounds of read options. Like `set_dumps()`, it is inherited by
+    ///     the iterators, snapshots and column family `Rdict` instances created
+    ///     afterwards. `WriteBatch` and `SstFileWriter` have their own
+    ///     `set_key_dumps()`, which must be set to the same function.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, encode_int_key, decode_int_key
+    ///
+    ///         def dumps(key):
+    ///             user_id, timestamp = key
+    ///             return encode_int_key(user_id) + encode_int_key(timestamp)
+    ///
+    ///         def loads(data):
+    ///             return decode_int_key(data[:8]), decode_int_key(data[8:])
+    ///
+    ///         db = Rdict("./tmp")
+    ///         db.set_key_dumps(dumps)
+    ///         db.set_key_loads(loads)
+    ///         db[(1, 1700000000)] = "login"
+    ///         events = list(db.items(from_key=(1, 0)))
+    fn set_key_dumps(&mut self, key_dumps: PyObject, py: Python) -> PyResult<()> {
+        self.key_dumps = Some(key_dumps);
+        // the preset read options encode their bounds with the key dumps function
+        self.read_opt = self.to_read_options(&self.read_opt_py, py)?;
+        Ok(())
+    }
+
+    /// set custom key loads function, converting the `bytes` returned by
+    /// the key dumps function back to keys
+    ///
+    /// Notes:
+    ///     This function is used by all the paths returning keys: iterators,
+    ///     `live_files()` and `split_ranges()`. It is ignored in raw mode.
+    fn set_key_loads(&mut self, key_loads: PyObject) {
+        self.key_loads = Some(key_loads)
+    }
+
     /// Optionally disable WAL or sync for this write.
     ///
     /// Example:
@@ -340,16 +874,19 @@ impl Rdict {
 
     /// Configure Read Options for all the get operations.
     fn set_read_options(&mut self, read_opt: &ReadOptionsPy, py: Python) -> PyResult<()> {
-        self.read_opt = read_opt.to_read_options(self.opt_py.raw_mode, py)?;
+        self.read_opt = self.to_read_options(read_opt, py)?;
         self.read_opt_py = read_opt.clone();
         Ok(())
     }
 
     /// Use list of keys for batch get.
+    ///
+    /// Raises `KeyError` if a single key does not exist. Missing keys
+    /// in a list of keys are returned as `None`.
     fn __getitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
-        match self.get(key, None, None, py) {
+        match self.get(key, None, None, None, py) {
             Ok(Some(v)) => Ok(v),
-            Ok(None) => Err(PyKeyError::new_err(format!("key {key} not found"))),
+            Ok(None) => Err(PyKeyError::new_err(key.clone().unbind())),
             Err(e) => Err(e),
         }
     }
@@ -359,24 +896,50 @@ impl Rdict {
     /// Args:
     ///     key: a single key or list of keys.
     ///     default: the default value to return if key not found.
+    ///         For a list of keys, it is substituted for each missing key.
     ///     read_opt: override preset read options
     ///         (or use Rdict.set_read_options to preset a read options used by default).
+    ///     ts: read the values as of this user-defined timestamp (an int),
+    ///         overriding the timestamp of the read options. Requires
+    ///         `Options.set_u64_timestamp_comparator()`.
     ///
     /// Returns:
     ///    None or default value if the key does not exist.
+    ///    A list of values in the order of the keys if given a list of keys
+    ///    (looked up with a single MultiGet).
+    ///
+    /// Notes:
+    ///     Unlike `__getitem__`, `get` never raises `KeyError`.
+    ///     The default only replaces true misses, so a stored `None`
+    ///     is returned as `None`. To tell the two apart, pass a
+    ///     sentinel as the default:
+    ///
+    ///     ::
+    ///
+    ///         missing = object()
+    ///         value = db.get("key", missing)
+    ///         if value is missing:
+    ///             ...
     #[inline]
-    #[pyo3(signature = (key, default = None, read_opt = None))]
+    #[pyo3(signature = (key, default = None, read_opt = None, ts = None))]
     fn get(
         &self,
         key: &Bound<PyAny>,
         default: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        ts: Option<u64>,
         py: Python,
     ) -> PyResult<Option<PyObject>> {
         let db = self.get_db()?;
-        let read_opt_option = match read_opt {
-            None => None,
-            Some(opt) => Some(opt.to_read_options(self.opt_py.raw_mode, py)?),
+        let read_opt_option = match (read_opt, ts) {
+            (None, None) => None,
+            (Some(opt), None) => Some(self.to_read_options(opt, py)?),
+            (opt, Some(ts)) => {
+                let ts = self.encode_timestamp(ts)?;
+                let mut opt = self.to_read_options(opt.unwrap_or(&self.read_opt_py), py)?;
+                opt.set_timestamp(ts);
+                Some(opt)
+            }
         };
         let read_opt = match &read_opt_option {
             None => &self.read_opt,
@@ -396,16 +959,18 @@ impl Rdict {
                     keys,
                     py,
                     read_opt,
-                    &self.loads,
+                    |value| self.decode_got_value(py, value),
+                    default,
+                    self.key_dumps.as_ref(),
                     &cf,
                     self.opt_py.raw_mode,
                 )?
                 .to_object(py),
             ));
         }
-        let key_bytes = encode_key(key, self.opt_py.raw_mode)?;
-        let value_result = db
-            .get_pinned_cf_opt(&cf, key_bytes, read_opt)
+        let key_bytes = self.encode_key(key)?;
+        let value_result = py
+            .allow_threads(|| db.get_pinned_cf_opt(&cf, &key_bytes[..], read_opt))
             .map_err(|e| PyException::new_err(e.to_string()))?;
         match value_result {
             None => {
@@ -416,15 +981,47 @@ impl Rdict {
                     Ok(None)
                 }
             }
-            Some(slice) => Ok(Some(decode_value(
-                py,
-                slice.as_ref(),
-                &self.loads,
-                self.opt_py.raw_mode,
-            )?)),
+            Some(slice) => Ok(Some(self.decode_got_value(py, slice.as_ref())?)),
         }
     }
 
+    /// Get value from key or a list of keys like `get()`, together with
+    /// the perf counters of this read.
+    ///
+    /// Args:
+    ///     key: a single key or list of keys.
+    ///     default: the default value to return if key not found.
+    ///     read_opt: override preset read options.
+    ///
+    /// Returns:
+    ///     a tuple `(value, counters)`, where counters is a dict with
+    ///     `block_cache_hit_count` (blocks found in the block cache),
+    ///     `block_read_count` (blocks read from the sst files, i.e.
+    ///     block cache misses), `block_read_byte`, `get_read_bytes`,
+    ///     `get_from_memtable_count` and `bloom_sst_miss_count`.
+    ///
+    /// Notes:
+    ///     The counters come from the `PerfContext` of the current thread,
+    ///     which is not reset. Counts are enabled for this read even if
+    ///     disabled with `PerfContext.disable()`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         value, counters = db.get_with_stats("key")
+    ///         if counters["block_read_count"] > 0:
+    ///             print("not served from the block cache")
+    #[pyo3(signature = (key, default = None, read_opt = None))]
+    fn get_with_stats(
+        &self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<(Option<PyObject>, HashMap<&'static str, u64>)> {
+        with_perf_counters(|| self.get(key, default, read_opt, None, py))
+    }
+
     /// Get a wide-column from a key.
     ///
     /// Args:
@@ -450,7 +1047,7 @@ impl Rdict {
         let db = self.get_db()?;
         let read_opt_option = match read_opt {
             None => None,
-            Some(opt) => Some(opt.to_read_options(self.opt_py.raw_mode, py)?),
+            Some(opt) => Some(self.to_read_options(opt, py)?),
         };
         let read_opt = match &read_opt_option {
             None => &self.read_opt,
@@ -463,9 +1060,9 @@ impl Rdict {
             }
             Some(cf) => cf.clone(),
         };
-        let key_bytes = encode_key(key, self.opt_py.raw_mode)?;
-        let column_result = db
-            .get_entity_cf_opt(&cf, key_bytes, read_opt)
+        let key_bytes = self.encode_key(key)?;
+        let column_result = py
+            .allow_threads(|| db.get_entity_cf_opt(&cf, &key_bytes[..], read_opt))
             .map_err(|e| PyException::new_err(e.to_string()))?;
         match column_result {
             None => {
@@ -480,7 +1077,7 @@ impl Rdict {
                 let result = PyList::empty_bound(py);
                 for column in columns.iter() {
                     let name = decode_value(py, column.name, &self.loads, self.opt_py.raw_mode)?;
-                    let value = decode_value(py, column.value, &self.loads, self.opt_py.raw_mode)?;
+                    let value = self.decode_value(py, column.value)?;
                     result.append(PyTuple::new_bound(py, [name, value]))?;
                 }
                 Ok(Some(result.to_object(py)))
@@ -488,40 +1085,495 @@ impl Rdict {
         }
     }
 
-    fn __setitem__(&self, key: &Bound<PyAny>, value: &Bound<PyAny>) -> PyResult<()> {
-        self.put(key, value, None)
-    }
-
-    /// Insert key value into database.
+    /// Get a value without copying it into a new `bytes` object.
+    ///
+    /// Only supported in raw mode. The returned `PinnedSlice` supports
+    /// the buffer protocol, so it can be wrapped by `memoryview`.
+    /// The value stays pinned in memory until the `PinnedSlice` is
+    /// closed or garbage collected.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         pinned = db.get_pinned(b"key")
+    ///         if pinned is not None:
+    ///             with memoryview(pinned) as view:
+    ///                 header = bytes(view[:16])
+    ///             pinned.close()
     ///
     /// Args:
-    ///     key: the key.
-    ///     value: the value.
-    ///     write_opt: override preset write options
-    ///         (or use Rdict.set_write_options to preset a write options used by default).
-    #[inline]
-    #[pyo3(signature = (key, value, write_opt = None))]
-    fn put(
+    ///     key: the key (bytes).
+    ///     read_opt: override preset read options
+    ///         (or use Rdict.set_read_options to preset a read options used by default).
+    ///
+    /// Returns:
+    ///    None if the key does not exist.
+    #[pyo3(signature = (key, read_opt = None))]
+    fn get_pinned(
         &self,
         key: &Bound<PyAny>,
-        value: &Bound<PyAny>,
-        write_opt: Option<&WriteOptionsPy>,
-    ) -> PyResult<()> {
-        let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
-        let value = encode_value(value, &self.dumps, self.opt_py.raw_mode)?;
-        let write_opt_option = write_opt.map(WriteOptions::from);
-        let write_opt = match &write_opt_option {
-            None => &self.write_opt,
-            Some(opt) => opt,
-        };
-        if let Some(cf) = &self.column_family {
-            db.put_cf_opt(cf, key, value, write_opt)
-        } else {
-            db.put_opt(key, value, write_opt)
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<Option<PinnedSlicePy>> {
+        if !self.opt_py.raw_mode {
+            return Err(PyException::new_err(
+                "get_pinned() is only supported in raw_mode",
+            ));
         }
-        .map_err(|e| PyException::new_err(e.to_string()))
-    }
+        let read_opt_option = match read_opt {
+            None => None,
+            Some(opt) => Some(self.to_read_options(opt, py)?),
+        };
+        let read_opt = match &read_opt_option {
+            None => &self.read_opt,
+            Some(opt) => opt,
+        };
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let key_bytes = self.encode_key(key)?;
+        PinnedSlicePy::new(&self.db, &cf, &key_bytes, read_opt, py)
+    }
+
+    /// Copy a value into a writable buffer, instead of a new `bytes` object.
+    ///
+    /// Only supported in raw mode. Nothing is written if the buffer is smaller
+    /// than the value, the returned size tells how large it must be.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         buffer = bytearray(4096)
+    ///         size = db.get_into(b"key", buffer)
+    ///         if size is not None and size > len(buffer):
+    ///             buffer = bytearray(size)
+    ///             size = db.get_into(b"key", buffer)
+    ///
+    /// Args:
+    ///     key: the key (bytes).
+    ///     buffer: a contiguous writable buffer, such as a `bytearray`,
+    ///         a writable `memoryview` or a numpy array.
+    ///     read_opt: override preset read options
+    ///         (or use Rdict.set_read_options to preset a read options used by default).
+    ///
+    /// Returns:
+    ///    None if the key does not exist, otherwise the size of the value in bytes:
+    ///    the number of bytes written, or the required size if it is larger
+    ///    than the buffer.
+    #[pyo3(signature = (key, buffer, read_opt = None))]
+    fn get_into(
+        &self,
+        key: &Bound<PyAny>,
+        buffer: &Bound<PyAny>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<Option<usize>> {
+        if !self.opt_py.raw_mode {
+            return Err(PyException::new_err(
+                "get_into() is only supported in raw_mode",
+            ));
+        }
+        let db = self.get_db()?;
+        let mut buffer = RawBuffer::get_writable(buffer)?;
+        let read_opt_option = match read_opt {
+            None => None,
+            Some(opt) => Some(self.to_read_options(opt, py)?),
+        };
+        let read_opt = match &read_opt_option {
+            None => &self.read_opt,
+            Some(opt) => opt,
+        };
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let key_bytes = self.encode_key(key)?;
+        let value = py
+            .allow_threads(|| db.get_pinned_cf_opt(&cf, &key_bytes[..], read_opt))
+            .map_err(|e| PyException::new_err(e.to_string()))?;
+        let value = match value {
+            None => return Ok(None),
+            Some(value) => value,
+        };
+        let decoded = match &self.value_transform {
+            None => None,
+            Some(transform) => Some(transform.decode(py, &value)?),
+        };
+        let value = decoded
+            .as_ref()
+            .map_or(&value[..], |decoded| decoded.as_bytes());
+        let target = buffer.as_mut_bytes();
+        if value.len() <= target.len() {
+            target[..value.len()].copy_from_slice(&value);
+        }
+        Ok(Some(value.len()))
+    }
+
+    /// Transform the encoded values of this column family, e.g. to compress
+    /// or encrypt them: `encode` is applied after `dumps` when writing, and
+    /// `decode` before `loads` when reading (including iterators, batch get
+    /// and snapshots). Both functions take and return `bytes`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         import zlib
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         blobs = db.create_column_family("blobs")
+    ///         blobs.set_value_transform(zlib.compress, zlib.decompress)
+    ///         blobs["doc"] = "a large document" * 1000
+    ///         assert blobs["doc"] == "a large document" * 1000
+    ///
+    /// Args:
+    ///     encode: the function transforming the encoded values, or None
+    ///         (with `decode=None`) to remove the transform.
+    ///     decode: the inverse function of `encode`.
+    ///
+    /// Notes:
+    ///     The transform is not saved in the db, and it is not inherited by
+    ///     other column family `Rdict` instances, it must be set on every
+    ///     instance reading or writing this column family. The values written
+    ///     by `WriteBatch`, `SstFileWriter` and `merge()` (which raises) are
+    ///     not transformed, and `get_pinned()` returns the stored values.
+    ///     `WriteBatchWithIndex` cannot read from or merge into an instance
+    ///     with a transform.
+    #[pyo3(signature = (encode, decode))]
+    fn set_value_transform(
+        &mut self,
+        encode: Option<PyObject>,
+        decode: Option<PyObject>,
+    ) -> PyResult<()> {
+        self.value_transform = match (encode, decode) {
+            (Some(encode), Some(decode)) => Some(ValueTransform { encode, decode }),
+            (None, None) => None,
+            _ => {
+                return Err(PyValueError::new_err(
+                    "encode and decode must both be set, or both be None",
+                ))
+            }
+        };
+        Ok(())
+    }
+
+    /// Set the type of the values returned by `get()` and `db[key]` in raw mode.
+    ///
+    /// Args:
+    ///     value_type: `"bytes"` (default), or `"memoryview"` to return
+    ///         read-only memoryviews of an owned copy of the values,
+    ///         which can be sliced without copying.
+    ///
+    /// Notes:
+    ///     Only supported in raw mode, the values of other dbs are decoded with
+    ///     `loads`. Iterators still return `bytes`. Column family `Rdict`
+    ///     instances obtained afterwards inherit this setting.
+    fn set_raw_value_type(&mut self, value_type: &str) -> PyResult<()> {
+        if !self.opt_py.raw_mode {
+            return Err(PyException::new_err(
+                "set_raw_value_type() is only supported in raw_mode",
+            ));
+        }
+        self.memoryview_values = match value_type {
+            "bytes" => false,
+            "memoryview" => true,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown value type `{value_type}`, must be `bytes` or `memoryview`"
+                )))
+            }
+        };
+        Ok(())
+    }
+
+    fn __setitem__(&self, key: &Bound<PyAny>, value: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.put(key, value, None, None, py)
+    }
+
+    /// Insert key value into database.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     value: the value.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///     ts: the user-defined timestamp (an int) of this version of the key.
+    ///         Requires `Options.set_u64_timestamp_comparator()`, which also
+    ///         makes it mandatory.
+    #[inline]
+    #[pyo3(signature = (key, value, write_opt = None, ts = None))]
+    fn put(
+        &self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        ts: Option<u64>,
+        py: Python,
+    ) -> PyResult<()> {
+        self.get_db()?;
+        let key = self.encode_key(key)?;
+        let value = self.encode_value(value)?;
+        let ts = ts.map(|ts| self.encode_timestamp(ts)).transpose()?;
+        self.put_encoded(&key, &value, write_opt, ts, py)
+    }
+
+    /// Merge a value into the existing value of a key, with the merge
+    /// operator set by `Options.set_merge_operator`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, MergeOperator
+    ///
+    ///         opt = Options()
+    ///         opt.set_merge_operator(MergeOperator.uint64_add())
+    ///         db = Rdict("./tmp", opt)
+    ///         db.merge("counter", 1)
+    ///         db.merge("counter", 41)
+    ///         assert db["counter"] == 42
+    ///
+    /// Raises:
+    ///     TypeError: if the value cannot be merged by the merge operator
+    ///         of the column family, e.g. a `str` with `MergeOperator.uint64_add()`.
+    ///         An exception is raised too when no merge operator is set.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     value: the value to merge.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    #[pyo3(signature = (key, value, write_opt = None))]
+    fn merge(
+        &self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        // the merge operators read the encoded values
+        if self.value_transform.is_some() {
+            return Err(PyException::new_err(
+                "merge() is not supported with a value transform",
+            ));
+        }
+        let key = self.encode_key(key)?;
+        let value = encode_value(value, &self.dumps, self.opt_py.raw_mode)?;
+        check_encoded_size("value", value.len(), self.opt_py.max_value_bytes)?;
+        check_merge_operand(
+            self.merge_operators
+                .read()
+                .unwrap()
+                .get(&self.column_family_name),
+            self.opt_py.raw_mode,
+            &value[..],
+        )?;
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        py.allow_threads(|| match &self.column_family {
+            Some(cf) => db.merge_cf_opt(cf, &key[..], &value[..], write_opt),
+            None => db.merge_opt(&key[..], &value[..], write_opt),
+        })
+        .map_err(|e| PyException::new_err(e.to_string()))
+    }
+
+    /// Insert key value into database only if the key does not exist yet.
+    ///
+    /// Notes:
+    ///     This is not a transaction. The GIL is released during the
+    ///     existence check and during the write, so writes from other threads,
+    ///     or from other `Rdict` instances opened on the same path, may
+    ///     interleave between them. The same holds for `pop()`, `popitem()`
+    ///     and `setdefault()`, which read and then write in separate calls.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         assert db.put_if_absent("config", {"retries": 3})
+    ///         assert not db.put_if_absent("config", {"retries": 5})
+    ///         assert db["config"] == {"retries": 3}
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     value: the value.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     True if the value was written, False if the key already existed.
+    #[pyo3(signature = (key, value, write_opt = None))]
+    fn put_if_absent(
+        &self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<bool> {
+        if self.__contains__(key, py)? {
+            return Ok(false);
+        }
+        self.put(key, value, write_opt, None, py)?;
+        Ok(true)
+    }
+
+    /// Remove a key and return its value, like `dict.pop()`.
+    ///
+    /// Notes:
+    ///     This is not a transaction, see `put_if_absent()`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         db["job"] = {"id": 1}
+    ///         assert db.pop("job") == {"id": 1}
+    ///         assert db.pop("job", None) is None
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     default: returned if the key does not exist. If not given,
+    ///         a missing key raises `KeyError`.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     The removed value, or the default if the key does not exist.
+    #[pyo3(signature = (key, *default, write_opt = None))]
+    fn pop(
+        &self,
+        key: &Bound<PyAny>,
+        default: &Bound<PyTuple>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        if default.len() > 1 {
+            return Err(PyTypeError::new_err(format!(
+                "pop expected at most 2 arguments, got {}",
+                default.len() + 1
+            )));
+        }
+        let key_bytes = self.encode_key(key)?;
+        match self.get_existing(&key_bytes[..], py)? {
+            Some(value) => {
+                self.delete_encoded(&key_bytes, write_opt, None, py)?;
+                Ok(value)
+            }
+            None => match default.get_item(0) {
+                Ok(default) => Ok(default.unbind()),
+                Err(_) => Err(PyKeyError::new_err(key.clone().unbind())),
+            },
+        }
+    }
+
+    /// Remove and return the `(key, value)` pair with the last key,
+    /// like `dict.popitem()`.
+    ///
+    /// Keys are sorted, so this pops the greatest key rather than the most
+    /// recently inserted one; pass `last=False` to pop the smallest key.
+    /// Snapshots taken before the call still see the removed pair.
+    ///
+    /// Notes:
+    ///     This is not a transaction, see `put_if_absent()`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         db.update({1: "a", 2: "b", 3: "c"})
+    ///         assert db.popitem() == (3, "c")
+    ///         assert db.popitem(last=False) == (1, "a")
+    ///
+    /// Args:
+    ///     last: pop the last key if True (default), else the first key.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     The removed `(key, value)` tuple. Raises `KeyError` if the Rdict is empty.
+    #[pyo3(signature = (last = true, write_opt = None))]
+    fn popitem(
+        &self,
+        last: bool,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let mut iter = self.iter(None, py)?;
+        if last {
+            iter.seek_to_last(py)?;
+        } else {
+            iter.seek_to_first(py)?;
+        }
+        // delete the bytes read, the decoded key may not encode back to them
+        let (key_bytes, value_bytes) = match iter.next_raw_pairs(1)?.pop() {
+            None => return Err(PyKeyError::new_err("popitem(): Rdict is empty")),
+            Some(pair) => pair,
+        };
+        drop(iter);
+        let (key, value) = self.decode_pair(py, &key_bytes, &value_bytes)?;
+        self.delete_encoded(&key_bytes, write_opt, None, py)?;
+        Ok(PyTuple::new_bound(py, [key, value]).into_any().unbind())
+    }
+
+    /// Return the value of a key, inserting `default` first if the key
+    /// does not exist, like `dict.setdefault()`.
+    ///
+    /// Notes:
+    ///     This is not a transaction, see `put_if_absent()`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         assert db.setdefault("retries", 3) == 3
+    ///         assert db.setdefault("retries", 5) == 3
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     default: the value to insert if the key does not exist.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///
+    /// Returns:
+    ///     The stored value, or `default` if it was inserted.
+    #[pyo3(signature = (key, default = None, write_opt = None))]
+    fn setdefault(
+        &self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let key_bytes = self.encode_key(key)?;
+        if let Some(value) = self.get_existing(&key_bytes[..], py)? {
+            return Ok(value);
+        }
+        let default = match default {
+            Some(default) => default.clone(),
+            None => py.None().into_bound(py),
+        };
+        let value = self.encode_value(&default)?;
+        self.put_encoded(&key_bytes, &value, write_opt, None, py)?;
+        Ok(default.unbind())
+    }
 
     /// Insert a wide-column.
     ///
@@ -541,9 +1593,10 @@ impl Rdict {
         names: Vec<Bound<PyAny>>,
         values: Vec<Bound<PyAny>>,
         write_opt: Option<&WriteOptionsPy>,
+        py: Python,
     ) -> PyResult<()> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
+        let key = self.encode_key(key)?;
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
@@ -567,36 +1620,35 @@ impl Rdict {
             names_vec.push(encode_value(name, &self.dumps, self.opt_py.raw_mode)?);
         }
         for value in values.iter() {
-            values_vec.push(encode_value(value, &self.dumps, self.opt_py.raw_mode)?);
+            values_vec.push(self.encode_value(value)?);
         }
-        db.put_entity_cf_opt(&cf, key, &names_vec, &values_vec, write_opt)
+        py.allow_threads(|| db.put_entity_cf_opt(&cf, &key[..], &names_vec, &values_vec, write_opt))
             .map_err(|e| PyException::new_err(e.to_string()))
     }
 
-    fn __contains__(&self, key: &Bound<PyAny>) -> PyResult<bool> {
+    /// Check if a key exists, without decoding its value.
+    ///
+    /// `key_may_exist` (the bloom filters and the memtables) answers most
+    /// misses without IO, and only a "maybe" is confirmed by reading the
+    /// pinned value, which is never deserialized.
+    pub(crate) fn __contains__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<bool> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
-        let may_exist = if let Some(cf) = &self.column_family {
-            db.key_may_exist_cf_opt(cf, &key[..], &self.read_opt)
-        } else {
-            db.key_may_exist_opt(&key[..], &self.read_opt)
-        };
-        if may_exist {
-            let value_result = if let Some(cf) = &self.column_family {
-                db.get_pinned_cf_opt(cf, key, &self.read_opt)
-            } else {
-                db.get_pinned_opt(key, &self.read_opt)
+        let key = self.encode_key(key)?;
+        py.allow_threads(|| {
+            let may_exist = match &self.column_family {
+                Some(cf) => db.key_may_exist_cf_opt(cf, &key[..], &self.read_opt),
+                None => db.key_may_exist_opt(&key[..], &self.read_opt),
             };
-            match value_result {
-                Ok(value) => match value {
-                    None => Ok(false),
-                    Some(_) => Ok(true),
-                },
-                Err(e) => Err(PyException::new_err(e.to_string())),
+            if !may_exist {
+                return Ok(false);
             }
-        } else {
-            Ok(false)
-        }
+            match &self.column_family {
+                Some(cf) => db.get_pinned_cf_opt(cf, &key[..], &self.read_opt),
+                None => db.get_pinned_opt(&key[..], &self.read_opt),
+            }
+            .map(|value| value.is_some())
+        })
+        .map_err(|e| PyException::new_err(e.to_string()))
     }
 
     /// Check if a key may exist without doing any IO.
@@ -639,10 +1691,10 @@ impl Rdict {
         py: Python,
     ) -> PyResult<PyObject> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
+        let key = self.encode_key(key)?;
         let read_opt_option = match read_opt {
             None => None,
-            Some(opt) => Some(opt.to_read_options(self.opt_py.raw_mode, py)?),
+            Some(opt) => Some(self.to_read_options(opt, py)?),
         };
         let read_opt = match &read_opt_option {
             None => &self.read_opt,
@@ -663,42 +1715,185 @@ impl Rdict {
             let (may, value) = db.key_may_exist_cf_opt_value(&cf, &key[..], read_opt);
             match value {
                 None => Ok((may, py.None()).to_object(py)),
-                Some(dat) => Ok((
-                    may,
-                    decode_value(py, dat.as_ref(), &self.loads, self.opt_py.raw_mode)?,
-                )
-                    .to_object(py)),
+                Some(dat) => Ok((may, self.decode_value(py, dat.as_ref())?).to_object(py)),
+            }
+        }
+    }
+
+    fn __delitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.delete(key, None, None, py)
+    }
+
+    /// Delete entry from the database.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    ///     ts: the user-defined timestamp (an int) of the deletion, older
+    ///         versions of the key are still visible to reads at an older
+    ///         timestamp. Requires `Options.set_u64_timestamp_comparator()`.
+    #[inline]
+    #[pyo3(signature = (key, write_opt = None, ts = None))]
+    fn delete(
+        &self,
+        key: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        ts: Option<u64>,
+        py: Python,
+    ) -> PyResult<()> {
+        self.get_db()?;
+        let key = self.encode_key(key)?;
+        let ts = ts.map(|ts| self.encode_timestamp(ts)).transpose()?;
+        self.delete_encoded(&key, write_opt, ts, py)
+    }
+
+    /// Delete many keys at once, in a single atomic write.
+    ///
+    /// The keys are encoded into one WriteBatch, and the GIL is released
+    /// while the batch is written. Deleting a key that does not exist is not an error.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         for i in range(1000):
+    ///             db[i] = i
+    ///
+    ///         db.delete_many(range(500))
+    ///         assert db.get([0, 499, 500]) == [None, None, 500]
+    ///
+    /// Args:
+    ///     keys: an iterable of keys.
+    ///     write_opt: override preset write options
+    ///         (or use Rdict.set_write_options to preset a write options used by default).
+    #[pyo3(signature = (keys, write_opt = None))]
+    fn delete_many(
+        &self,
+        keys: &Bound<PyAny>,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        let mut batch = WriteBatch::default();
+        for key in keys.iter()? {
+            let key = key?;
+            let key = self.encode_key(&key)?;
+            match &self.column_family {
+                Some(cf) => batch.delete_cf(cf, key),
+                None => batch.delete(key),
             }
         }
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        py.allow_threads(|| db.write_opt(batch, write_opt))
+            .map_err(|e| PyException::new_err(e.to_string()))
     }
 
-    fn __delitem__(&self, key: &Bound<PyAny>) -> PyResult<()> {
-        self.delete(key, None)
-    }
-
-    /// Delete entry from the database.
+    /// Put many keys and values at once, in a single atomic write,
+    /// like `dict.update`.
+    ///
+    /// The keys and values are encoded into one WriteBatch, and the GIL is
+    /// released while the batch is written. If encoding any pair fails,
+    /// nothing is written. Keyword arguments are put after `other`, so they
+    /// take precedence, as with `dict.update`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         db.update({"a": 1, "b": 2})
+    ///         db.update((i, i * i) for i in range(1000))
+    ///         db.update(c=3, d=4)
+    ///
+    ///         # stream pairs that do not fit in memory
+    ///         db.update(((i, i) for i in range(10**8)), atomic=False)
     ///
     /// Args:
-    ///     key: the key.
+    ///     other: a mapping (an object with `keys()`), or an iterable
+    ///         of `(key, value)` pairs.
     ///     write_opt: override preset write options
     ///         (or use Rdict.set_write_options to preset a write options used by default).
-    #[inline]
-    #[pyo3(signature = (key, write_opt = None))]
-    fn delete(&self, key: &Bound<PyAny>, write_opt: Option<&WriteOptionsPy>) -> PyResult<()> {
+    ///     atomic: if False, put the pairs one by one instead of
+    ///         building a WriteBatch. The update is then no longer atomic,
+    ///         and the pairs before a failing one are already written.
+    ///     kwargs: more keys and values, the keys being `str`.
+    ///         `write_opt` and `atomic` cannot be used as keys this way.
+    #[pyo3(signature = (other = None, write_opt = None, atomic = true, **kwargs))]
+    fn update(
+        &self,
+        other: Option<&Bound<PyAny>>,
+        write_opt: Option<&WriteOptionsPy>,
+        atomic: bool,
+        kwargs: Option<&Bound<PyDict>>,
+        py: Python,
+    ) -> PyResult<()> {
         let db = self.get_db()?;
-        let key = encode_key(key, self.opt_py.raw_mode)?;
-
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
             Some(opt) => opt,
         };
-        if let Some(cf) = &self.column_family {
-            db.delete_cf_opt(cf, key, write_opt)
-        } else {
-            db.delete_opt(key, write_opt)
+        let mut batch = WriteBatch::default();
+        let mut put = |key: &Bound<PyAny>, value: &Bound<PyAny>| -> PyResult<()> {
+            let key = self.encode_key(key)?;
+            let value = self.encode_value(value)?;
+            if atomic {
+                match &self.column_family {
+                    Some(cf) => batch.put_cf(cf, key, value),
+                    None => batch.put(key, value),
+                }
+                return Ok(());
+            }
+            py.allow_threads(|| match &self.column_family {
+                Some(cf) => db.put_cf_opt(cf, &key[..], &value[..], write_opt),
+                None => db.put_opt(&key[..], &value[..], write_opt),
+            })
+            .map_err(|e| PyException::new_err(e.to_string()))
+        };
+        match other {
+            None => {}
+            Some(other) => {
+                if let Ok(dict) = other.downcast::<PyDict>() {
+                    for (key, value) in dict.iter() {
+                        put(&key, &value)?;
+                    }
+                } else if other.hasattr("keys")? {
+                    for key in other.call_method0("keys")?.iter()? {
+                        let key = key?;
+                        put(&key, &other.get_item(&key)?)?;
+                    }
+                } else {
+                    for (i, pair) in other.iter()?.enumerate() {
+                        let pair: Vec<Bound<PyAny>> = pair?.iter()?.collect::<PyResult<_>>()?;
+                        if pair.len() != 2 {
+                            return Err(PyException::new_err(format!(
+                                "update sequence element #{i} has length {}; 2 is required",
+                                pair.len()
+                            )));
+                        }
+                        put(&pair[0], &pair[1])?;
+                    }
+                }
+            }
         }
-        .map_err(|e| PyException::new_err(e.to_string()))
+        if let Some(kwargs) = kwargs {
+            for (key, value) in kwargs.iter() {
+                put(&key, &value)?;
+            }
+        }
+        if !atomic {
+            return Ok(());
+        }
+        py.allow_threads(|| db.write_opt(batch, write_opt))
+            .map_err(|e| PyException::new_err(e.to_string()))
     }
 
     /// Reversible for iterating over keys and values.
@@ -744,7 +1939,7 @@ impl Rdict {
     ///
     /// Returns: Reversible
     #[pyo3(signature = (read_opt = None))]
-    fn iter(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<RdictIter> {
+    pub(crate) fn iter(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<RdictIter> {
         let read_opt: ReadOptionsPy = match read_opt {
             None => ReadOptionsPy::default(py)?,
             Some(opt) => opt.clone(),
@@ -755,12 +1950,394 @@ impl Rdict {
             &self.column_family,
             read_opt,
             &self.loads,
+            self.key_dumps.as_ref(),
+            self.key_loads.as_ref(),
             self.opt_py.raw_mode,
             py,
         )
+        .map(|iter| iter.with_value_transform(self.value_transform.clone()))
+    }
+
+    /// Estimated number of keys, based on the `rocksdb.estimate-num-keys` property.
+    ///
+    /// Notes:
+    ///     This is fast, but only an estimate: overwritten keys, deletions
+    ///     and merge operands that are not yet compacted make it inaccurate,
+    ///     in both directions, in particular after heavy deletions.
+    ///     Use `exact_len()` (a full keys-only scan without the GIL) when
+    ///     precision is required. `bool(db)` does not use this estimate,
+    ///     see `__bool__`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         for i in range(1000):
+    ///             db[i] = i
+    ///         print(len(db))  # about 1000
+    ///         assert db.exact_len() == 1000
+    pub(crate) fn __len__(&self) -> PyResult<usize> {
+        let db = self.get_db()?;
+        let estimate = match &self.column_family {
+            None => db.property_int_value("rocksdb.estimate-num-keys"),
+            Some(cf) => db.property_int_value_cf(cf, "rocksdb.estimate-num-keys"),
+        }
+        .map_err(|e| PyException::new_err(e.to_string()))?;
+        Ok(estimate.unwrap_or(0) as usize)
+    }
+
+    /// Whether the column family has at least one key.
+    ///
+    /// This seeks to the first key instead of using the estimate of
+    /// `__len__`, so it is exact and cheap, even after heavy deletions.
+    fn __bool__(&self, py: Python) -> PyResult<bool> {
+        let mut iter = self.iter(None, py)?;
+        iter.seek_to_first(py)?;
+        if !iter.valid() {
+            iter.status()?;
+        }
+        Ok(iter.valid())
+    }
+
+    /// Iterate over the keys, like `keys()`.
+    fn __iter__(&self, py: Python) -> PyResult<RdictKeys> {
+        RdictKeys::new(self.iter(None, py)?, false, None, py)
+    }
+
+    /// Compare the keys and values with a mapping, e.g. `db == {"a": 1}`.
+    ///
+    /// The scan stops as soon as a pair differs or more keys than
+    /// `len(other)` are found, so comparing a huge db with a small dict
+    /// is cheap. Two `Rdict` are only equal if they are the same object:
+    /// use `db.to_dict() == other.to_dict()` to compare their contents.
+    ///
+    /// Notes:
+    ///     Since `Rdict` defines `__eq__`, it is not hashable, like `dict`.
+    fn __eq__(slf: &Bound<Self>, other: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
+        if other.is_instance_of::<Rdict>() {
+            return Ok(other.is(slf).into_py(py));
+        }
+        let other = match other.downcast::<PyMapping>() {
+            Ok(other) => other,
+            Err(_) => return Ok(py.NotImplemented()),
+        };
+        let expected = other.len()?;
+        let mut iter = slf.borrow().iter(None, py)?;
+        iter.seek_to_first(py)?;
+        let mut count = 0;
+        while iter.valid() {
+            count += 1;
+            if count > expected {
+                return Ok(false.into_py(py));
+            }
+            let value = match other.get_item(iter.key(py)?) {
+                Ok(value) => value,
+                Err(e) if e.is_instance_of::<PyKeyError>(py) => return Ok(false.into_py(py)),
+                Err(e) => return Err(e),
+            };
+            if !value.eq(iter.value(py)?)? {
+                return Ok(false.into_py(py));
+            }
+            iter.next(py)?;
+        }
+        iter.status()?;
+        Ok((count == expected).into_py(py))
+    }
+
+    fn __ne__(slf: &Bound<Self>, other: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
+        let eq = Self::__eq__(slf, other, py)?;
+        if eq.is(&py.NotImplemented()) {
+            return Ok(eq);
+        }
+        Ok((!eq.extract::<bool>(py)?).into_py(py))
+    }
+
+    /// `db | other` returns a new `dict` with the keys and values of the db,
+    /// updated with the mapping `other`.
+    ///
+    /// Notes:
+    ///     The result is a plain `dict`, since the db cannot be copied
+    ///     implicitly to a new path: the whole db is loaded in memory,
+    ///     with the safety limit of `to_dict()`. Use `db |= other`
+    ///     to update the db itself.
+    fn __or__(&self, other: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
+        let other = match other.downcast::<PyMapping>() {
+            Ok(other) => other,
+            Err(_) => return Ok(py.NotImplemented()),
+        };
+        let result = self.to_dict(None, None, None, Some(TO_DICT_MAX_ITEMS), py)?;
+        result.bind(py).downcast::<PyDict>()?.update(other)?;
+        Ok(result)
+    }
+
+    /// `other | db` returns a new `dict` with the keys and values of the
+    /// mapping `other`, updated with the db, see `__or__`.
+    fn __ror__(&self, other: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
+        let other = match other.downcast::<PyMapping>() {
+            Ok(other) => other,
+            Err(_) => return Ok(py.NotImplemented()),
+        };
+        let result = PyDict::new_bound(py);
+        result.update(other)?;
+        let items = self.to_dict(None, None, None, Some(TO_DICT_MAX_ITEMS), py)?;
+        result.update(items.bind(py).downcast::<PyMapping>()?)?;
+        Ok(result.into_any().unbind())
+    }
+
+    /// `db |= other` updates the db with a single atomic `WriteBatch`,
+    /// like `update(other)`.
+    fn __ior__(&self, other: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        self.update(Some(other), None, true, None, py)
+    }
+
+    /// Exact number of keys, by scanning the whole column family.
+    ///
+    /// Same as `count()` without range: the keys are counted in Rust
+    /// without decoding them and without holding the GIL.
+    /// See `__len__` for a fast estimate.
+    ///
+    /// Args:
+    ///     read_opt: ReadOptions
+    #[pyo3(signature = (read_opt = None))]
+    fn exact_len(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<u64> {
+        self.count(None, None, read_opt, py)
+    }
+
+    /// Alias of `exact_len()`.
+    ///
+    /// Args:
+    ///     read_opt: ReadOptions
+    #[pyo3(signature = (read_opt = None))]
+    fn count_exact(&self, read_opt: Option<&ReadOptionsPy>, py: Python) -> PyResult<u64> {
+        self.exact_len(read_opt, py)
+    }
+
+    /// Count the number of keys in the range `[from_key, to_key)`.
+    ///
+    /// Notes:
+    ///     The iteration runs entirely in Rust without holding the GIL.
+    ///     The result is exact since it scans the whole range, unlike
+    ///     the `rocksdb.estimate-num-keys` property, which is fast but
+    ///     only an estimate. Iterate bounds in `read_opt` are respected,
+    ///     and `to_key` overrides the iterate upper bound.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         total = db.count()
+    ///         in_range = db.count(from_key=100, to_key=200)
+    ///
+    /// Args:
+    ///     from_key: count from this key (included), or from the first key if None.
+    ///     to_key: count until this key (excluded), or until the last key if None.
+    ///     read_opt: ReadOptions
+    #[pyo3(signature = (from_key = None, to_key = None, read_opt = None))]
+    fn count(
+        &self,
+        from_key: Option<&Bound<PyAny>>,
+        to_key: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<u64> {
+        let mut iter = self.range_iter(from_key, to_key, read_opt, py)?;
+        py.allow_threads(|| iter.count_remaining())
+    }
+
+    /// Iterate through the keys and values pairs in the range `[start, end)`.
+    ///
+    /// Notes:
+    ///     The GIL is released while the iterator advances, so that
+    ///     ranges can be scanned in parallel by several threads,
+    ///     see `split_ranges()`. `end` overrides the iterate upper
+    ///     bound of `read_opt`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         for k, v in db.range(100, 200):
+    ///             print(f"{k} -> {v}")
+    ///
+    /// Args:
+    ///     start: iterate from this key (included), or from the first key if None.
+    ///     end: iterate until this key (excluded), or until the last key if None.
+    ///     read_opt: ReadOptions
+    #[pyo3(signature = (start = None, end = None, read_opt = None))]
+    fn range(
+        &self,
+        start: Option<&Bound<PyAny>>,
+        end: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<RdictItems> {
+        let mut read_opt: ReadOptionsPy = match read_opt {
+            None => ReadOptionsPy::default(py)?,
+            Some(opt) => opt.clone(),
+        };
+        if let Some(end) = end {
+            read_opt.set_iterate_upper_bound(end, py)?;
+        }
+        RdictItems::new(self.iter(Some(&read_opt), py)?, false, start, py)
+    }
+
+    /// Export the keys and values in the range `[from_key, to_key)` to a dict.
+    ///
+    /// Meant for small tables, e.g. to ship a lookup table to worker
+    /// processes. The whole range is loaded in memory, so the export is
+    /// aborted if the range has more than `max_items` keys.
+    ///
+    /// Notes:
+    ///     The GIL is released while reading every batch of keys.
+    ///     Keys that are different in the db but equal in Python
+    ///     (e.g. `1` and `1.0`) collapse into a single dict entry.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         db.update({"a": 1, "b": 2, "c": 3})
+    ///         assert db.to_dict() == {"a": 1, "b": 2, "c": 3}
+    ///         assert db.to_dict(from_key="b") == {"b": 2, "c": 3}
+    ///
+    /// Args:
+    ///     from_key: export from this key (included), or from the first key if None.
+    ///     to_key: export until this key (excluded), or until the last key if None.
+    ///     read_opt: ReadOptions
+    ///     max_items: raise an exception if the range has more keys,
+    ///         None to export any number of keys.
+    #[pyo3(signature = (from_key = None, to_key = None, read_opt = None, max_items = Some(TO_DICT_MAX_ITEMS)))]
+    fn to_dict(
+        &self,
+        from_key: Option<&Bound<PyAny>>,
+        to_key: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        max_items: Option<usize>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let mut iter = self.range_iter(from_key, to_key, read_opt, py)?;
+        let result = PyDict::new_bound(py);
+        let mut count = 0;
+        loop {
+            let pairs = py.allow_threads(|| iter.next_raw_pairs(EXPORT_BATCH_SIZE))?;
+            if pairs.is_empty() {
+                return Ok(result.into_any().unbind());
+            }
+            count += pairs.len();
+            if let Some(max_items) = max_items.filter(|max_items| count > *max_items) {
+                return Err(PyException::new_err(format!(
+                    "to_dict(): more than {max_items} keys, pass `max_items=None` to export all"
+                )));
+            }
+            for (key, value) in pairs.iter() {
+                let (key, value) = self.decode_pair(py, key, value)?;
+                result.set_item(key, value)?;
+            }
+        }
+    }
+
+    /// Copy the keys and values in the range `[from_key, to_key)`
+    /// to another `Rdict`, e.g. to recompress a db with other options.
+    ///
+    /// The range is read with an iterator and written to `other` with
+    /// a `WriteBatch` every `batch_size` keys, the GIL is released while
+    /// reading and writing every batch. Both `self` and `other` may be
+    /// column families.
+    ///
+    /// Notes:
+    ///     If both dbs are in raw mode without value transform, the stored
+    ///     bytes are copied as is. Otherwise every pair is decoded like
+    ///     `items()` and encoded again with the dumps, key dumps and
+    ///     value transform of `other`.
+    ///     The copy is not atomic: `other` keeps the batches written
+    ///     before an error. The source is read from the consistent view
+    ///     of an iterator, taken when the copy starts.
+    ///     Not available with user-defined timestamps.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, DBCompressionType
+    ///
+    ///         opt = Options()
+    ///         opt.set_compression_type(DBCompressionType.zstd())
+    ///         with Rdict("./tmp") as db, Rdict("./tmp_zstd", opt) as other:
+    ///             copied = db.copy_to(other)
+    ///
+    /// Args:
+    ///     other: the destination `Rdict`.
+    ///     from_key: copy from this key (included), or from the first key if None.
+    ///     to_key: copy until this key (excluded), or until the last key if None.
+    ///     batch_size: number of keys written with every `WriteBatch`.
+    ///     write_opt: override the write options of `other`.
+    ///
+    /// Returns:
+    ///     the number of keys copied.
+    #[pyo3(signature = (other, from_key = None, to_key = None, batch_size = 10_000, write_opt = None))]
+    fn copy_to(
+        &self,
+        other: PyRef<Rdict>,
+        from_key: Option<&Bound<PyAny>>,
+        to_key: Option<&Bound<PyAny>>,
+        batch_size: usize,
+        write_opt: Option<&WriteOptionsPy>,
+        py: Python,
+    ) -> PyResult<u64> {
+        let other_db = other.get_db()?;
+        if batch_size == 0 {
+            return Err(PyValueError::new_err("batch_size must be positive"));
+        }
+        if self.opt_py.timestamp_size != 0 || other.opt_py.timestamp_size != 0 {
+            return Err(PyException::new_err(
+                "copy_to() is not supported with user-defined timestamps",
+            ));
+        }
+        let other_cf = match &other.column_family {
+            None => {
+                other
+                    .get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &other.write_opt,
+            Some(opt) => opt,
+        };
+        let copy_bytes = self.opt_py.raw_mode
+            && other.opt_py.raw_mode
+            && self.value_transform.is_none()
+            && other.value_transform.is_none();
+        let mut iter = self.range_iter(from_key, to_key, None, py)?;
+        let mut copied = 0;
+        loop {
+            let pairs = py.allow_threads(|| iter.next_raw_pairs(batch_size))?;
+            if pairs.is_empty() {
+                return Ok(copied);
+            }
+            let mut batch = WriteBatch::default();
+            for (key, value) in pairs.iter() {
+                if copy_bytes {
+                    check_encoded_size("key", key.len(), other.opt_py.max_key_bytes)?;
+                    check_encoded_size("value", value.len(), other.opt_py.max_value_bytes)?;
+                    batch.put_cf(&other_cf, key, value);
+                } else {
+                    let (key, value) = self.decode_pair(py, key, value)?;
+                    let key = other.encode_key(key.bind(py))?;
+                    let value = other.encode_value(value.bind(py))?;
+                    batch.put_cf(&other_cf, key, value);
+                }
+            }
+            py.allow_threads(|| other_db.write_opt(batch, write_opt))
+                .map_err(|e| PyException::new_err(e.to_string()))?;
+            copied += pairs.len() as u64;
+        }
     }
 
-    /// Iterate through all keys and values pairs.
+    /// A view of all keys and values pairs, see `RdictItemsView`.
+    ///
+    /// The view can be iterated several times, every iteration starts a
+    /// new iterator with the arguments given here.
     ///
     /// Examples:
     ///     ::
@@ -774,18 +2351,22 @@ impl Rdict {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     limit: stop after this number of items, no limit if None.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, limit = None))]
     fn items(
-        &self,
+        slf: &Bound<Self>,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
-        py: Python,
-    ) -> PyResult<RdictItems> {
-        RdictItems::new(self.iter(read_opt, py)?, backwards, from_key)
+        limit: Option<usize>,
+    ) -> RdictItemsView {
+        RdictItemsView(ViewArgs::new(slf, backwards, from_key, read_opt, limit))
     }
 
-    /// Iterate through all keys
+    /// A view of all keys, see `RdictKeysView`.
+    ///
+    /// The view supports `in`, `len()` and can be iterated several times,
+    /// every iteration starts a new iterator with the arguments given here.
     ///
     /// Examples:
     ///     ::
@@ -798,18 +2379,22 @@ impl Rdict {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     limit: stop after this number of items, no limit if None.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, limit = None))]
     fn keys(
-        &self,
+        slf: &Bound<Self>,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
-        py: Python,
-    ) -> PyResult<RdictKeys> {
-        RdictKeys::new(self.iter(read_opt, py)?, backwards, from_key)
+        limit: Option<usize>,
+    ) -> RdictKeysView {
+        RdictKeysView(ViewArgs::new(slf, backwards, from_key, read_opt, limit))
     }
 
-    /// Iterate through all values.
+    /// A view of all values, see `RdictValuesView`.
+    ///
+    /// The view can be iterated several times, every iteration starts a
+    /// new iterator with the arguments given here.
     ///
     /// Examples:
     ///     ::
@@ -822,15 +2407,82 @@ impl Rdict {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     limit: stop after this number of items, no limit if None.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, limit = None))]
     fn values(
-        &self,
+        slf: &Bound<Self>,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        limit: Option<usize>,
+    ) -> RdictValuesView {
+        RdictValuesView(ViewArgs::new(slf, backwards, from_key, read_opt, limit))
+    }
+
+    /// Iterate through all keys that start with `prefix`.
+    ///
+    /// Notes:
+    ///     The iteration stops at the first key that does not share the prefix,
+    ///     using an iterate upper bound computed from the prefix.
+    ///     If a prefix extractor is configured for this column family,
+    ///     `prefix_same_as_start` is set automatically. If the prefix is
+    ///     shorter than the prefix extractor length, a warning is raised and
+    ///     `total_order_seek` is used instead, since prefix bloom filters
+    ///     cannot be used for such a prefix.
+    ///     The computed upper bound replaces the upper bound of `read_opt`.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         user_keys = [k for k in db.keys_with_prefix("user:")]
+    ///
+    /// Args:
+    ///     prefix: in raw mode, a `bytes` prefix of the keys. When a key dumps
+    ///         function is set, a `bytes` prefix of the dumped keys. Otherwise a
+    ///         `str` or `bytes` prefix, matching the keys of the same type, or a
+    ///         tuple, matching the tuple keys starting with its elements.
+    ///     read_opt: ReadOptions
+    #[pyo3(signature = (prefix, read_opt = None))]
+    fn keys_with_prefix(
+        &self,
+        prefix: &Bound<PyAny>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<RdictKeys> {
+        RdictKeys::new(
+            self.prefix_iter(prefix, read_opt, py)?,
+            false,
+            Some(prefix),
+            py,
+        )
+    }
+
+    /// Iterate through all keys and values pairs whose keys start with `prefix`.
+    ///
+    /// See `keys_with_prefix()` for details.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         for k, v in db.items_with_prefix("user:"):
+    ///             print(f"{k} -> {v}")
+    ///
+    /// Args:
+    ///     prefix: the prefix of the keys, see `keys_with_prefix()`.
+    ///     read_opt: ReadOptions
+    #[pyo3(signature = (prefix, read_opt = None))]
+    fn items_with_prefix(
+        &self,
+        prefix: &Bound<PyAny>,
+        read_opt: Option<&ReadOptionsPy>,
         py: Python,
-    ) -> PyResult<RdictValues> {
-        RdictValues::new(self.iter(read_opt, py)?, backwards, from_key)
+    ) -> PyResult<RdictItems> {
+        RdictItems::new(
+            self.prefix_iter(prefix, read_opt, py)?,
+            false,
+            Some(prefix),
+            py,
+        )
     }
 
     /// Iterate through all values as widecolumns
@@ -854,7 +2506,7 @@ impl Rdict {
         read_opt: Option<&ReadOptionsPy>,
         py: Python,
     ) -> PyResult<RdictColumns> {
-        RdictColumns::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictColumns::new(self.iter(read_opt, py)?, backwards, from_key, py)
     }
 
     /// Iterate through all keys and entities pairs.
@@ -879,10 +2531,10 @@ impl Rdict {
         read_opt: Option<&ReadOptionsPy>,
         py: Python,
     ) -> PyResult<RdictEntities> {
-        RdictEntities::new(self.iter(read_opt, py)?, backwards, from_key)
+        RdictEntities::new(self.iter(read_opt, py)?, backwards, from_key, py)
     }
 
-    /// Manually flush the current column family.
+    /// Manually flush the current column family, or the given column families.
     ///
     /// Notes:
     ///     Manually call mem-table flush.
@@ -890,11 +2542,53 @@ impl Rdict {
     ///     stopping the python program, to ensure that all written
     ///     key-value pairs have been flushed to the disk.
     ///
+    ///     With `Options.set_atomic_flush(True)`, the column families passed
+    ///     in `column_families` are flushed atomically: after a crash, either
+    ///     all or none of their flushed data is recovered, which keeps them
+    ///     consistent when the WAL is disabled. Otherwise they are flushed
+    ///     one by one.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, Options, WriteOptions
+    ///
+    ///         opt = Options()
+    ///         opt.create_if_missing(True)
+    ///         opt.set_atomic_flush(True)
+    ///         db = Rdict("./tmp", opt)
+    ///         index = db.create_column_family("index", opt)
+    ///
+    ///         write_opt = WriteOptions()
+    ///         write_opt.disable_wal(True)
+    ///         db.set_write_options(write_opt)
+    ///         index.set_write_options(write_opt)
+    ///         db["user:1"] = "alice"
+    ///         index["alice"] = "user:1"
+    ///
+    ///         db.flush(column_families=[
+    ///             db.get_column_family_handle("default"),
+    ///             db.get_column_family_handle("index"),
+    ///         ])
+    ///
     /// Args:
     ///     wait (bool): whether to wait for the flush to finish.
-    #[pyo3(signature = (wait = true))]
-    fn flush(&self, wait: bool, py: Python) -> PyResult<()> {
+    ///     column_families (list[ColumnFamily]): column families to flush
+    ///         together (see `get_column_family_handle()`), instead of the
+    ///         current column family.
+    #[pyo3(signature = (wait = true, column_families = None))]
+    fn flush(
+        &self,
+        wait: bool,
+        column_families: Option<Vec<ColumnFamilyPy>>,
+        py: Python,
+    ) -> PyResult<()> {
         let db = self.get_db()?;
+        if let Some(column_families) = column_families {
+            let cfs: Vec<Arc<UnboundColumnFamily>> =
+                column_families.into_iter().map(|cf| cf.cf).collect();
+            return py.allow_threads(|| flush_cfs(db, &cfs, wait));
+        }
 
         py.allow_threads(|| {
             let mut f_opt = FlushOptions::new();
@@ -910,6 +2604,13 @@ impl Rdict {
 
     /// Flushes the WAL buffer. If `sync` is set to `true`, also syncs
     /// the data to disk.
+    ///
+    /// Notes:
+    ///     With `Options.set_manual_wal_flush(True)`, writes stay in the
+    ///     WAL buffer until this is called (or the buffer is full): they
+    ///     are lost if the process crashes before. After `flush_wal(False)`
+    ///     they survive a crash of the process but not of the machine,
+    ///     after `flush_wal(True)` they survive both.
     #[pyo3(signature = (sync = true))]
     fn flush_wal(&self, sync: bool, py: Python) -> PyResult<()> {
         let db = self.get_db()?;
@@ -917,6 +2618,57 @@ impl Rdict {
             .map_err(|e| PyException::new_err(e.into_string()))
     }
 
+    /// List the write-ahead log files, oldest first.
+    ///
+    /// Notes:
+    ///     `GetSortedWalFiles()` is not exposed by the rocksdb C API, the
+    ///     `.log` files of the WAL directory (see `Options.set_wal_dir`) and
+    ///     of its `archive` subdirectory are listed instead. The start
+    ///     sequence number is read from the first record of each file, it
+    ///     is None for empty files (e.g. the current WAL before `flush_wal()`).
+    ///
+    /// Returns:
+    ///     a list of dicts with keys `path_name`, `log_number`, `type`
+    ///     (`"alive"` or `"archived"`), `start_sequence` and `size_in_bytes`.
+    fn get_sorted_wal_files(&self, py: Python) -> PyResult<PyObject> {
+        let path = self.path()?;
+        let wal_dir = match self.current_options()?.remove("wal_dir") {
+            Some(wal_dir) if !wal_dir.is_empty() => PathBuf::from(wal_dir),
+            _ => PathBuf::from(&path),
+        };
+        let mut wal_files = Vec::new();
+        for (dir, wal_type) in [(wal_dir.join("archive"), "archived"), (wal_dir, "alive")] {
+            if !dir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&dir)? {
+                let file_path = entry?.path();
+                if file_path.extension().map_or(true, |ext| ext != "log") {
+                    continue;
+                }
+                let log_number = file_path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse::<u64>().ok());
+                if let Some(log_number) = log_number {
+                    wal_files.push((log_number, wal_type, file_path));
+                }
+            }
+        }
+        wal_files.sort_by_key(|(log_number, _, _)| *log_number);
+        let result = PyList::empty_bound(py);
+        for (log_number, wal_type, file_path) in wal_files {
+            let wal_file = PyDict::new_bound(py);
+            wal_file.set_item("path_name", file_path.to_string_lossy())?;
+            wal_file.set_item("log_number", log_number)?;
+            wal_file.set_item("type", wal_type)?;
+            wal_file.set_item("start_sequence", wal_start_sequence(&file_path)?)?;
+            wal_file.set_item("size_in_bytes", fs::metadata(&file_path)?.len())?;
+            result.append(wal_file)?;
+        }
+        Ok(result.to_object(py))
+    }
+
     /// Creates column family with given name and options.
     ///
     /// Args:
@@ -934,6 +2686,20 @@ impl Rdict {
                 self.opt_py.raw_mode
             )));
         }
+        if options.ordered_numeric_keys != self.opt_py.ordered_numeric_keys {
+            return Err(PyException::new_err(format!(
+                "Options should have ordered_numeric_keys={}",
+                self.opt_py.ordered_numeric_keys
+            )));
+        }
+        if options.timestamp_size != self.opt_py.timestamp_size {
+            return Err(PyException::new_err(if self.opt_py.timestamp_size == 0 {
+                "Options should not have user-defined timestamps"
+            } else {
+                "Options should have user-defined timestamps, \
+                 call Options.set_u64_timestamp_comparator()"
+            }));
+        }
         // write slice_transform info into config file
         if let Some(slice_transform) = options.prefix_extractor {
             self.slice_transforms
@@ -941,6 +2707,12 @@ impl Rdict {
                 .unwrap()
                 .insert(name.to_string(), slice_transform);
         }
+        if let Some(merge_operator) = options.merge_operator {
+            self.merge_operators
+                .write()
+                .unwrap()
+                .insert(name.to_string(), merge_operator);
+        }
         self.dump_config()?;
         db.create_cf(name, &options.inner_opt)
             .map_err(|e| PyException::new_err(e.to_string()))?;
@@ -969,18 +2741,27 @@ impl Rdict {
                 "column name `{name}` does not exist, use `create_cf` to creat it",
             ))),
             Some(cf) => Ok(Self {
-                db: self.db.clone(),
+                db: self.db.child(),
                 write_opt: (&self.write_opt_py).into(),
                 flush_opt: self.flush_opt,
-                read_opt: self.read_opt_py.to_read_options(self.opt_py.raw_mode, py)?,
+                read_opt: self.to_read_options(&self.read_opt_py, py)?,
                 loads: self.loads.clone(),
                 dumps: self.dumps.clone(),
+                key_loads: self.key_loads.clone(),
+                key_dumps: self.key_dumps.clone(),
                 column_family: Some(cf),
+                column_family_name: name.to_string(),
                 write_opt_py: self.write_opt_py.clone(),
                 read_opt_py: self.read_opt_py.clone(),
                 opt_py: self.opt_py.clone(),
                 access_type: self.access_type.clone(),
                 slice_transforms: self.slice_transforms.clone(),
+                merge_operators: self.merge_operators.clone(),
+                serializer: self.serializer.clone(),
+                memoryview_values: self.memoryview_values,
+                // the transform applies to the values of a column family
+                value_transform: None,
+                background_error: self.background_error.clone(),
             }),
         }
     }
@@ -1066,26 +2847,25 @@ impl Rdict {
     ) -> PyResult<()> {
         let db = self.get_db()?;
         let opts = &opts.borrow(py).0;
-        if let Some(cf) = &self.column_family {
-            db.ingest_external_file_cf_opts(cf, opts, paths)
-        } else {
-            db.ingest_external_file_opts(opts, paths)
-        }
+        py.allow_threads(|| match &self.column_family {
+            Some(cf) => db.ingest_external_file_cf_opts(cf, opts, paths),
+            None => db.ingest_external_file_opts(opts, paths),
+        })
         .map_err(|e| PyException::new_err(e.to_string()))
     }
 
     /// Tries to catch up with the primary by reading as much as possible from the
     /// log files.
-    pub fn try_catch_up_with_primary(&self) -> PyResult<()> {
+    pub fn try_catch_up_with_primary(&self, py: Python) -> PyResult<()> {
         let db = self.get_db()?;
-        db.try_catch_up_with_primary()
+        py.allow_threads(|| db.try_catch_up_with_primary())
             .map_err(|e| PyException::new_err(e.to_string()))
     }
 
     /// Request stopping background work, if wait is true wait until it's done.
-    pub fn cancel_all_background(&self, wait: bool) -> PyResult<()> {
+    pub fn cancel_all_background(&self, wait: bool, py: Python) -> PyResult<()> {
         let db = self.get_db()?;
-        db.cancel_all_background_work(wait);
+        py.allow_threads(|| db.cancel_all_background_work(wait));
         Ok(())
     }
 
@@ -1095,32 +2875,33 @@ impl Rdict {
     ///     This WriteBatch does not write to the current column family.
     ///
     /// Args:
-    ///     write_batch: WriteBatch instance. This instance will be consumed.
+    ///     write_batch: WriteBatch instance (consumed), or WriteBatchWithIndex
+    ///         instance (not consumed, call `clear()` to reuse it).
     ///     write_opt: use default value if not provided.
     #[pyo3(signature = (write_batch, write_opt = None))]
     pub fn write(
         &self,
-        write_batch: &mut WriteBatchPy,
+        write_batch: &Bound<PyAny>,
         write_opt: Option<&WriteOptionsPy>,
+        py: Python,
     ) -> PyResult<()> {
         let db = self.get_db()?;
-        if self.opt_py.raw_mode != write_batch.raw_mode {
-            return if self.opt_py.raw_mode {
-                Err(PyException::new_err(
-                    "must set raw_mode=True for WriteBatch",
-                ))
-            } else {
-                Err(PyException::new_err(
-                    "must set raw_mode=False for WriteBatch",
-                ))
-            };
+        if let Ok(write_batch) = write_batch.downcast::<WriteBatchWithIndexPy>() {
+            let write_batch = write_batch.borrow();
+            self.check_write_batch_raw_mode(write_batch.raw_mode)?;
+            let write_opt = WriteOpt::from(write_opt.unwrap_or(&self.write_opt_py));
+            let write_batch: &WriteBatchWithIndexPy = &write_batch;
+            return py.allow_threads(|| write_batch.write_to(db, &write_opt));
         }
+        let mut write_batch = write_batch.downcast::<WriteBatchPy>()?.borrow_mut();
+        self.check_write_batch_raw_mode(write_batch.raw_mode)?;
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
             Some(opt) => opt,
         };
-        db.write_opt(write_batch.consume()?, write_opt)
+        let write_batch = write_batch.consume()?;
+        py.allow_threads(|| db.write_opt(write_batch, write_opt))
             .map_err(|e| PyException::new_err(e.to_string()))
     }
 
@@ -1136,10 +2917,61 @@ impl Rdict {
         begin: &Bound<PyAny>,
         end: &Bound<PyAny>,
         write_opt: Option<&WriteOptionsPy>,
+        py: Python,
     ) -> PyResult<()> {
         let db = self.get_db()?;
-        let from = encode_key(begin, self.opt_py.raw_mode)?;
-        let to = encode_key(end, self.opt_py.raw_mode)?;
+        let from = self.encode_key(begin)?;
+        let to = self.encode_key(end)?;
+        let cf = match &self.column_family {
+            None => {
+                self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
+                    .cf
+            }
+            Some(cf) => cf.clone(),
+        };
+        let write_opt_option = write_opt.map(WriteOptions::from);
+        let write_opt = match &write_opt_option {
+            None => &self.write_opt,
+            Some(opt) => opt,
+        };
+        py.allow_threads(|| db.delete_range_cf_opt(&cf, &from[..], &to[..], write_opt))
+            .map_err(|e| PyException::new_err(e.to_string()))
+    }
+
+    /// Removes all the keys of the current column family.
+    ///
+    /// The keys between the first and the last key are removed with a single
+    /// range deletion (see `delete_range()`), so the column family is empty
+    /// right away without iterating over its keys, and snapshots taken
+    /// before still see the old data.
+    ///
+    /// Notes:
+    ///     The space is reclaimed by later compactions, call
+    ///     `compact_range(None, None)` to reclaim it right away.
+    ///     `delete_files_in_range()` is not used, since it would also
+    ///     remove the data seen by existing snapshots.
+    ///     Not available with user-defined timestamps.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         db.update({i: i for i in range(1000)})
+    ///         db.clear()
+    ///         assert not db
+    ///
+    /// Args:
+    ///     write_opt: WriteOptions
+    #[pyo3(signature = (write_opt = None))]
+    fn clear(&self, write_opt: Option<&WriteOptionsPy>, py: Python) -> PyResult<()> {
+        let db = self.get_db()?;
+        if self.opt_py.timestamp_size != 0 {
+            return Err(PyException::new_err(
+                "clear() is not supported with user-defined timestamps",
+            ));
+        }
         let cf = match &self.column_family {
             None => {
                 self.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?
@@ -1147,28 +2979,89 @@ impl Rdict {
             }
             Some(cf) => cf.clone(),
         };
+        let bounds = py
+            .allow_threads(|| {
+                // with a prefix extractor, the default prefix mode iterators
+                // do not support seeking to the first or last key
+                let mut read_opt = ReadOptions::default();
+                read_opt.set_total_order_seek(true);
+                let mut iter = db.raw_iterator_cf_opt(&cf, read_opt);
+                iter.seek_to_first();
+                let first = iter.key().map(<[u8]>::to_vec);
+                iter.seek_to_last();
+                let last = iter.key().map(<[u8]>::to_vec);
+                iter.status().map(|_| first.zip(last))
+            })
+            .map_err(|e| PyException::new_err(e.to_string()))?;
+        let (first, last) = match bounds {
+            None => return Ok(()),
+            Some(bounds) => bounds,
+        };
+        // the end of a range deletion is excluded, so delete the last key as well
+        let mut batch = WriteBatch::default();
+        batch.delete_range_cf(&cf, first, &last);
+        batch.delete_cf(&cf, last);
         let write_opt_option = write_opt.map(WriteOptions::from);
         let write_opt = match &write_opt_option {
             None => &self.write_opt,
             Some(opt) => opt,
         };
-        db.delete_range_cf_opt(&cf, from, to, write_opt)
+        py.allow_threads(|| db.write_opt(batch, write_opt))
             .map_err(|e| PyException::new_err(e.to_string()))
     }
 
+    /// Deletes the table files whose keys are all in the range `[begin, end]`
+    /// of the current column family, without writing tombstones.
+    ///
+    /// Much cheaper than `delete_range()` for large ranges, but keys in the
+    /// memtables or in files overlapping the range boundaries are not
+    /// deleted, follow up with `delete_range()` to delete them.
+    /// See also `Options.set_sst_partitioner_fixed_prefix()`.
+    ///
+    /// Args:
+    ///     begin: included
+    ///     end: included
+    pub fn delete_files_in_range(
+        &self,
+        begin: &Bound<PyAny>,
+        end: &Bound<PyAny>,
+        py: Python,
+    ) -> PyResult<()> {
+        let db = self.get_db()?;
+        let from = self.encode_key(begin)?;
+        let to = self.encode_key(end)?;
+        py.allow_threads(|| match &self.column_family {
+            None => db.delete_file_in_range(from, to),
+            Some(cf) => db.delete_file_in_range_cf(cf, from, to),
+        })
+        .map_err(|e| PyException::new_err(e.to_string()))
+    }
+
     /// Flush memory to disk, and drop the current column family.
     ///
     /// Notes:
     ///     Calling `db.close()` is nearly equivalent to first calling
     ///     `db.flush()` and then `del db`. However, `db.close()` does
     ///     not guarantee the underlying RocksDB to be actually closed.
-    ///     Other Column Family `Rdict` instances, `ColumnFamily`
-    ///     (cf handle) instances, iterator instances such as`RdictIter`,
-    ///     `RdictItems`, `RdictKeys`, `RdictValues` can all keep RocksDB
-    ///     alive. `del` or `close` all associated instances mentioned
-    ///     above to actually shut down RocksDB.
+    ///     Column Family `Rdict` instances, `ColumnFamily` (cf handle)
+    ///     instances, snapshots and iterator instances such as `RdictIter`,
+    ///     `RdictItems`, `RdictKeys`, `RdictValues` hold their own
+    ///     reference to RocksDB and keep it alive. `del` all associated
+    ///     instances mentioned above to actually shut down RocksDB.
+    ///     The views returned by `keys()`, `values()` and `items()` only
+    ///     hold the `Rdict`, so they do not keep RocksDB alive after `close()`.
+    ///
+    ///     After `close()`, the methods of this instance, and of the
+    ///     Column Family `Rdict` instances, snapshots and iterators created
+    ///     from it raise `DbClosedError`. Calling `close()` again does nothing.
     ///
     fn close(&mut self, py: Python) -> PyResult<()> {
+        if self.db.get().is_none() {
+            // the instance this one was created from may be closed, release the DB
+            drop(self.column_family.take());
+            self.db.release();
+            return Ok(());
+        }
         // do not flush if readonly
         if let AccessTypeInner::ReadOnly { .. } | AccessTypeInner::Secondary { .. } =
             &self.access_type.0
@@ -1203,6 +3096,126 @@ impl Rdict {
         }
     }
 
+    fn __enter__(slf: Bound<Self>) -> Bound<Self> {
+        slf
+    }
+
+    /// Close the Rdict when leaving the `with` block, even on exceptions.
+    ///
+    /// Notes:
+    ///     Flushes according to the preset flush options, as `close()` does.
+    ///     It is fine to call `close()` inside the `with` block. The DB lock
+    ///     is only released once all associated instances are closed or
+    ///     deleted (see `close()`).
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         with Rdict("./tmp") as db:
+    ///             db["a"] = 1
+    ///
+    ///         # the lock is released, the db can be opened again
+    ///         with Rdict("./tmp") as db:
+    ///             assert db["a"] == 1
+    fn __exit__(
+        &mut self,
+        _exc_type: &Bound<PyAny>,
+        _exc_val: &Bound<PyAny>,
+        _exc_tb: &Bound<PyAny>,
+        py: Python,
+    ) -> PyResult<bool> {
+        self.close(py)?;
+        // do not suppress exceptions
+        Ok(false)
+    }
+
+    /// Whether `close()` was called on this instance,
+    /// or on the instance it was created from.
+    #[getter]
+    fn is_closed(&self) -> bool {
+        self.db.get().is_none()
+    }
+
+    /// The number of background errors (failed flushes, compactions or
+    /// manifest writes) of the db since it was opened, to monitor its health.
+    ///
+    /// Notes:
+    ///     After a severe background error, writes fail until the error is
+    ///     cleared. rocksdb recovers on its own from retryable errors (such as
+    ///     a full disk once space is freed), otherwise call `resume()` after
+    ///     fixing the underlying issue, or close and reopen the db.
+    ///     See `background_error` for the status of the last error, or use
+    ///     `Options.add_event_listener()` with an `on_background_error`
+    ///     method to be notified of the errors as they happen.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         db["a"] = 1
+    ///         db.flush()
+    ///         assert db.background_errors == 0
+    #[getter]
+    fn background_errors(&self) -> PyResult<u64> {
+        let db = self.get_db()?;
+        let errors = db
+            .property_int_value("rocksdb.background-errors")
+            .map_err(|e| PyException::new_err(e.to_string()))?;
+        Ok(errors.unwrap_or(0))
+    }
+
+    /// The status (error message) of the last background error of the db
+    /// since it was opened or resumed, or None.
+    ///
+    /// See `background_errors` for the number of errors.
+    #[getter]
+    fn background_error(&self) -> PyResult<Option<String>> {
+        self.get_db()?;
+        Ok(self.background_error.lock().unwrap().clone())
+    }
+
+    /// Resume the writes after a background error, once its cause is fixed.
+    ///
+    /// Waits for the recovery flush (and the pending compactions) to finish,
+    /// and clears `background_error`. Does nothing if the db has no error.
+    ///
+    /// Raises:
+    ///     an exception if the error cannot be recovered from, such as a
+    ///     corruption, or if the recovery fails again. Close and reopen
+    ///     the db in these cases.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         if db.background_error is not None:
+    ///             # e.g. after freeing disk space
+    ///             db.resume()
+    fn resume(&self, py: Python) -> PyResult<()> {
+        let db = self.get_db()?;
+        py.allow_threads(|| {
+            unsafe {
+                ffi_try!(librocksdb_sys::rocksdb_resume(db.inner()));
+            }
+            Ok::<_, PyErr>(())
+        })?;
+        *self.background_error.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Get the statistics of this db.
+    ///
+    /// Returns:
+    ///     a `Statistics` object, or None if `Options.enable_statistics()`
+    ///     was not called before opening the db.
+    fn get_statistics(&self) -> PyResult<Option<StatisticsPy>> {
+        self.get_db()?;
+        Ok(StatisticsPy::new(&self.opt_py.inner_opt))
+    }
+
     /// Return current database path.
     fn path(&self) -> PyResult<String> {
         Ok(self
@@ -1228,12 +3241,12 @@ impl Rdict {
         let from = if begin.is_none() {
             None
         } else {
-            Some(encode_key(begin, self.opt_py.raw_mode)?)
+            Some(self.encode_key(begin)?)
         };
         let to = if end.is_none() {
             None
         } else {
-            Some(encode_key(end, self.opt_py.raw_mode)?)
+            Some(self.encode_key(end)?)
         };
         py.allow_threads(|| {
             if let Some(cf) = &self.column_family {
@@ -1283,11 +3296,109 @@ impl Rdict {
         .map_err(|e| PyException::new_err(e.to_string()))
     }
 
+    /// Returns the options RocksDB is actually using for the current column family.
+    ///
+    /// RocksDB sanitizes some options at open time (for example clamping
+    /// `max_open_files`), so the `Options` passed to `Rdict()` do not necessarily
+    /// reflect what is in effect. This method reads the latest `OPTIONS-*` file
+    /// persisted by RocksDB, which is rewritten after open and after `set_options()`.
+    ///
+    /// Returns:
+    ///     a dict mapping option names (str) to their values (str),
+    ///     including DB-wide options, options of the current column family,
+    ///     and its table factory options (e.g. `block_size`).
+    fn current_options(&self) -> PyResult<HashMap<String, String>> {
+        let path = self.path()?;
+        let cf_name = self.column_family_name.as_str();
+        let options_file = latest_options_file(&path)?;
+        let content = fs::read_to_string(options_file)?;
+        let cf_suffix = format!("\"{cf_name}\"");
+        let mut result = HashMap::new();
+        let mut in_section = false;
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                let section = &line[1..line.len() - 1];
+                in_section = section == "DBOptions"
+                    || ((section.starts_with("CFOptions ")
+                        || section.starts_with("TableOptions/"))
+                        && section.ends_with(&cf_suffix));
+                continue;
+            }
+            if in_section {
+                if let Some((name, value)) = line.split_once('=') {
+                    result.insert(name.trim().to_string(), value.trim().to_string());
+                }
+            }
+        }
+        Ok(result)
+    }
+
     /// The sequence number of the most recent transaction.
+    ///
+    /// Every update (put, delete, merge...) increments the sequence number by
+    /// one, a new db starts at `0`. The sequence number is shared by all the
+    /// column families of the db.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         # start tailing the WAL after the current state
+    ///         start = db.latest_sequence_number() + 1
+    ///         db["a"] = 1
+    ///         assert db.latest_sequence_number() == start
+    ///
+    ///         for seq_number, batch in db.get_updates_since(start):
+    ///             print(seq_number, batch.iterate(decode=True))
     fn latest_sequence_number(&self) -> PyResult<u64> {
         Ok(self.get_db()?.latest_sequence_number())
     }
 
+    /// Iterate over the write-ahead log from a sequence number, for change data capture.
+    ///
+    /// Notes:
+    ///     The updates are only available as long as the WAL files are kept,
+    ///     see `Options.set_wal_ttl_seconds` and `Options.set_wal_size_limit_mb`.
+    ///     The first batch may start before `seq_number` if `seq_number` is in
+    ///     the middle of a batch. The batches contain the updates of all the
+    ///     column families, with column family ids.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict
+    ///
+    ///         db = Rdict("./tmp")
+    ///         start = db.latest_sequence_number() + 1
+    ///         db["a"] = 1
+    ///         del db["a"]
+    ///
+    ///         for seq_number, batch in db.get_updates_since(start):
+    ///             print(seq_number, batch.iterate(decode=True))
+    ///
+    /// Args:
+    ///     seq_number: the sequence number to start from.
+    ///
+    /// Returns:
+    ///     an iterator of `(sequence_number, WriteBatch)` tuples.
+    fn get_updates_since(&self, seq_number: u64) -> PyResult<WalIteratorPy> {
+        WalIteratorPy::new(
+            &self.db,
+            seq_number,
+            &self.dumps,
+            &self.loads,
+            self.key_dumps.as_ref(),
+            self.key_loads.as_ref(),
+            self.opt_py.raw_mode,
+        )
+    }
+
     /// Returns a list of all table files with their level, start key and end key
     fn live_files(&self, py: Python) -> PyResult<PyObject> {
         let db = self.get_db()?;
@@ -1295,10 +3406,16 @@ impl Rdict {
             Ok(lfs) => {
                 let result = PyList::empty_bound(py);
                 for lf in lfs {
+                    // other column families may not use the key loads function
+                    let key_loads = self
+                        .key_loads
+                        .as_ref()
+                        .filter(|_| lf.column_family_name == self.column_family_name);
                     result.append(display_live_file_dict(
                         lf,
                         py,
                         &self.loads,
+                        key_loads,
                         self.opt_py.raw_mode,
                     )?)?
                 }
@@ -1308,14 +3425,154 @@ impl Rdict {
         }
     }
 
+    /// Verify the block checksums of all the table files of the db, and
+    /// raise an exception describing the first corruption found.
+    ///
+    /// Notes:
+    ///     `VerifyChecksum()` is not exposed by the rocksdb C API, so this
+    ///     scans all the column families with checksum verification
+    ///     instead, which verifies every data block of the live table
+    ///     files (the other blocks are verified when the files are
+    ///     opened). The checksums of whole files (`VerifyFileChecksums()`)
+    ///     cannot be verified with the C API. The GIL is released during
+    ///     the scan.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         try:
+    ///             db.verify_checksum()
+    ///         except Exception as e:
+    ///             print(f"corrupted db: {e}")
+    fn verify_checksum(&self, py: Python) -> PyResult<()> {
+        let db = self.get_db()?;
+        let cf_names = DB::list_cf(&self.opt_py.inner_opt, db.path())
+            .map_err(|e| PyException::new_err(e.to_string()))?;
+        py.allow_threads(|| {
+            for name in cf_names {
+                let mut read_opt = ReadOptions::default();
+                read_opt.set_verify_checksums(true);
+                read_opt.fill_cache(false);
+                read_opt.set_total_order_seek(true);
+                read_opt.set_ignore_range_deletions(true);
+                let mut iter = match unsafe { db.cf_handle_unbounded(&name) } {
+                    None => continue,
+                    Some(cf) => db.raw_iterator_cf_opt(&cf, read_opt),
+                };
+                iter.seek_to_first();
+                while iter.valid() {
+                    iter.next();
+                }
+                iter.status()
+                    .map_err(|e| format!("column family `{name}`: {e}"))?;
+            }
+            Ok(())
+        })
+        .map_err(PyException::new_err)
+    }
+
+    /// Split the keys of this column family into `n` ranges of roughly
+    /// equal size on disk, to scan them in parallel with `range()`.
+    ///
+    /// Returns a list of `(start, end)` tuples covering all keys, where
+    /// `start` is None for the first range and `end` is None for the
+    /// last range.
+    ///
+    /// Notes:
+    ///     The boundaries are the start keys of the table files (see
+    ///     `live_files()`), so fewer than `n` ranges are returned when
+    ///     there are not enough table files, and data that is only in the
+    ///     memtables is not taken into account. Call `flush()` first
+    ///     for a better split.
+    ///
+    /// Examples:
+    ///     ::
+    ///
+    ///         from concurrent.futures import ThreadPoolExecutor
+    ///
+    ///         def scan(bounds):
+    ///             return sum(1 for _ in db.range(*bounds))
+    ///
+    ///         with ThreadPoolExecutor(4) as pool:
+    ///             total = sum(pool.map(scan, db.split_ranges(4)))
+    ///
+    /// Args:
+    ///     n: the maximum number of ranges.
+    fn split_ranges(&self, n: usize, py: Python) -> PyResult<Vec<(PyObject, PyObject)>> {
+        if n == 0 {
+            return Err(PyException::new_err("n must be positive"));
+        }
+        let db = self.get_db()?;
+        let live_files = db
+            .live_files()
+            .map_err(|e| PyException::new_err(e.to_string()))?;
+        let raw_mode = self.opt_py.raw_mode;
+        let ordered_numeric_keys = self.opt_py.ordered_numeric_keys;
+        let compare = |a: &[u8], b: &[u8]| {
+            if raw_mode {
+                a.cmp(b)
+            } else if ordered_numeric_keys {
+                rocksdict_numeric_compare(a, b)
+            } else {
+                rocksdict_compare(a, b)
+            }
+        };
+        let mut files: Vec<(Vec<u8>, usize)> = live_files
+            .into_iter()
+            .filter(|lf| lf.column_family_name == self.column_family_name)
+            .filter_map(|lf| lf.start_key.map(|start_key| (start_key, lf.size)))
+            .collect();
+        files.sort_by(|a, b| compare(&a.0, &b.0));
+        let total_size: usize = files.iter().map(|(_, size)| size).sum();
+
+        // start a new range at the first file beyond each `total_size / n` share
+        let mut boundaries: Vec<Vec<u8>> = Vec::new();
+        let mut accumulated_size = 0;
+        for (start_key, size) in files {
+            if boundaries.len() + 1 == n {
+                break;
+            }
+            let share_reached = accumulated_size > 0
+                && accumulated_size as u128 * n as u128
+                    >= total_size as u128 * (boundaries.len() + 1) as u128;
+            let is_new_key =
+                !matches!(boundaries.last(), Some(last) if compare(last, &start_key).is_ge());
+            if share_reached && is_new_key {
+                boundaries.push(start_key);
+            }
+            accumulated_size += size;
+        }
+
+        let mut keys = vec![py.None()];
+        for boundary in boundaries {
+            keys.push(decode_key_with(
+                py,
+                &boundary,
+                &self.loads,
+                self.key_loads.as_ref(),
+                raw_mode,
+            )?);
+        }
+        keys.push(py.None());
+        Ok(keys
+            .windows(2)
+            .map(|bounds| (bounds[0].clone_ref(py), bounds[1].clone_ref(py)))
+            .collect())
+    }
+
     /// Delete the database.
     ///
+    /// Notes:
+    ///     Raises an exception if the database is still open in this process.
+    ///     Close or delete all instances using it first (see `close()`).
+    ///
     /// Args:
     ///     path (str): path to this database
     ///     options (rocksdict.Options): Rocksdb options object
     #[staticmethod]
     #[pyo3(signature = (path, options = OptionsPy::new(false)))]
     fn destroy(path: &str, options: OptionsPy, py: Python) -> PyResult<()> {
+        check_not_open(path, "destroy")?;
         let inner_opt = options.inner_opt;
 
         py.allow_threads(|| {
@@ -1325,7 +3582,13 @@ impl Rdict {
         .map_err(|e| PyException::new_err(e.to_string()))
     }
 
-    /// Repair the database.
+    /// Repair the database, recovering as much data as possible from its SST files.
+    ///
+    /// Notes:
+    ///     Some data may be lost, so make a backup of the directory first.
+    ///     Use the same options as for opening the database (in particular,
+    ///     `raw_mode` and the column families comparator).
+    ///     Raises an exception if the database is still open in this process.
     ///
     /// Args:
     ///     path (str): path to this database
@@ -1333,6 +3596,7 @@ impl Rdict {
     #[staticmethod]
     #[pyo3(signature = (path, options = OptionsPy::new(false)))]
     fn repair(path: &str, options: OptionsPy, py: Python) -> PyResult<()> {
+        check_not_open(path, "repair")?;
         let inner_opt = options.inner_opt;
 
         py.allow_threads(|| DB::repair(&inner_opt, path))
@@ -1346,20 +3610,75 @@ impl Rdict {
     }
 }
 
+/// Sequence number of the first write batch of a WAL file.
+///
+/// A WAL record has a 7 bytes header (checksum, length and type) or an
+/// 11 bytes header for recyclable records (types 5 to 8), and the
+/// payload of a write batch starts with its sequence number.
+fn wal_start_sequence(path: &Path) -> PyResult<Option<u64>> {
+    let mut buffer = [0u8; 19];
+    let mut len = 0;
+    let mut file = fs::File::open(path)?;
+    while len < buffer.len() {
+        match file.read(&mut buffer[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    let header_len = match buffer[6] {
+        1..=4 => 7,
+        5..=8 => 11,
+        _ => return Ok(None),
+    };
+    if len < header_len + 8 {
+        return Ok(None);
+    }
+    let sequence = &buffer[header_len..header_len + 8];
+    Ok(Some(u64::from_le_bytes(sequence.try_into().unwrap())))
+}
+
+/// find the `OPTIONS-xxxxxx` file with the largest file number
+fn latest_options_file(path: &str) -> PyResult<PathBuf> {
+    let mut latest: Option<(u64, PathBuf)> = None;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        if let Some(number) = file_name.strip_prefix("OPTIONS-") {
+            if let Ok(number) = number.parse::<u64>() {
+                let is_newer = match &latest {
+                    None => true,
+                    Some((latest_number, _)) => number > *latest_number,
+                };
+                if is_newer {
+                    latest = Some((number, entry.path()));
+                }
+            }
+        }
+    }
+    match latest {
+        Some((_, file)) => Ok(file),
+        None => Err(PyException::new_err(format!(
+            "no OPTIONS file found in `{path}`"
+        ))),
+    }
+}
+
 fn display_live_file_dict(
     lf: LiveFile,
     py: Python,
     pickle_loads: &PyObject,
+    key_loads: Option<&PyObject>,
     raw_mode: bool,
 ) -> PyResult<PyObject> {
     let result = PyDict::new_bound(py);
     let start_key = match lf.start_key {
         None => py.None(),
-        Some(k) => decode_value(py, &k, pickle_loads, raw_mode)?,
+        Some(k) => decode_key_with(py, &k, pickle_loads, key_loads, raw_mode)?,
     };
     let end_key = match lf.end_key {
         None => py.None(),
-        Some(k) => decode_value(py, &k, pickle_loads, raw_mode)?,
+        Some(k) => decode_key_with(py, &k, pickle_loads, key_loads, raw_mode)?,
     };
     result.set_item("name", lf.name)?;
     result.set_item("size", lf.size)?;
@@ -1371,27 +3690,74 @@ fn display_live_file_dict(
     Ok(result.to_object(py))
 }
 
+/// Flush several column families with a single call, atomically with `atomic_flush`.
+fn flush_cfs(db: &DB, cfs: &[Arc<UnboundColumnFamily>], wait: bool) -> PyResult<()> {
+    let mut handles: Vec<_> = cfs.iter().map(|cf| cf.inner()).collect();
+    unsafe {
+        let f_opt = librocksdb_sys::rocksdb_flushoptions_create();
+        librocksdb_sys::rocksdb_flushoptions_set_wait(f_opt, wait as c_uchar);
+        let mut err: *mut c_char = null_mut();
+        librocksdb_sys::rocksdb_flush_cfs(
+            db.inner(),
+            f_opt,
+            handles.as_mut_ptr(),
+            handles.len() as c_int,
+            &mut err,
+        );
+        librocksdb_sys::rocksdb_flushoptions_destroy(f_opt);
+        if !err.is_null() {
+            return Err(PyException::new_err(error_message(err)));
+        }
+    }
+    Ok(())
+}
+
+/// Number of keys read with the GIL released by `Rdict.to_dict()`.
+const EXPORT_BATCH_SIZE: usize = 10_000;
+
+/// Default safety limit of `Rdict.to_dict()`, also used by `db | other`.
+const TO_DICT_MAX_ITEMS: usize = 1_000_000;
+
+/// Raise if an encoded key or value is longer than
+/// `Options.set_max_key_bytes()` or `Options.set_max_value_bytes()`.
+#[inline]
+fn check_encoded_size(kind: &str, len: usize, max: Option<usize>) -> PyResult<()> {
+    match max {
+        Some(max) if len > max => Err(PyException::new_err(format!(
+            "encoded {kind} of {len} bytes exceeds the limit of {max} bytes"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// `decode` converts the values to python objects.
+#[allow(clippy::too_many_arguments)]
 fn get_batch_inner<'a>(
     db: &DB,
     key_list: &Bound<PyList>,
     py: Python<'a>,
     read_opt: &ReadOptions,
-    loads: &PyObject,
+    decode: impl Fn(&[u8]) -> PyResult<PyObject>,
+    default: Option<&Bound<PyAny>>,
+    key_dumps: Option<&PyObject>,
     cf: &Arc<UnboundColumnFamily>,
     raw_mode: bool,
 ) -> PyResult<Bound<'a, PyList>> {
     let keys_py = key_list.iter().collect::<Vec<_>>();
-    let mut keys: Vec<Cow<[u8]>> = Vec::with_capacity(key_list.len());
+    let mut keys: Vec<EncodedBytes> = Vec::with_capacity(key_list.len());
     for key in keys_py.iter() {
-        keys.push(encode_key(key, raw_mode)?);
+        keys.push(encode_key_with(key, key_dumps, raw_mode)?);
     }
     let values = py.allow_threads(|| db.batched_multi_get_cf_opt(cf, &keys, false, read_opt));
     let result = PyList::empty_bound(py);
     for v in values {
         match v {
             Ok(value) => match value {
-                None => result.append(py.None())?,
-                Some(slice) => result.append(decode_value(py, slice.as_ref(), loads, raw_mode)?)?,
+                None => match default {
+                    Some(default) => result.append(default)?,
+                    None => result.append(py.None())?,
+                },
+                Some(slice) => result.append(decode(slice.as_ref())?)?,
             },
             Err(e) => return Err(PyException::new_err(e.to_string())),
         }
@@ -1413,7 +3779,7 @@ impl Drop for Rdict {
         // important, always drop column families first
         // to ensure that CF handles have shorter life than DB.
         drop(self.column_family.take());
-        self.db.close();
+        self.db.release();
     }
 }
 
@@ -1463,30 +3829,28 @@ impl AccessType {
         AccessType(AccessTypeInner::ReadWrite)
     }
 
-    /// Define DB Access Types.
+    /// Open the db in read only mode, several processes can open the same
+    /// db in read only mode, alongside one read write process.
     ///
     /// Notes:
-    ///     There are four access types:
-    ///       - ReadWrite: default value
-    ///       - ReadOnly
-    ///       - WithTTL
-    ///       - Secondary
+    ///     The WAL is replayed into memory, but not written back, so the
+    ///     updates not yet flushed by the read write process are visible
+    ///     as of the opening. The read only instance does not see later
+    ///     updates, reopen it or use `AccessType.secondary()` to catch up.
     ///
     /// Examples:
     ///     ::
     ///
     ///         from rocksdict import Rdict, AccessType
     ///
-    ///         # open with 24 hours ttl
-    ///         db = Rdict("./main_path", access_type = AccessType.with_ttl(24 * 3600))
-    ///
-    ///         # open as read_only
     ///         db = Rdict("./main_path", access_type = AccessType.read_only())
     ///
-    ///         # open as secondary
-    ///         db = Rdict("./main_path", access_type = AccessType.secondary("./secondary_path"))
-    ///
+    ///         # refuse to open if there are WAL files to replay
+    ///         db = Rdict("./main_path", access_type = AccessType.read_only(True))
     ///
+    /// Args:
+    ///     error_if_log_file_exist: raise an exception when opening if
+    ///         there are WAL files in the db, instead of replaying them.
     #[staticmethod]
     #[pyo3(signature = (error_if_log_file_exist = false))]
     fn read_only(error_if_log_file_exist: bool) -> Self {
@@ -1,8 +1,8 @@
 use crate::db_reference::{DbReference, DbReferenceHolder};
-use crate::encoder::{decode_value, encode_key};
+use crate::encoder::{decode_value_with, encode_key_with, ValueTransform};
 use crate::exceptions::DbClosedError;
 use crate::{Rdict, RdictItems, RdictIter, RdictKeys, RdictValues, ReadOptionsPy};
-use pyo3::exceptions::PyException;
+use pyo3::exceptions::{PyException, PyKeyError};
 use pyo3::prelude::*;
 use rocksdb::{ReadOptions, UnboundColumnFamily};
 use std::sync::Arc;
@@ -41,6 +41,9 @@ pub struct Snapshot {
     pub(crate) inner: *const librocksdb_sys::rocksdb_snapshot_t,
     pub(crate) column_family: Option<Arc<UnboundColumnFamily>>,
     pub(crate) pickle_loads: PyObject,
+    pub(crate) key_dumps: Option<PyObject>,
+    pub(crate) key_loads: Option<PyObject>,
+    pub(crate) value_transform: Option<ValueTransform>,
     pub(crate) read_opt: ReadOptions,
     // decrease db Rc last
     pub(crate) db: DbReferenceHolder,
@@ -60,18 +63,52 @@ impl Snapshot {
             None => ReadOptionsPy::default(py)?,
             Some(opt) => opt.clone(),
         };
-        let opt_pointer = read_opt.to_read_opt(self.raw_mode, py)?;
+        let opt_pointer = read_opt.to_read_opt(self.raw_mode, self.key_dumps.as_ref(), py)?;
         unsafe {
-            set_snapshot(opt_pointer.0, self.inner);
+            set_snapshot(opt_pointer.inner, self.inner);
         }
-        RdictIter::new(
+        RdictIter::from_read_opt(
             &self.db,
             &self.column_family,
-            read_opt,
+            opt_pointer,
             &self.pickle_loads,
+            self.key_dumps.as_ref(),
+            self.key_loads.as_ref(),
             self.raw_mode,
-            py,
         )
+        .map(|iter| iter.with_value_transform(self.value_transform.clone()))
+    }
+
+    /// Count the number of keys in this snapshot, in the range `[from_key, to_key)`.
+    ///
+    /// This is an exact count (a full scan of the range) performed without
+    /// holding the GIL.
+    ///
+    /// Args:
+    ///     from_key: count from this key (included), or from the first key if None.
+    ///     to_key: count until this key (excluded), or until the last key if None.
+    ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
+    #[pyo3(signature = (from_key = None, to_key = None, read_opt = None))]
+    fn count(
+        &self,
+        from_key: Option<&Bound<PyAny>>,
+        to_key: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<u64> {
+        let mut read_opt: ReadOptionsPy = match read_opt {
+            None => ReadOptionsPy::default(py)?,
+            Some(opt) => opt.clone(),
+        };
+        if let Some(to_key) = to_key {
+            read_opt.set_iterate_upper_bound(to_key, py)?;
+        }
+        let mut iter = self.iter(Some(&read_opt), py)?;
+        match from_key {
+            None => iter.seek_to_first(py)?,
+            Some(from_key) => iter.seek(from_key, py)?,
+        }
+        py.allow_threads(|| iter.count_remaining())
     }
 
     /// Iterate through all keys and values pairs.
@@ -82,15 +119,18 @@ impl Snapshot {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     limit: stop after this number of items, no limit if None.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, limit = None))]
     fn items(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictItems> {
-        RdictItems::new(self.iter(read_opt, py)?, backwards, from_key)
+        let iter = RdictItems::new(self.iter(read_opt, py)?, backwards, from_key, py)?;
+        Ok(iter.with_limit(limit))
     }
 
     /// Iterate through all keys.
@@ -101,15 +141,18 @@ impl Snapshot {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     limit: stop after this number of items, no limit if None.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, limit = None))]
     fn keys(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictKeys> {
-        RdictKeys::new(self.iter(read_opt, py)?, backwards, from_key)
+        let iter = RdictKeys::new(self.iter(read_opt, py)?, backwards, from_key, py)?;
+        Ok(iter.with_limit(limit))
     }
 
     /// Iterate through all values.
@@ -120,32 +163,46 @@ impl Snapshot {
     ///         or the nearest next key for iteration
     ///         (depending on iteration direction).
     ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
-    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None))]
+    ///     limit: stop after this number of items, no limit if None.
+    #[pyo3(signature = (backwards = false, from_key = None, read_opt = None, limit = None))]
     fn values(
         &self,
         backwards: bool,
         from_key: Option<&Bound<PyAny>>,
         read_opt: Option<&ReadOptionsPy>,
+        limit: Option<usize>,
         py: Python,
     ) -> PyResult<RdictValues> {
-        RdictValues::new(self.iter(read_opt, py)?, backwards, from_key)
+        let iter = RdictValues::new(self.iter(read_opt, py)?, backwards, from_key, py)?;
+        Ok(iter.with_limit(limit))
     }
 
     /// read from snapshot
     fn __getitem__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<PyObject> {
-        let db = self.get_db();
-        let key = encode_key(key, self.raw_mode)?;
-        let value_result = if let Some(cf) = &self.column_family {
-            db.get_pinned_cf_opt(cf, &key[..], &self.read_opt)
-        } else {
-            db.get_pinned_opt(&key[..], &self.read_opt)
-        };
-        match value_result {
-            Ok(value) => match value {
-                None => Err(PyException::new_err("key not found")),
-                Some(slice) => decode_value(py, slice.as_ref(), &self.pickle_loads, self.raw_mode),
-            },
-            Err(e) => Err(PyException::new_err(e.to_string())),
+        match self.get_inner(key, py)? {
+            Some(value) => Ok(value),
+            None => Err(PyKeyError::new_err(key.clone().unbind())),
+        }
+    }
+
+    /// Get value from key in the snapshot.
+    ///
+    /// Args:
+    ///     key: a single key.
+    ///     default: the default value to return if key not found.
+    ///
+    /// Returns:
+    ///    None or default value if the key does not exist.
+    #[pyo3(signature = (key, default = None))]
+    fn get(
+        &self,
+        key: &Bound<PyAny>,
+        default: Option<&Bound<PyAny>>,
+        py: Python,
+    ) -> PyResult<Option<PyObject>> {
+        match self.get_inner(key, py)? {
+            Some(value) => Ok(Some(value)),
+            None => Ok(default.map(|default| default.to_object(py))),
         }
     }
 }
@@ -158,9 +215,7 @@ impl Snapshot {
             .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?
             .inner();
         let snapshot = unsafe { librocksdb_sys::rocksdb_create_snapshot(db_inner) };
-        let r_opt: ReadOptions = rdict
-            .read_opt_py
-            .to_read_options(rdict.opt_py.raw_mode, py)?;
+        let r_opt: ReadOptions = rdict.to_read_options(&rdict.read_opt_py, py)?;
         unsafe {
             set_snapshot(r_opt.inner(), snapshot);
         }
@@ -168,23 +223,52 @@ impl Snapshot {
             inner: snapshot,
             column_family: rdict.column_family.clone(),
             pickle_loads: rdict.loads.clone(),
+            key_dumps: rdict.key_dumps.clone(),
+            key_loads: rdict.key_loads.clone(),
+            value_transform: rdict.value_transform.clone(),
             read_opt: r_opt,
             db: rdict.db.clone(),
             raw_mode: rdict.opt_py.raw_mode,
         })
     }
 
-    fn get_db(&self) -> &DbReference {
+    fn get_inner(&self, key: &Bound<PyAny>, py: Python) -> PyResult<Option<PyObject>> {
+        let db = self.get_db()?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        let value_result = py.allow_threads(|| match &self.column_family {
+            Some(cf) => db.get_pinned_cf_opt(cf, &key[..], &self.read_opt),
+            None => db.get_pinned_opt(&key[..], &self.read_opt),
+        });
+        match value_result {
+            Ok(value) => match value {
+                None => Ok(None),
+                Some(slice) => Ok(Some(decode_value_with(
+                    py,
+                    slice.as_ref(),
+                    &self.pickle_loads,
+                    self.value_transform.as_ref(),
+                    self.raw_mode,
+                )?)),
+            },
+            Err(e) => Err(PyException::new_err(e.to_string())),
+        }
+    }
+
+    fn get_db(&self) -> PyResult<&DbReference> {
         self.db
             .get()
-            .expect("Snapshot should never close its DbReference")
+            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))
     }
 }
 
 impl Drop for Snapshot {
     fn drop(&mut self) {
+        let db = self
+            .db
+            .get_for_release()
+            .expect("Snapshot should never release its DbReference");
         unsafe {
-            librocksdb_sys::rocksdb_release_snapshot(self.get_db().inner(), self.inner);
+            librocksdb_sys::rocksdb_release_snapshot(db.inner(), self.inner);
         }
     }
 }
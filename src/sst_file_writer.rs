@@ -1,7 +1,8 @@
-use crate::encoder::{encode_key, encode_value};
+use crate::encoder::{encode_key_with, encode_value, named_serializer, resolve_serializer};
+use crate::options::{compression_type_name, DBCompressionTypePy, U64_TIMESTAMP_SIZE};
 use crate::util::{error_message, to_cpath};
 use crate::{ffi_try, ffi_try_impl, OptionsPy};
-use libc::{self, c_char, size_t};
+use libc::{self, c_char, c_int, size_t};
 use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
 use pyo3::PyResult;
@@ -19,7 +20,13 @@ pub struct SstFileWriterPy {
     pub(crate) inner: *mut librocksdb_sys::rocksdb_sstfilewriter_t,
     opts: Options,
     dumps: PyObject,
+    key_dumps: Option<PyObject>,
     raw_mode: bool,
+    num_entries: u64,
+    /// a file is opened and not finished yet
+    is_open: bool,
+    /// size of the user-defined timestamps of the comparator
+    timestamp_size: usize,
 }
 
 unsafe impl Send for SstFileWriterPy {}
@@ -50,38 +57,119 @@ impl SstFileWriterPy {
     ///
     /// Args:
     ///     options: this options must have the same `raw_mode` as the Rdict DB.
+    ///         Files are written through the env of the options (see `Options.set_env`),
+    ///         and compressed like the bottommost level of the options: with
+    ///         `Options.set_bottommost_compression_type` if set, otherwise with
+    ///         the last level of `Options.set_compression_per_level` if set,
+    ///         otherwise with `Options.set_compression_type`.
+    ///     serializer: the serializer of the values, must be the same as the
+    ///         `serializer` of the Rdict DB (see `Rdict`).
     #[new]
-    #[pyo3(signature = (options = OptionsPy::new(false)))]
-    fn create(options: OptionsPy, py: Python) -> PyResult<Self> {
+    #[pyo3(signature = (options = OptionsPy::new(false), serializer = None))]
+    fn create(options: OptionsPy, serializer: Option<&Bound<PyAny>>, py: Python) -> PyResult<Self> {
         let env_options = EnvOptions::default();
         let raw_mode = options.raw_mode;
+        let timestamp_size = options.timestamp_size;
         let options = &options.inner_opt;
         let writer = Self::create_raw(options, &env_options);
-        let pickle = PyModule::import_bound(py, "pickle")?.to_object(py);
-        let pickle_dumps = pickle.getattr(py, "dumps")?;
+        let dumps = match serializer {
+            Some(serializer) => resolve_serializer(py, serializer)?.1,
+            None => named_serializer(py, "pickle")?.0,
+        };
 
         Ok(Self {
             inner: writer,
             opts: options.clone(),
-            dumps: pickle_dumps,
+            dumps,
+            key_dumps: None,
             raw_mode,
+            num_entries: 0,
+            is_open: false,
+            timestamp_size,
         })
     }
 
+    /// Set the compression of the written files, to match the column family
+    /// the files are ingested into.
+    ///
+    /// Must be called before `open()`, it overrides the compression of the options.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import SstFileWriter, DBCompressionType
+    ///
+    ///         writer = SstFileWriter()
+    ///         writer.set_compression(DBCompressionType.zstd(), level=9)
+    ///         assert writer.compression() == "zstd"
+    ///
+    /// Args:
+    ///     compression_type: the compression type.
+    ///     level: the compression level, the default level of the compression
+    ///         type if None.
+    #[pyo3(signature = (compression_type, level = None))]
+    fn set_compression(
+        &mut self,
+        compression_type: &DBCompressionTypePy,
+        level: Option<c_int>,
+    ) -> PyResult<()> {
+        if self.is_open {
+            return Err(PyException::new_err(
+                "set_compression() must be called before open() or after finish()",
+            ));
+        }
+        // the bottommost compression takes precedence for sst files
+        self.opts.set_compression_type(compression_type.0);
+        self.opts
+            .set_bottommost_compression_type(compression_type.0);
+        if let Some(level) = level {
+            // the other arguments are the rocksdb defaults
+            self.opts.set_compression_options(-14, level, 0, 0);
+            self.opts
+                .set_bottommost_compression_options(-14, level, 0, 0, true);
+        }
+        let writer = Self::create_raw(&self.opts, &EnvOptions::default());
+        unsafe { librocksdb_sys::rocksdb_sstfilewriter_destroy(self.inner) };
+        self.inner = writer;
+        Ok(())
+    }
+
+    /// Returns the name of the compression type set by `set_compression()`,
+    /// or of `Options.set_compression_type` of the options (see `DBCompressionType.from_name`).
+    fn compression(&self) -> PyResult<&'static str> {
+        let raw = unsafe { librocksdb_sys::rocksdb_options_get_compression(self.opts.inner()) };
+        compression_type_name(raw)
+            .ok_or_else(|| PyException::new_err(format!("unknown compression type {raw}")))
+    }
+
     /// set custom dumps function
     fn set_dumps(&mut self, dumps: PyObject) {
         self.dumps = dumps
     }
 
+    /// set custom key dumps function, see `Rdict.set_key_dumps()`
+    ///
+    /// The keys must be added in the order of the bytes returned by this function.
+    fn set_key_dumps(&mut self, key_dumps: PyObject) {
+        self.key_dumps = Some(key_dumps)
+    }
+
     /// Prepare SstFileWriter to write into file located at "file_path".
-    fn open(&self, path: &str) -> PyResult<()> {
+    ///
+    /// This resets `num_entries()`.
+    fn open(&mut self, path: &str, py: Python) -> PyResult<()> {
         let cpath = to_cpath(path)?;
-        self.open_raw(&cpath)
+        py.allow_threads(|| self.open_raw(&cpath))?;
+        self.num_entries = 0;
+        self.is_open = true;
+        Ok(())
     }
 
     /// Finalize writing to sst file and close file.
-    fn finish(&mut self) -> PyResult<()> {
-        self.finish_raw()
+    fn finish(&mut self, py: Python) -> PyResult<()> {
+        py.allow_threads(|| self.finish_raw())?;
+        self.is_open = false;
+        Ok(())
     }
 
     /// returns the current file size
@@ -89,23 +177,92 @@ impl SstFileWriterPy {
         self.file_size_raw()
     }
 
+    /// returns the number of entries (puts and deletions) added to the
+    /// currently opened file, to check the count before calling `finish()`
+    fn num_entries(&self) -> u64 {
+        self.num_entries
+    }
+
     /// Adds a Put key with value to currently opened file
     /// REQUIRES: key is after any previously added key according to comparator.
-    fn __setitem__(&mut self, key: &Bound<PyAny>, value: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
+    fn __setitem__(
+        &mut self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        py: Python,
+    ) -> PyResult<()> {
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
         let value = encode_value(value, &self.dumps, self.raw_mode)?;
-        self.setitem_raw(&key, &value)
+        py.allow_threads(|| self.setitem_raw(&key, &value))
     }
 
     /// Adds a deletion key to currently opened file
     /// REQUIRES: key is after any previously added key according to comparator.
-    fn __delitem__(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
-        let key = encode_key(key, self.raw_mode)?;
-        self.delitem_raw(&key)
+    fn __delitem__(&mut self, key: &Bound<PyAny>, py: Python) -> PyResult<()> {
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        py.allow_threads(|| self.delitem_raw(&key))
+    }
+
+    /// Adds a Put key with a user-defined timestamp and value to currently opened file
+    /// REQUIRES: key is after any previously added key according to comparator
+    /// (for the same key, the timestamps must be decreasing).
+    ///
+    /// The options must be set with `Options.set_u64_timestamp_comparator()`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import SstFileWriter, Options
+    ///
+    ///         opt = Options(raw_mode=True)
+    ///         opt.set_u64_timestamp_comparator()
+    ///         writer = SstFileWriter(opt)
+    ///         writer.open("./file.sst")
+    ///         writer.put_with_ts(b"key", 2, b"new value")
+    ///         writer.put_with_ts(b"key", 1, b"old value")
+    ///         writer.finish()
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     ts: the timestamp, an unsigned 64 bits int.
+    ///     value: the value.
+    fn put_with_ts(
+        &mut self,
+        key: &Bound<PyAny>,
+        ts: u64,
+        value: &Bound<PyAny>,
+        py: Python,
+    ) -> PyResult<()> {
+        self.check_timestamp_size()?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        let value = encode_value(value, &self.dumps, self.raw_mode)?;
+        py.allow_threads(|| self.put_with_ts_raw(&key, &ts.to_le_bytes(), &value))
+    }
+
+    /// Adds a deletion key with a user-defined timestamp to currently opened file,
+    /// see `put_with_ts()`.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     ts: the timestamp, an unsigned 64 bits int.
+    fn delete_with_ts(&mut self, key: &Bound<PyAny>, ts: u64, py: Python) -> PyResult<()> {
+        self.check_timestamp_size()?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        py.allow_threads(|| self.delete_with_ts_raw(&key, &ts.to_le_bytes()))
     }
 }
 
 impl SstFileWriterPy {
+    fn check_timestamp_size(&self) -> PyResult<()> {
+        if self.timestamp_size != U64_TIMESTAMP_SIZE {
+            return Err(PyException::new_err(
+                "timestamps require the options to be set with \
+                 Options.set_u64_timestamp_comparator()",
+            ));
+        }
+        Ok(())
+    }
+
     #[inline]
     fn create_raw(
         opts: &Options,
@@ -152,6 +309,7 @@ impl SstFileWriterPy {
                 value.len() as size_t,
             ));
         }
+        self.num_entries += 1;
         Ok(())
     }
 
@@ -164,6 +322,39 @@ impl SstFileWriterPy {
                 key.len() as size_t,
             ));
         }
+        self.num_entries += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn put_with_ts_raw(&mut self, key: &[u8], ts: &[u8], value: &[u8]) -> PyResult<()> {
+        unsafe {
+            ffi_try!(librocksdb_sys::rocksdb_sstfilewriter_put_with_ts(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            ));
+        }
+        self.num_entries += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn delete_with_ts_raw(&mut self, key: &[u8], ts: &[u8]) -> PyResult<()> {
+        unsafe {
+            ffi_try!(librocksdb_sys::rocksdb_sstfilewriter_delete_with_ts(
+                self.inner,
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                ts.as_ptr() as *const c_char,
+                ts.len() as size_t,
+            ));
+        }
+        self.num_entries += 1;
         Ok(())
     }
 }
@@ -0,0 +1,145 @@
+use crate::util::unknown_name_error;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rocksdb::statistics::{Histogram, Ticker};
+use rocksdb::Options;
+use std::collections::HashMap;
+
+/// Statistics of a db, returned by `Rdict.get_statistics()`.
+///
+/// Statistics must be enabled with `Options.enable_statistics()` before
+/// opening the db. Tickers are counters and histograms are distributions,
+/// both identified by their rocksdb names (e.g. `"rocksdb.block.cache.hit"`),
+/// see `Statistics.ticker_names()` and `Statistics.histogram_names()`.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, Options, StatsLevel
+///
+///         opt = Options()
+///         opt.enable_statistics()
+///         opt.set_statistics_level(StatsLevel.all())
+///         db = Rdict("./tmp", opt)
+///         db["a"] = 1
+///         db["a"]
+///
+///         stats = db.get_statistics()
+///         hit = stats.ticker("rocksdb.block.cache.hit")
+///         miss = stats.ticker("rocksdb.block.cache.miss")
+///         print(stats.histogram("rocksdb.db.get.micros"))
+///
+///         db.close()
+///         Rdict.destroy("./tmp")
+#[pyclass(name = "Statistics")]
+pub(crate) struct StatisticsPy {
+    /// shares the statistics object of the db options
+    opt: Options,
+    /// ticker values at the last `reset()`
+    baseline: HashMap<&'static str, u64>,
+}
+
+impl StatisticsPy {
+    /// Returns `None` if statistics are not enabled.
+    pub(crate) fn new(opt: &Options) -> Option<Self> {
+        opt.get_statistics()?;
+        Some(StatisticsPy {
+            opt: opt.clone(),
+            baseline: HashMap::new(),
+        })
+    }
+
+    fn ticker_value(&self, ticker: &Ticker) -> u64 {
+        let baseline = self.baseline.get(ticker.name()).copied().unwrap_or(0);
+        self.opt.get_ticker_count(*ticker).saturating_sub(baseline)
+    }
+}
+
+#[pymethods]
+impl StatisticsPy {
+    /// Get the value of a ticker (a counter) since the last `reset()`.
+    ///
+    /// Args:
+    ///     name: rocksdb name of the ticker, e.g. `"rocksdb.bytes.written"`.
+    pub fn ticker(&self, name: &str) -> PyResult<u64> {
+        match Ticker::iter().find(|t| t.name() == name) {
+            Some(ticker) => Ok(self.ticker_value(ticker)),
+            None => Err(unknown_name_error(
+                "ticker",
+                name,
+                Ticker::iter().map(|t| t.name()).collect(),
+            )),
+        }
+    }
+
+    /// Get the values of all tickers, as a dict from name to value.
+    pub fn tickers(&self) -> HashMap<&'static str, u64> {
+        Ticker::iter()
+            .map(|t| (t.name(), self.ticker_value(t)))
+            .collect()
+    }
+
+    /// Get a histogram (a distribution) as a dict with keys
+    /// `median`, `p95`, `p99`, `average`, `std_dev`, `max`, `count` and `sum`.
+    ///
+    /// Notes:
+    ///     Histograms are not affected by `reset()`.
+    ///
+    /// Args:
+    ///     name: rocksdb name of the histogram, e.g. `"rocksdb.db.get.micros"`.
+    pub fn histogram(&self, name: &str, py: Python) -> PyResult<PyObject> {
+        let histogram = match Histogram::iter().find(|h| h.name() == name) {
+            Some(histogram) => histogram,
+            None => {
+                return Err(unknown_name_error(
+                    "histogram",
+                    name,
+                    Histogram::iter().map(|h| h.name()).collect(),
+                ))
+            }
+        };
+        let data = self.opt.get_histogram_data(*histogram);
+        let result = PyDict::new_bound(py);
+        result.set_item("median", data.median())?;
+        result.set_item("p95", data.p95())?;
+        result.set_item("p99", data.p99())?;
+        result.set_item("average", data.average())?;
+        result.set_item("std_dev", data.std_dev())?;
+        result.set_item("max", data.max())?;
+        result.set_item("count", data.count())?;
+        result.set_item("sum", data.sum())?;
+        Ok(result.to_object(py))
+    }
+
+    /// Reset the tickers of this `Statistics` object to zero.
+    ///
+    /// The statistics of the db itself are not modified, other
+    /// objects returned by `Rdict.get_statistics()` are not affected.
+    pub fn reset(&mut self) {
+        self.baseline = Ticker::iter()
+            .map(|t| (t.name(), self.opt.get_ticker_count(*t)))
+            .collect();
+    }
+
+    /// Human readable dump of all statistics, as written to the LOG file.
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> String {
+        self.opt.get_statistics().unwrap_or_default()
+    }
+
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    /// Names of all the tickers.
+    #[staticmethod]
+    pub fn ticker_names() -> Vec<&'static str> {
+        Ticker::iter().map(|t| t.name()).collect()
+    }
+
+    /// Names of all the histograms.
+    #[staticmethod]
+    pub fn histogram_names() -> Vec<&'static str> {
+        Histogram::iter().map(|h| h.name()).collect()
+    }
+}
@@ -1,8 +1,11 @@
 use libc::{c_char, c_void};
 use pyo3::exceptions::PyException;
-use pyo3::PyResult;
+use pyo3::{PyErr, PyResult, Python};
 use std::ffi::{CStr, CString};
 use std::path::Path;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
 
 #[macro_export]
 macro_rules! ffi_try {
@@ -48,3 +51,49 @@ pub(crate) fn to_cpath<P: AsRef<Path>>(path: P) -> PyResult<CString> {
         ))),
     }
 }
+
+/// Unknown name error, listing the valid names sharing the longest
+/// common prefix with `name`.
+pub(crate) fn unknown_name_error(kind: &str, name: &str, valid: Vec<&str>) -> PyErr {
+    let common_prefix = |n: &str| {
+        n.chars()
+            .zip(name.chars())
+            .take_while(|(a, b)| a == b)
+            .count()
+    };
+    let longest = valid.iter().map(|n| common_prefix(n)).max().unwrap_or(0);
+    let nearby: Vec<&str> = valid
+        .into_iter()
+        .filter(|n| common_prefix(n) == longest)
+        .collect();
+    PyException::new_err(format!(
+        "unknown {kind} name `{name}`, valid names include: {}",
+        nearby.join(", ")
+    ))
+}
+
+type PythonTask = Box<dyn FnOnce(Python) + Send>;
+
+/// Run `task` with the GIL on a dedicated thread.
+///
+/// Rocksdb background threads may hold db mutexes while calling loggers
+/// and event listeners, so they must not wait for the GIL themselves.
+pub(crate) fn run_with_gil_later(task: PythonTask) {
+    static SENDER: OnceLock<Mutex<Sender<PythonTask>>> = OnceLock::new();
+    let sender = SENDER.get_or_init(|| {
+        let (sender, receiver) = channel::<PythonTask>();
+        thread::Builder::new()
+            .name("rocksdict-callbacks".to_string())
+            .spawn(move || {
+                for task in receiver {
+                    Python::with_gil(task);
+                }
+            })
+            .expect("failed to start the callback thread");
+        Mutex::new(sender)
+    });
+    if let Ok(sender) = sender.lock() {
+        // the callback thread is gone once the interpreter shuts down
+        let _ = sender.send(task);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::{Rdict, RdictItems, RdictKeys, RdictValues, ReadOptionsPy};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+/// The arguments of `Rdict.keys()`, `Rdict.values()` and `Rdict.items()`,
+/// used to create a new iterator for every iteration of a view.
+pub(crate) struct ViewArgs {
+    rdict: Py<Rdict>,
+    backwards: bool,
+    from_key: Option<PyObject>,
+    read_opt: Option<ReadOptionsPy>,
+    limit: Option<usize>,
+}
+
+impl ViewArgs {
+    pub(crate) fn new(
+        rdict: &Bound<Rdict>,
+        backwards: bool,
+        from_key: Option<&Bound<PyAny>>,
+        read_opt: Option<&ReadOptionsPy>,
+        limit: Option<usize>,
+    ) -> Self {
+        ViewArgs {
+            rdict: rdict.clone().unbind(),
+            backwards,
+            from_key: from_key.map(|key| key.clone().unbind()),
+            read_opt: read_opt.cloned(),
+            limit,
+        }
+    }
+
+    /// The view covers the whole column family, so membership can be
+    /// checked with point lookups instead of a scan.
+    fn is_unbounded(&self) -> bool {
+        self.from_key.is_none() && self.read_opt.is_none() && self.limit.is_none()
+    }
+
+    fn len(&self, py: Python) -> PyResult<usize> {
+        if self.from_key.is_some() || self.read_opt.is_some() {
+            // the estimate is for the whole column family
+            return self.keys(py)?.count_remaining(py);
+        }
+        let estimate = self.rdict.borrow(py).__len__()?;
+        Ok(self.limit.map_or(estimate, |limit| estimate.min(limit)))
+    }
+
+    fn keys(&self, py: Python) -> PyResult<RdictKeys> {
+        let from_key = self.from_key.as_ref().map(|key| key.bind(py));
+        let iter = self.rdict.borrow(py).iter(self.read_opt.as_ref(), py)?;
+        Ok(RdictKeys::new(iter, self.backwards, from_key, py)?.with_limit(self.limit))
+    }
+
+    fn values(&self, py: Python) -> PyResult<RdictValues> {
+        let from_key = self.from_key.as_ref().map(|key| key.bind(py));
+        let iter = self.rdict.borrow(py).iter(self.read_opt.as_ref(), py)?;
+        Ok(RdictValues::new(iter, self.backwards, from_key, py)?.with_limit(self.limit))
+    }
+
+    fn items(&self, py: Python) -> PyResult<RdictItems> {
+        let from_key = self.from_key.as_ref().map(|key| key.bind(py));
+        let iter = self.rdict.borrow(py).iter(self.read_opt.as_ref(), py)?;
+        Ok(RdictItems::new(iter, self.backwards, from_key, py)?.with_limit(self.limit))
+    }
+}
+
+/// Whether an iterator yields an item equal to `item`.
+fn scan_contains(iter: Bound<PyAny>, item: &Bound<PyAny>) -> PyResult<bool> {
+    for value in iter.iter()? {
+        if value?.eq(item)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// A view of the keys of an `Rdict`, returned by `Rdict.keys()`.
+///
+/// Every iteration creates a new iterator, which sees the keys
+/// as of its creation, so a view can be iterated several times.
+///
+/// Notes:
+///     `len()` is the `rocksdb.estimate-num-keys` estimate, see `Rdict.__len__`,
+///     unless the view has a `from_key` or `read_opt`: then the keys of
+///     the view are counted, which scans the view.
+///     `in` is a point lookup if the view has no `from_key`,
+///     `read_opt` or `limit`, or else a scan of the view.
+///
+/// Examples:
+///     ::
+///
+///         keys = db.keys()
+///         if "a" in keys:
+///             print(list(keys))
+#[pyclass]
+pub(crate) struct RdictKeysView(pub(crate) ViewArgs);
+
+#[pymethods]
+impl RdictKeysView {
+    fn __iter__(&self, py: Python) -> PyResult<RdictKeys> {
+        self.0.keys(py)
+    }
+
+    fn __len__(&self, py: Python) -> PyResult<usize> {
+        self.0.len(py)
+    }
+
+    fn __contains__(&self, key: &Bound<PyAny>, py: Python) -> PyResult<bool> {
+        if self.0.is_unbounded() {
+            return self.0.rdict.borrow(py).__contains__(key, py);
+        }
+        scan_contains(Bound::new(py, self.0.keys(py)?)?.into_any(), key)
+    }
+}
+
+/// A view of the values of an `Rdict`, returned by `Rdict.values()`.
+///
+/// Every iteration creates a new iterator, see `RdictKeysView`.
+///
+/// Notes:
+///     `in` scans the values of the view.
+#[pyclass]
+pub(crate) struct RdictValuesView(pub(crate) ViewArgs);
+
+#[pymethods]
+impl RdictValuesView {
+    fn __iter__(&self, py: Python) -> PyResult<RdictValues> {
+        self.0.values(py)
+    }
+
+    fn __len__(&self, py: Python) -> PyResult<usize> {
+        self.0.len(py)
+    }
+
+    fn __contains__(&self, value: &Bound<PyAny>, py: Python) -> PyResult<bool> {
+        scan_contains(Bound::new(py, self.0.values(py)?)?.into_any(), value)
+    }
+}
+
+/// A view of the `(key, value)` pairs of an `Rdict`, returned by `Rdict.items()`.
+///
+/// Every iteration creates a new iterator, see `RdictKeysView`.
+///
+/// Notes:
+///     `(key, value) in items` is a point lookup if the view has no
+///     `from_key`, `read_opt` or `limit`, or else a scan of the view.
+#[pyclass]
+pub(crate) struct RdictItemsView(pub(crate) ViewArgs);
+
+#[pymethods]
+impl RdictItemsView {
+    fn __iter__(&self, py: Python) -> PyResult<RdictItems> {
+        self.0.items(py)
+    }
+
+    fn __len__(&self, py: Python) -> PyResult<usize> {
+        self.0.len(py)
+    }
+
+    fn __contains__(&self, item: &Bound<PyAny>, py: Python) -> PyResult<bool> {
+        let pair = match item.downcast::<PyTuple>() {
+            Ok(pair) if pair.len() == 2 => pair,
+            _ => return Ok(false),
+        };
+        if !self.0.is_unbounded() {
+            return scan_contains(Bound::new(py, self.0.items(py)?)?.into_any(), item);
+        }
+        let rdict = self.0.rdict.borrow(py);
+        let key = pair.get_item(0)?;
+        let key = rdict.encode_key(&key)?;
+        match rdict.get_existing(&key[..], py)? {
+            None => Ok(false),
+            Some(value) => value.bind(py).eq(pair.get_item(1)?),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::db_reference::DbReferenceHolder;
+use crate::exceptions::DbClosedError;
+use crate::WriteBatchPy;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use rocksdb::{DBWALIterator, Error, WriteBatch};
+
+/// Iterator over the updates of the write-ahead log, returned by
+/// `Rdict.get_updates_since()`.
+///
+/// Yields `(sequence_number, WriteBatch)` tuples, where `sequence_number`
+/// is the sequence number of the first update in the batch. Use
+/// `WriteBatch.iterate()` to inspect the updates.
+#[pyclass(name = "WalIterator")]
+pub(crate) struct WalIteratorPy {
+    inner: DBWALIterator,
+    dumps: PyObject,
+    loads: PyObject,
+    key_dumps: Option<PyObject>,
+    key_loads: Option<PyObject>,
+    raw_mode: bool,
+    // drop DB last
+    _db: DbReferenceHolder,
+}
+
+unsafe impl Send for WalIteratorPy {}
+
+impl WalIteratorPy {
+    pub(crate) fn new(
+        db: &DbReferenceHolder,
+        seq_number: u64,
+        dumps: &PyObject,
+        loads: &PyObject,
+        key_dumps: Option<&PyObject>,
+        key_loads: Option<&PyObject>,
+        raw_mode: bool,
+    ) -> PyResult<Self> {
+        let inner = db
+            .get()
+            .ok_or_else(|| DbClosedError::new_err("DB instance already closed"))?
+            .get_updates_since(seq_number)
+            .map_err(|e| PyException::new_err(e.to_string()))?;
+        Ok(WalIteratorPy {
+            inner,
+            dumps: dumps.clone(),
+            loads: loads.clone(),
+            key_dumps: key_dumps.cloned(),
+            key_loads: key_loads.cloned(),
+            raw_mode,
+            _db: db.clone(),
+        })
+    }
+
+    /// Read the next batch, may read the log files from disk.
+    ///
+    /// Does not need the GIL.
+    fn next_batch(&mut self) -> Option<Result<(u64, WriteBatch), Error>> {
+        self.inner.next()
+    }
+}
+
+#[pymethods]
+impl WalIteratorPy {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> PyResult<Option<(u64, WriteBatchPy)>> {
+        if self._db.get().is_none() {
+            return Err(DbClosedError::new_err("DB instance already closed"));
+        }
+        match py.allow_threads(|| self.next_batch()) {
+            None => Ok(None),
+            Some(Err(e)) => Err(PyException::new_err(e.to_string())),
+            Some(Ok((seq_number, batch))) => {
+                let mut batch = WriteBatchPy::from_write_batch(py, batch, self.raw_mode)?;
+                batch.set_dumps(self.dumps.clone());
+                batch.set_loads(self.loads.clone());
+                if let Some(key_dumps) = &self.key_dumps {
+                    batch.set_key_dumps(key_dumps.clone());
+                }
+                if let Some(key_loads) = &self.key_loads {
+                    batch.set_key_loads(key_loads.clone());
+                }
+                Ok(Some((seq_number, batch)))
+            }
+        }
+    }
+}
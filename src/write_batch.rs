@@ -1,7 +1,8 @@
-use crate::encoder::{encode_key, encode_value};
+use crate::encoder::{decode_key_with, decode_value, encode_key_with, encode_value};
 use crate::ColumnFamilyPy;
 use pyo3::exceptions::PyException;
 use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyList, PyTuple};
 use rocksdb::WriteBatch;
 
 macro_rules! inner_ref {
@@ -39,6 +40,9 @@ pub(crate) struct WriteBatchPy {
     inner: Option<WriteBatch>,
     default_column_family: Option<ColumnFamilyPy>,
     dumps: PyObject,
+    loads: PyObject,
+    key_dumps: Option<PyObject>,
+    key_loads: Option<PyObject>,
     pub(crate) raw_mode: bool,
 }
 
@@ -60,22 +64,82 @@ impl WriteBatchPy {
             inner: Some(WriteBatch::default()),
             default_column_family: None,
             dumps: pickle.getattr(py, "dumps")?,
+            loads: pickle.getattr(py, "loads")?,
+            key_dumps: None,
+            key_loads: None,
             raw_mode,
         })
     }
 
+    /// Create a WriteBatch from its serialized representation (see `data()`).
+    ///
+    /// The updates keep their column family ids, so the batch must be
+    /// written to a db with the same column families.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import Rdict, WriteBatch
+    ///
+    ///         wb = WriteBatch()
+    ///         wb["a"] = 1
+    ///         replayed = WriteBatch.from_data(wb.data())
+    ///
+    ///         db = Rdict("./tmp")
+    ///         db.write(replayed)
+    ///         assert db["a"] == 1
+    ///
+    /// Args:
+    ///     data (bytes): serialized WriteBatch.
+    ///     raw_mode (bool): make sure that this is consistent with the Rdict.
+    ///
+    /// Raises:
+    ///     Exception: if the data is not a valid WriteBatch.
+    #[staticmethod]
+    #[pyo3(signature = (data, raw_mode = false))]
+    pub fn from_data(data: &[u8], raw_mode: bool, py: Python) -> PyResult<Self> {
+        // validate the payload before handing it to RocksDB
+        let records = parse_write_batch(data)?;
+        let count = u32::from_le_bytes(data[8..WRITE_BATCH_HEADER].try_into().unwrap());
+        if count as usize != records.len() {
+            return Err(PyException::new_err(format!(
+                "corrupted WriteBatch data: header count {count} but {} records",
+                records.len()
+            )));
+        }
+        Self::from_write_batch(py, WriteBatch::from_data(data), raw_mode)
+    }
+
     /// change to a custom dumps function
     pub fn set_dumps(&mut self, dumps: PyObject) {
         self.dumps = dumps
     }
 
+    /// change to a custom loads function, used by `iterate(decode=True)`
+    pub fn set_loads(&mut self, loads: PyObject) {
+        self.loads = loads
+    }
+
+    /// change to a custom key dumps function, see `Rdict.set_key_dumps()`
+    ///
+    /// Notes:
+    ///     This must be the key dumps function of the Rdict the batch is written to.
+    pub fn set_key_dumps(&mut self, key_dumps: PyObject) {
+        self.key_dumps = Some(key_dumps)
+    }
+
+    /// change to a custom key loads function, used by `iterate(decode=True)`
+    pub fn set_key_loads(&mut self, key_loads: PyObject) {
+        self.key_loads = Some(key_loads)
+    }
+
     pub fn __len__(&self) -> PyResult<usize> {
         self.len()
     }
 
     pub fn __setitem__(&mut self, key: &Bound<PyAny>, value: &Bound<PyAny>) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
         let value = encode_value(value, &self.dumps, self.raw_mode)?;
         match &self.default_column_family {
             None => inner.put(key, value),
@@ -86,7 +150,7 @@ impl WriteBatchPy {
 
     pub fn __delitem__(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
         match &self.default_column_family {
             None => inner.delete(key),
             Some(cf) => inner.delete_cf(&cf.cf, key),
@@ -115,6 +179,11 @@ impl WriteBatchPy {
         Ok(inner.len())
     }
 
+    /// number of updates in the batch, same as `len()`
+    pub fn count(&self) -> PyResult<usize> {
+        self.len()
+    }
+
     /// Return WriteBatch serialized size (in bytes).
     pub fn size_in_bytes(&self) -> PyResult<usize> {
         let inner = inner_ref!(self)?;
@@ -127,6 +196,72 @@ impl WriteBatchPy {
         Ok(inner.is_empty())
     }
 
+    /// Return the serialized representation of the batch.
+    ///
+    /// Notes:
+    ///     The format is the internal RocksDB WriteBatch format:
+    ///     8 bytes of sequence number, 4 bytes of count, followed by the records.
+    pub fn data(&self, py: Python) -> PyResult<PyObject> {
+        let inner = inner_ref!(self)?;
+        Ok(PyBytes::new_bound(py, inner.data()).to_object(py))
+    }
+
+    /// List the updates in the batch, in the order they were added.
+    ///
+    /// Each update is a tuple `(op, column_family_id, key, value)`, where `op`
+    /// is one of `"put"`, `"delete"`, `"single_delete"`, `"delete_range"`,
+    /// `"merge"` and `"put_entity"`. The `value` is None for deletions,
+    /// and is the end key for `"delete_range"`.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import WriteBatch
+    ///
+    ///         wb = WriteBatch()
+    ///         wb["a"] = 1
+    ///         del wb["b"]
+    ///         assert wb.iterate(decode=True) == [("put", 0, "a", 1), ("delete", 0, "b", None)]
+    ///
+    /// Args:
+    ///     decode: decode keys and values like `Rdict` does (with the function
+    ///         set by `set_loads`), otherwise return the raw encoded bytes.
+    ///         Wide-column entities and merge operands are never decoded,
+    ///         since the encoding of the operands depends on the merge operator.
+    #[pyo3(signature = (decode = false))]
+    pub fn iterate(&self, decode: bool, py: Python) -> PyResult<PyObject> {
+        let inner = inner_ref!(self)?;
+        let records = parse_write_batch(inner.data())?;
+        let result = PyList::empty_bound(py);
+        for record in records {
+            let convert = |bytes: Option<&[u8]>, is_key: bool| -> PyResult<PyObject> {
+                match bytes {
+                    None => Ok(py.None()),
+                    Some(bytes) if decode && is_key => {
+                        let key_loads = self.key_loads.as_ref();
+                        decode_key_with(py, bytes, &self.loads, key_loads, self.raw_mode)
+                    }
+                    Some(bytes) if decode && !matches!(record.op, "put_entity" | "merge") => {
+                        decode_value(py, bytes, &self.loads, self.raw_mode)
+                    }
+                    Some(bytes) => Ok(PyBytes::new_bound(py, bytes).to_object(py)),
+                }
+            };
+            // the end key of delete_range is decoded as a key
+            let value_is_key = record.op == "delete_range";
+            result.append(PyTuple::new_bound(
+                py,
+                [
+                    record.op.to_object(py),
+                    record.column_family_id.to_object(py),
+                    convert(Some(record.key), true)?,
+                    convert(record.value, value_is_key)?,
+                ],
+            ))?;
+        }
+        Ok(result.to_object(py))
+    }
+
     /// Insert a value into the database under the given key.
     ///
     /// Args:
@@ -139,7 +274,7 @@ impl WriteBatchPy {
         column_family: Option<ColumnFamilyPy>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
         let value = encode_value(value, &self.dumps, self.raw_mode)?;
         match column_family {
             Some(cf) => inner.put_cf(&cf.cf, key, value),
@@ -148,6 +283,33 @@ impl WriteBatchPy {
         Ok(())
     }
 
+    /// Merge a value into the existing value of a key, with the merge
+    /// operator set by `Options.set_merge_operator`.
+    ///
+    /// Notes:
+    ///     Unlike `Rdict.merge()`, the value is not checked against the merge
+    ///     operator of the db. Values that the merge operator cannot merge
+    ///     are ignored, e.g. a `str` counts as 0 with `MergeOperator.uint64_add()`.
+    ///
+    /// Args:
+    ///     column_family: override the default column family set by set_default_column_family
+    #[pyo3(signature = (key, value, column_family = None))]
+    pub fn merge(
+        &mut self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        column_family: Option<ColumnFamilyPy>,
+    ) -> PyResult<()> {
+        let inner = inner_mut!(self)?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        let value = encode_value(value, &self.dumps, self.raw_mode)?;
+        match column_family {
+            Some(cf) => inner.merge_cf(&cf.cf, key, value),
+            None => inner.merge(key, value),
+        }
+        Ok(())
+    }
+
     /// Insert a wide-column.
     ///
     /// The length of `names` and `values` must be the same.
@@ -165,7 +327,7 @@ impl WriteBatchPy {
         values: Vec<Bound<PyAny>>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
         let cf = if let Some(cf) = &self.default_column_family {
             cf
         } else {
@@ -202,7 +364,7 @@ impl WriteBatchPy {
         column_family: Option<ColumnFamilyPy>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let key = encode_key(key, self.raw_mode)?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
         match column_family {
             Some(cf) => inner.delete_cf(&cf.cf, key),
             None => inner.delete(key),
@@ -210,6 +372,31 @@ impl WriteBatchPy {
         Ok(())
     }
 
+    /// Removes the database entry for key, assuming it was written exactly once.
+    ///
+    /// Notes:
+    ///     SingleDelete only works if the key has been put exactly once since the
+    ///     last deletion, and has not been overwritten or merged. Otherwise the
+    ///     behavior is undefined. It allows RocksDB to drop the tombstone as soon as
+    ///     it meets the put during compaction.
+    ///
+    /// Args:
+    ///     column_family: override the default column family set by set_default_column_family
+    #[pyo3(signature = (key, column_family = None))]
+    pub fn single_delete(
+        &mut self,
+        key: &Bound<PyAny>,
+        column_family: Option<ColumnFamilyPy>,
+    ) -> PyResult<()> {
+        let inner = inner_mut!(self)?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        match column_family {
+            Some(cf) => inner.single_delete_cf(&cf.cf, key),
+            None => inner.single_delete(key),
+        }
+        Ok(())
+    }
+
     /// Remove database entries in column family from start key to end key.
     ///
     /// Notes:
@@ -229,8 +416,8 @@ impl WriteBatchPy {
         column_family: Option<ColumnFamilyPy>,
     ) -> PyResult<()> {
         let inner = inner_mut!(self)?;
-        let from = encode_key(begin, self.raw_mode)?;
-        let to = encode_key(end, self.raw_mode)?;
+        let from = encode_key_with(begin, self.key_dumps.as_ref(), self.raw_mode)?;
+        let to = encode_key_with(end, self.key_dumps.as_ref(), self.raw_mode)?;
         match column_family {
             Some(cf) => inner.delete_range_cf(&cf.cf, from, to),
             None => inner.delete_range(from, to),
@@ -238,6 +425,50 @@ impl WriteBatchPy {
         Ok(())
     }
 
+    /// Record the state of the batch, to be restored by `rollback_to_save_point()`.
+    ///
+    /// Save points can be nested: each call pushes a new save point on a stack.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import WriteBatch
+    ///
+    ///         wb = WriteBatch()
+    ///         wb["a"] = 1
+    ///         wb.set_save_point()
+    ///         wb["b"] = 2
+    ///         wb.rollback_to_save_point()
+    ///         assert len(wb) == 1
+    pub fn set_save_point(&mut self) -> PyResult<()> {
+        let inner = inner_mut!(self)?;
+        inner.set_save_point();
+        Ok(())
+    }
+
+    /// Remove all updates added since the most recent save point,
+    /// and remove that save point.
+    ///
+    /// Raises:
+    ///     Exception: if there is no save point.
+    pub fn rollback_to_save_point(&mut self) -> PyResult<()> {
+        let inner = inner_mut!(self)?;
+        inner
+            .rollback_to_save_point()
+            .map_err(|e| save_point_error("rollback_to_save_point", e))
+    }
+
+    /// Remove the most recent save point, keeping the updates added since.
+    ///
+    /// Raises:
+    ///     Exception: if there is no save point.
+    pub fn pop_save_point(&mut self) -> PyResult<()> {
+        let inner = inner_mut!(self)?;
+        inner
+            .pop_save_point()
+            .map_err(|e| save_point_error("pop_save_point", e))
+    }
+
     /// Clear all updates buffered in this batch.
     pub fn clear(&mut self) -> PyResult<()> {
         let inner = inner_mut!(self)?;
@@ -247,6 +478,16 @@ impl WriteBatchPy {
 }
 
 impl WriteBatchPy {
+    pub(crate) fn from_write_batch(
+        py: Python,
+        batch: WriteBatch,
+        raw_mode: bool,
+    ) -> PyResult<Self> {
+        let mut write_batch = Self::default(py, raw_mode)?;
+        write_batch.inner = Some(batch);
+        Ok(write_batch)
+    }
+
     #[inline]
     pub(crate) fn consume(&mut self) -> PyResult<WriteBatch> {
         if let Some(inner) = self.inner.take() {
@@ -259,3 +500,131 @@ impl WriteBatchPy {
         }
     }
 }
+
+fn save_point_error(method: &str, e: rocksdb::Error) -> PyErr {
+    if e.kind() == rocksdb::ErrorKind::NotFound {
+        PyException::new_err(format!(
+            "{method}() called without save point, call set_save_point() first"
+        ))
+    } else {
+        PyException::new_err(e.to_string())
+    }
+}
+
+// WriteBatch record tags, see `db/dbformat.h` of RocksDB.
+const TYPE_DELETION: u8 = 0x0;
+const TYPE_VALUE: u8 = 0x1;
+const TYPE_MERGE: u8 = 0x2;
+const TYPE_LOG_DATA: u8 = 0x3;
+const TYPE_CF_DELETION: u8 = 0x4;
+const TYPE_CF_VALUE: u8 = 0x5;
+const TYPE_CF_MERGE: u8 = 0x6;
+const TYPE_SINGLE_DELETION: u8 = 0x7;
+const TYPE_CF_SINGLE_DELETION: u8 = 0x8;
+const TYPE_NOOP: u8 = 0xD;
+const TYPE_CF_RANGE_DELETION: u8 = 0xE;
+const TYPE_RANGE_DELETION: u8 = 0xF;
+const TYPE_WIDE_COLUMN_ENTITY: u8 = 0x16;
+const TYPE_CF_WIDE_COLUMN_ENTITY: u8 = 0x17;
+
+/// header of the WriteBatch format: 8 bytes sequence number + 4 bytes count.
+const WRITE_BATCH_HEADER: usize = 12;
+
+struct WriteBatchRecord<'a> {
+    op: &'static str,
+    column_family_id: u32,
+    key: &'a [u8],
+    value: Option<&'a [u8]>,
+}
+
+struct WriteBatchReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> WriteBatchReader<'a> {
+    fn corrupted() -> PyErr {
+        PyException::new_err("corrupted WriteBatch data")
+    }
+
+    fn byte(&mut self) -> PyResult<u8> {
+        let (first, rest) = self.data.split_first().ok_or_else(Self::corrupted)?;
+        self.data = rest;
+        Ok(*first)
+    }
+
+    fn varint32(&mut self) -> PyResult<u32> {
+        let mut result: u32 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            result |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(Self::corrupted())
+    }
+
+    fn slice(&mut self) -> PyResult<&'a [u8]> {
+        let len = self.varint32()? as usize;
+        if len > self.data.len() {
+            return Err(Self::corrupted());
+        }
+        let (slice, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(slice)
+    }
+}
+
+/// Parse the serialized WriteBatch, like the `WriteBatch::Handler` of RocksDB.
+fn parse_write_batch(data: &[u8]) -> PyResult<Vec<WriteBatchRecord>> {
+    if data.len() < WRITE_BATCH_HEADER {
+        return Err(WriteBatchReader::corrupted());
+    }
+    let mut reader = WriteBatchReader {
+        data: &data[WRITE_BATCH_HEADER..],
+    };
+    let mut records = Vec::new();
+    while !reader.data.is_empty() {
+        let tag = reader.byte()?;
+        let column_family_id = match tag {
+            TYPE_CF_DELETION
+            | TYPE_CF_VALUE
+            | TYPE_CF_MERGE
+            | TYPE_CF_SINGLE_DELETION
+            | TYPE_CF_RANGE_DELETION
+            | TYPE_CF_WIDE_COLUMN_ENTITY => reader.varint32()?,
+            _ => 0,
+        };
+        let (op, has_value) = match tag {
+            TYPE_VALUE | TYPE_CF_VALUE => ("put", true),
+            TYPE_DELETION | TYPE_CF_DELETION => ("delete", false),
+            TYPE_SINGLE_DELETION | TYPE_CF_SINGLE_DELETION => ("single_delete", false),
+            TYPE_RANGE_DELETION | TYPE_CF_RANGE_DELETION => ("delete_range", true),
+            TYPE_MERGE | TYPE_CF_MERGE => ("merge", true),
+            TYPE_WIDE_COLUMN_ENTITY | TYPE_CF_WIDE_COLUMN_ENTITY => ("put_entity", true),
+            TYPE_LOG_DATA => {
+                reader.slice()?;
+                continue;
+            }
+            TYPE_NOOP => continue,
+            _ => {
+                return Err(PyException::new_err(format!(
+                    "unsupported WriteBatch record type {tag:#x}"
+                )))
+            }
+        };
+        let key = reader.slice()?;
+        let value = if has_value {
+            Some(reader.slice()?)
+        } else {
+            None
+        };
+        records.push(WriteBatchRecord {
+            op,
+            column_family_id,
+            key,
+            value,
+        });
+    }
+    Ok(records)
+}
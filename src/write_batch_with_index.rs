@@ -0,0 +1,587 @@
+use crate::db_reference::DbReference;
+use crate::encoder::{decode_value, encode_key_with, encode_value};
+use crate::options::{check_merge_operand, WriteOpt};
+use crate::util::error_message;
+use crate::{ffi_try, ffi_try_impl, ColumnFamilyPy, Rdict, RdictIter, ReadOptionsPy};
+use libc::{c_char, c_uchar, c_void, size_t};
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use rocksdb::{AsColumnFamilyRef, DEFAULT_COLUMN_FAMILY_NAME};
+use std::ptr::null_mut;
+use std::sync::Arc;
+
+/// WriteBatchWithIndex class. A WriteBatch that keeps an index of its
+/// updates, so that they can be read before being written to the db.
+///
+/// Use db.write() to ingest WriteBatchWithIndex. Unlike `WriteBatch`,
+/// the batch is not consumed by `db.write()`, call `clear()` to reuse it.
+///
+/// Example:
+///     ::
+///
+///         from rocksdict import Rdict, WriteBatchWithIndex
+///
+///         db = Rdict("./tmp")
+///         db["a"] = 1
+///         wbwi = WriteBatchWithIndex()
+///         wbwi["b"] = 2
+///
+///         assert wbwi.get_from_batch("a") is None
+///         assert wbwi.get_from_batch_and_db(db, "a") == 1
+///         assert [k for k, _ in wbwi.iter_with_base(db).items()] == ["a", "b"]
+///
+///         db.write(wbwi)
+///         assert db["b"] == 2
+///
+/// Args:
+///     raw_mode (bool): make sure that this is consistent with the Rdict.
+///     overwrite_key (bool): if True, a later update of a key overwrites the
+///         index entry of earlier updates of the same key, so that the
+///         iterators only return the latest update.
+#[pyclass(name = "WriteBatchWithIndex")]
+pub(crate) struct WriteBatchWithIndexPy {
+    pub(crate) inner: *mut librocksdb_sys::rocksdb_writebatch_wi_t,
+    default_column_family: Option<ColumnFamilyPy>,
+    dumps: PyObject,
+    loads: PyObject,
+    key_dumps: Option<PyObject>,
+    pub(crate) raw_mode: bool,
+    /// shared with the iterators created by `iter_with_base()`, which read
+    /// the index of the batch, so the batch is not modified while they live
+    iterators: Arc<()>,
+}
+
+/// Held by the iterators created by `iter_with_base()`.
+pub(crate) struct BatchIteratorRef {
+    /// keeps the batch alive
+    _write_batch: PyObject,
+    _iterators: Arc<()>,
+}
+
+unsafe impl Send for WriteBatchWithIndexPy {}
+// the batch is only modified through `&mut self`
+unsafe impl Sync for WriteBatchWithIndexPy {}
+
+/// DB options required by the C API to read from a batch.
+struct DbOptions(*mut librocksdb_sys::rocksdb_options_t);
+
+impl Drop for DbOptions {
+    fn drop(&mut self) {
+        unsafe { librocksdb_sys::rocksdb_options_destroy(self.0) }
+    }
+}
+
+#[pymethods]
+impl WriteBatchWithIndexPy {
+    #[new]
+    #[pyo3(signature = (raw_mode = false, overwrite_key = false))]
+    pub fn new(py: Python, raw_mode: bool, overwrite_key: bool) -> PyResult<Self> {
+        let pickle = PyModule::import_bound(py, "pickle")?.to_object(py);
+        Ok(WriteBatchWithIndexPy {
+            inner: unsafe {
+                librocksdb_sys::rocksdb_writebatch_wi_create(0, overwrite_key as c_uchar)
+            },
+            default_column_family: None,
+            dumps: pickle.getattr(py, "dumps")?,
+            loads: pickle.getattr(py, "loads")?,
+            key_dumps: None,
+            raw_mode,
+            iterators: Arc::new(()),
+        })
+    }
+
+    /// change to a custom dumps function
+    pub fn set_dumps(&mut self, dumps: PyObject) {
+        self.dumps = dumps
+    }
+
+    /// change to a custom loads function, used by `get_from_batch()`
+    pub fn set_loads(&mut self, loads: PyObject) {
+        self.loads = loads
+    }
+
+    /// change to a custom key dumps function, see `Rdict.set_key_dumps()`
+    ///
+    /// Notes:
+    ///     This must be the key dumps function of the Rdict the batch is used with.
+    pub fn set_key_dumps(&mut self, key_dumps: PyObject) {
+        self.key_dumps = Some(key_dumps)
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.len()
+    }
+
+    pub fn __setitem__(&mut self, key: &Bound<PyAny>, value: &Bound<PyAny>) -> PyResult<()> {
+        let column_family = self.default_column_family.clone();
+        self.put(key, value, column_family)
+    }
+
+    pub fn __delitem__(&mut self, key: &Bound<PyAny>) -> PyResult<()> {
+        let column_family = self.default_column_family.clone();
+        self.delete(key, column_family)
+    }
+
+    /// Set the default item for `a[i] = j` and `del a[i]` syntax.
+    ///
+    /// Args:
+    ///     - column_family (ColumnFamily | None): column family descriptor or None (for default family).
+    #[pyo3(signature = (column_family=None))]
+    pub fn set_default_column_family(&mut self, column_family: Option<ColumnFamilyPy>) {
+        self.default_column_family = column_family;
+    }
+
+    /// number of updates in the batch
+    pub fn len(&self) -> usize {
+        unsafe { librocksdb_sys::rocksdb_writebatch_wi_count(self.inner) as usize }
+    }
+
+    /// Check whether the batch is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert a value into the database under the given key.
+    ///
+    /// Args:
+    ///     column_family: override the default column family set by set_default_column_family
+    #[pyo3(signature = (key, value, column_family = None))]
+    pub fn put(
+        &mut self,
+        key: &Bound<PyAny>,
+        value: &Bound<PyAny>,
+        column_family: Option<ColumnFamilyPy>,
+    ) -> PyResult<()> {
+        self.check_no_iterators("modify")?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        let value = encode_value(value, &self.dumps, self.raw_mode)?;
+        unsafe {
+            match column_family {
+                Some(cf) => librocksdb_sys::rocksdb_writebatch_wi_put_cf(
+                    self.inner,
+                    cf.cf.inner(),
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    value.as_ptr() as *const c_char,
+                    value.len() as size_t,
+                ),
+                None => librocksdb_sys::rocksdb_writebatch_wi_put(
+                    self.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    value.as_ptr() as *const c_char,
+                    value.len() as size_t,
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the database entry for key. Does nothing if the key was not found.
+    ///
+    /// Args:
+    ///     column_family: override the default column family set by set_default_column_family
+    #[pyo3(signature = (key, column_family = None))]
+    pub fn delete(
+        &mut self,
+        key: &Bound<PyAny>,
+        column_family: Option<ColumnFamilyPy>,
+    ) -> PyResult<()> {
+        self.check_no_iterators("modify")?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        unsafe {
+            match column_family {
+                Some(cf) => librocksdb_sys::rocksdb_writebatch_wi_delete_cf(
+                    self.inner,
+                    cf.cf.inner(),
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                ),
+                None => librocksdb_sys::rocksdb_writebatch_wi_delete(
+                    self.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge a value into the existing value of a key of `db`.
+    ///
+    /// The value is checked against the merge operator of `db` and rejected
+    /// like `Rdict.merge()` does, the update is added to the column family
+    /// of `db`.
+    ///
+    /// Args:
+    ///     db: the Rdict (or column family Rdict) the batch will be written to.
+    ///     key: the key.
+    ///     value: the value to merge.
+    ///
+    /// Raises:
+    ///     Exception: if `db` has no merge operator or has a value transform.
+    ///     TypeError: if the merge operator cannot merge `value`.
+    pub fn merge(&mut self, db: &Rdict, key: &Bound<PyAny>, value: &Bound<PyAny>) -> PyResult<()> {
+        self.check_no_iterators("modify")?;
+        self.check_raw_mode(db)?;
+        check_no_value_transform(db, "merge")?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        let value = encode_value(value, &self.dumps, self.raw_mode)?;
+        check_merge_operand(
+            db.merge_operators
+                .read()
+                .unwrap()
+                .get(&db.column_family_name),
+            self.raw_mode,
+            &value[..],
+        )?;
+        let cf = match &db.column_family {
+            None => db.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?.cf,
+            Some(cf) => cf.clone(),
+        };
+        unsafe {
+            librocksdb_sys::rocksdb_writebatch_wi_merge_cf(
+                self.inner,
+                cf.inner(),
+                key.as_ptr() as *const c_char,
+                key.len() as size_t,
+                value.as_ptr() as *const c_char,
+                value.len() as size_t,
+            )
+        }
+        Ok(())
+    }
+
+    /// Removes the database entry for key, assuming it was written exactly once.
+    ///
+    /// Notes:
+    ///     SingleDelete only works if the key has been put exactly once since the
+    ///     last deletion, and has not been overwritten or merged. Otherwise the
+    ///     behavior is undefined.
+    ///
+    /// Args:
+    ///     column_family: override the default column family set by set_default_column_family
+    #[pyo3(signature = (key, column_family = None))]
+    pub fn single_delete(
+        &mut self,
+        key: &Bound<PyAny>,
+        column_family: Option<ColumnFamilyPy>,
+    ) -> PyResult<()> {
+        self.check_no_iterators("modify")?;
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        unsafe {
+            match column_family {
+                Some(cf) => librocksdb_sys::rocksdb_writebatch_wi_singledelete_cf(
+                    self.inner,
+                    cf.cf.inner(),
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                ),
+                None => librocksdb_sys::rocksdb_writebatch_wi_singledelete(
+                    self.inner,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Not supported by RocksDB for WriteBatchWithIndex, which cannot index
+    /// range deletions. Use `WriteBatch.delete_range()` or `Rdict.delete_range()`.
+    ///
+    /// Raises:
+    ///     Exception: always.
+    #[pyo3(signature = (begin, end, column_family = None))]
+    #[allow(unused_variables)]
+    pub fn delete_range(
+        &mut self,
+        begin: &Bound<PyAny>,
+        end: &Bound<PyAny>,
+        column_family: Option<ColumnFamilyPy>,
+    ) -> PyResult<()> {
+        Err(PyException::new_err(
+            "delete_range() is not supported by WriteBatchWithIndex, \
+            use WriteBatch or Rdict.delete_range()",
+        ))
+    }
+
+    /// Record the state of the batch, to be restored by `rollback_to_save_point()`.
+    ///
+    /// Save points can be nested: each call pushes a new save point on a stack.
+    ///
+    /// Example:
+    ///     ::
+    ///
+    ///         from rocksdict import WriteBatchWithIndex
+    ///
+    ///         wbwi = WriteBatchWithIndex()
+    ///         wbwi["a"] = 1
+    ///         wbwi.set_save_point()
+    ///         wbwi["b"] = 2
+    ///         wbwi.rollback_to_save_point()
+    ///         assert wbwi.get_from_batch("b") is None
+    pub fn set_save_point(&mut self) -> PyResult<()> {
+        self.check_no_iterators("modify")?;
+        unsafe { librocksdb_sys::rocksdb_writebatch_wi_set_save_point(self.inner) }
+        Ok(())
+    }
+
+    /// Remove all updates added since the most recent save point,
+    /// and remove that save point.
+    ///
+    /// Raises:
+    ///     Exception: if there is no save point.
+    pub fn rollback_to_save_point(&mut self) -> PyResult<()> {
+        self.check_no_iterators("modify")?;
+        let mut err: *mut c_char = null_mut();
+        unsafe {
+            librocksdb_sys::rocksdb_writebatch_wi_rollback_to_save_point(self.inner, &mut err)
+        }
+        if err.is_null() {
+            return Ok(());
+        }
+        let message = error_message(err);
+        if message.starts_with("NotFound") {
+            Err(PyException::new_err(
+                "rollback_to_save_point() called without save point, \
+                call set_save_point() first",
+            ))
+        } else {
+            Err(PyException::new_err(message))
+        }
+    }
+
+    /// Clear all updates buffered in this batch.
+    ///
+    /// Raises an exception if iterators created by `iter_with_base()`
+    /// are still alive.
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.check_no_iterators("clear")?;
+        unsafe { librocksdb_sys::rocksdb_writebatch_wi_clear(self.inner) }
+        Ok(())
+    }
+
+    /// Read a key from the batch only, ignoring the database.
+    ///
+    /// Args:
+    ///     key: the key.
+    ///     default: returned if the key is not in the batch, or deleted in the batch.
+    ///     column_family: override the default column family set by set_default_column_family
+    #[pyo3(signature = (key, default = None, column_family = None))]
+    pub fn get_from_batch(
+        &self,
+        key: &Bound<PyAny>,
+        default: Option<PyObject>,
+        column_family: Option<ColumnFamilyPy>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        let column_family = column_family.or_else(|| self.default_column_family.clone());
+        let options = DbOptions(unsafe { librocksdb_sys::rocksdb_options_create() });
+        let mut val_len: size_t = 0;
+        let val_ptr = unsafe {
+            match column_family {
+                Some(cf) => ffi_try!(librocksdb_sys::rocksdb_writebatch_wi_get_from_batch_cf(
+                    self.inner,
+                    options.0,
+                    cf.cf.inner(),
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                )),
+                None => ffi_try!(librocksdb_sys::rocksdb_writebatch_wi_get_from_batch(
+                    self.inner,
+                    options.0,
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                )),
+            }
+        };
+        decode_owned_value(py, val_ptr, val_len, &self.loads, self.raw_mode, default)
+    }
+
+    /// Read a key from the batch, falling back to the database.
+    ///
+    /// Updates in the batch take precedence over the database,
+    /// the value is decoded with the `loads` function of `db`.
+    ///
+    /// Args:
+    ///     db: the Rdict (or column family Rdict) to read from.
+    ///     key: the key.
+    ///     default: returned if the key is found neither in the batch nor in the db.
+    ///     read_opt: override the read options of the db.
+    ///
+    /// Raises:
+    ///     Exception: if `db` has a value transform, which the updates
+    ///         of the batch do not go through.
+    #[pyo3(signature = (db, key, default = None, read_opt = None))]
+    pub fn get_from_batch_and_db(
+        &self,
+        db: &Rdict,
+        key: &Bound<PyAny>,
+        default: Option<PyObject>,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<PyObject> {
+        self.check_raw_mode(db)?;
+        check_no_value_transform(db, "get_from_batch_and_db")?;
+        let db_inner = db.get_db()?.inner();
+        let key = encode_key_with(key, self.key_dumps.as_ref(), self.raw_mode)?;
+        let read_opt = match read_opt {
+            None => db
+                .read_opt_py
+                .to_read_opt(self.raw_mode, db.key_dumps.as_ref(), py)?,
+            Some(opt) => opt.to_read_opt(self.raw_mode, db.key_dumps.as_ref(), py)?,
+        };
+        let cf = match &db.column_family {
+            None => db.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?.cf,
+            Some(cf) => cf.clone(),
+        };
+        let mut val_len: size_t = 0;
+        let val_ptr = unsafe {
+            ffi_try!(
+                librocksdb_sys::rocksdb_writebatch_wi_get_from_batch_and_db_cf(
+                    self.inner,
+                    db_inner,
+                    read_opt.inner,
+                    cf.inner(),
+                    key.as_ptr() as *const c_char,
+                    key.len() as size_t,
+                    &mut val_len,
+                )
+            )
+        };
+        decode_owned_value(py, val_ptr, val_len, &db.loads, self.raw_mode, default)
+    }
+
+    /// Create an iterator over the database merged with the updates in this batch.
+    ///
+    /// Notes:
+    ///     The batch cannot be modified or cleared while the iterator is
+    ///     alive, `del` the iterator first.
+    ///
+    /// Args:
+    ///     db: the Rdict (or column family Rdict) to iterate on.
+    ///     read_opt: ReadOptions, must have the same `raw_mode` argument.
+    ///
+    /// Returns: Reversible
+    ///
+    /// Raises:
+    ///     Exception: if `db` has a value transform.
+    #[pyo3(signature = (db, read_opt = None))]
+    pub fn iter_with_base(
+        slf: &Bound<Self>,
+        db: &Rdict,
+        read_opt: Option<&ReadOptionsPy>,
+        py: Python,
+    ) -> PyResult<RdictIter> {
+        let this = slf.borrow();
+        this.check_raw_mode(db)?;
+        check_no_value_transform(db, "iter_with_base")?;
+        let read_opt: ReadOptionsPy = match read_opt {
+            None => ReadOptionsPy::default(py)?,
+            Some(opt) => opt.clone(),
+        };
+        let cf = match &db.column_family {
+            None => db.get_column_family_handle(DEFAULT_COLUMN_FAMILY_NAME)?.cf,
+            Some(cf) => cf.clone(),
+        };
+        let mut iter = RdictIter::new(
+            &db.db,
+            &Some(cf.clone()),
+            read_opt,
+            &db.loads,
+            db.key_dumps.as_ref(),
+            db.key_loads.as_ref(),
+            this.raw_mode,
+            py,
+        )?;
+        // the merged iterator takes the ownership of the base iterator
+        iter.inner = unsafe {
+            librocksdb_sys::rocksdb_writebatch_wi_create_iterator_with_base_cf(
+                this.inner,
+                iter.inner,
+                cf.inner(),
+            )
+        };
+        iter.write_batch = Some(BatchIteratorRef {
+            _write_batch: slf.clone().into_any().unbind(),
+            _iterators: this.iterators.clone(),
+        });
+        Ok(iter)
+    }
+}
+
+impl WriteBatchWithIndexPy {
+    /// Write the batch to `db`, does not need the GIL.
+    pub(crate) fn write_to(&self, db: &DbReference, write_opt: &WriteOpt) -> PyResult<()> {
+        unsafe {
+            ffi_try!(librocksdb_sys::rocksdb_write_writebatch_wi(
+                db.inner(),
+                write_opt.0,
+                self.inner,
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_no_iterators(&self, operation: &str) -> PyResult<()> {
+        if Arc::strong_count(&self.iterators) > 1 {
+            return Err(PyException::new_err(format!(
+                "cannot {operation} WriteBatchWithIndex while iterators \
+                created by iter_with_base() are alive"
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_raw_mode(&self, db: &Rdict) -> PyResult<()> {
+        if db.opt_py.raw_mode != self.raw_mode {
+            return Err(PyException::new_err(format!(
+                "must set raw_mode={} for WriteBatchWithIndex",
+                db.opt_py.raw_mode
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// the updates of the batch are not transformed, so they cannot be
+/// mixed with the values of a db with a value transform
+fn check_no_value_transform(db: &Rdict, method: &str) -> PyResult<()> {
+    if db.value_transform.is_some() {
+        return Err(PyException::new_err(format!(
+            "{method}() is not supported with a value transform"
+        )));
+    }
+    Ok(())
+}
+
+/// decode and free a value returned by the C API
+fn decode_owned_value(
+    py: Python,
+    val_ptr: *mut c_char,
+    val_len: size_t,
+    loads: &PyObject,
+    raw_mode: bool,
+    default: Option<PyObject>,
+) -> PyResult<PyObject> {
+    if val_ptr.is_null() {
+        return Ok(default.unwrap_or_else(|| py.None()));
+    }
+    let value = unsafe { std::slice::from_raw_parts(val_ptr as *const u8, val_len) };
+    let result = decode_value(py, value, loads, raw_mode);
+    unsafe { librocksdb_sys::rocksdb_free(val_ptr as *mut c_void) };
+    result
+}
+
+impl Drop for WriteBatchWithIndexPy {
+    fn drop(&mut self) {
+        unsafe {
+            librocksdb_sys::rocksdb_writebatch_wi_destroy(self.inner);
+        }
+        self.inner = null_mut();
+    }
+}
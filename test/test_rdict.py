@@ -8,15 +8,66 @@ from rocksdict import (
     CuckooTableOptions,
     DbClosedError,
     WriteBatch,
-    Checkpoint
+    WriteBatchWithIndex,
+    Checkpoint,
+    Cache,
+    BlockBasedOptions,
+    ChecksumType,
+    FileChecksumGenFactory,
+    BlockBasedIndexType,
+    RateLimiter,
+    WriteBufferManager,
+    Env,
+    DBPath,
+    MergeOperator,
+    DBCompressionType,
+    UniversalCompactionStopStyle,
+    UniversalCompactOptions,
+    MemtableFactory,
+    FifoCompactOptions,
+    DBCompactionStyle,
+    DBRecoveryMode,
+    ReadOptions,
+    WriteOptions,
+    SstFileWriter,
+    IngestExternalFileOptions,
+    Statistics,
+    PerfContext,
+    StatsLevel,
+    LogLevel,
+    encode_int_key,
+    decode_int_key,
+    encode_float_key,
+    decode_float_key,
+    encode_key,
+    decode_key,
+    encode_value,
+    decode_value,
 )
 from random import randint, random, getrandbits
 import os
+import re
 import gc
+import operator
 import sys
 import platform
+import time
+import uuid
+import zlib
 from json import loads, dumps
+from datetime import datetime, timedelta, timezone
 from subprocess import Popen
+from threading import Thread
+
+try:
+    import numpy as np
+except ImportError:
+    np = None
+
+try:
+    import msgpack
+except ImportError:
+    msgpack = None
 
 
 TEST_INT_RANGE_UPPER = 999999
@@ -250,6 +301,30 @@ class TestIterInt(unittest.TestCase):
             [k for k in self.test_dict.keys(from_key=key, backwards=True)], ref_list
         )
 
+    def test_limit(self):
+        assert self.ref_dict is not None
+        assert self.test_dict is not None
+        ref_list = sorted(self.ref_dict.items())
+        self.assertEqual(list(self.test_dict.items(limit=10)), ref_list[:10])
+        self.assertEqual(list(self.test_dict.keys(limit=0)), [])
+        self.assertEqual(list(self.test_dict.values(backwards=True, limit=3)),
+                         [v for _, v in ref_list[-3:]][::-1])
+        # paginate: each page starts after the last key of the previous one
+        pages = []
+        from_key = None
+        while True:
+            page = list(self.test_dict.keys(from_key=from_key, limit=1001))
+            if pages:
+                page = page[1:]
+            if not page:
+                break
+            pages.append(page)
+            from_key = page[-1]
+        self.assertEqual(sum(pages, []), [k for k, _ in ref_list])
+        snapshot = self.test_dict.snapshot()
+        self.assertEqual(len(list(snapshot.keys(limit=5))), 5)
+        del snapshot
+
     @classmethod
     def tearDownClass(cls):
         assert cls.test_dict is not None
@@ -341,7 +416,6 @@ class TestBigInt(unittest.TestCase):
     def setUpClass(cls) -> None:
         cls.opt = Options()
         cls.opt.create_if_missing(True)
-        cls.opt.set_plain_table_factory(PlainTableFactoryOptions())
         cls.opt.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
         cls.test_dict = Rdict(cls.path, cls.opt)
 
@@ -359,6 +433,36 @@ class TestBigInt(unittest.TestCase):
         del self.test_dict[key]
         self.assertFalse(key in self.test_dict)
 
+    def test_big_int_round_trip(self):
+        assert self.test_dict is not None
+        keys = [-(2**200) - 1, -(2**200), -(2**64), -1, 0, 2**63, 2**200, 2**200 + 1]
+        for key in keys:
+            self.test_dict[key] = -key
+        self.assertEqual(self.test_dict.get(keys), [-key for key in keys])
+        self.assertEqual(decode_key(encode_key(2**200)), 2**200)
+        for key in keys:
+            del self.test_dict[key]
+
+    def test_big_int_write_batch_and_sst(self):
+        assert self.test_dict is not None
+        wb = WriteBatch()
+        wb[2**200] = -(2**200)
+        self.test_dict.write(wb)
+        self.assertEqual(self.test_dict[2**200], -(2**200))
+
+        sst_path = "./temp_big_int.sst"
+        writer = SstFileWriter(self.opt)
+        writer.open(sst_path)
+        writer[-(2**201)] = 2**201
+        writer[2**201] = -(2**201)
+        writer.finish()
+        self.test_dict.ingest_external_file([sst_path])
+        os.remove(sst_path)
+        self.assertEqual(self.test_dict[-(2**201)], 2**201)
+        self.assertEqual(self.test_dict[2**201], -(2**201))
+        for key in [2**200, -(2**201), 2**201]:
+            del self.test_dict[key]
+
     @classmethod
     def tearDownClass(cls):
         assert cls.test_dict is not None
@@ -810,6 +914,30 @@ class TestColumnFamiliesDefaultOpts(unittest.TestCase):
         Rdict.destroy(cls.path)
 
 
+class TestCreateMissingColumnFamilies(unittest.TestCase):
+    path = "./temp_create_missing_cfs"
+
+    def test_open_creates_missing(self):
+        with self.assertRaises(Exception) as ctx:
+            Rdict(self.path, Options(), column_families={"a": Options()})
+        self.assertIn("set_create_missing_column_families", str(ctx.exception))
+
+        opt = Options()
+        opt.set_create_missing_column_families(True)
+        self.assertTrue(opt.to_dict()["create_missing_column_families"])
+        db = Rdict(self.path, opt, column_families={"a": Options(), "b": Options()})
+        db.get_column_family("a")["key"] = "a"
+        db.close()
+
+        # existing column families do not need to be passed again
+        db = Rdict(self.path, opt, column_families={"c": Options()})
+        self.assertEqual(db.get_column_family("a")["key"], "a")
+        db.get_column_family("c")["key"] = "c"
+        db.close()
+        self.assertEqual(sorted(Rdict.list_cf(self.path)), ["a", "b", "c", "default"])
+        Rdict.destroy(self.path)
+
+
 class TestColumnFamiliesDefaultOptsCreate(unittest.TestCase):
     cfs = None
     test_dict = None
@@ -864,7 +992,6 @@ class TestColumnFamiliesCustomOpts(unittest.TestCase):
         plain_opts = Options()
         plain_opts.create_missing_column_families(True)
         plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
-        plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
         cls.cfs = {"string": Options(), "integer": plain_opts}
         cls.test_dict = Rdict(cls.path, options=plain_opts, column_families=cls.cfs)
 
@@ -911,7 +1038,6 @@ class TestColumnFamiliesCustomOptionsCreate(unittest.TestCase):
         cls.plain_opts = Options()
         cls.plain_opts.create_missing_column_families(True)
         cls.plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
-        cls.plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
         cls.test_dict = Rdict(cls.path, options=cls.plain_opts, column_families=cls.cfs)
 
     def test_column_families_custom_options_auto_reopen(self):
@@ -960,7 +1086,6 @@ class TestColumnFamiliesCustomOptionsCreateReopenOverride(unittest.TestCase):
         cls.plain_opts = Options()
         cls.plain_opts.create_missing_column_families(True)
         cls.plain_opts.set_prefix_extractor(SliceTransform.create_max_len_prefix(8))
-        cls.plain_opts.set_plain_table_factory(PlainTableFactoryOptions())
         cls.test_dict = Rdict(cls.path, options=cls.plain_opts)
 
     def test_column_families_custom_options_auto_reopen_override(self):
@@ -1203,5 +1328,4227 @@ class TestCheckpointRaw(unittest.TestCase):
         Rdict.destroy(cls.checkpoint_path, cls.opt)
 
 
+class TestGetPinned(unittest.TestCase):
+    test_dict = None
+    opt = None
+    path = "./temp_get_pinned"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options(raw_mode=True)
+        cls.test_dict = Rdict(cls.path, cls.opt)
+
+    def test_get_pinned(self):
+        assert self.test_dict is not None
+        self.test_dict[b"big"] = b"x" * (1 << 20)
+        self.test_dict[b"empty"] = b""
+        pinned = self.test_dict.get_pinned(b"big")
+        assert pinned is not None
+        self.assertEqual(len(pinned), 1 << 20)
+        with memoryview(pinned) as view:
+            self.assertTrue(view.readonly)
+            self.assertEqual(view, b"x" * (1 << 20))
+        pinned.close()
+        self.assertRaises(Exception, lambda: memoryview(pinned))
+        empty = self.test_dict.get_pinned(b"empty")
+        assert empty is not None
+        self.assertEqual(bytes(empty), b"")
+        self.assertIsNone(self.test_dict.get_pinned(b"missing"))
+
+    def test_close_with_view_alive(self):
+        assert self.test_dict is not None
+        self.test_dict[b"key"] = b"value"
+        pinned = self.test_dict.get_pinned(b"key", ReadOptions())
+        assert pinned is not None
+        view = memoryview(pinned)
+        self.assertRaises(BufferError, pinned.close)
+        view.release()
+        pinned.close()
+
+    def test_context_manager(self):
+        assert self.test_dict is not None
+        self.test_dict[b"with"] = b"value"
+        with self.test_dict.get_pinned(b"with") as pinned, memoryview(pinned) as view:
+            self.assertEqual(view, b"value")
+        self.assertRaises(Exception, lambda: len(pinned))
+        # the view must be released before leaving the block
+        with self.assertRaises(BufferError):
+            with self.test_dict.get_pinned(b"with") as pinned:
+                view = memoryview(pinned)
+        view.release()
+        pinned.close()
+
+    def test_pinned_survives_compaction(self):
+        assert self.test_dict is not None
+        for i in range(1000):
+            self.test_dict[i.to_bytes(4, "big")] = i.to_bytes(4, "big") * 256
+        self.test_dict.flush()
+        pinned = [self.test_dict.get_pinned(i.to_bytes(4, "big")) for i in range(1000)]
+        for i in range(1000):
+            self.test_dict[i.to_bytes(4, "big")] = b"overwritten"
+        self.test_dict.flush()
+        self.test_dict.compact_range(None, None)
+        for i, p in enumerate(pinned):
+            assert p is not None
+            self.assertEqual(bytes(p), i.to_bytes(4, "big") * 256)
+            p.close()
+        self.assertEqual(self.test_dict[(0).to_bytes(4, "big")], b"overwritten")
+
+    def test_not_raw_mode(self):
+        path = "./temp_get_pinned_not_raw"
+        db = Rdict(path)
+        db["key"] = "value"
+        self.assertRaises(Exception, lambda: db.get_pinned("key"))
+        db.close()
+        Rdict.destroy(path)
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path, Options(raw_mode=True))
+
+
+class TestCurrentOptions(unittest.TestCase):
+    test_dict = None
+    path = "./temp_current_options"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        opt = Options()
+        opt.set_write_buffer_size(32 * 1024 * 1024)
+        opt.set_max_open_files(100)
+        cls.test_dict = Rdict(cls.path, opt)
+
+    def test_current_options(self):
+        assert self.test_dict is not None
+        current = self.test_dict.current_options()
+        self.assertEqual(current["write_buffer_size"], str(32 * 1024 * 1024))
+        self.assertEqual(current["max_open_files"], "100")
+        self.assertIn("block_size", current)
+
+    def test_current_options_after_set_options(self):
+        assert self.test_dict is not None
+        self.test_dict.set_options({"write_buffer_size": str(16 * 1024 * 1024)})
+        current = self.test_dict.current_options()
+        self.assertEqual(current["write_buffer_size"], str(16 * 1024 * 1024))
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestCount(unittest.TestCase):
+    test_dict = None
+    path = "./temp_count"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+        for i in range(1000):
+            cls.test_dict[i] = i
+
+    def test_count(self):
+        assert self.test_dict is not None
+        self.assertEqual(self.test_dict.count(), 1000)
+        self.assertEqual(self.test_dict.count(from_key=100), 900)
+        self.assertEqual(self.test_dict.count(to_key=100), 100)
+        self.assertEqual(self.test_dict.count(from_key=100, to_key=200), 100)
+
+    def test_count_read_opt_bounds(self):
+        assert self.test_dict is not None
+        read_opt = ReadOptions()
+        read_opt.set_iterate_lower_bound(10)
+        read_opt.set_iterate_upper_bound(20)
+        self.assertEqual(self.test_dict.count(read_opt=read_opt), 10)
+        self.assertEqual(len(list(self.test_dict.keys(read_opt=read_opt))), 10)
+
+    def test_count_snapshot(self):
+        assert self.test_dict is not None
+        snapshot = self.test_dict.snapshot()
+        self.test_dict[5000] = 5000
+        self.assertEqual(snapshot.count(), 1000)
+        self.assertEqual(len(list(snapshot.keys())), 1000)
+        self.assertEqual(self.test_dict.count(), 1001)
+        del self.test_dict[5000]
+        del snapshot
+
+    def test_length_hint(self):
+        assert self.test_dict is not None
+        self.assertGreater(operator.length_hint(self.test_dict.keys()), 0)
+        self.assertGreater(operator.length_hint(self.test_dict.items()), 0)
+        # bounded iterators do not report the estimate of the whole db
+        bounded = iter(self.test_dict.keys(from_key=990))
+        self.assertEqual(operator.length_hint(bounded, -1), -1)
+        limited = iter(self.test_dict.keys(from_key=990, limit=5))
+        self.assertEqual(operator.length_hint(limited), 5)
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestPrefixIteration(unittest.TestCase):
+    path = "./temp_prefix_iteration"
+
+    def test_prefix_without_extractor(self):
+        db = Rdict(self.path)
+        for k in ["a", "ab", "abc", "abd", "b", "ba"]:
+            db[k] = k.upper()
+        db[1] = 1
+        self.assertEqual(list(db.keys_with_prefix("ab")), ["ab", "abc", "abd"])
+        self.assertEqual(list(db.items_with_prefix("b")), [("b", "B"), ("ba", "BA")])
+        self.assertEqual(list(db.keys_with_prefix("c")), [])
+        self.assertRaises(Exception, lambda: db.keys_with_prefix(1))
+        db.close()
+        Rdict.destroy(self.path)
+
+    def test_prefix_all_0xff(self):
+        db = Rdict(self.path, Options(raw_mode=True))
+        db[b"\xfe\xff"] = b"1"
+        db[b"\xff"] = b"2"
+        db[b"\xff\xff"] = b"3"
+        db[b"\xff\xff\x00"] = b"4"
+        db[b"\xff\xff\xff"] = b"5"
+        self.assertEqual(
+            list(db.keys_with_prefix(b"\xff\xff")),
+            [b"\xff\xff", b"\xff\xff\x00", b"\xff\xff\xff"],
+        )
+        self.assertEqual(list(db.keys_with_prefix(b"\xfe")), [b"\xfe\xff"])
+        db.close()
+        Rdict.destroy(self.path, Options(raw_mode=True))
+
+    def test_prefix_with_extractor(self):
+        opt = Options(raw_mode=True)
+        opt.set_prefix_extractor(SliceTransform.create_fixed_prefix(4))
+        db = Rdict(self.path, opt)
+        for tenant in [b"aaaa", b"aaab", b"bbbb"]:
+            for i in range(10):
+                db[tenant + bytes([i])] = b"v"
+        db.flush()
+        self.assertEqual(len(list(db.keys_with_prefix(b"aaaa"))), 10)
+        self.assertEqual(len(list(db.keys_with_prefix(b"aaaa\x01"))), 1)
+        with self.assertWarns(UserWarning):
+            keys = list(db.keys_with_prefix(b"aa"))
+        self.assertEqual(len(keys), 20)
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+
+class TestCustomLoadsAllReadPaths(unittest.TestCase):
+    test_dict = None
+    path = "./temp_custom_loads_all_read_paths"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+        cls.test_dict.set_loads(lambda x: ("custom", loads(x.decode("utf-8"))))
+        cls.test_dict.set_dumps(lambda x: bytes(dumps(x), "utf-8"))
+        cls.test_dict.create_column_family("cf")
+        for i in range(10):
+            cls.test_dict[i] = [i]
+
+    def test_get(self):
+        assert self.test_dict is not None
+        self.assertEqual(self.test_dict[0], ("custom", [0]))
+        self.assertEqual(self.test_dict.get(1), ("custom", [1]))
+        self.assertEqual(self.test_dict[[2, 3]], [("custom", [2]), ("custom", [3])])
+        may_exist, value = self.test_dict.key_may_exist(4, fetch=True)
+        self.assertTrue(may_exist)
+        self.assertEqual(value, ("custom", [4]))
+
+    def test_iterators(self):
+        assert self.test_dict is not None
+        expected = [("custom", [i]) for i in range(10)]
+        self.assertEqual(list(self.test_dict.values()), expected)
+        self.assertEqual([v for _, v in self.test_dict.items()], expected)
+        it = self.test_dict.iter()
+        it.seek_to_first()
+        self.assertEqual(it.value(), ("custom", [0]))
+        del it
+
+    def test_snapshot(self):
+        assert self.test_dict is not None
+        snapshot = self.test_dict.snapshot()
+        self.assertEqual(snapshot[5], ("custom", [5]))
+        self.assertEqual(list(snapshot.values())[0], ("custom", [0]))
+        del snapshot
+
+    def test_column_family(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.get_column_family("cf")
+        cf["x"] = {"a": 1}
+        self.assertEqual(cf["x"], ("custom", {"a": 1}))
+        self.assertEqual(list(cf.values()), [("custom", {"a": 1})])
+        self.assertEqual(cf.snapshot()["x"], ("custom", {"a": 1}))
+        del cf
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestWriteBatchRangeAndSingleDelete(unittest.TestCase):
+    test_dict = None
+    path = "./temp_write_batch_range_single_delete"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_replace_prefix(self):
+        assert self.test_dict is not None
+        for i in range(100):
+            self.test_dict[f"old:{i:03}"] = i
+        snapshot = self.test_dict.snapshot()
+        wb = WriteBatch()
+        wb.delete_range("old:", "old;")
+        for i in range(50):
+            wb.put(f"old:{i:03}", -i)
+        self.assertEqual(len(wb), 51)
+        write_opt = WriteOptions()
+        write_opt.sync = True
+        self.test_dict.write(wb, write_opt)
+        self.assertEqual(list(self.test_dict.values()), [-i for i in range(50)])
+        self.assertEqual(list(snapshot.values()), list(range(100)))
+        del snapshot
+
+    def test_single_delete(self):
+        assert self.test_dict is not None
+        self.test_dict["single"] = 1
+        cf = self.test_dict.create_column_family("single_delete_cf")
+        cf["single"] = 2
+        wb = WriteBatch()
+        wb.single_delete("single")
+        wb.single_delete("single", self.test_dict.get_column_family_handle("single_delete_cf"))
+        self.assertEqual(len(wb), 2)
+        self.test_dict.write(wb)
+        self.assertNotIn("single", self.test_dict)
+        self.assertNotIn("single", cf)
+        del cf
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestWriteBatchWithIndex(unittest.TestCase):
+    test_dict = None
+    path = "./temp_write_batch_with_index"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_read_your_own_writes(self):
+        assert self.test_dict is not None
+        self.test_dict["a"] = 1
+        self.test_dict["c"] = 3
+        wbwi = WriteBatchWithIndex(overwrite_key=True)
+        wbwi["b"] = 2
+        wbwi.put("d", {"x": 4})
+        del wbwi["c"]
+        self.assertEqual(len(wbwi), 3)
+
+        self.assertIsNone(wbwi.get_from_batch("a"))
+        self.assertEqual(wbwi.get_from_batch("b"), 2)
+        self.assertEqual(wbwi.get_from_batch("c", "deleted"), "deleted")
+        self.assertEqual(wbwi.get_from_batch_and_db(self.test_dict, "a"), 1)
+        self.assertEqual(wbwi.get_from_batch_and_db(self.test_dict, "d"), {"x": 4})
+        self.assertIsNone(wbwi.get_from_batch_and_db(self.test_dict, "c"))
+
+        it = wbwi.iter_with_base(self.test_dict)
+        it.seek_to_first()
+        merged = []
+        while it.valid():
+            merged.append((it.key(), it.value()))
+            it.next()
+        del it
+        self.assertEqual(merged, [("a", 1), ("b", 2), ("d", {"x": 4})])
+
+        # nothing is visible in the db before writing
+        self.assertNotIn("b", self.test_dict)
+        self.test_dict.write(wbwi)
+        self.assertEqual(list(self.test_dict.items()), merged)
+
+        # the batch is not consumed
+        self.assertEqual(len(wbwi), 3)
+        wbwi.clear()
+        self.assertTrue(wbwi.is_empty())
+
+    def test_no_changes_while_iterating(self):
+        assert self.test_dict is not None
+        wbwi = WriteBatchWithIndex()
+        wbwi["x"] = 1
+        it = wbwi.iter_with_base(self.test_dict)
+        it.seek("x")
+        self.assertRaises(Exception, wbwi.clear)
+        self.assertRaises(Exception, lambda: wbwi.put("y", 2))
+        self.assertRaises(Exception, lambda: wbwi.delete("x"))
+        with self.assertRaises(Exception):
+            wbwi["y"] = 2
+        self.assertEqual(it.key(), "x")
+        del it
+        gc.collect()
+        wbwi["y"] = 2
+        wbwi.clear()
+        self.assertTrue(wbwi.is_empty())
+
+    def test_raw_mode_mismatch(self):
+        assert self.test_dict is not None
+        wbwi = WriteBatchWithIndex(raw_mode=True)
+        wbwi[b"key"] = b"value"
+        self.assertEqual(wbwi.get_from_batch(b"key"), b"value")
+        self.assertRaises(Exception, lambda: self.test_dict.write(wbwi))
+        self.assertRaises(Exception, lambda: wbwi.get_from_batch_and_db(self.test_dict, b"key"))
+
+    def test_merge(self):
+        assert self.test_dict is not None
+        wbwi = WriteBatchWithIndex()
+        # no merge operator
+        self.assertRaises(Exception, lambda: wbwi.merge(self.test_dict, "counter", 1))
+
+        path = "./temp_write_batch_with_index_merge"
+        opt = Options()
+        opt.set_merge_operator(MergeOperator.uint64_add())
+        db = Rdict(path, opt)
+        cf_opt = Options()
+        cf_opt.set_merge_operator(MergeOperator.string_append(","))
+        tags = db.create_column_family("tags", cf_opt)
+        db["counter"] = 1
+        wbwi.merge(db, "counter", 2)
+        wbwi.merge(tags, "k", "a")
+        wbwi.merge(tags, "k", "b")
+        self.assertRaises(TypeError, lambda: wbwi.merge(db, "counter", "x"))
+        self.assertRaises(TypeError, lambda: wbwi.merge(tags, "k", 1))
+        self.assertEqual(len(wbwi), 3)
+        self.assertEqual(wbwi.get_from_batch_and_db(db, "counter"), 3)
+        self.assertEqual(wbwi.get_from_batch_and_db(tags, "k"), "a,b")
+        db.write(wbwi)
+        self.assertEqual(db["counter"], 3)
+        self.assertEqual(tags["k"], "a,b")
+        del tags
+        db.close()
+        gc.collect()
+        Rdict.destroy(path)
+
+    def test_single_delete(self):
+        assert self.test_dict is not None
+        self.test_dict["single"] = 1
+        wbwi = WriteBatchWithIndex()
+        wbwi.put("single_2", 2)
+        wbwi.single_delete("single")
+        self.assertIsNone(wbwi.get_from_batch_and_db(self.test_dict, "single"))
+        self.test_dict.write(wbwi)
+        self.assertNotIn("single", self.test_dict)
+        self.assertEqual(self.test_dict["single_2"], 2)
+
+    def test_delete_range(self):
+        wbwi = WriteBatchWithIndex()
+        self.assertRaises(Exception, lambda: wbwi.delete_range("a", "z"))
+        self.assertTrue(wbwi.is_empty())
+
+    def test_save_points(self):
+        wbwi = WriteBatchWithIndex()
+        self.assertRaisesRegex(
+            Exception, "without save point", wbwi.rollback_to_save_point
+        )
+        wbwi["a"] = 1
+        wbwi.set_save_point()
+        wbwi["b"] = 2
+        wbwi.set_save_point()
+        del wbwi["a"]
+        wbwi.rollback_to_save_point()
+        self.assertEqual(wbwi.get_from_batch("a"), 1)
+        self.assertEqual(wbwi.get_from_batch("b"), 2)
+        wbwi.rollback_to_save_point()
+        self.assertEqual(wbwi.get_from_batch("a"), 1)
+        self.assertIsNone(wbwi.get_from_batch("b"))
+        self.assertEqual(len(wbwi), 1)
+        self.assertRaises(Exception, wbwi.rollback_to_save_point)
+
+    def test_no_new_methods_while_iterating(self):
+        assert self.test_dict is not None
+        wbwi = WriteBatchWithIndex()
+        wbwi["x"] = 1
+        wbwi.set_save_point()
+        it = wbwi.iter_with_base(self.test_dict)
+        self.assertRaises(Exception, lambda: wbwi.single_delete("x"))
+        self.assertRaises(Exception, wbwi.set_save_point)
+        self.assertRaises(Exception, wbwi.rollback_to_save_point)
+        del it
+        gc.collect()
+        wbwi.rollback_to_save_point()
+
+    def test_value_transform(self):
+        assert self.test_dict is not None
+        blobs = self.test_dict.create_column_family("wbwi_blobs")
+        blobs.set_value_transform(zlib.compress, zlib.decompress)
+        blobs["a"] = "a" * 100
+        wbwi = WriteBatchWithIndex()
+        wbwi["b"] = 2
+        self.assertRaises(Exception, lambda: wbwi.get_from_batch_and_db(blobs, "a"))
+        self.assertRaises(Exception, lambda: wbwi.iter_with_base(blobs))
+        self.assertRaises(Exception, lambda: wbwi.merge(blobs, "a", "b"))
+        blobs.set_value_transform(None, None)
+        self.assertEqual(wbwi.get_from_batch_and_db(blobs, "b"), 2)
+        del blobs
+        self.test_dict.drop_column_family("wbwi_blobs")
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+@unittest.skipIf(np is None, reason="numpy is not installed")
+class TestNumpyValues(unittest.TestCase):
+    test_dict = None
+    path = "./temp_numpy_values"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_round_trip(self):
+        assert self.test_dict is not None and np is not None
+        arrays = [
+            np.arange(12, dtype=np.int64).reshape(3, 4),
+            np.linspace(0, 1, 7, dtype=np.float32),
+            np.array([True, False]),
+            np.array(3.5),
+            np.zeros((0, 3), dtype=np.uint8),
+            np.array(["ab", "c"]),
+            np.arange(10, dtype=">i4"),
+        ]
+        for i, arr in enumerate(arrays):
+            self.test_dict[i] = arr
+        for i, arr in enumerate(arrays):
+            value = self.test_dict[i]
+            self.assertIs(type(value), np.ndarray)
+            self.assertEqual(value.dtype, arr.dtype)
+            self.assertEqual(value.shape, arr.shape)
+            self.assertTrue(np.array_equal(value, arr))
+            # decoded arrays are writable
+            value[...] = value
+
+    def test_non_contiguous(self):
+        assert self.test_dict is not None and np is not None
+        arr = np.arange(100, dtype=np.float64).reshape(10, 10)[::2, ::3]
+        self.test_dict["non_contiguous"] = arr
+        self.assertTrue(np.array_equal(self.test_dict["non_contiguous"], arr))
+        self.test_dict["transposed"] = arr.T
+        self.assertTrue(np.array_equal(self.test_dict["transposed"], arr.T))
+
+    def test_pickle_fallback(self):
+        assert self.test_dict is not None and np is not None
+        arr = np.array([{"a": 1}, None], dtype=object)
+        self.test_dict["object"] = arr
+        self.assertEqual(list(self.test_dict["object"]), [{"a": 1}, None])
+
+    def test_custom_serializer(self):
+        assert np is not None
+        arr = np.arange(6, dtype=np.int32).reshape(2, 3)
+        self.assertEqual(encode_value(arr)[0], 7)
+        dumped = []
+
+        def dumps(value):
+            dumped.append(value)
+            return zlib.compress(repr(value.tolist()).encode())
+
+        # the arrays are passed to the serializer instead of the fast path
+        self.assertEqual(encode_value(arr, dumps=dumps)[1:], dumps(arr))
+        self.assertEqual(len(dumped), 2)
+
+    def test_value_transform(self):
+        assert self.test_dict is not None and np is not None
+        arr = np.arange(6, dtype=np.int32)
+        transformed = []
+
+        def encode(data):
+            transformed.append(bytes(data))
+            return zlib.compress(data)
+
+        cf = self.test_dict.create_column_family("transformed")
+        cf.set_value_transform(encode, zlib.decompress)
+        cf["arr"] = arr
+        self.assertTrue(np.array_equal(cf["arr"], arr))
+        # the transformed bytes are pickled, not in the numpy encoding
+        self.assertEqual(len(transformed), 1)
+        self.assertNotEqual(transformed[0][0], 7)
+        del cf
+
+    def test_corrupted(self):
+        assert np is not None
+        encoded = encode_value(np.arange(4, dtype=np.int64))
+        self.assertTrue(np.array_equal(decode_value(encoded), np.arange(4)))
+        header = encoded[:-32]
+        for corrupted in [encoded[:-1], encoded + b"\x00", header]:
+            with self.assertRaisesRegex(Exception, "corrupted numpy array encoding"):
+                decode_value(corrupted)
+        empty = encode_value(np.zeros((0, 3), dtype=np.int64))
+        self.assertEqual(decode_value(empty).shape, (0, 3))
+
+    def test_scalar_keys(self):
+        assert np is not None
+        path = self.path + "_scalar_keys"
+        db = Rdict(path)
+        keys = [np.int64(-3), np.uint8(200), np.int32(7), np.float32(0.5), np.float64(-1.25), np.bool_(True)]
+        for key in keys:
+            db[key] = str(key)
+        # numpy scalars are the same keys as python numbers
+        self.assertEqual(db[-3], "-3")
+        self.assertEqual(db[np.int16(200)], "200")
+        self.assertEqual(db[0.5], "0.5")
+        self.assertEqual(db[True], "True")
+        # ints sort numerically, then floats and bools like their python equivalents
+        self.assertEqual(list(db.keys()), [-3, 7, 200, 0.5, -1.25, True])
+        self.assertEqual(list(db.keys(from_key=np.int64(0))), [7, 200, 0.5, -1.25, True])
+        self.assertEqual(encode_key(np.int64(42)), encode_key(42))
+        with self.assertRaises(Exception):
+            db[np.array([1])] = 1
+        db.close()
+        Rdict.destroy(path)
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestRawModeBuffers(unittest.TestCase):
+    path = "./temp_raw_mode_buffers"
+    sst_path = "./temp_raw_mode_buffers.sst"
+
+    def setUp(self):
+        self.db = Rdict(self.path, Options(raw_mode=True))
+
+    def test_buffers(self):
+        data = b"0123456789"
+        self.db[bytearray(b"key")] = memoryview(data)[2:5]
+        self.assertEqual(self.db[memoryview(b"key")], b"234")
+        self.assertTrue(bytearray(b"key") in self.db)
+        del self.db[memoryview(bytearray(b"key"))]
+        self.assertFalse(b"key" in self.db)
+
+        self.db.put(b"empty", bytearray())
+        self.assertEqual(self.db[b"empty"], b"")
+        self.assertEqual(self.db.get([memoryview(b"empty"), b"missing"]), [b"", None])
+
+    def test_non_contiguous(self):
+        self.assertRaises(TypeError, self.db.put, b"key", memoryview(b"0123456789")[::2])
+        self.assertRaises(TypeError, self.db.put, memoryview(b"0123456789")[::2], b"value")
+        self.assertRaises(KeyError, self.db.put, "not a buffer", b"value")
+
+    @unittest.skipIf(np is None, reason="numpy is not installed")
+    def test_numpy_buffer(self):
+        array = np.arange(4, dtype=np.uint32)
+        self.db[b"array"] = array
+        self.assertEqual(self.db[b"array"], array.tobytes())
+        self.assertRaises(TypeError, self.db.put, b"array", np.arange(4)[::2])
+
+    def test_write_batch_and_sst(self):
+        wb = WriteBatch(raw_mode=True)
+        wb[bytearray(b"a")] = memoryview(b"1")
+        self.db.write(wb)
+        self.assertEqual(self.db[b"a"], b"1")
+
+        writer = SstFileWriter(Options(raw_mode=True))
+        writer.open(self.sst_path)
+        writer[memoryview(b"b")] = bytearray(b"2")
+        writer.finish()
+        self.db.ingest_external_file([self.sst_path])
+        self.assertEqual(self.db[b"b"], b"2")
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path, Options(raw_mode=True))
+        if os.path.exists(self.sst_path):
+            os.remove(self.sst_path)
+
+
+class TestBackgroundErrors(unittest.TestCase):
+    path = "./temp_background_errors"
+
+    class Listener:
+        def __init__(self):
+            self.errors = []
+
+        def on_background_error(self, info):
+            self.errors.append(info)
+
+    def test_no_background_errors(self):
+        listener = self.Listener()
+        opt = Options()
+        opt.add_event_listener(listener)
+        db = Rdict(self.path, opt)
+        for i in range(100):
+            db[i] = i
+        db.flush()
+        db.compact_range(None, None)
+        self.assertEqual(db.background_errors, 0)
+        self.assertIsNone(db.background_error)
+        # nothing to resume
+        db.resume()
+        cf = db.create_column_family("cf")
+        self.assertEqual(cf.background_errors, 0)
+        self.assertIsNone(cf.background_error)
+        del cf
+        db.close()
+        self.assertEqual(listener.errors, [])
+        self.assertRaises(DbClosedError, lambda: db.background_errors)
+        self.assertRaises(DbClosedError, lambda: db.background_error)
+        self.assertRaises(DbClosedError, db.resume)
+
+    @unittest.skipIf(sys.platform.startswith("win"), reason="cannot move an open db on windows")
+    def test_flush_error(self):
+        listener = self.Listener()
+        opt = Options()
+        opt.add_event_listener(listener)
+        path = self.path + "_flush_error"
+        moved_path = path + "_moved"
+        db = Rdict(path, opt)
+        db["a"] = 1
+        # the table file of the flush cannot be created once the directory is moved
+        os.rename(path, moved_path)
+        try:
+            self.assertRaises(Exception, db.flush)
+            self.assertGreaterEqual(db.background_errors, 1)
+        finally:
+            os.rename(moved_path, path)
+        deadline = time.time() + 10
+        while not listener.errors and time.time() < deadline:
+            time.sleep(0.05)
+        self.assertEqual(listener.errors[0]["reason"], "flush")
+        self.assertIsInstance(listener.errors[0]["status"], str)
+        self.assertEqual(db.background_error, listener.errors[-1]["status"])
+        # close() flushes again, which fails as the db is still in the error state
+        self.assertRaises(Exception, db.close)
+        self.assertTrue(db.is_closed)
+        del db
+        gc.collect()
+        Rdict.destroy(path)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestReleaseGil(unittest.TestCase):
+    path = "./temp_release_gil"
+
+    def setUp(self):
+        opt = Options(raw_mode=True)
+        table = BlockBasedOptions()
+        table.disable_cache()
+        opt.set_block_based_table_factory(table)
+        self.db = Rdict(self.path, options=opt)
+        for i in range(20000):
+            self.db[b"%08d" % i] = os.urandom(100)
+        self.db.flush()
+
+    def counter_advances_during(self, blocking_call):
+        counter = [0]
+        done = [False]
+
+        def count_up():
+            while not done[0]:
+                counter[0] += 1
+
+        # without a blocking call releasing the GIL, the main thread would
+        # only be interrupted after the switch interval
+        interval = sys.getswitchinterval()
+        sys.setswitchinterval(1)
+        thread = Thread(target=count_up)
+        try:
+            thread.start()
+            while counter[0] == 0:
+                time.sleep(0.001)
+            before = counter[0]
+            blocking_call()
+            after = counter[0]
+        finally:
+            done[0] = True
+            thread.join()
+            sys.setswitchinterval(interval)
+        # the counter can only advance if the GIL is released during the call
+        return after > before
+
+    def test_count_releases_gil(self):
+        self.assertTrue(self.counter_advances_during(lambda: self.db.count()))
+
+    def test_reads_release_gil(self):
+        keys = [b"%08d" % i for i in range(20000)]
+        values = []
+        # the block cache is disabled, so that the keys are read from the table file
+        self.assertTrue(self.counter_advances_during(lambda: values.extend(self.db.get(keys))))
+        self.assertEqual(len(values), 20000)
+        self.assertTrue(all(len(value) == 100 for value in values))
+
+    def test_concurrent_writes(self):
+        def writer(n):
+            for i in range(1000):
+                self.db.put(b"t%d:%d" % (n, i), b"%d" % i)
+            wb = WriteBatch(raw_mode=True)
+            wb.delete(b"t%d:0" % n)
+            self.db.write(wb)
+
+        threads = [Thread(target=writer, args=(n,)) for n in range(4)]
+        for t in threads:
+            t.start()
+        for t in threads:
+            t.join()
+        for n in range(4):
+            self.assertNotIn(b"t%d:0" % n, self.db)
+            self.assertEqual(self.db[b"t%d:999" % n], b"999")
+
+    def tearDown(self):
+        self.db.close()
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestMappingOperators(unittest.TestCase):
+    path = "./temp_mapping_operators"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+
+    def test_eq(self):
+        data = {"a": 1, 2: "b", 3.5: [3], b"c": None, True: False}
+        self.db.update(data)
+        self.assertEqual(self.db, data)
+        self.assertEqual(data, self.db)
+        self.assertFalse(self.db != data)
+        self.assertNotEqual(self.db, {**data, "extra": 1})
+        self.assertNotEqual(self.db, {**data, "a": 2})
+        smaller = dict(data)
+        del smaller[b"c"]
+        self.assertNotEqual(self.db, smaller)
+        self.assertNotEqual(self.db, {})
+        self.assertNotEqual(self.db, [("a", 1)])
+        self.assertEqual(self.db, self.db)
+
+    def test_eq_empty(self):
+        self.assertEqual(self.db, {})
+        self.assertNotEqual(self.db, {"a": 1})
+
+    def test_or(self):
+        self.db.update({"a": 1, "b": 2})
+        self.assertEqual(self.db | {"b": 3, "c": 4}, {"a": 1, "b": 3, "c": 4})
+        self.assertEqual({"b": 3, "c": 4} | self.db, {"a": 1, "b": 2, "c": 4})
+        self.assertIsInstance(self.db | {}, dict)
+        with self.assertRaises(TypeError):
+            self.db | [("a", 1)]
+
+    def test_ior(self):
+        db = self.db
+        self.db |= {"a": 1, 1: "a"}
+        self.assertIs(self.db, db)
+        self.db |= [("b", 2)]
+        self.assertEqual(self.db, {"a": 1, 1: "a", "b": 2})
+
+    def test_iter(self):
+        self.db.update({1: 1, 2: 2})
+        self.assertEqual(list(self.db), [1, 2])
+        self.assertEqual(dict(self.db), {1: 1, 2: 2})
+
+    def test_mutable_mapping(self):
+        from collections.abc import Mapping, MutableMapping
+
+        self.assertIsInstance(self.db, MutableMapping)
+        self.assertIsInstance(self.db, Mapping)
+        cf = self.db.create_column_family("cf")
+        self.assertIsInstance(cf, MutableMapping)
+        cf["a"] = 1
+        self.assertEqual(cf, {"a": 1})
+
+    def tearDown(self):
+        self.db.close()
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestExport(unittest.TestCase):
+    path = "./temp_export"
+    other_path = "./temp_export_other"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+        self.other = Rdict(self.other_path)
+
+    def test_to_dict(self):
+        data = {"a": 1, "b": [2], "c": {"x": 3}, b"d": None}
+        self.db.update(data)
+        self.assertEqual(self.db.to_dict(), data)
+        self.assertEqual(self.db.to_dict(from_key="b", to_key="c"), {"b": [2]})
+        self.assertEqual(self.db.to_dict(max_items=4), data)
+        self.assertRaises(Exception, self.db.to_dict, max_items=3)
+        self.assertEqual(len(self.db.to_dict(max_items=None)), 4)
+
+    def test_copy_to(self):
+        self.db.update({i: str(i) for i in range(100)})
+        self.assertEqual(self.db.copy_to(self.other, batch_size=7), 100)
+        self.assertEqual(self.other.to_dict(), {i: str(i) for i in range(100)})
+        self.assertRaises(ValueError, self.db.copy_to, self.other, batch_size=0)
+
+    def test_copy_range_to_column_family(self):
+        self.db.update({i: i for i in range(100)})
+        cf = self.other.create_column_family("cf")
+        self.assertEqual(self.db.copy_to(cf, from_key=10, to_key=20), 10)
+        self.assertEqual(cf.to_dict(), {i: i for i in range(10, 20)})
+        self.assertEqual(self.other.to_dict(), {})
+        src = self.db.create_column_family("src")
+        src["a"] = 1
+        self.assertEqual(src.copy_to(self.other), 1)
+        self.assertEqual(self.other["a"], 1)
+
+    def test_reencode(self):
+        self.db.update({"a": 1, "b": 2})
+        self.other.set_dumps(lambda v: str(v).encode())
+        self.other.set_loads(lambda b: int(b.decode()))
+        self.db.copy_to(self.other)
+        self.assertEqual(self.other.to_dict(), {"a": 1, "b": 2})
+        self.other.set_loads(lambda b: b)
+        self.assertEqual(self.other["a"], b"1")
+
+    def test_raw_mode(self):
+        self.db.close()
+        Rdict.destroy(self.path)
+        opt = Options(raw_mode=True)
+        self.db = Rdict(self.path, opt)
+        self.db[b"\x00"] = b"\xff"
+        self.db[b"k"] = b"v"
+        self.assertEqual(self.db.to_dict(), {b"\x00": b"\xff", b"k": b"v"})
+        self.other.close()
+        Rdict.destroy(self.other_path)
+        self.other = Rdict(self.other_path, Options(raw_mode=True))
+        self.assertEqual(self.db.copy_to(self.other), 2)
+        self.assertEqual(self.other[b"\x00"], b"\xff")
+
+    def tearDown(self):
+        self.db.close()
+        self.other.close()
+        gc.collect()
+        Rdict.destroy(self.path)
+        Rdict.destroy(self.other_path)
+
+
+class TestPerfContext(unittest.TestCase):
+    path = "./temp_perf_context"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+        self.db.update({i: i for i in range(100)})
+
+    def test_get_with_stats(self):
+        value, counters = self.db.get_with_stats(1)
+        self.assertEqual(value, 1)
+        self.assertGreaterEqual(counters["get_from_memtable_count"], 1)
+        self.assertEqual(counters["block_read_count"], 0)
+        self.db.flush()
+        self.db.get(1)
+        value, counters = self.db.get_with_stats(1)
+        self.assertEqual(value, 1)
+        self.assertGreaterEqual(counters["block_cache_hit_count"], 1)
+        self.assertEqual(counters["block_read_count"], 0)
+        value, _ = self.db.get_with_stats(1000, "default")
+        self.assertEqual(value, "default")
+        values, _ = self.db.get_with_stats([1, 2])
+        self.assertEqual(values, [1, 2])
+
+    def test_perf_context(self):
+        self.db.flush()
+        ctx = PerfContext()
+        ctx.reset()
+        self.assertEqual(ctx.metric("block_cache_hit_count"), 0)
+        for i in range(100):
+            self.db.get(i)
+        self.assertGreater(ctx.metric("block_cache_hit_count")
+                           + ctx.metric("block_read_count"), 0)
+        self.assertGreater(ctx.metrics()["get_read_bytes"], 0)
+        self.assertIn("block_cache_hit_count", ctx.report())
+        self.assertIn("block_cache_hit_count", PerfContext.metric_names())
+        self.assertRaises(Exception, ctx.metric, "block_cache_hit")
+
+    def test_io_attribution(self):
+        self.db.close()
+        Rdict.destroy(self.path)
+        opt = Options()
+        opt.enable_statistics()
+        self.db = Rdict(self.path, opt)
+        stats = self.db.get_statistics()
+        stats.reset()
+        self.db["a"] = "x" * 1000
+        self.assertGreaterEqual(stats.ticker("rocksdb.wal.bytes"), 1000)
+        self.db.flush()
+        _, counters = self.db.get_with_stats("a")
+        self.assertGreaterEqual(counters["block_read_byte"]
+                                + counters["block_cache_hit_count"], 1)
+
+    def test_disable(self):
+        ctx = PerfContext()
+        PerfContext.disable()
+        try:
+            ctx.reset()
+            self.db.get(1)
+            self.assertEqual(ctx.metric("get_from_memtable_count"), 0)
+            # counts are enabled for get_with_stats
+            _, counters = self.db.get_with_stats(1)
+            self.assertGreaterEqual(counters["get_from_memtable_count"], 1)
+        finally:
+            PerfContext.enable()
+
+    def tearDown(self):
+        self.db.close()
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestUseAfterClose(unittest.TestCase):
+    path = "./temp_use_after_close"
+
+    def test_close(self):
+        db = Rdict(self.path)
+        db.update({i: i for i in range(10)})
+        cf = db.create_column_family("cf")
+        cf["a"] = 1
+        it = iter(db.items())
+        snapshot = db.snapshot()
+        raw_iter = db.iter()
+        raw_iter.seek_to_first()
+        self.assertTrue(raw_iter.valid())
+        keys = db.keys()
+        self.assertFalse(db.is_closed)
+        db.close()
+        db.close()
+        self.assertTrue(db.is_closed)
+        self.assertRaises(DbClosedError, lambda: db[0])
+        self.assertRaises(DbClosedError, db.put, 0, 0)
+        self.assertRaises(DbClosedError, db.path)
+        self.assertRaises(DbClosedError, list, keys)
+        self.assertRaises(DbClosedError, db.iter)
+        self.assertRaises(DbClosedError, db.snapshot)
+        # the instances created from the closed instance are closed too
+        self.assertRaises(DbClosedError, next, it)
+        self.assertRaises(DbClosedError, lambda: snapshot[9])
+        self.assertRaises(DbClosedError, snapshot.iter)
+        self.assertRaises(DbClosedError, lambda: cf["a"])
+        self.assertRaises(DbClosedError, raw_iter.seek_to_first)
+        self.assertRaises(DbClosedError, raw_iter.key)
+        self.assertRaises(DbClosedError, raw_iter.status)
+        self.assertFalse(raw_iter.valid())
+        self.assertTrue(cf.is_closed)
+        cf.close()
+        del it, snapshot, raw_iter, cf, keys, db
+        gc.collect()
+        # the lock is released
+        with Rdict(self.path) as db:
+            self.assertEqual(db[0], 0)
+            db.close()
+        self.assertTrue(db.is_closed)
+
+    def test_close_column_family(self):
+        db = Rdict(self.path)
+        cf = db.create_column_family("cf")
+        cf["a"] = 1
+        cf_iter = iter(cf.keys())
+        other_cf = db.get_column_family("cf")
+        cf.close()
+        self.assertTrue(cf.is_closed)
+        self.assertRaises(DbClosedError, next, cf_iter)
+        # closing a column family does not close the db or the other instances
+        self.assertFalse(db.is_closed)
+        self.assertEqual(other_cf["a"], 1)
+        db["b"] = 2
+        self.assertEqual(db["b"], 2)
+        del cf_iter, cf, other_cf
+        db.close()
+
+    def tearDown(self):
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestParanoidChecks(unittest.TestCase):
+    path = "./temp_paranoid_checks"
+
+    def test_options(self):
+        opt = Options()
+        opt.set_paranoid_checks(True)
+        opt.set_paranoid_file_checks(True)
+        self.assertTrue(opt.to_dict()["paranoid_checks"])
+        db = Rdict(self.path, opt)
+        db["a"] = 1
+        db.flush()
+        options = db.current_options()
+        self.assertEqual(options["paranoid_checks"], "true")
+        self.assertEqual(options["paranoid_file_checks"], "true")
+        self.assertEqual(db["a"], 1)
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+    def test_damaged_table_file(self):
+        opt = Options()
+        opt.set_paranoid_checks(True)
+        db = Rdict(self.path, opt)
+        db.update({i: i for i in range(100)})
+        db.flush()
+        db.close()
+        sst = [f for f in os.listdir(self.path) if f.endswith(".sst")]
+        self.assertEqual(len(sst), 1)
+        with open(os.path.join(self.path, sst[0]), "ab") as f:
+            f.write(b"garbage")
+        self.assertRaises(Exception, Rdict, self.path, opt)
+        Rdict.destroy(self.path, opt)
+
+    def tearDown(self):
+        gc.collect()
+        if os.path.exists(self.path):
+            Rdict.destroy(self.path)
+
+
+class TestViews(unittest.TestCase):
+    path = "./temp_views"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+        self.db.update({i: str(i) for i in range(10)})
+
+    def test_reiterable(self):
+        keys = self.db.keys()
+        self.assertEqual(list(keys), list(range(10)))
+        self.assertEqual(list(keys), list(range(10)))
+        values = self.db.values(backwards=True, limit=2)
+        self.assertEqual(list(values), ["9", "8"])
+        self.assertEqual(list(values), ["9", "8"])
+        items = self.db.items(from_key=8)
+        self.assertEqual(list(items), [(8, "8"), (9, "9")])
+        # each iteration sees the keys as of its start
+        self.db[10] = "10"
+        self.assertEqual(list(items), [(8, "8"), (9, "9"), (10, "10")])
+
+    def test_concurrent_iterations(self):
+        keys = self.db.keys()
+        first, second = iter(keys), iter(keys)
+        self.assertEqual(next(first), 0)
+        self.assertEqual(next(first), 1)
+        self.assertEqual(next(second), 0)
+        self.assertEqual(list(zip(first, second)), [(i + 2, i + 1) for i in range(8)])
+
+    def test_contains(self):
+        self.assertIn(3, self.db.keys())
+        self.assertNotIn(30, self.db.keys())
+        self.assertIn("3", self.db.values())
+        self.assertNotIn(3, self.db.values())
+        self.assertIn((3, "3"), self.db.items())
+        self.assertNotIn((3, "4"), self.db.items())
+        self.assertNotIn((30, "30"), self.db.items())
+        self.assertNotIn(3, self.db.items())
+        # restricted views scan their own range
+        self.assertNotIn(3, self.db.keys(from_key=5))
+        self.assertIn(6, self.db.keys(from_key=5))
+        self.assertNotIn((9, "9"), self.db.items(limit=3))
+        self.assertIn("1", self.db.values(limit=3))
+
+    def test_len(self):
+        self.db.flush()
+        self.assertAlmostEqual(len(self.db.keys()), 10, delta=2)
+        self.assertEqual(len(self.db.items(limit=3)), 3)
+        self.assertEqual(len(self.db.values()), len(self.db))
+        # bounded views are counted
+        self.assertEqual(len(self.db.keys(from_key=5)), 5)
+        self.assertEqual(len(self.db.keys(backwards=True, from_key=5)), 6)
+        self.assertEqual(len(self.db.items(from_key=5, limit=3)), 3)
+        read_opt = ReadOptions()
+        read_opt.set_iterate_upper_bound(3)
+        self.assertEqual(len(self.db.values(read_opt=read_opt)), 3)
+
+    def test_column_family(self):
+        cf = self.db.create_column_family("cf")
+        cf["only"] = 1
+        self.assertEqual(list(cf.keys()), ["only"])
+        self.assertNotIn(0, cf.keys())
+        self.assertIn(("only", 1), cf.items())
+        del cf
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestClear(unittest.TestCase):
+    path = "./temp_clear"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+
+    def test_clear_column_family(self):
+        cf = self.db.create_column_family("cf")
+        self.db["kept"] = 1
+        for i in range(1000):
+            cf[i] = i
+        cf.flush()
+        # in the memtable, and in the table files
+        for i in range(1000, 1100):
+            cf[i] = i
+        cf["last"] = "x"
+        snapshot = cf.snapshot()
+        self.assertGreater(len(cf), 0)
+        cf.clear()
+        self.assertFalse(cf)
+        self.assertEqual(list(cf.keys()), [])
+        self.assertEqual(list(cf.keys(backwards=True)), [])
+        self.assertNotIn("last", cf)
+        self.assertEqual(self.db["kept"], 1)
+        self.assertEqual(snapshot[1050], 1050)
+        self.assertEqual(snapshot["last"], "x")
+        del snapshot
+        cf.compact_range(None, None)
+        self.assertEqual(len(cf), 0)
+        cf[0] = "new"
+        self.assertEqual(dict(cf.items()), {0: "new"})
+        del cf
+
+    def test_clear_empty(self):
+        self.db.clear()
+        self.assertFalse(self.db)
+
+    def test_clear_hash_memtable(self):
+        self.db.close()
+        Rdict.destroy(self.path)
+        for factory in [MemtableFactory.hash_skip_list(1000, 4, 4),
+                        MemtableFactory.hash_link_list(1000)]:
+            opt = Options(raw_mode=True)
+            opt.set_prefix_extractor(SliceTransform.create_fixed_prefix(3))
+            opt.set_allow_concurrent_memtable_write(False)
+            opt.set_memtable_factory(factory)
+            db = Rdict(self.path, opt)
+            # all the keys are in the memtable
+            for i in range(100):
+                db[b"key%d" % i] = b"v"
+            db.clear()
+            for i in range(100):
+                self.assertNotIn(b"key%d" % i, db)
+            read_opt = ReadOptions()
+            read_opt.set_total_order_seek(True)
+            self.assertEqual(list(db.keys(read_opt=read_opt)), [])
+            db.close()
+            Rdict.destroy(self.path, opt)
+        self.db = Rdict(self.path)
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestMaxSizeGuard(unittest.TestCase):
+    path = "./temp_max_size_guard"
+
+    def setUp(self):
+        opt = Options()
+        opt.set_max_key_bytes(16)
+        opt.set_max_value_bytes(100)
+        self.db = Rdict(self.path, opt)
+
+    def test_limits(self):
+        self.db["k"] = "small"
+        self.assertRaises(Exception, self.db.put, "k" * 100, 1)
+        self.assertRaises(Exception, self.db.put, "big", "v" * 1000)
+        self.assertRaises(Exception, self.db.update, {"a": 1, "big": b"v" * 1000})
+        self.assertNotIn("a", self.db)
+        self.assertNotIn("big", self.db)
+        self.assertEqual(self.db["k"], "small")
+
+    def test_column_family(self):
+        cf = self.db.create_column_family("cf")
+        self.assertRaises(Exception, cf.put, "big", "v" * 1000)
+        self.assertNotIn("big", cf)
+        del cf
+
+    def test_not_persisted(self):
+        self.db.close()
+        self.db = Rdict(self.path)
+        self.db["big"] = "v" * 1000
+        self.assertEqual(len(self.db["big"]), 1000)
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestContains(unittest.TestCase):
+    path = "./temp_contains"
+
+    def setUp(self):
+        table_opt = BlockBasedOptions()
+        table_opt.set_bloom_filter(10, False)
+        opt = Options()
+        opt.set_block_based_table_factory(table_opt)
+        self.db = Rdict(self.path, opt)
+
+    def test_contains(self):
+        for i in range(1000):
+            self.db[i] = i
+        self.db.flush()
+        del self.db[0]
+        self.db[1000] = None
+        self.assertNotIn(0, self.db)
+        self.assertIn(1, self.db)
+        self.assertIn(1000, self.db)
+        for i in range(1001, 2000):
+            self.assertNotIn(i, self.db)
+
+    def test_no_value_decoding(self):
+        def loads(_):
+            raise AssertionError("value decoded")
+
+        self.db.set_loads(loads)
+        self.db["pickled"] = [1]
+        self.assertIn("pickled", self.db)
+        self.assertNotIn("absent", self.db)
+        self.assertRaises(AssertionError, lambda: self.db["pickled"])
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestPopSetdefault(unittest.TestCase):
+    path = "./temp_pop_setdefault"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+
+    def test_pop(self):
+        self.db["a"] = {"x": 1}
+        self.db["none"] = None
+        self.assertEqual(self.db.pop("a"), {"x": 1})
+        self.assertNotIn("a", self.db)
+        self.assertIsNone(self.db.pop("a", None))
+        self.assertEqual(self.db.pop("a", 0), 0)
+        self.assertIsNone(self.db.pop("none", 0))
+        self.assertNotIn("none", self.db)
+        with self.assertRaises(KeyError) as ctx:
+            self.db.pop("a")
+        self.assertEqual(ctx.exception.args, ("a",))
+        self.assertRaises(TypeError, self.db.pop, "a", 1, 2)
+
+    def test_setdefault(self):
+        self.assertEqual(self.db.setdefault("n", [1]), [1])
+        self.assertEqual(self.db["n"], [1])
+        self.assertEqual(self.db.setdefault("n", [2]), [1])
+        self.assertIsNone(self.db.setdefault("empty"))
+        self.assertIn("empty", self.db)
+
+    def test_popitem(self):
+        self.db.update({1: "a", 2: "b", 3: "c"})
+        snapshot = self.db.snapshot()
+        self.assertEqual(self.db.popitem(), (3, "c"))
+        self.assertEqual(self.db.popitem(last=False), (1, "a"))
+        self.assertEqual(dict(self.db.items()), {2: "b"})
+        self.assertEqual(snapshot[3], "c")
+        self.assertEqual(self.db.popitem(), (2, "b"))
+        with self.assertRaises(KeyError):
+            self.db.popitem()
+        del snapshot
+
+    def test_key_error_repr(self):
+        with self.assertRaises(KeyError) as ctx:
+            self.db["absent"]
+        self.assertEqual(str(ctx.exception), repr("absent"))
+
+    def test_column_family(self):
+        cf = self.db.create_column_family("cf")
+        self.db["k"] = "default"
+        self.assertEqual(cf.setdefault("k", "cf"), "cf")
+        self.assertEqual(cf.pop("k"), "cf")
+        self.assertEqual(self.db["k"], "default")
+        self.assertNotIn("k", cf)
+        cf["z"] = 1
+        self.assertEqual(cf.popitem(), ("z", 1))
+        self.assertEqual(self.db.popitem(), ("k", "default"))
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestGetDefault(unittest.TestCase):
+    path = "./temp_get_default"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+
+    def test_default(self):
+        self.db["none"] = None
+        self.db["one"] = 1
+        missing = object()
+        self.assertIsNone(self.db.get("none", missing))
+        self.assertIs(self.db.get("absent", missing), missing)
+        self.assertIsNone(self.db.get("absent"))
+        self.assertIsNone(self.db["none"])
+        self.assertRaises(KeyError, lambda: self.db["absent"])
+        self.assertEqual(
+            self.db.get(["one", "absent", "none"], missing),
+            [1, missing, None],
+        )
+        self.assertEqual(self.db.get(["one", "absent"]), [1, None])
+
+    def test_column_family(self):
+        cf = self.db.create_column_family("cf")
+        cf["none"] = None
+        cf["one"] = 1
+        self.db["two"] = 2
+        self.assertIsNone(cf.get("none", 0))
+        self.assertEqual(cf.get("two", 0), 0)
+        self.assertEqual(cf.get(["one", "two", "none"], 0), [1, 0, None])
+        self.assertRaises(KeyError, lambda: cf["two"])
+
+    def test_snapshot(self):
+        self.db["none"] = None
+        snapshot = self.db.snapshot()
+        self.db["later"] = 1
+        missing = object()
+        self.assertIsNone(snapshot.get("none", missing))
+        self.assertIs(snapshot.get("later", missing), missing)
+        self.assertIsNone(snapshot.get("later"))
+        self.assertRaises(KeyError, lambda: snapshot["later"])
+        del snapshot
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestValueTransform(unittest.TestCase):
+    path = "./temp_value_transform"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+
+    def test_column_family_transform(self):
+        blobs = self.db.create_column_family("blobs")
+        blobs.set_value_transform(zlib.compress, zlib.decompress)
+        doc = "a large document " * 1000
+        blobs["doc"] = doc
+        blobs.put(1, {"nested": [1, 2]})
+        blobs.update({"x": b"bytes"})
+        self.db["doc"] = doc
+
+        self.assertEqual(blobs["doc"], doc)
+        self.assertEqual(blobs.get(["doc", 1, "missing"]), [doc, {"nested": [1, 2]}, None])
+        self.assertEqual(dict(blobs.items()), {1: {"nested": [1, 2]}, "doc": doc, "x": b"bytes"})
+        self.assertEqual(list(blobs.values(from_key="x", limit=1)), [b"bytes"])
+        self.assertEqual(blobs.snapshot()["doc"], doc)
+        self.assertRaises(Exception, blobs.merge, "doc", "more")
+
+        # the values are stored compressed
+        raw = self.db.get_column_family("blobs")
+        self.assertRaises(Exception, raw.get, "doc")
+        raw.set_value_transform(zlib.compress, zlib.decompress)
+        self.assertEqual(raw["doc"], doc)
+        # the other column family is not affected
+        self.assertEqual(self.db["doc"], doc)
+        self.assertEqual(dict(self.db.items()), {"doc": doc})
+
+        blobs.set_value_transform(None, None)
+        self.assertRaises(Exception, blobs.get, "doc")
+        self.assertRaises(ValueError, blobs.set_value_transform, zlib.compress, None)
+        del blobs, raw
+
+    def test_raw_mode(self):
+        db = Rdict(self.path + "_raw", Options(raw_mode=True))
+        db.set_value_transform(zlib.compress, zlib.decompress)
+        db[b"key"] = b"value" * 100
+        buffer = bytearray(1000)
+        self.assertEqual(db.get_into(b"key", buffer), 500)
+        self.assertEqual(buffer[:500], b"value" * 100)
+        db.set_raw_value_type("memoryview")
+        self.assertEqual(db[b"key"], b"value" * 100)
+        pinned = db.get_pinned(b"key")
+        self.assertLess(len(pinned), 500)
+        pinned.close()
+        db.close()
+        Rdict.destroy(self.path + "_raw", Options(raw_mode=True))
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestRawValueTypes(unittest.TestCase):
+    path = "./temp_raw_value_types"
+
+    def setUp(self):
+        self.db = Rdict(self.path, Options(raw_mode=True))
+        self.db[b"key"] = b"0123456789"
+        self.db[b"empty"] = b""
+
+    def test_memoryview_values(self):
+        self.db.set_raw_value_type("memoryview")
+        value = self.db[b"key"]
+        self.assertIsInstance(value, memoryview)
+        self.assertTrue(value.readonly)
+        self.assertEqual(value[2:5], b"234")
+        self.assertEqual(bytes(self.db.get(b"empty")), b"")
+        values = self.db.get([b"key", b"missing"])
+        self.assertEqual(bytes(values[0]), b"0123456789")
+        self.assertIsNone(values[1])
+        self.db.set_raw_value_type("bytes")
+        self.assertEqual(self.db[b"key"], b"0123456789")
+        self.assertRaises(ValueError, self.db.set_raw_value_type, "str")
+
+    def test_get_into(self):
+        buffer = bytearray(16)
+        self.assertEqual(self.db.get_into(b"key", buffer), 10)
+        self.assertEqual(buffer[:10], b"0123456789")
+        self.assertIsNone(self.db.get_into(b"missing", buffer))
+
+        # undersized buffers are left untouched
+        small = bytearray(b"abc")
+        self.assertEqual(self.db.get_into(b"key", small), 10)
+        self.assertEqual(small, b"abc")
+        self.assertEqual(self.db.get_into(b"empty", small), 0)
+        self.assertEqual(self.db.get_into(b"empty", bytearray()), 0)
+        self.assertEqual(self.db.get_into(b"key", memoryview(buffer)[4:14]), 10)
+        self.assertEqual(buffer[4:14], b"0123456789")
+
+        self.assertRaises(Exception, self.db.get_into, b"key", b"read-only bytes")
+        self.assertRaises(TypeError, self.db.get_into, b"key", memoryview(buffer)[::2])
+
+    @unittest.skipIf(np is None, reason="numpy is not installed")
+    def test_get_into_numpy(self):
+        array = np.zeros(3, dtype=np.uint32)
+        self.db[b"array"] = np.arange(3, dtype=np.uint32)
+        self.assertEqual(self.db.get_into(b"array", array), 12)
+        self.assertEqual(array.tolist(), [0, 1, 2])
+
+    def test_not_raw_mode(self):
+        db = Rdict(self.path + "_not_raw")
+        self.assertRaises(Exception, db.set_raw_value_type, "memoryview")
+        self.assertRaises(Exception, db.get_into, "key", bytearray(8))
+        db.close()
+        Rdict.destroy(self.path + "_not_raw")
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path, Options(raw_mode=True))
+
+
+class TestRawModeNumericKeys(unittest.TestCase):
+    path = "./temp_raw_mode_numeric_keys"
+    sst_path = "./temp_raw_mode_numeric_keys.sst"
+
+    def test_int_key_codec(self):
+        self.assertEqual(encode_int_key(1, width=4), b"\x80\x00\x00\x01")
+        self.assertEqual(encode_int_key(1, width=4, signed=False), b"\x00\x00\x00\x01")
+        for value in [-(2 ** 63), -1, 0, 1, 2 ** 63 - 1]:
+            self.assertEqual(decode_int_key(encode_int_key(value)), value)
+        self.assertEqual(decode_int_key(encode_int_key(2 ** 100, width=16, signed=False), signed=False), 2 ** 100)
+        self.assertRaises(OverflowError, lambda: encode_int_key(2 ** 63))
+        self.assertRaises(OverflowError, lambda: encode_int_key(-1, signed=False))
+        self.assertRaises(OverflowError, lambda: encode_int_key(256, width=1, signed=False))
+
+    def test_float_key_codec(self):
+        values = [float("-inf"), -1e10, -1.5, -0.0, 0.0, 1e-300, 2.5, float("inf")]
+        encoded = [encode_float_key(v) for v in values]
+        self.assertEqual(sorted(encoded), encoded)
+        self.assertEqual([decode_float_key(e) for e in encoded], values)
+
+    def test_ordering_in_db(self):
+        opt = Options(raw_mode=True)
+        values = [randint(-(2 ** 40), 2 ** 40) for _ in range(1000)]
+        writer = SstFileWriter(opt)
+        writer.open(self.sst_path)
+        for v in sorted(set(values)):
+            writer[encode_int_key(v)] = b"sst"
+        writer.finish()
+        db = Rdict(self.path, opt)
+        db.ingest_external_file([self.sst_path])
+        for i in range(-100, 100):
+            db[encode_int_key(i)] = b"db"
+        keys = [decode_int_key(k) for k in db.keys()]
+        self.assertEqual(keys, sorted(set(values) | set(range(-100, 100))))
+        db.close()
+        Rdict.destroy(self.path, opt)
+        os.remove(self.sst_path)
+
+
+class TestDatetimeUuidKeys(unittest.TestCase):
+    path = "./temp_datetime_uuid_keys"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+
+    def test_datetime_keys(self):
+        start = datetime(1969, 12, 31, 23, 59, 59, 999999, tzinfo=timezone.utc)
+        times = [start + timedelta(microseconds=i * 7919) for i in range(-999, 1000, 37)]
+        times.append(datetime(1, 1, 1, tzinfo=timezone.utc))
+        times.append(datetime(9999, 12, 31, tzinfo=timezone.utc))
+        for t in reversed(times):
+            self.db[t] = t.isoformat()
+        ordered = sorted(times)
+        self.assertEqual(list(self.db.keys()), ordered)
+        self.assertEqual(self.db[times[5]], times[5].isoformat())
+
+        # other timezones are the same points in time, read back in UTC
+        paris = timezone(timedelta(hours=1))
+        self.assertEqual(self.db[datetime(1970, 1, 1, 1, tzinfo=paris) - timedelta(microseconds=1)], start.isoformat())
+        key = next(iter(self.db.keys(from_key=start)))
+        self.assertEqual(key, start)
+        self.assertEqual(key.tzinfo, timezone.utc)
+        self.assertEqual(list(self.db.keys(from_key=ordered[-2], backwards=True))[:2], ordered[-3:-1][::-1])
+        self.assertRaises(ValueError, self.db.put, datetime(2024, 1, 1), 1)
+
+    def test_uuid_keys(self):
+        ids = sorted(uuid.uuid4() for _ in range(100))
+        for i, key in enumerate(ids):
+            self.db[key] = i
+        self.assertEqual(list(self.db.keys()), ids)
+        self.assertEqual(self.db[ids[42]], 42)
+        self.assertEqual(list(self.db.keys(from_key=ids[98])), ids[98:])
+        self.assertEqual(decode_key(encode_key(ids[0])), ids[0])
+        self.assertEqual(encode_key(ids[0]), b"\x09" + ids[0].bytes)
+
+    def test_mixed_types(self):
+        keys = [b"b", "s", 1, 1.5, True, datetime(2024, 1, 1, tzinfo=timezone.utc), uuid.UUID(int=1)]
+        for key in reversed(keys):
+            self.db[key] = key
+        self.assertEqual(list(self.db.keys()), keys)
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestTupleKeys(unittest.TestCase):
+    path = "./temp_tuple_keys"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+
+    def test_order(self):
+        keys = [
+            ("a",), ("a", "b"), ("a", "b\x00"), ("a", "b\x00c"), ("a", "bc"),
+            ("a\x00",), ("a\x00", ""), ("b", 1),
+        ]
+        for key in reversed(keys):
+            self.db[key] = key
+        self.assertEqual(list(self.db.keys()), sorted(keys))
+        self.assertEqual(self.db[("a", "b\x00c")], ("a", "b\x00c"))
+
+    def test_numbers(self):
+        numbers = [-2**100, -256, -255, -1, 0, 1, 255, 256, 2**100]
+        floats = [-float("inf"), -1.5, -0.0, 0.0, 1e-300, 2.5, float("inf")]
+        keys = [(1, n, 0.5) for n in numbers] + [(2, f) for f in floats]
+        for key in reversed(keys):
+            self.db[key] = True
+        self.assertEqual(list(self.db.keys()), keys)
+        key = (b"\x00\xff", True, (3, ("nested",)), -7)
+        self.db[key] = 1
+        self.assertEqual(self.db.get(key), 1)
+        self.assertEqual(decode_key(encode_key(key)), key)
+        self.assertIn(key, list(self.db.keys()))
+        self.assertRaises(Exception, encode_key, ("a", object()))
+
+    def test_prefix_and_ranges(self):
+        for tenant in ["t1", "t2", "t10"]:
+            for category in ["x", "y"]:
+                for ts in range(3):
+                    self.db[(tenant, category, ts)] = ts
+        self.db["t1"] = "not a tuple"
+        self.db[("t1",)] = "tenant"
+
+        t1 = [("t1",)] + [("t1", c, ts) for c in ["x", "y"] for ts in range(3)]
+        self.assertEqual(list(self.db.keys_with_prefix(("t1",))), t1)
+        self.assertEqual(list(self.db.keys_with_prefix(("t1", "y"))), t1[4:])
+        self.assertEqual([k for k, _ in self.db.range(("t1",), ("t1", "zzz"))], t1)
+        self.assertEqual(self.db.count(from_key=("t1", "x"), to_key=("t1", "y")), 3)
+
+        self.db.delete_range(("t1",), ("t1", "y"))
+        self.assertEqual(list(self.db.keys_with_prefix(("t1",))), t1[4:])
+        self.assertEqual(self.db["t1"], "not a tuple")
+        self.assertEqual(len(list(self.db.keys_with_prefix(("t10",)))), 6)
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestEncodingIntrospection(unittest.TestCase):
+    path = "./temp_encoding_introspection"
+    sst_path = "./temp_encoding_introspection.sst"
+
+    def test_key_encoding(self):
+        self.assertEqual(encode_key(b"a"), b"\x01a")
+        self.assertEqual(encode_key("a"), b"\x02a")
+        self.assertEqual(encode_key(1), b"\x03\x01")
+        self.assertEqual(encode_key(True), b"\x05\x01")
+        self.assertEqual(encode_key(b"a", raw_mode=True), b"a")
+        for key in [b"bytes", "str", -(2 ** 70), 0, 1.5, False]:
+            self.assertEqual(decode_key(encode_key(key)), key)
+        self.assertRaises(Exception, lambda: encode_key([1, 2]))
+        self.assertRaises(Exception, lambda: encode_key("a", raw_mode=True))
+
+    def test_value_encoding(self):
+        self.assertEqual(encode_value("a"), b"\x02a")
+        self.assertEqual(decode_value(encode_value({"a": [1, 2]})), {"a": [1, 2]})
+        encoded = encode_value({"a": 1}, dumps=lambda o: dumps(o).encode())
+        self.assertEqual(encoded, b"\x06" + dumps({"a": 1}).encode())
+        self.assertEqual(decode_value(encoded, loads=lambda b: loads(b.decode())), {"a": 1})
+        self.assertEqual(decode_value(b"raw", raw_mode=True), b"raw")
+
+    def test_sst_ordering(self):
+        keys = [f"key{randint(0, 10000)}" for _ in range(100)]
+        writer = SstFileWriter()
+        writer.open(self.sst_path)
+        self.assertEqual(writer.num_entries(), 0)
+        for key in sorted(set(keys), key=encode_key):
+            writer[key] = key
+        del writer["zzz"]
+        self.assertEqual(writer.num_entries(), len(set(keys)) + 1)
+        self.assertRaises(Exception, writer.__setitem__, "a", "out of order")
+        self.assertEqual(writer.num_entries(), len(set(keys)) + 1)
+        writer.finish()
+        db = Rdict(self.path)
+        db.ingest_external_file([self.sst_path])
+        self.assertEqual(list(db.keys()), sorted(set(keys)))
+        db.close()
+        Rdict.destroy(self.path)
+        os.remove(self.sst_path)
+
+
+class TestSstFileWriterCompression(unittest.TestCase):
+    path = "./temp_sst_compression"
+    sst_path = "./temp_sst_compression.sst"
+
+    def write_sst(self, writer):
+        writer.open(self.sst_path)
+        for i in range(1000):
+            writer[f"key{i:04}"] = "value" * 20
+        writer.finish()
+        return os.path.getsize(self.sst_path)
+
+    def test_set_compression(self):
+        opt = Options()
+        opt.set_compression_type(DBCompressionType.none())
+        writer = SstFileWriter(opt)
+        self.assertEqual(writer.compression(), "none")
+        uncompressed_size = self.write_sst(writer)
+
+        writer.set_compression(DBCompressionType.zstd(), level=9)
+        self.assertEqual(writer.compression(), "zstd")
+        self.assertLess(self.write_sst(writer), uncompressed_size / 2)
+
+        db = Rdict(self.path)
+        db.ingest_external_file([self.sst_path])
+        self.assertEqual(db["key0999"], "value" * 20)
+        db.close()
+
+    def test_set_compression_while_open(self):
+        writer = SstFileWriter()
+        writer.open(self.sst_path)
+        self.assertRaises(Exception, writer.set_compression, DBCompressionType.lz4())
+        writer["key"] = "value"
+        writer.finish()
+        writer.set_compression(DBCompressionType.lz4())
+        self.assertEqual(writer.compression(), "lz4")
+
+    def tearDown(self):
+        gc.collect()
+        Rdict.destroy(self.path)
+        if os.path.exists(self.sst_path):
+            os.remove(self.sst_path)
+
+
+class TestSerializer(unittest.TestCase):
+    path = "./temp_serializer"
+    sst_path = "./temp_serializer.sst"
+
+    @unittest.skipIf(msgpack is None, reason="msgpack is not installed")
+    def test_msgpack(self):
+        db = Rdict(self.path, serializer="msgpack")
+        db["a"] = {"b": [1, 2]}
+        db.close()
+
+        db = Rdict(self.path)
+        self.assertEqual(db["a"], {"b": [1, 2]})
+        db.set_loads(lambda b: b)
+        self.assertEqual(db["a"], msgpack.packb({"b": [1, 2]}))
+        db.close()
+
+    def test_json(self):
+        db = Rdict(self.path, serializer="json")
+        db["a"] = [1, {"b": None}]
+        writer = SstFileWriter(serializer="json")
+        writer.open(self.sst_path)
+        writer["b"] = {"c": 1}
+        writer.finish()
+        db.ingest_external_file([self.sst_path])
+        db.close()
+
+        db = Rdict(self.path)
+        self.assertEqual(db["a"], [1, {"b": None}])
+        self.assertEqual(db["b"], {"c": 1})
+        db.close()
+
+    def test_mismatch(self):
+        Rdict(self.path).close()
+        self.assertRaises(Exception, Rdict, self.path, serializer="json")
+        self.assertRaises(Exception, Rdict, self.path, serializer="unknown")
+        Rdict(self.path, serializer="pickle").close()
+
+    def test_custom(self):
+        db = Rdict(self.path, serializer=(lambda o: repr(o).encode(), lambda b: eval(b)))
+        db["a"] = (1, 2)
+        db.close()
+        self.assertRaises(Exception, Rdict, self.path)
+        db = Rdict(self.path, serializer=(lambda o: repr(o).encode(), lambda b: eval(b)))
+        self.assertEqual(db["a"], (1, 2))
+        db.close()
+
+    def tearDown(self):
+        gc.collect()
+        Rdict.destroy(self.path)
+        if os.path.exists(self.sst_path):
+            os.remove(self.sst_path)
+
+
+class TestSstFileWriterTimestamps(unittest.TestCase):
+    sst_path = "./temp_sst_timestamps.sst"
+
+    def test_put_with_ts(self):
+        opt = Options(raw_mode=True)
+        opt.set_u64_timestamp_comparator()
+        writer = SstFileWriter(opt)
+        writer.open(self.sst_path)
+        writer.put_with_ts(b"a", 2, b"new")
+        writer.put_with_ts(b"a", 1, b"old")
+        writer.delete_with_ts(b"b", 5)
+        writer.put_with_ts(b"c", 1, b"value")
+        # the timestamps of a key must be decreasing
+        self.assertRaises(Exception, writer.put_with_ts, b"c", 2, b"value")
+        self.assertEqual(writer.num_entries(), 4)
+        writer.finish()
+
+    def test_requires_timestamp_comparator(self):
+        writer = SstFileWriter(Options(raw_mode=True))
+        writer.open(self.sst_path)
+        self.assertRaises(Exception, writer.put_with_ts, b"a", 1, b"value")
+        self.assertRaises(Exception, writer.delete_with_ts, b"a", 1)
+        self.assertRaises(Exception, Options().set_u64_timestamp_comparator)
+
+    def tearDown(self):
+        gc.collect()
+        if os.path.exists(self.sst_path):
+            os.remove(self.sst_path)
+
+
+class TestUserDefinedTimestamps(unittest.TestCase):
+    path = "./temp_user_defined_timestamps"
+
+    def open(self):
+        opt = Options(raw_mode=True)
+        opt.set_u64_timestamp_comparator()
+        return Rdict(self.path, opt)
+
+    def test_read_at_timestamp(self):
+        db = self.open()
+        db.put(b"a", b"old", ts=1)
+        db.put(b"a", b"new", ts=2)
+        db.put(b"b", b"value", ts=1)
+        db.delete(b"b", ts=3)
+        self.assertEqual(db.get(b"a", ts=1), b"old")
+        self.assertEqual(db.get(b"a", ts=5), b"new")
+        self.assertIsNone(db.get(b"a", ts=0))
+        self.assertEqual(db.get(b"b", ts=2), b"value")
+        self.assertIsNone(db.get(b"b", ts=3))
+        self.assertEqual(db.get([b"a", b"b"], ts=2), [b"new", b"value"])
+
+        read_opt = ReadOptions()
+        read_opt.set_timestamp(1)
+        self.assertEqual(read_opt.to_dict()["timestamp"], 1)
+        self.assertEqual(db.get(b"a", read_opt=read_opt), b"old")
+        # the ts argument overrides the read options
+        self.assertEqual(db.get(b"a", read_opt=read_opt, ts=2), b"new")
+        db.set_read_options(read_opt)
+        self.assertEqual(db[b"a"], b"old")
+        db.close()
+
+    def test_reopen(self):
+        db = self.open()
+        db.put(b"a", b"value", ts=1)
+        db.close()
+        # the timestamp comparator is saved in the config
+        db = Rdict(self.path)
+        self.assertEqual(db.get(b"a", ts=1), b"value")
+        db.close()
+        self.assertRaises(Exception, Rdict, self.path, Options(raw_mode=True))
+
+    def test_requires_timestamp_comparator(self):
+        db = Rdict(self.path, Options(raw_mode=True))
+        self.assertRaises(Exception, db.put, b"a", b"value", ts=1)
+        self.assertRaises(Exception, db.delete, b"a", ts=1)
+        self.assertRaises(Exception, db.get, b"a", ts=1)
+        db.close()
+        opt = Options(raw_mode=True)
+        opt.set_u64_timestamp_comparator()
+        self.assertRaises(Exception, Rdict, self.path, opt)
+
+    def tearDown(self):
+        gc.collect()
+        Rdict.destroy(self.path, Options(raw_mode=True))
+
+
+class TestWriteBatchIntrospection(unittest.TestCase):
+    test_dict = None
+    path = "./temp_write_batch_introspection"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_iterate(self):
+        assert self.test_dict is not None
+        self.test_dict.create_column_family("introspection_cf")
+        cf = self.test_dict.get_column_family_handle("introspection_cf")
+        wb = WriteBatch()
+        wb.put("a", {"v": 1})
+        wb.put(1, "b", cf)
+        wb.delete("c")
+        wb.single_delete(2.5, cf)
+        wb.delete_range("d", "e")
+        self.assertEqual(wb.count(), 5)
+        self.assertEqual(len(wb), 5)
+        self.assertEqual(wb.size_in_bytes(), len(wb.data()))
+
+        records = wb.iterate()
+        self.assertEqual([op for op, _, _, _ in records],
+                         ["put", "put", "delete", "single_delete", "delete_range"])
+        self.assertEqual(records[0][2], encode_key("a"))
+        self.assertEqual(records[0][3], encode_value({"v": 1}))
+        self.assertEqual(records[2][3], None)
+
+        decoded = wb.iterate(decode=True)
+        cf_id = decoded[1][1]
+        self.assertNotEqual(cf_id, 0)
+        self.assertEqual(decoded, [
+            ("put", 0, "a", {"v": 1}),
+            ("put", cf_id, 1, "b"),
+            ("delete", 0, "c", None),
+            ("single_delete", cf_id, 2.5, None),
+            ("delete_range", 0, "d", "e"),
+        ])
+
+    def test_raw_mode_and_custom_loads(self):
+        wb = WriteBatch(raw_mode=True)
+        wb[b"k"] = b"v"
+        self.assertEqual(wb.iterate(decode=True), [("put", 0, b"k", b"v")])
+        wb = WriteBatch()
+        wb.set_dumps(lambda o: dumps(o).encode())
+        wb.set_loads(lambda b: loads(b.decode()))
+        wb["json"] = [1, 2]
+        self.assertEqual(wb.iterate(decode=True), [("put", 0, "json", [1, 2])])
+
+    def test_merge_operands_not_decoded(self):
+        wb = WriteBatch(raw_mode=True)
+        wb.merge(b"counter", (5).to_bytes(8, "little"))
+        wb.merge(b"log", b"line")
+        self.assertEqual(wb.iterate(decode=True), [
+            ("merge", 0, b"counter", (5).to_bytes(8, "little")),
+            ("merge", 0, b"log", b"line"),
+        ])
+        wb = WriteBatch()
+        wb.set_loads(lambda b: loads(b.decode()))
+        wb.merge("counter", 5)
+        wb.merge("log", "line")
+        self.assertEqual(wb.iterate(decode=True), [
+            ("merge", 0, "counter", encode_value(5)),
+            ("merge", 0, "log", encode_value("line")),
+        ])
+
+    def test_from_data(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.create_column_family("replay_cf")
+        cf_handle = self.test_dict.get_column_family_handle("replay_cf")
+        wb = WriteBatch()
+        wb.put("replayed", 1)
+        wb.put("replayed", 2, cf_handle)
+        wb.delete("not_there")
+        replayed = WriteBatch.from_data(wb.data())
+        self.assertEqual(replayed.iterate(), wb.iterate())
+        self.assertEqual(replayed.data(), wb.data())
+        self.test_dict.write(replayed)
+        self.assertEqual(self.test_dict["replayed"], 1)
+        self.assertEqual(cf["replayed"], 2)
+        del cf
+
+    def test_from_malformed_data(self):
+        wb = WriteBatch()
+        wb.put("a", 1)
+        data = wb.data()
+        self.assertRaises(Exception, lambda: WriteBatch.from_data(b"short"))
+        self.assertRaises(Exception, lambda: WriteBatch.from_data(data[:-1]))
+        self.assertRaises(Exception, lambda: WriteBatch.from_data(data + b"\xff"))
+        # wrong count in the header
+        self.assertRaises(Exception, lambda: WriteBatch.from_data(data[:8] + b"\x02\x00\x00\x00" + data[12:]))
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestPutIfAbsent(unittest.TestCase):
+    test_dict = None
+    path = "./temp_put_if_absent"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_put_if_absent(self):
+        assert self.test_dict is not None
+        self.assertTrue(self.test_dict.put_if_absent("init", {"retries": 3}))
+        self.assertFalse(self.test_dict.put_if_absent("init", {"retries": 5}))
+        self.assertEqual(self.test_dict["init"], {"retries": 3})
+        del self.test_dict["init"]
+        self.assertTrue(self.test_dict.put_if_absent("init", 1, WriteOptions()))
+        self.assertEqual(self.test_dict["init"], 1)
+
+    def test_column_family(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.create_column_family("put_if_absent_cf")
+        self.test_dict["shared"] = "default"
+        self.assertTrue(cf.put_if_absent("shared", "cf"))
+        self.assertFalse(cf.put_if_absent("shared", "again"))
+        self.assertEqual(cf["shared"], "cf")
+        self.assertEqual(self.test_dict["shared"], "default")
+        del cf
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestDeleteMany(unittest.TestCase):
+    test_dict = None
+    path = "./temp_delete_many"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_delete_many(self):
+        assert self.test_dict is not None
+        for i in range(1000):
+            self.test_dict[i] = i
+        self.test_dict.delete_many(range(500))
+        self.assertEqual(self.test_dict.get([0, 499, 500]), [None, None, 500])
+        self.test_dict.delete_many((k for k in [500, "missing", 501]), WriteOptions())
+        self.assertEqual(list(self.test_dict.keys()), list(range(502, 1000)))
+        self.assertRaises(Exception, lambda: self.test_dict.delete_many([[1]]))
+        self.assertEqual(self.test_dict.count(), 498)
+
+    def test_column_family(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.create_column_family("delete_many_cf")
+        self.test_dict["both"] = 1
+        cf["both"] = 2
+        cf.delete_many(["both"])
+        self.assertNotIn("both", cf)
+        self.assertIn("both", self.test_dict)
+        del self.test_dict["both"]
+        del cf
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestUpdate(unittest.TestCase):
+    test_dict = None
+    path = "./temp_update"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_update(self):
+        assert self.test_dict is not None
+        self.test_dict.update({i: i * i for i in range(100)})
+        self.test_dict.update(((str(i), [i]) for i in range(10)), WriteOptions())
+        self.test_dict.update([[1000, "list pair"]])
+        self.assertEqual(self.test_dict[10], 100)
+        self.assertEqual(self.test_dict["9"], [9])
+        self.assertEqual(self.test_dict[1000], "list pair")
+
+    def test_mapping(self):
+        assert self.test_dict is not None
+        from collections import OrderedDict
+
+        class Mapping:
+            def keys(self):
+                return ["m1", "m2"]
+
+            def __getitem__(self, key):
+                return key.upper()
+
+        self.test_dict.update(OrderedDict(o1=1))
+        self.test_dict.update(Mapping())
+        self.assertEqual(self.test_dict.get(["o1", "m1", "m2"]), [1, "M1", "M2"])
+
+    def test_invalid_pairs_write_nothing(self):
+        assert self.test_dict is not None
+        self.assertRaises(Exception, lambda: self.test_dict.update([("ok", 1), ("a", 1, 2)]))
+        self.assertRaises(Exception, lambda: self.test_dict.update([("ok", 1), 2]))
+        self.assertNotIn("ok", self.test_dict)
+
+    def test_kwargs(self):
+        assert self.test_dict is not None
+        self.test_dict.update(kw1=1, kw2=2)
+        self.test_dict.update({"kw2": "mapping", "kw3": 3}, kw2="kwarg")
+        self.test_dict.update([("kw4", 4)], kw5=5)
+        self.assertEqual(
+            self.test_dict.get(["kw1", "kw2", "kw3", "kw4", "kw5"]),
+            [1, "kwarg", 3, 4, 5],
+        )
+
+    def test_failing_encode(self):
+        assert self.test_dict is not None
+        pairs = [("enc1", 1), ("enc2", lambda: None), ("enc3", 3)]
+        self.assertRaises(Exception, self.test_dict.update, pairs)
+        self.assertNotIn("enc1", self.test_dict)
+        self.assertRaises(Exception, self.test_dict.update, pairs, atomic=False)
+        self.assertEqual(self.test_dict["enc1"], 1)
+        self.assertNotIn("enc3", self.test_dict)
+
+    def test_streaming(self):
+        assert self.test_dict is not None
+        self.test_dict.update((("stream", i) for i in range(10)), atomic=False)
+        self.test_dict.update({"stream_map": 1}, WriteOptions(), False, stream_kw=2)
+        self.assertEqual(self.test_dict.get(["stream", "stream_map", "stream_kw"]), [9, 1, 2])
+
+
+    def test_column_family(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.create_column_family("update_cf")
+        cf.update({"cf_only": 1})
+        self.assertEqual(cf["cf_only"], 1)
+        self.assertNotIn("cf_only", self.test_dict)
+        del cf
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestPerCallOptions(unittest.TestCase):
+    test_dict = None
+    path = "./temp_per_call_options"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_write_options_keywords(self):
+        opt = WriteOptions(sync=True, no_slowdown=True, low_pri=True, memtable_insert_hint_per_batch=True)
+        self.assertTrue(opt.sync)
+        self.assertFalse(opt.disable_wal)
+        self.assertTrue(opt.no_slowdown)
+        self.assertTrue(opt.low_pri)
+        self.assertTrue(opt.memtable_insert_hint_per_batch)
+
+    def test_override_does_not_mutate_defaults(self):
+        assert self.test_dict is not None
+        read_opt = ReadOptions()
+        read_opt.fill_cache(False)
+        self.test_dict.put("k", 1, WriteOptions(sync=True))
+        self.assertEqual(self.test_dict.get("k", read_opt=read_opt), 1)
+        self.assertEqual(self.test_dict.get(["k", "x"], read_opt=read_opt), [1, None])
+        self.assertTrue(self.test_dict.key_may_exist("k", read_opt=read_opt))
+        self.test_dict.delete("k", WriteOptions(disable_wal=True))
+        self.assertNotIn("k", self.test_dict)
+
+    def test_mixed_concurrent_writers(self):
+        assert self.test_dict is not None
+        db = self.test_dict
+        sync_opt = WriteOptions(sync=True)
+        fast_opt = WriteOptions(disable_wal=True)
+
+        def writer(prefix, opt):
+            for i in range(200):
+                db.put(f"{prefix}{i:03}", i, opt)
+                wb = WriteBatch()
+                wb.put(f"{prefix}batch{i:03}", i)
+                db.write(wb, opt)
+
+        threads = [Thread(target=writer, args=(f"t{n}:", sync_opt if n % 2 else fast_opt)) for n in range(4)]
+        for t in threads:
+            t.start()
+        for t in threads:
+            t.join()
+        for n in range(4):
+            self.assertEqual(db.count(f"t{n}:", f"t{n};"), 400)
+        self.assertFalse(sync_opt.disable_wal)
+        self.assertFalse(fast_opt.sync)
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestLen(unittest.TestCase):
+    test_dict = None
+    path = "./temp_len"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_len(self):
+        assert self.test_dict is not None
+        self.assertEqual(self.test_dict.exact_len(), 0)
+        for i in range(1000):
+            self.test_dict[i] = i
+        self.test_dict.flush()
+        self.assertGreater(len(self.test_dict), 0)
+        self.assertAlmostEqual(len(self.test_dict), 1000, delta=200)
+        self.assertEqual(self.test_dict.exact_len(), 1000)
+        del self.test_dict[0]
+        self.assertEqual(self.test_dict.exact_len(), 999)
+
+    def test_column_family_len(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.create_column_family("len_cf")
+        self.assertEqual(len(cf), 0)
+        self.assertEqual(cf.exact_len(), 0)
+        cf["a"] = 1
+        self.assertEqual(cf.exact_len(), 1)
+        del cf
+
+    def test_bool(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.create_column_family("bool_cf")
+        self.assertFalse(cf)
+        for i in range(100):
+            cf[i] = i
+        cf.flush()
+        self.assertTrue(cf)
+        cf.delete_many(range(100))
+        # the estimate still counts the deleted keys
+        self.assertFalse(cf)
+        self.assertEqual(cf.exact_len(), 0)
+        del cf
+
+    def test_count_exact(self):
+        assert self.test_dict is not None
+        cf = self.test_dict.create_column_family("count_exact_cf")
+        self.assertEqual(cf.count_exact(), 0)
+        for i in range(100):
+            cf[i] = i
+        del cf[0]
+        self.assertEqual(cf.count_exact(), 99)
+        self.assertEqual(cf.count_exact(), cf.exact_len())
+        del cf
+
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestWriteBatchSavePoints(unittest.TestCase):
+    test_dict = None
+    path = "./temp_write_batch_save_points"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.test_dict = Rdict(cls.path)
+
+    def test_rollback(self):
+        assert self.test_dict is not None
+        wb = WriteBatch()
+        for record in [1, 2, "bad", 4]:
+            wb.set_save_point()
+            try:
+                wb.put(f"record{record}:start", True)
+                wb.put(f"record{record}:value", int(record))
+                wb.pop_save_point()
+            except ValueError:
+                wb.rollback_to_save_point()
+        self.assertEqual(wb.count(), 6)
+        self.test_dict.write(wb)
+        self.assertEqual(list(self.test_dict.keys()), [
+            "record1:start", "record1:value",
+            "record2:start", "record2:value",
+            "record4:start", "record4:value",
+        ])
+
+    def test_nested_save_points(self):
+        wb = WriteBatch()
+        wb["a"] = 1
+        wb.set_save_point()
+        wb["b"] = 2
+        wb.set_save_point()
+        del wb["a"]
+        self.assertEqual(wb.count(), 3)
+        wb.rollback_to_save_point()
+        self.assertEqual(wb.count(), 2)
+        wb.rollback_to_save_point()
+        self.assertEqual(wb.count(), 1)
+
+    def test_no_save_point(self):
+        wb = WriteBatch()
+        wb["a"] = 1
+        self.assertRaises(Exception, wb.rollback_to_save_point)
+        self.assertRaises(Exception, wb.pop_save_point)
+        self.assertEqual(wb.count(), 1)
+
+    @classmethod
+    def tearDownClass(cls):
+        assert cls.test_dict is not None
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestContextManager(unittest.TestCase):
+    path = "./temp_context_manager"
+
+    def test_close_on_exit(self):
+        with Rdict(self.path) as db:
+            db["a"] = 1
+        self.assertRaises(DbClosedError, lambda: db["a"])
+        # the lock is released, so it can be reopened
+        with Rdict(self.path) as db:
+            self.assertEqual(db["a"], 1)
+
+    def test_close_on_exception(self):
+        with self.assertRaises(ZeroDivisionError):
+            with Rdict(self.path) as db:
+                db["b"] = 2
+                _ = 1 / 0
+        with Rdict(self.path) as db:
+            self.assertEqual(db["b"], 2)
+
+    def test_close_inside_block(self):
+        with Rdict(self.path) as db:
+            db.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestStatistics(unittest.TestCase):
+    path = "./temp_statistics"
+
+    def test_statistics(self):
+        opt = Options()
+        opt.enable_statistics()
+        opt.set_statistics_level(StatsLevel.all())
+        db = Rdict(self.path, opt)
+        for i in range(100):
+            db[i] = i
+        for i in range(100):
+            self.assertEqual(db[i], i)
+        stats = db.get_statistics()
+        assert stats is not None
+        self.assertEqual(stats.ticker("rocksdb.number.keys.written"), 100)
+        self.assertEqual(stats.ticker("rocksdb.number.keys.read"), 100)
+        self.assertEqual(stats.tickers()["rocksdb.number.keys.written"], 100)
+        histogram = stats.histogram("rocksdb.db.get.micros")
+        self.assertEqual(histogram["count"], 100)
+        self.assertEqual(set(histogram), {"median", "p95", "p99", "average", "std_dev", "max", "count", "sum"})
+        self.assertIn("rocksdb.number.keys.written", stats.to_string())
+
+        stats.reset()
+        self.assertEqual(stats.ticker("rocksdb.number.keys.written"), 0)
+        db["one more"] = 1
+        self.assertEqual(stats.ticker("rocksdb.number.keys.written"), 1)
+        self.assertEqual(db.get_statistics().ticker("rocksdb.number.keys.written"), 101)
+
+        with self.assertRaises(Exception) as context:
+            stats.ticker("rocksdb.number.keys.writen")
+        self.assertIn("rocksdb.number.keys.written", str(context.exception))
+        self.assertRaises(Exception, lambda: stats.histogram("rocksdb.unknown"))
+        self.assertIn("rocksdb.block.cache.hit", Statistics.ticker_names())
+        self.assertIn("rocksdb.db.write.micros", Statistics.histogram_names())
+        db.close()
+
+    def test_statistics_disabled(self):
+        db = Rdict(self.path + "_disabled")
+        self.assertIsNone(db.get_statistics())
+        db.close()
+        Rdict.destroy(self.path + "_disabled")
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestRateLimiter(unittest.TestCase):
+    paths = ["./temp_rate_limiter_1", "./temp_rate_limiter_2"]
+
+    def test_shared_rate_limiter(self):
+        limiter = RateLimiter(64 * 1024 * 1024, refill_period_us=50_000, fairness=5, mode="all_io")
+        opt = Options()
+        opt.set_rate_limiter(limiter)
+        # the limiter is owned by the options from now on
+        del limiter
+        gc.collect()
+        dbs = [Rdict(path, opt) for path in self.paths]
+        for db in dbs:
+            for i in range(1000):
+                db[i] = b"x" * 100
+            db.flush()
+            db.compact_range(None, None)
+        for db in dbs:
+            self.assertEqual(db.exact_len(), 1000)
+            db.close()
+
+    def test_auto_tuned(self):
+        opt = Options()
+        opt.set_rate_limiter(RateLimiter(1024 * 1024, auto_tuned=True))
+        db = Rdict(self.paths[0], opt)
+        db["a"] = 1
+        db.close()
+
+    def test_invalid_arguments(self):
+        self.assertRaises(Exception, lambda: RateLimiter(1024, mode="sometimes"))
+        self.assertRaises(Exception, lambda: RateLimiter(0))
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        for path in cls.paths:
+            Rdict.destroy(path)
+
+
+class TestDestroyAndRepair(unittest.TestCase):
+    path = "./temp_destroy_and_repair"
+
+    def test_refuse_while_open(self):
+        db = Rdict(self.path)
+        db["a"] = 1
+        it = db.iter()
+        db.close()
+        # the iterator still keeps the db open
+        self.assertRaises(Exception, lambda: Rdict.destroy(self.path))
+        self.assertRaises(Exception, lambda: Rdict.repair(self.path))
+        self.assertTrue(os.path.exists(os.path.join(self.path, "rocksdict-config.json")))
+        del it
+        gc.collect()
+        with Rdict(self.path) as db:
+            self.assertEqual(db["a"], 1)
+        Rdict.destroy(self.path)
+        self.assertFalse(os.path.exists(os.path.join(self.path, "CURRENT")))
+
+    def test_repair(self):
+        with Rdict(self.path) as db:
+            for i in range(100):
+                db[i] = i
+            db.flush()
+        Rdict.repair(self.path)
+        with Rdict(self.path) as db:
+            self.assertEqual(list(db.values()), list(range(100)))
+        Rdict.destroy(self.path)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestSharedCache(unittest.TestCase):
+    paths = ["./temp_shared_cache_1", "./temp_shared_cache_2"]
+
+    def test_shared_block_cache(self):
+        cache = Cache(16 * 1024 * 1024, num_shard_bits=2)
+        table_opt = BlockBasedOptions()
+        table_opt.set_block_cache(cache)
+        opt = Options()
+        opt.set_block_based_table_factory(table_opt)
+        dbs = [Rdict(path, opt) for path in self.paths]
+        for db in dbs:
+            for i in range(10000):
+                db[i] = b"x" * 100
+            db.flush()
+
+        usages = [cache.get_usage()]
+        for db in dbs:
+            for i in range(10000):
+                db[i]
+            usages.append(cache.get_usage())
+        # both databases fill the same cache
+        self.assertLess(usages[0], usages[1])
+        self.assertLess(usages[1], usages[2])
+
+        cache.set_capacity(1024)
+        self.assertLessEqual(cache.get_usage(), usages[1])
+        # the databases keep the cache alive
+        del cache, table_opt, opt
+        gc.collect()
+        for db in dbs:
+            self.assertEqual(db[0], b"x" * 100)
+            db.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        for path in cls.paths:
+            Rdict.destroy(path)
+
+
+class TestGetUpdatesSince(unittest.TestCase):
+    paths = ["./temp_get_updates_since", "./temp_get_updates_since_replica"]
+
+    def test_latest_sequence_number(self):
+        path = self.paths[0] + "_seq"
+        db = Rdict(path)
+        self.assertEqual(db.latest_sequence_number(), 0)
+        db["a"] = 1
+        db.create_column_family("cf")["b"] = 2
+        del db["a"]
+        self.assertEqual(db.latest_sequence_number(), 3)
+        db.close()
+        self.assertRaises(Exception, db.latest_sequence_number)
+        with Rdict(path) as db:
+            self.assertEqual(db.latest_sequence_number(), 3)
+        Rdict.destroy(path)
+
+    def test_tail_and_replay(self):
+        db = Rdict(self.paths[0])
+        start = db.latest_sequence_number() + 1
+        db["a"] = 1
+        wb = WriteBatch()
+        wb.put("b", [2])
+        wb.delete("a")
+        db.write(wb)
+
+        updates = list(db.get_updates_since(start))
+        self.assertEqual([seq for seq, _ in updates], [start, start + 1])
+        self.assertEqual([batch.iterate(decode=True) for _, batch in updates], [
+            [("put", 0, "a", 1)],
+            [("put", 0, "b", [2]), ("delete", 0, "a", None)],
+        ])
+        self.assertEqual(db.latest_sequence_number(), start + 2)
+
+        # only the updates after a sequence number
+        later = list(db.get_updates_since(start + 1))
+        self.assertEqual(len(later), 1)
+        self.assertEqual(later[0][1].data(), wb.data())
+
+        replica = Rdict(self.paths[1])
+        for _, batch in updates:
+            replica.write(WriteBatch.from_data(batch.data()))
+        self.assertEqual(dict(replica.items()), dict(db.items()))
+        replica.close()
+        db.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        for path in cls.paths:
+            Rdict.destroy(path)
+
+
+class TestWriteBufferManager(unittest.TestCase):
+    paths = ["./temp_write_buffer_manager_1", "./temp_write_buffer_manager_2"]
+
+    def test_shared_write_buffer_manager(self):
+        cache = Cache(64 * 1024 * 1024)
+        wbm = WriteBufferManager(16 * 1024 * 1024, cache=cache)
+        self.assertTrue(wbm.enabled())
+        self.assertEqual(wbm.buffer_size(), 16 * 1024 * 1024)
+        opt = Options()
+        opt.set_write_buffer_manager(wbm)
+        dbs = [Rdict(path, opt) for path in self.paths]
+        usages = [wbm.memory_usage()]
+        for db in dbs:
+            for i in range(1000):
+                db[i] = b"x" * 100
+            usages.append(wbm.memory_usage())
+        # both databases are accounted in the same manager
+        self.assertLess(usages[0], usages[1])
+        self.assertLess(usages[1], usages[2])
+        # memtables are charged against the cache
+        self.assertGreaterEqual(cache.get_usage(), usages[2])
+
+        wbm.set_buffer_size(32 * 1024 * 1024)
+        self.assertEqual(wbm.get_buffer_size(), 32 * 1024 * 1024)
+        # the databases keep the manager alive
+        del wbm, cache, opt
+        gc.collect()
+        for db in dbs:
+            db[0] = b"y"
+            self.assertEqual(db[0], b"y")
+            db.close()
+
+    def test_default_arguments(self):
+        wbm = WriteBufferManager(1024 * 1024)
+        self.assertEqual(wbm.buffer_size(), 1024 * 1024)
+        self.assertEqual(wbm.memory_usage(), 0)
+        wbm = WriteBufferManager(1024 * 1024, allow_stall=True)
+        self.assertEqual(wbm.get_usage(), 0)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        for path in cls.paths:
+            Rdict.destroy(path)
+
+
+class TestWalOptions(unittest.TestCase):
+    path = "./temp_wal_options"
+    wal_path = "./temp_wal_options_wal"
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options()
+        cls.opt.set_wal_dir(os.path.abspath(cls.wal_path))
+        cls.opt.set_wal_ttl_seconds(3600)
+        cls.opt.set_wal_size_limit_mb(64)
+
+    def test_wal_dir_and_archive(self):
+        db = Rdict(self.path, self.opt)
+        start = db.latest_sequence_number() + 1
+        db["a"] = 1
+        db.flush()
+        db["b"] = 2
+        self.assertTrue(any(f.endswith(".log") for f in os.listdir(self.wal_path)))
+        self.assertFalse(any(f.endswith(".log") for f in os.listdir(self.path)))
+        # the flushed WAL is archived and can still be tailed
+        updates = [batch.iterate(decode=True) for _, batch in db.get_updates_since(start)]
+        self.assertEqual(updates, [[("put", 0, "a", 1)], [("put", 0, "b", 2)]])
+        db.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestMemEnv(unittest.TestCase):
+    path = "./temp_mem_env"
+
+    def test_in_memory_db(self):
+        env = Env.mem_env()
+        env.set_background_threads(2)
+        env.set_high_priority_background_threads(1)
+        env.set_low_priority_background_threads(2)
+        env.lower_thread_pool_io_priority()
+        opt = Options()
+        opt.set_env(env)
+        db = Rdict(self.path, opt)
+        for i in range(100):
+            db[i] = str(i)
+        db.flush()
+        cf = db.create_column_family("cf", opt)
+        cf["a"] = 1
+        self.assertEqual(list(db.values()), [str(i) for i in range(100)])
+        self.assertEqual(cf["a"], 1)
+        self.assertFalse(os.path.exists(self.path))
+        del cf
+        db.close()
+        self.assertFalse(os.path.exists(self.path))
+
+    def test_default_env(self):
+        opt = Options()
+        opt.set_env(Env.default())
+        with Rdict(self.path, opt) as db:
+            db["a"] = 1
+        self.assertTrue(os.path.exists(self.path))
+        Rdict.destroy(self.path)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestFifoCompaction(unittest.TestCase):
+    path = "./temp_fifo_compaction"
+
+    def test_bounded_size(self):
+        fifo = FifoCompactOptions(max_table_files_size=300 * 1024)
+        self.assertFalse(fifo.allow_compaction)
+        fifo.allow_compaction = True
+        fifo.allow_compaction = False
+        opt = Options()
+        opt.set_compaction_style(DBCompactionStyle.fifo())
+        opt.set_fifo_compaction_options(fifo)
+        opt.set_ttl(24 * 60 * 60)
+        db = Rdict(self.path, opt)
+        # ten files of about 100KB each
+        for batch in range(10):
+            for i in range(100):
+                db[batch * 100 + i] = os.urandom(1000)
+            db.flush()
+        db.compact_range(None, None)
+
+        files = db.live_files()
+        self.assertLessEqual(sum(f["size"] for f in files), 300 * 1024)
+        self.assertLess(len(files), 10)
+        # the oldest files were dropped
+        self.assertNotIn(0, db)
+        self.assertIn(999, db)
+        db.close()
+
+    def test_column_family(self):
+        opt = Options()
+        opt.set_compaction_style(DBCompactionStyle.fifo())
+        opt.set_fifo_compaction_options(FifoCompactOptions(max_table_files_size=300 * 1024))
+        db = Rdict(self.path + "_cf")
+        events = db.create_column_family("events", opt)
+        for batch in range(10):
+            for i in range(100):
+                events[batch * 100 + i] = os.urandom(1000)
+                db[batch * 100 + i] = os.urandom(1000)
+            events.flush()
+            db.flush()
+        events.compact_range(None, None)
+        db.compact_range(None, None)
+        # only the FIFO column family drops its oldest data
+        self.assertNotIn(0, events)
+        self.assertIn(999, events)
+        self.assertIn(0, db)
+        del events
+        db.close()
+        Rdict.destroy(self.path + "_cf")
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestMemtableFactory(unittest.TestCase):
+    path = "./temp_memtable_factory"
+
+    def test_hash_memtables_require_prefix_extractor(self):
+        opt = Options()
+        for factory in [MemtableFactory.hash_skip_list(1000, 4, 4),
+                        MemtableFactory.hash_link_list(1000)]:
+            self.assertRaises(Exception, lambda: opt.set_memtable_factory(factory))
+        opt.set_memtable_factory(MemtableFactory.vector())
+
+    def test_memtables(self):
+        for factory in [MemtableFactory.vector(),
+                        MemtableFactory.hash_skip_list(1000, 4, 4),
+                        MemtableFactory.hash_link_list(1000)]:
+            opt = Options(raw_mode=True)
+            opt.set_prefix_extractor(SliceTransform.create_fixed_prefix(3))
+            opt.set_allow_concurrent_memtable_write(False)
+            opt.set_memtable_factory(factory)
+            db = Rdict(self.path, opt)
+            for i in range(100):
+                db[b"key" + str(i).encode()] = str(i).encode()
+            self.assertEqual(db[b"key42"], b"42")
+            db.flush()
+            self.assertEqual(db[b"key99"], b"99")
+            db.close()
+            Rdict.destroy(self.path, opt)
+
+    def test_vector_bulk_load(self):
+        opt = Options()
+        opt.set_memtable_factory(MemtableFactory.vector())
+        db = Rdict(self.path, opt)
+        wb = WriteBatch()
+        for i in range(10000):
+            wb.put(i, i * i)
+        db.write(wb)
+        db.flush()
+        self.assertEqual(db[1234], 1234 * 1234)
+        self.assertEqual(list(db.keys()), list(range(10000)))
+        db.close()
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("allow_concurrent_memtable_write=false", content)
+        self.assertIn("VectorRepFactory", content)
+        Rdict.destroy(self.path, opt)
+
+
+class TestWriteThreadOptions(unittest.TestCase):
+    path = "./temp_write_thread_options"
+
+    def test_incompatible_options(self):
+        opt = Options()
+        opt.set_enable_pipelined_write(True)
+        self.assertRaises(Exception, lambda: opt.set_unordered_write(True))
+        opt.set_enable_pipelined_write(False)
+        opt.set_unordered_write(True)
+        self.assertRaises(Exception, lambda: opt.set_enable_pipelined_write(True))
+        opt.set_inplace_update_support(True)
+        self.assertRaises(Exception, lambda: opt.set_allow_concurrent_memtable_write(True))
+
+    def write_threaded(self, opt, threads=4, n=5000):
+        db = Rdict(self.path, opt)
+
+        def write(t):
+            wo = WriteOptions()
+            wo.disable_wal = True
+            for i in range(n):
+                db.put(t * n + i, i, wo)
+
+        start = time.perf_counter()
+        workers = [Thread(target=write, args=(t,)) for t in range(threads)]
+        for w in workers:
+            w.start()
+        for w in workers:
+            w.join()
+        elapsed = time.perf_counter() - start
+        self.assertEqual(db.count(), threads * n)
+        db.close()
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        Rdict.destroy(self.path)
+        return elapsed, content
+
+    def test_multithreaded_writes(self):
+        baseline, _ = self.write_threaded(Options())
+        for tuning in ["pipelined", "unordered"]:
+            opt = Options()
+            opt.set_allow_concurrent_memtable_write(True)
+            opt.set_enable_write_thread_adaptive_yield(True)
+            opt.set_max_subcompactions(4)
+            if tuning == "pipelined":
+                opt.set_enable_pipelined_write(True)
+            else:
+                opt.set_unordered_write(True)
+            elapsed, content = self.write_threaded(opt)
+            self.assertIn(f"enable_pipelined_write={str(tuning == 'pipelined').lower()}",
+                          content)
+            self.assertIn(f"unordered_write={str(tuning == 'unordered').lower()}", content)
+            self.assertIn("allow_concurrent_memtable_write=true", content)
+            self.assertIn("enable_write_thread_adaptive_yield=true", content)
+            self.assertIn("max_subcompactions=4", content)
+            # generous bound, the writes are dominated by the python interpreter
+            self.assertLess(elapsed, baseline * 3 + 1)
+
+
+class TestSstPartitioner(unittest.TestCase):
+    path = "./temp_sst_partitioner"
+
+    def test_invalid_prefix_len(self):
+        self.assertRaises(Exception, lambda: Options().set_sst_partitioner_fixed_prefix(0))
+
+    def test_fixed_prefix(self):
+        opt = Options(raw_mode=True)
+        opt.set_sst_partitioner_fixed_prefix(4)
+        db = Rdict(self.path, opt)
+        tenants = [b"ten%d" % t for t in range(5)]
+        for batch in range(3):
+            for tenant in tenants:
+                for i in range(100):
+                    db[tenant + b"key%d-%d" % (batch, i)] = b"v" * 100
+            db.flush()
+        db.compact_range(None, None)
+        live_files = db.live_files()
+        self.assertGreaterEqual(len(live_files), len(tenants))
+        for lf in live_files:
+            self.assertEqual(lf["start_key"][:4], lf["end_key"][:4])
+
+        db.delete_files_in_range(b"ten1", b"ten2")
+        self.assertEqual(len(db.live_files()), len(live_files) - 1)
+        self.assertIsNone(db.get(b"ten1key0-0"))
+        self.assertEqual(db[b"ten2key0-0"], b"v" * 100)
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+
+class TestIteratorStatus(unittest.TestCase):
+    path = "./temp_iterator_status"
+
+    def test_corruption_raises(self):
+        opt = Options(raw_mode=True)
+        opt.set_compression_type(DBCompressionType.none())
+        db = Rdict(self.path, opt)
+        for i in range(1000):
+            db[b"key%04d" % i] = b"x" * 100
+        db.flush()
+        it = iter(db.items())
+        self.assertEqual(len(list(it)), 1000)
+        it.status()
+        cf = db.create_column_family("verified", opt)
+        cf[b"cf key"] = b"cf value"
+        cf.flush()
+        db.verify_checksum()
+        del it, cf
+        db.close()
+
+        sst_files = [os.path.join(self.path, f) for f in os.listdir(self.path)
+                     if f.endswith(".sst")]
+        sst_file = max(sst_files, key=os.path.getsize)
+        with open(sst_file, "r+b") as f:
+            f.seek(64)
+            f.write(b"\0" * 64)
+
+        db = Rdict(self.path, opt)
+        with self.assertRaises(Exception) as ctx:
+            db.verify_checksum()
+        self.assertIn("default", str(ctx.exception))
+        self.assertRaises(Exception, lambda: list(db.keys()))
+        self.assertRaises(Exception, lambda: list(db.items(limit=10)))
+        it = db.iter()
+        it.seek_to_first()
+        self.assertFalse(it.valid())
+        self.assertRaises(Exception, it.status)
+        del it
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+
+class TestFileChecksums(unittest.TestCase):
+    path = "./temp_file_checksums"
+
+    def test_crc32c(self):
+        opt = Options()
+        opt.set_file_checksum_gen_factory(FileChecksumGenFactory.crc32c())
+        db = Rdict(self.path, opt)
+        for i in range(1000):
+            db[i] = i
+        db.flush()
+        db.compact_range(None, None)
+        db.verify_checksum()
+        self.assertEqual(db[999], 999)
+        db.close()
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("file_checksum_gen_factory=FileChecksumGenCrc32cFactory", content)
+        manifest = next(f for f in os.listdir(self.path) if f.startswith("MANIFEST-"))
+        with open(os.path.join(self.path, manifest), "rb") as f:
+            self.assertIn(b"FileChecksumCrc32c", f.read())
+        Rdict.destroy(self.path)
+
+
+class TestOrderedNumericKeys(unittest.TestCase):
+    path = "./temp_ordered_numeric_keys"
+
+    def setUp(self):
+        opt = Options()
+        opt.set_ordered_numeric_keys(True)
+        self.db = Rdict(self.path, opt)
+
+    def test_numeric_order(self):
+        numbers = [float("-inf"), -(2**70), -3, -2.5, -1, -0.5, 0, -0.0, 0.0]
+        numbers += [1e-300, 1, 1.5, 2, 255, 256.0, 2**64, 2**200, 2.0**200, 2**200 + 1]
+        numbers += [float("inf")]
+        for number in reversed(numbers):
+            self.db[number] = number
+        self.db.flush()
+        keys = list(self.db.keys())
+        self.assertEqual(keys, sorted(numbers, key=lambda k: (k, isinstance(k, float))))
+        self.assertEqual([str(k) for k in keys[6:9]], ["0", "-0.0", "0.0"])
+        self.assertEqual(list(self.db.keys(from_key=-2.6))[:3], [-2.5, -1, -0.5])
+        self.assertEqual(list(self.db.keys(from_key=1.5, backwards=True))[:3], [1.5, 1, 1e-300])
+        self.assertEqual(list(self.db.keys(from_key=300))[:2], [2**64, 2**200])
+        self.assertEqual(list(self.db.keys(from_key=2.0**200))[:2], [2.0**200, 2**200 + 1])
+
+    def test_type_order(self):
+        for key in [True, 2.5, "a", -1, b"a"]:
+            self.db[key] = key
+        self.assertEqual(list(self.db.keys()), [b"a", "a", -1, 2.5, True])
+
+    def test_reopen(self):
+        self.db[1.5] = 1
+        self.db[-7] = 2
+        self.db.close()
+        self.assertRaises(Exception, Rdict, self.path, Options())
+        opt, _ = Options.load_latest(self.path)
+        self.assertTrue(opt.ordered_numeric_keys)
+        self.db = Rdict(self.path)
+        self.db[-0.25] = 3
+        self.assertEqual(list(self.db.keys()), [-7, -0.25, 1.5])
+
+    def test_raw_mode(self):
+        self.assertRaises(Exception, Options(raw_mode=True).set_ordered_numeric_keys, True)
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestIngestBehind(unittest.TestCase):
+    path = "./temp_ingest_behind"
+    sst_path = "./temp_ingest_behind.sst"
+
+    def write_sst(self, opt, items):
+        writer = SstFileWriter(opt)
+        writer.open(self.sst_path)
+        for key, value in items:
+            writer[key] = value
+        writer.finish()
+
+    def test_ingest_behind(self):
+        opt = Options()
+        opt.set_compaction_style(DBCompactionStyle.universal())
+        opt.set_allow_ingest_behind(True)
+        db = Rdict(self.path, opt)
+        db["user"] = "live"
+        db.flush()
+
+        self.write_sst(opt, [("history", "old"), ("user", "historical")])
+        ingest_opt = IngestExternalFileOptions()
+        ingest_opt.set_ingest_behind(True)
+        db.ingest_external_file([self.sst_path], ingest_opt)
+        self.assertEqual(db.get(["history", "user"]), ["old", "live"])
+
+        # by default, ingested keys supersede the existing ones
+        self.write_sst(opt, [("user", "overwritten")])
+        ingest_opt = IngestExternalFileOptions()
+        ingest_opt.set_allow_blocking_flush(False)
+        ingest_opt.set_snapshot_consistency(True)
+        db.ingest_external_file([self.sst_path], ingest_opt)
+        self.assertEqual(db["user"], "overwritten")
+        db.close()
+
+    def test_ingest_behind_requires_option(self):
+        opt = Options()
+        db = Rdict(self.path, opt)
+        self.write_sst(opt, [("user", "historical")])
+        ingest_opt = IngestExternalFileOptions()
+        ingest_opt.set_ingest_behind(True)
+        with self.assertRaises(Exception):
+            db.ingest_external_file([self.sst_path], ingest_opt)
+        db.close()
+
+    def tearDown(self):
+        gc.collect()
+        Rdict.destroy(self.path)
+        if os.path.exists(self.sst_path):
+            os.remove(self.sst_path)
+
+
+class TestAtomicFlush(unittest.TestCase):
+    path = "./temp_atomic_flush"
+
+    def test_flush_column_families(self):
+        # write without WAL, flush both column families, then exit without closing the db
+        script = (
+            "import os\n"
+            "from rocksdict import Rdict, Options, WriteOptions\n"
+            "opt = Options()\n"
+            "opt.set_atomic_flush(True)\n"
+            f"db = Rdict({self.path!r}, opt)\n"
+            "index = db.create_column_family('index', opt)\n"
+            "write_opt = WriteOptions()\n"
+            "write_opt.disable_wal(True)\n"
+            "db.set_write_options(write_opt)\n"
+            "index.set_write_options(write_opt)\n"
+            "db['user:1'] = 'alice'\n"
+            "index['alice'] = 'user:1'\n"
+            "handles = [db.get_column_family_handle(n) for n in ('default', 'index')]\n"
+            "db.flush(column_families=handles)\n"
+            "db['user:2'] = 'bob'\n"
+            "index['bob'] = 'user:2'\n"
+            "os._exit(0)\n"
+        )
+        self.assertEqual(Popen([sys.executable, "-c", script]).wait(), 0)
+        db = Rdict(self.path)
+        index = db.get_column_family("index")
+        self.assertEqual(db.get(["user:1", "user:2"]), ["alice", None])
+        self.assertEqual(index.get(["alice", "bob"]), ["user:1", None])
+        del index
+        db.close()
+
+    def test_flush_arguments(self):
+        db = Rdict(self.path)
+        db["a"] = 1
+        db.flush(False, [db.get_column_family_handle("default")])
+        with self.assertRaises(TypeError):
+            db.flush(column_families=["default"])
+        db.close()
+
+    def tearDown(self):
+        gc.collect()
+        Rdict.destroy(self.path)
+
+
+class TestManualWalFlush(unittest.TestCase):
+    path = "./temp_manual_wal_flush"
+
+    def write_and_crash(self, flush_wal):
+        # write with manual WAL flush, then exit without closing the db
+        script = (
+            "import os\n"
+            "from rocksdict import Rdict, Options\n"
+            "opt = Options()\n"
+            "opt.set_manual_wal_flush(True)\n"
+            f"db = Rdict({self.path!r}, opt)\n"
+            "db['flushed'] = 1\n"
+            "db.flush_wal(False)\n"
+            "db['buffered'] = 2\n"
+            f"{'db.flush_wal(False)' if flush_wal else 'pass'}\n"
+            "os._exit(0)\n"
+        )
+        self.assertEqual(Popen([sys.executable, "-c", script]).wait(), 0)
+        db = Rdict(self.path)
+        result = db.get(["flushed", "buffered"])
+        db.close()
+        Rdict.destroy(self.path)
+        return result
+
+    def test_wal_recovery_modes(self):
+        modes = {
+            "kTolerateCorruptedTailRecords": DBRecoveryMode.tolerate_corrupted_tail_records(),
+            "kAbsoluteConsistency": DBRecoveryMode.absolute_consistency(),
+            "kPointInTimeRecovery": DBRecoveryMode.point_in_time(),
+            "kSkipAnyCorruptedRecords": DBRecoveryMode.skip_any_corrupted_record(),
+        }
+        for name, mode in modes.items():
+            opt = Options()
+            opt.set_wal_recovery_mode(mode)
+            db = Rdict(self.path, opt)
+            self.assertEqual(db.current_options()["wal_recovery_mode"], name)
+            db.close()
+            Rdict.destroy(self.path)
+
+    def test_point_in_time_recovery(self):
+        script = (
+            "import os\n"
+            "from rocksdict import Rdict\n"
+            f"db = Rdict({self.path!r})\n"
+            "db['a'] = 1\n"
+            "db.flush_wal(True)\n"
+            "db['b'] = 'x' * 1000\n"
+            "db.flush_wal(True)\n"
+            "os._exit(0)\n"
+        )
+        self.assertEqual(Popen([sys.executable, "-c", script]).wait(), 0)
+        # cut the last record, as if the process died while writing it
+        wal = max(f for f in os.listdir(self.path) if f.endswith(".log"))
+        wal = os.path.join(self.path, wal)
+        os.truncate(wal, os.path.getsize(wal) - 100)
+        opt = Options()
+        opt.set_wal_recovery_mode(DBRecoveryMode.point_in_time())
+        db = Rdict(self.path, opt)
+        self.assertEqual(db.get(["a", "b"]), [1, None])
+        db.close()
+        Rdict.destroy(self.path)
+
+    def test_crash_loses_buffered_writes(self):
+        self.assertEqual(self.write_and_crash(flush_wal=False), [1, None])
+        self.assertEqual(self.write_and_crash(flush_wal=True), [1, 2])
+
+    def test_wal_options(self):
+        opt = Options()
+        opt.set_manual_wal_flush(True)
+        opt.set_track_and_verify_wals_in_manifest(True)
+        opt.set_wal_ttl_seconds(3600)
+        opt.set_wal_size_limit_mb(64)
+        self.assertTrue(opt.to_dict()["manual_wal_flush"])
+        db = Rdict(self.path, opt)
+        options = db.current_options()
+        self.assertEqual(options["manual_wal_flush"], "true")
+        self.assertEqual(options["track_and_verify_wals_in_manifest"], "true")
+        self.assertEqual(options["WAL_ttl_seconds"], "3600")
+        self.assertEqual(options["WAL_size_limit_MB"], "64")
+
+        start = db.latest_sequence_number() + 1
+        db["a"] = 1
+        db.flush_wal(True)
+        db.flush()
+        db["b"] = 2
+        db.flush_wal(True)
+        wal_files = db.get_sorted_wal_files()
+        self.assertGreaterEqual(len(wal_files), 2)
+        log_numbers = [f["log_number"] for f in wal_files]
+        self.assertEqual(log_numbers, sorted(log_numbers))
+        # the first WAL is archived when the flush purges obsolete files
+        self.assertEqual([f["start_sequence"] for f in wal_files][-2:], [start, start + 1])
+        self.assertIn(wal_files[-2]["type"], ["alive", "archived"])
+        self.assertEqual(wal_files[-1]["type"], "alive")
+        for wal_file in wal_files:
+            self.assertEqual(os.path.getsize(wal_file["path_name"]), wal_file["size_in_bytes"])
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+
+class TestRowCache(unittest.TestCase):
+    path = "./temp_row_cache"
+
+    def test_row_cache_hits(self):
+        cache = Cache(1024 * 1024)
+        opt = Options()
+        opt.set_row_cache(cache)
+        opt.enable_statistics()
+        opt.set_statistics_level(StatsLevel.all())
+        db = Rdict(self.path, opt)
+        for i in range(100):
+            db[i] = i
+        db.flush()
+        del opt, cache
+        gc.collect()
+        stats = db.get_statistics()
+        stats.reset()
+        self.assertEqual(db[42], 42)
+        self.assertEqual(stats.ticker("rocksdb.row.cache.miss"), 1)
+        self.assertEqual(stats.ticker("rocksdb.row.cache.hit"), 0)
+        for n in range(1, 11):
+            self.assertEqual(db[42], 42)
+            self.assertEqual(stats.ticker("rocksdb.row.cache.hit"), n)
+        self.assertEqual(stats.ticker("rocksdb.row.cache.miss"), 1)
+        db.close()
+        Rdict.destroy(self.path)
+
+    def test_row_cache_usage(self):
+        cache = Cache(1024 * 1024)
+        opt = Options()
+        opt.set_row_cache(cache)
+        db = Rdict(self.path, opt)
+        cf = db.create_column_family("cf", Options())
+        db["a"] = b"x" * 100
+        cf["b"] = b"y" * 100
+        db.flush()
+        cf.flush()
+        usage = cache.get_usage()
+        self.assertEqual(db["a"], b"x" * 100)
+        self.assertGreater(cache.get_usage(), usage)
+        usage = cache.get_usage()
+        self.assertEqual(cf["b"], b"y" * 100)
+        self.assertGreater(cache.get_usage(), usage)
+        del cf
+        db.close()
+        Rdict.destroy(self.path)
+
+
+class TestOptionsDict(unittest.TestCase):
+    path = "./temp_options_dict"
+
+    def test_round_trip(self):
+        opt = Options(raw_mode=True)
+        opt.set_max_background_jobs(3)
+        opt.set_max_background_compactions(2)
+        opt.set_max_subcompactions(4)
+        opt.set_write_buffer_size(8 * 1024 * 1024)
+        opt.set_max_bytes_for_level_multiplier(8.0)
+        opt.set_use_fsync(True)
+        opt.create_if_missing(True)
+        config = opt.to_dict()
+        self.assertTrue(config["raw_mode"])
+        self.assertEqual(config["max_background_jobs"], 3)
+        self.assertEqual(config["max_background_compactions"], 2)
+        self.assertEqual(config["max_subcompactions"], 4)
+        self.assertEqual(config["write_buffer_size"], 8 * 1024 * 1024)
+        self.assertEqual(config["max_bytes_for_level_multiplier"], 8.0)
+        self.assertIs(config["use_fsync"], True)
+
+        loaded = Options.from_dict(loads(dumps(config)))
+        self.assertTrue(loaded.raw_mode)
+        self.assertEqual(loaded.to_dict(), config)
+        self.assertFalse(Options.from_dict({}).raw_mode)
+
+        db = Rdict(self.path, loaded)
+        db[b"key"] = b"value"
+        db.close()
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("max_background_jobs=3", content)
+        self.assertIn("max_background_compactions=2", content)
+        self.assertIn("max_subcompactions=4", content)
+        self.assertIn("use_fsync=true", content)
+        Rdict.destroy(self.path, loaded)
+
+    def test_lsm_shape(self):
+        opt = Options()
+        opt.set_write_buffer_size(16 * 1024 * 1024)
+        opt.set_max_write_buffer_number(4)
+        opt.set_min_write_buffer_number_to_merge(2)
+        opt.set_target_file_size_base(32 * 1024 * 1024)
+        opt.set_max_bytes_for_level_base(128 * 1024 * 1024)
+        opt.set_max_bytes_for_level_multiplier(6.0)
+        config = opt.to_dict()
+        self.assertEqual(config["write_buffer_size"], 16 * 1024 * 1024)
+        self.assertEqual(config["max_write_buffer_number"], 4)
+        self.assertEqual(config["min_write_buffer_number_to_merge"], 2)
+        self.assertEqual(config["target_file_size_base"], 32 * 1024 * 1024)
+        self.assertEqual(config["max_bytes_for_level_base"], 128 * 1024 * 1024)
+        self.assertEqual(config["max_bytes_for_level_multiplier"], 6.0)
+
+        db = Rdict(self.path, opt)
+        db["key"] = "value"
+        db.close()
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("max_write_buffer_number=4", content)
+        self.assertIn("min_write_buffer_number_to_merge=2", content)
+        self.assertIn("max_bytes_for_level_multiplier=6", content)
+        Rdict.destroy(self.path, opt)
+
+    def test_open_files(self):
+        opt = Options()
+        opt.set_max_open_files(20)
+        opt.set_max_file_opening_threads(4)
+        opt.set_table_cache_num_shard_bits(4)
+        config = opt.to_dict()
+        self.assertEqual(config["max_open_files"], 20)
+        self.assertEqual(config["max_file_opening_threads"], 4)
+        self.assertEqual(config["table_cache_num_shard_bits"], 4)
+
+        opt.set_disable_auto_compactions(True)
+        db = Rdict(self.path, opt)
+        # more SST files than `max_open_files`
+        for i in range(40):
+            db[i] = i
+            db.flush()
+        self.assertEqual([db[i] for i in range(40)], list(range(40)))
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+    def test_unknown_option(self):
+        with self.assertRaises(Exception) as ctx:
+            Options.from_dict({"max_background_job": 4})
+        self.assertIn("max_background_jobs", str(ctx.exception))
+        self.assertRaises(Exception, lambda: Options.from_dict({"num_levels": "seven"}))
+
+    def test_read_write_options(self):
+        read_opt = ReadOptions()
+        read_opt.set_iterate_upper_bound(10)
+        read_opt.set_readahead_size(1024)
+        self.assertEqual(read_opt.to_dict()["iterate_upper_bound"], 10)
+        self.assertEqual(read_opt.to_dict()["readahead_size"], 1024)
+        self.assertIsNone(read_opt.to_dict()["iterate_lower_bound"])
+        write_opt = WriteOptions(sync=True, low_pri=True)
+        self.assertTrue(write_opt.to_dict()["sync"])
+        self.assertEqual(WriteOptions(**write_opt.to_dict()).to_dict(), write_opt.to_dict())
+
+
+class TestUniversalCompaction(unittest.TestCase):
+    path = "./temp_universal_compaction"
+
+    def test_options_file(self):
+        uco = UniversalCompactOptions(size_ratio=10,
+                                      min_merge_width=3,
+                                      max_size_amplification_percent=150,
+                                      stop_style=UniversalCompactionStopStyle.similar())
+        self.assertEqual(uco.size_ratio, 10)
+        self.assertEqual(uco.max_merge_width, 2147483647)
+        uco.max_merge_width = 20
+        opt = Options()
+        opt.set_compaction_style(DBCompactionStyle.universal())
+        opt.set_universal_compaction_options(uco)
+        opt.set_num_levels(1)
+        opt.set_level_zero_file_num_compaction_trigger(8)
+        with Rdict(self.path, opt) as db:
+            for i in range(1000):
+                db[i] = i
+            db.flush()
+
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("compaction_style=kCompactionStyleUniversal", content)
+        for setting in ["size_ratio=10", "min_merge_width=3", "max_merge_width=20",
+                        "max_size_amplification_percent=150",
+                        "stop_style=kCompactionStopStyleSimilarSize"]:
+            self.assertIn(setting, content)
+        self.assertIn("num_levels=1", content)
+        self.assertIn("level0_file_num_compaction_trigger=8", content)
+
+        # the settings are loaded back when reopening
+        with Rdict(self.path) as db:
+            self.assertEqual(db[999], 999)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestCompressionPerLevel(unittest.TestCase):
+    path = "./temp_compression_per_level"
+
+    def test_options_file(self):
+        opt = Options()
+        opt.set_compression_per_level([DBCompressionType.none()] * 3 + [DBCompressionType.lz4()] * 4)
+        opt.set_bottommost_compression_type(DBCompressionType.zstd())
+        opt.set_bottommost_compression_options(-14, 5, 0, 16 * 1024)
+        opt.set_compression_options(-14, 1, 0, 0)
+        with Rdict(self.path, opt) as db:
+            for i in range(1000):
+                db[i] = str(i) * 10
+            db.compact_range(None, None)
+            self.assertEqual(db[999], "999" * 10)
+
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("compression_per_level=kNoCompression:kNoCompression:kNoCompression:kLZ4Compression", content)
+        self.assertIn("bottommost_compression=kZSTD", content)
+        self.assertRegex(content, r"bottommost_compression_opts=\{[^}]*max_dict_bytes=16384")
+        self.assertRegex(content, r"bottommost_compression_opts=\{[^}]*level=5")
+        self.assertRegex(content, r"bottommost_compression_opts=\{[^}]*enabled=true")
+
+    def test_disabled_bottommost_compression_options(self):
+        path = self.path + "_disabled"
+        opt = Options()
+        opt.set_bottommost_compression_options(-14, 5, 0, 16 * 1024, enabled=False)
+        opt.set_bottommost_zstd_max_train_bytes(1024 * 1024, False)
+        with Rdict(path, opt) as db:
+            db["a"] = 1
+        options_file = max(f for f in os.listdir(path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(path, options_file)) as f:
+            content = f.read()
+        self.assertRegex(content, r"bottommost_compression_opts=\{[^}]*enabled=false")
+        Rdict.destroy(path)
+
+    def test_invalid_compression_per_level(self):
+        opt = Options()
+        self.assertRaises(Exception, lambda: opt.set_compression_per_level(["zstd"]))
+
+    def test_compression_from_name(self):
+        opt = Options()
+        opt.set_compression_per_level([DBCompressionType.from_name(name)
+                                       for name in ["none", "snappy", "zlib", "bz2", "lz4", "lz4hc", "zstd"]])
+        with self.assertRaises(Exception) as cm:
+            DBCompressionType.from_name("brotli")
+        self.assertIn("brotli", str(cm.exception))
+        self.assertIn("zstd", str(cm.exception))
+
+    def test_zstd_dictionary(self):
+        def sst_size(train: bool) -> int:
+            path = self.path + "_zstd_dict"
+            table_opt = BlockBasedOptions()
+            table_opt.set_block_size(1024)
+            opt = Options()
+            opt.set_block_based_table_factory(table_opt)
+            opt.set_compression_type(DBCompressionType.none())
+            opt.set_bottommost_compression_type(DBCompressionType.zstd())
+            if train:
+                opt.set_bottommost_compression_options(-14, 3, 0, 16 * 1024)
+                opt.set_bottommost_zstd_max_train_bytes(100 * 16 * 1024)
+            db = Rdict(path, opt)
+            for i in range(20000):
+                db[i] = {"id": i, "name": f"user_{i * 7919 % 10007}", "status": "active", "tags": ["a", "b"]}
+            db.compact_range(None, None)
+            size = db.property_int_value("rocksdb.total-sst-files-size")
+            self.assertEqual(db[42]["id"], 42)
+            db.close()
+            Rdict.destroy(path, opt)
+            return size
+
+        self.assertLess(sst_size(train=True), sst_size(train=False))
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestBlobFiles(unittest.TestCase):
+    path = "./temp_blob_files"
+
+    def test_blob_round_trip(self):
+        opt = Options()
+        opt.set_enable_blob_files(True)
+        opt.set_min_blob_size(1024)
+        opt.set_blob_file_size(1024 * 1024)
+        opt.set_blob_compression_type(DBCompressionType.lz4())
+        opt.set_enable_blob_gc(True)
+        opt.set_blob_gc_age_cutoff(0.5)
+        opt.set_blob_gc_force_threshold(0.8)
+        db = Rdict(self.path, opt)
+        large = {i: os.urandom(4096) for i in range(100)}
+        for k, v in large.items():
+            db[k] = v
+        db["small"] = b"inline"
+        db.flush()
+
+        self.assertTrue(any(f.endswith(".blob") for f in os.listdir(self.path)))
+        self.assertGreater(db.property_int_value("rocksdb.num-blob-files"), 0)
+        self.assertIsNotNone(db.property_value("rocksdb.blob-stats"))
+        self.assertEqual(db[0], large[0])
+        self.assertEqual(db["small"], b"inline")
+        self.assertEqual({k: v for k, v in db.items() if k != "small"}, large)
+
+        db.set_options({"min_blob_size": "8192", "blob_garbage_collection_age_cutoff": "0.25"})
+        db[100] = os.urandom(4096)
+        db.flush()
+        self.assertEqual(len(db[100]), 4096)
+        db.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestMergeOperator(unittest.TestCase):
+    path = "./temp_merge_operator"
+
+    def test_uint64_add(self):
+        opt = Options()
+        opt.set_merge_operator(MergeOperator.uint64_add())
+        db = Rdict(self.path, opt)
+        db.merge("counter", 1)
+        db.merge("counter", 2)
+        self.assertEqual(db["counter"], 3)
+        db["counter"] = 10
+        db.merge("counter", -20)
+        self.assertEqual(db["counter"], -10)
+        db.merge("big", 2 ** 64)
+        db.merge("big", 2 ** 64)
+        self.assertEqual(db["big"], 2 ** 65)
+        wb = WriteBatch()
+        wb.merge("counter", 5)
+        wb.merge("counter", 5)
+        db.write(wb)
+        self.assertEqual(db["counter"], 0)
+        # not an int
+        self.assertRaises(TypeError, lambda: db.merge("counter", "a"))
+        self.assertRaises(TypeError, lambda: db.merge("counter", 1.5))
+        self.assertRaises(TypeError, lambda: db.merge("counter", True))
+        self.assertEqual(db["counter"], 0)
+        # invalid operands of a WriteBatch count as 0
+        wb = WriteBatch()
+        wb.merge("counter", "a")
+        wb.merge("counter", 3)
+        db.write(wb)
+        self.assertEqual(db["counter"], 3)
+        db.flush()
+        db.compact_range(None, None)
+        db["after_flush"] = 1
+        self.assertEqual(db["after_flush"], 1)
+        self.assertEqual(db["counter"], 3)
+        db.close()
+
+        # the merge operator is restored from the rocksdict config
+        db = Rdict(self.path)
+        db.merge("big", -(2 ** 65))
+        self.assertEqual(db["big"], 0)
+        db.close()
+        Rdict.destroy(self.path)
+
+    def test_string_append(self):
+        opt = Options()
+        opt.set_merge_operator(MergeOperator.string_append(","))
+        db = Rdict(self.path, opt)
+        db.merge("tags", "a")
+        db.merge("tags", "b")
+        self.assertEqual(db["tags"], "a,b")
+        db.merge("bytes", b"x")
+        db.merge("bytes", b"y")
+        self.assertEqual(db["bytes"], b"x,y")
+        self.assertRaises(TypeError, lambda: db.merge("tags", 1))
+        # bytes cannot be appended to a str, they are ignored
+        db.merge("tags", b"c")
+        self.assertEqual(db["tags"], "a,b")
+        db["number"] = 1
+        db.merge("number", "x")
+        self.assertEqual(db["number"], 1)
+        db.flush()
+        db["after_flush"] = 1
+        self.assertEqual(db["after_flush"], 1)
+        self.assertEqual(db["tags"], "a,b")
+
+        cf_opt = Options()
+        cf_opt.set_merge_operator(MergeOperator.string_append())
+        cf = db.create_column_family("no_delimiter", cf_opt)
+        cf.merge("k", "a")
+        cf.merge("k", "b")
+        self.assertEqual(cf["k"], "ab")
+        del cf
+        db.close()
+        Rdict.destroy(self.path)
+        self.assertRaises(Exception, lambda: MergeOperator.string_append(1))
+
+    def test_raw_mode(self):
+        opt = Options(raw_mode=True)
+        opt.set_merge_operator(MergeOperator.uint64_add())
+        db = Rdict(self.path, opt)
+        db.merge(b"counter", (2 ** 64 - 1).to_bytes(8, "little"))
+        db.merge(b"counter", (3).to_bytes(8, "little"))
+        self.assertEqual(int.from_bytes(db[b"counter"], "little"), 2)
+        self.assertRaises(TypeError, lambda: db.merge(b"counter", b"short"))
+        wb = WriteBatch(raw_mode=True)
+        wb.merge(b"counter", b"short")
+        db.write(wb)
+        self.assertEqual(int.from_bytes(db[b"counter"], "little"), 2)
+        db.flush()
+        db[b"after_flush"] = b"1"
+        self.assertEqual(db[b"after_flush"], b"1")
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+        opt = Options(raw_mode=True)
+        opt.set_merge_operator(MergeOperator.string_append(b"|"))
+        db = Rdict(self.path, opt)
+        db[b"log"] = b"start"
+        db.merge(b"log", b"a")
+        db.merge(b"log", b"b")
+        self.assertEqual(db[b"log"], b"start|a|b")
+        db.close()
+        Rdict.destroy(self.path, opt)
+
+    def test_without_merge_operator(self):
+        db = Rdict(self.path)
+        self.assertRaises(Exception, lambda: db.merge("a", 1))
+        self.assertNotIn("a", db)
+        db.flush()
+        db["a"] = 1
+        self.assertEqual(db["a"], 1)
+        db.close()
+        Rdict.destroy(self.path)
+
+
+class TestDbPaths(unittest.TestCase):
+    path = "./temp_db_paths"
+    db_paths = ["./temp_db_paths_fast", "./temp_db_paths_slow"]
+
+    @classmethod
+    def setUpClass(cls) -> None:
+        cls.opt = Options()
+        cls.opt.set_db_paths([
+            DBPath(os.path.abspath(cls.db_paths[0]), 64 * 1024),
+            (os.path.abspath(cls.db_paths[1]), 1024 * 1024 * 1024),
+        ])
+        cls.opt.set_compression_type(DBCompressionType.none())
+
+    def test_sst_files_in_both_paths(self):
+        db = Rdict(self.path, self.opt)
+        for batch in range(10):
+            for i in range(100):
+                db[batch * 100 + i] = os.urandom(1000)
+            db.flush()
+        db.compact_range(None, None)
+        # a small new file fits in the fast path
+        for i in range(10):
+            db[1000 + i] = os.urandom(1000)
+        db.flush()
+        for path in self.db_paths:
+            self.assertTrue(any(f.endswith(".sst") for f in os.listdir(path)), path)
+        self.assertEqual(len(list(db.keys())), 1010)
+        db.close()
+
+    def test_invalid_paths(self):
+        opt = Options()
+        self.assertRaises(Exception, lambda: opt.set_db_paths(["no_target_size"]))
+        self.assertRaises(Exception, lambda: opt.set_db_paths([("path", "size")]))
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestReadOnly(unittest.TestCase):
+    path = "./temp_read_only"
+
+    def test_read_only_with_unflushed_wal(self):
+        writer = Rdict(self.path)
+        writer["flushed"] = 1
+        writer.flush()
+        writer["in_wal"] = 2
+        reader = Rdict(self.path, access_type=AccessType.read_only())
+        # the WAL is replayed
+        self.assertEqual(reader["flushed"], 1)
+        self.assertEqual(reader["in_wal"], 2)
+        self.assertRaises(Exception, lambda: reader.put("a", 1))
+        # later updates are not visible
+        writer["later"] = 3
+        self.assertNotIn("later", reader)
+        reader.close()
+        self.assertRaises(Exception,
+                          lambda: Rdict(self.path, access_type=AccessType.read_only(True)))
+        self.assertRaises(Exception,
+                          lambda: Rdict(self.path, access_type=AccessType.read_only(error_if_log_file_exist=True)))
+        writer.close()
+
+        reader = Rdict(self.path, access_type=AccessType.read_only())
+        self.assertEqual(reader["later"], 3)
+        reader.close()
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestLoadLatestOptions(unittest.TestCase):
+    path = "./temp_load_latest_options"
+
+    def test_reopen_with_loaded_options(self):
+        opt = Options(raw_mode=True)
+        opt.set_write_buffer_size(1024 * 1024)
+        db = Rdict(self.path, opt)
+        db.create_column_family("cf", opt)
+        db[b"a"] = b"1"
+        db.get_column_family("cf")[b"b"] = b"2"
+        db.close()
+
+        loaded_opt, column_families = Options.load_latest(self.path)
+        self.assertTrue(loaded_opt.raw_mode)
+        self.assertEqual(set(column_families), {"default", "cf"})
+        db = Rdict(self.path, loaded_opt, column_families=column_families)
+        self.assertEqual(db[b"a"], b"1")
+        self.assertEqual(db.get_column_family("cf")[b"b"], b"2")
+        db.close()
+
+    def test_raw_mode_mismatch(self):
+        Rdict(self.path, Options(raw_mode=True)).close()
+        with self.assertRaises(Exception) as ctx:
+            Rdict(self.path, Options(raw_mode=False))
+        self.assertIn("raw_mode=True", str(ctx.exception))
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path, Options(raw_mode=True))
+
+
+class TestSplitRanges(unittest.TestCase):
+    path = "./temp_split_ranges"
+
+    @classmethod
+    def setUpClass(cls):
+        opt = Options()
+        opt.set_disable_auto_compactions(True)
+        cls.test_dict = Rdict(cls.path, opt)
+        for batch in range(8):
+            for i in range(1000):
+                cls.test_dict[batch * 1000 + i] = i
+            cls.test_dict.flush()
+
+    def test_split_ranges(self):
+        ranges = self.test_dict.split_ranges(4)
+        self.assertEqual(len(ranges), 4)
+        self.assertIsNone(ranges[0][0])
+        self.assertIsNone(ranges[-1][1])
+        for (_, end), (start, _) in zip(ranges, ranges[1:]):
+            self.assertEqual(end, start)
+        self.assertEqual(self.test_dict.split_ranges(1), [(None, None)])
+        self.assertRaises(Exception, lambda: self.test_dict.split_ranges(0))
+
+    def test_parallel_scan(self):
+        from concurrent.futures import ThreadPoolExecutor
+
+        def scan(bounds):
+            return [k for k, _ in self.test_dict.range(*bounds)]
+
+        with ThreadPoolExecutor(4) as pool:
+            keys = [k for part in pool.map(scan, self.test_dict.split_ranges(4)) for k in part]
+        self.assertEqual(keys, list(range(8000)))
+
+    def test_range(self):
+        self.assertEqual([k for k, _ in self.test_dict.range(10, 13)], [10, 11, 12])
+        self.assertEqual(len(list(self.test_dict.range(start=7990))), 10)
+        self.assertEqual(len(list(self.test_dict.range(end=10))), 10)
+
+    @classmethod
+    def tearDownClass(cls):
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestPlainTable(unittest.TestCase):
+    path = "./temp_plain_table"
+
+    @classmethod
+    def setUpClass(cls):
+        cls.opt = Options(raw_mode=True)
+        cls.opt.set_prefix_extractor(SliceTransform.create_fixed_prefix(4))
+        cls.opt.set_allow_mmap_reads(True)
+        cls.opt.set_plain_table_factory(PlainTableFactoryOptions(user_key_length=8))
+        cls.test_dict = Rdict(cls.path, cls.opt)
+        for i in range(10):
+            for j in range(100):
+                cls.test_dict[b"%04d%04d" % (i, j)] = b"%d" % (i * j)
+        cls.test_dict.flush()
+
+    def test_point_get(self):
+        self.assertEqual(self.test_dict[b"00070011"], b"77")
+        self.assertEqual(self.test_dict.get(b"00070100"), None)
+        self.assertNotIn(b"00110000", self.test_dict)
+
+    def test_prefix_scan(self):
+        keys = list(self.test_dict.keys_with_prefix(b"0003"))
+        self.assertEqual(keys, [b"0003%04d" % j for j in range(100)])
+
+    def test_validation(self):
+        self.assertRaises(Exception,
+                          lambda: Options().set_plain_table_factory(PlainTableFactoryOptions()))
+        opt = Options(raw_mode=True)
+        with self.assertRaises(Exception) as ctx:
+            opt.set_plain_table_factory(PlainTableFactoryOptions())
+        self.assertIn("set_prefix_extractor", str(ctx.exception))
+        opt.set_prefix_extractor(SliceTransform.create_fixed_prefix(4))
+        with self.assertRaises(Exception) as ctx:
+            opt.set_plain_table_factory(PlainTableFactoryOptions())
+        self.assertIn("set_allow_mmap_reads", str(ctx.exception))
+
+    @classmethod
+    def tearDownClass(cls):
+        cls.test_dict.close()
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+@unittest.skipIf(sys.platform.startswith("win"), reason="CuckooTable is not used on windows")
+class TestCuckooTable(unittest.TestCase):
+    path = "./temp_cuckoo_table"
+
+    @classmethod
+    def setUpClass(cls):
+        cls.opt = Options(raw_mode=True)
+        cls.opt.set_allow_mmap_reads(True)
+        cls.opt.set_cuckoo_table_factory(CuckooTableOptions(hash_table_ratio=0.8))
+
+    def test_read_only_lookups(self):
+        db = Rdict(self.path, self.opt)
+        for i in range(1000):
+            db[b"%08d" % i] = b"%08d" % (i * i)
+        db.flush()
+        db.close()
+
+        db = Rdict(self.path, self.opt, access_type=AccessType.read_only())
+        self.assertEqual(db[b"00000012"], b"00000144")
+        self.assertEqual(db[[b"00000001", b"00000999", b"99999999"]],
+                         [b"00000001", b"00998001", None])
+        self.assertNotIn(b"99999999", db)
+        self.assertEqual(list(db.keys()), [b"%08d" % i for i in range(1000)])
+        db.close()
+
+    def test_key_length_mismatch(self):
+        db = Rdict(self.path + "_mismatch", self.opt)
+        db[b"short"] = b"value"
+        db[b"longer key"] = b"value"
+        self.assertRaises(Exception, lambda: db.flush())
+        db.close()
+        Rdict.destroy(self.path + "_mismatch", self.opt)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path, cls.opt)
+
+
+class TestDirectIO(unittest.TestCase):
+    path = "./temp_direct_io"
+
+    def test_direct_io(self):
+        opt = Options()
+        opt.set_use_direct_reads(True)
+        opt.set_use_direct_io_for_flush_and_compaction(True)
+        try:
+            db = Rdict(self.path, opt)
+        except Exception as e:
+            self.skipTest(f"direct I/O not supported by the file system: {e}")
+        for i in range(1000):
+            db[i] = i
+        db.flush()
+        db.compact_range(None, None)
+        self.assertEqual(db[500], 500)
+        self.assertEqual(db.exact_len(), 1000)
+        db.close()
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("use_direct_reads=true", content)
+        self.assertIn("use_direct_io_for_flush_and_compaction=true", content)
+
+    def test_direct_reads_with_mmap_reads(self):
+        opt = Options()
+        opt.set_use_direct_reads(True)
+        opt.set_allow_mmap_reads(True)
+        self.assertRaises(Exception, lambda: Rdict(self.path + "_mmap", opt))
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+        Rdict.destroy(cls.path + "_mmap")
+
+
+class TestLogger(unittest.TestCase):
+    path = "./temp_logger"
+
+    @staticmethod
+    def wait_for(lines):
+        deadline = time.time() + 10
+        while not lines and time.time() < deadline:
+            time.sleep(0.05)
+
+    def test_info_log_level(self):
+        opt = Options()
+        opt.set_info_log_level(LogLevel.error())
+        Rdict(self.path, opt).close()
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            self.assertIn("info_log_level=ERROR_LEVEL", f.read())
+        Rdict.destroy(self.path)
+
+    def test_logger_callback(self):
+        lines = []
+        opt = Options()
+        opt.set_logger(lambda level, message: lines.append((level, message)))
+        db = Rdict(self.path, opt)
+        db["a"] = 1
+        db.flush()
+        db.close()
+        self.wait_for(lines)
+        self.assertTrue(lines)
+        for level, message in lines:
+            self.assertIn(level, {"debug", "info", "warn", "error", "fatal", "header"})
+            self.assertIsInstance(message, str)
+        self.assertNotIn("debug", {level for level, _ in lines})
+        # the info log is not written to the LOG file
+        self.assertNotIn("LOG", os.listdir(self.path))
+        Rdict.destroy(self.path)
+
+    def test_logger_level(self):
+        lines = []
+        opt = Options()
+        opt.set_logger(lambda level, message: lines.append(level), LogLevel.header())
+        db = Rdict(self.path, opt)
+        db["a"] = 1
+        db.flush()
+        db.close()
+        self.wait_for(lines)
+        self.assertEqual(set(lines), {"header"})
+        Rdict.destroy(self.path)
+
+    def test_not_callable(self):
+        self.assertRaises(Exception, lambda: Options().set_logger("not callable"))
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestEventListener(unittest.TestCase):
+    path = "./temp_event_listener"
+
+    class Listener:
+        def __init__(self):
+            self.events = []
+
+        def on_flush_begin(self, info):
+            self.events.append(("flush_begin", info))
+
+        def on_flush_completed(self, info):
+            self.events.append(("flush_completed", info))
+
+        def on_compaction_completed(self, info):
+            self.events.append(("compaction_completed", info))
+
+    def test_flush_and_compaction(self):
+        listener = self.Listener()
+        opt = Options()
+        opt.add_event_listener(listener)
+        db = Rdict(self.path, opt)
+        # overlapping files, so that the compaction is not a trivial move
+        for batch in range(2):
+            for i in range(100):
+                db[2 * i + batch] = i
+            db.flush()
+        db.compact_range(None, None)
+        db.close()
+
+        deadline = time.time() + 10
+        while len(listener.events) < 5 and time.time() < deadline:
+            time.sleep(0.05)
+        names = [name for name, _ in listener.events]
+        self.assertEqual(names.count("flush_begin"), 2)
+        self.assertEqual(names.count("flush_completed"), 2)
+        self.assertEqual(names.count("compaction_completed"), 1)
+
+        flush_info = dict(listener.events)["flush_completed"]
+        self.assertEqual(flush_info["cf_name"], "default")
+        self.assertTrue(flush_info["file_path"].endswith(".sst"))
+        self.assertLessEqual(flush_info["smallest_seqno"], flush_info["largest_seqno"])
+
+        compaction_info = dict(listener.events)["compaction_completed"]
+        self.assertIsNone(compaction_info["status"])
+        self.assertEqual(len(compaction_info["input_files"]), 2)
+        self.assertEqual(compaction_info["input_records"], 200)
+        self.assertGreater(compaction_info["total_output_bytes"], 0)
+
+    def test_invalid_listener(self):
+        self.assertRaises(Exception, lambda: Options().add_event_listener(object()))
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestPartitionedIndex(unittest.TestCase):
+    path = "./temp_partitioned_index"
+
+    def test_partitioned_index_and_filters(self):
+        table_opt = BlockBasedOptions()
+        table_opt.set_index_type(BlockBasedIndexType.two_level_index_search())
+        table_opt.set_bloom_filter(10, False)
+        table_opt.set_partition_filters(True)
+        table_opt.set_metadata_block_size(256)
+        table_opt.set_cache_index_and_filter_blocks(True)
+        table_opt.set_pin_top_level_index_and_filter(True)
+        table_opt.set_format_version(5)
+        table_opt.set_checksum_type(ChecksumType.xxh3())
+        opt = Options()
+        opt.enable_statistics()
+        opt.set_block_based_table_factory(table_opt)
+        db = Rdict(self.path, opt)
+        for i in range(10000):
+            db[i] = i
+        db.flush()
+        for i in range(0, 10000, 7):
+            self.assertEqual(db[i], i)
+        self.assertIsNone(db.get(10001))
+        stats = db.get_statistics()
+        self.assertGreater(stats.ticker("rocksdb.block.cache.index.add"), 0)
+        self.assertGreater(stats.ticker("rocksdb.block.cache.filter.add"), 0)
+        db.close()
+
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            content = f.read()
+        self.assertIn("index_type=kTwoLevelIndexSearch", content)
+        self.assertIn("partition_filters=true", content)
+        self.assertIn("checksum=kXXH3", content)
+
+    @classmethod
+    def tearDownClass(cls):
+        gc.collect()
+        Rdict.destroy(cls.path)
+
+
+class TestRibbonFilter(unittest.TestCase):
+    path = "./temp_ribbon_filter"
+
+    def filter_block_size(self, configure_filter):
+        table_opt = BlockBasedOptions()
+        configure_filter(table_opt)
+        opt = Options()
+        opt.enable_statistics()
+        opt.set_block_based_table_factory(table_opt)
+        db = Rdict(self.path, opt)
+        for i in range(10000):
+            db[i] = i
+        db.flush()
+        for i in range(10000, 11000):
+            self.assertIsNone(db.get(i))
+        self.assertGreater(db.get_statistics().ticker("rocksdb.bloom.filter.useful"), 0)
+        properties = db.property_value("rocksdb.aggregated-table-properties")
+        db.close()
+        Rdict.destroy(self.path)
+        return int(re.search(r"filter block size=(\d+)", properties).group(1))
+
+    def test_ribbon_filter(self):
+        bloom = self.filter_block_size(lambda t: t.set_bloom_filter(10, False))
+        ribbon = self.filter_block_size(lambda t: t.set_ribbon_filter(10))
+        # flushes count as level -1
+        hybrid_ribbon = self.filter_block_size(lambda t: t.set_hybrid_ribbon_filter(10, -1))
+        hybrid_bloom = self.filter_block_size(lambda t: t.set_hybrid_ribbon_filter(10, 0))
+        self.assertLess(ribbon, bloom)
+        self.assertLess(hybrid_ribbon, bloom)
+        self.assertEqual(hybrid_bloom, bloom)
+
+    def test_whole_key_filtering(self):
+        table_opt = BlockBasedOptions()
+        table_opt.set_ribbon_filter(10)
+        table_opt.set_whole_key_filtering(False)
+        opt = Options()
+        opt.set_prefix_extractor(SliceTransform.create_fixed_prefix(2))
+        opt.set_block_based_table_factory(table_opt)
+        with Rdict(self.path, opt) as db:
+            db["key"] = 1
+            self.assertEqual(db["key"], 1)
+        options_file = max(f for f in os.listdir(self.path) if f.startswith("OPTIONS-"))
+        with open(os.path.join(self.path, options_file)) as f:
+            self.assertIn("whole_key_filtering=false", f.read())
+        Rdict.destroy(self.path)
+
+
+def pack_tuple(key):
+    return b"".join(encode_int_key(k) for k in key)
+
+
+def unpack_tuple(data):
+    return tuple(decode_int_key(data[i : i + 8]) for i in range(0, len(data), 8))
+
+
+class TestKeyDumps(unittest.TestCase):
+    path = "./temp_key_dumps"
+    sst_path = "./temp_key_dumps.sst"
+
+    def setUp(self):
+        self.db = Rdict(self.path)
+        self.db.set_key_dumps(pack_tuple)
+        self.db.set_key_loads(unpack_tuple)
+
+    def test_get_put_delete(self):
+        self.db[(1, 2)] = "a"
+        self.db.put((1, -2), "b")
+        self.assertEqual(self.db[(1, 2)], "a")
+        self.assertEqual(self.db.get([(1, -2), (3, 4)]), ["b", None])
+        self.assertIn((1, 2), self.db)
+        del self.db[(1, 2)]
+        self.assertNotIn((1, 2), self.db)
+        with self.assertRaises(Exception):
+            self.db["not a tuple"] = 1
+
+    def test_range_scan_order(self):
+        keys = [(user, ts) for user in (3, -1, 0, 1) for ts in (100, -5, 7)]
+        for key in keys:
+            self.db[key] = key[1]
+        self.assertEqual(list(self.db.keys()), sorted(keys))
+        self.assertEqual(list(self.db.keys(from_key=(0, 0))), sorted(keys)[4:])
+        self.assertEqual(list(self.db.keys(backwards=True, from_key=(0, 0))), [(0, -5), (-1, 100), (-1, 7), (-1, -5)])
+
+        read_opt = ReadOptions()
+        read_opt.set_iterate_lower_bound((1, -5))
+        read_opt.set_iterate_upper_bound((3, 0))
+        self.assertEqual(list(self.db.keys(read_opt=read_opt)), [(1, -5), (1, 7), (1, 100), (3, -5)])
+
+        it = self.db.iter()
+        it.seek((1, 0))
+        self.assertEqual(it.key(), (1, 7))
+        it.seek_for_prev((1, 0))
+        self.assertEqual(it.key(), (1, -5))
+        del it
+
+        self.db.delete_range((-1, 0), (1, 0))
+        self.assertEqual(list(self.db.keys()), [(-1, -5), (1, 7), (1, 100), (3, -5), (3, 7), (3, 100)])
+        self.assertEqual(list(self.db.keys_with_prefix(encode_int_key(3))), [(3, -5), (3, 7), (3, 100)])
+
+    def test_write_batch(self):
+        wb = WriteBatch()
+        wb.set_key_dumps(pack_tuple)
+        wb.set_key_loads(unpack_tuple)
+        wb[(2, 1)] = 1
+        wb.delete_range((0, 0), (1, 0))
+        self.assertEqual(wb.iterate(decode=True)[0], ("put", 0, (2, 1), 1))
+        self.db.write(wb)
+        self.assertEqual(self.db[(2, 1)], 1)
+
+    def test_sst_file_writer(self):
+        writer = SstFileWriter()
+        writer.set_key_dumps(pack_tuple)
+        writer.open(self.sst_path)
+        for key in [(-1, 0), (0, 5), (5, 0)]:
+            writer[key] = key[0]
+        writer.finish()
+        self.db.ingest_external_file([self.sst_path])
+        self.assertEqual(list(self.db.items()), [((-1, 0), -1), ((0, 5), 0), ((5, 0), 5)])
+
+    def test_pop_setdefault_encode_once(self):
+        calls = []
+
+        def dumps(key):
+            calls.append(key)
+            return pack_tuple(key)
+
+        self.db.set_key_dumps(dumps)
+        self.db[(1, 2)] = "a"
+        del calls[:]
+        self.assertEqual(self.db.pop((1, 2)), "a")
+        self.assertEqual(calls, [(1, 2)])
+        self.assertNotIn((1, 2), self.db)
+        del calls[:]
+        self.assertEqual(self.db.setdefault((3, 4), "b"), "b")
+        self.assertEqual(calls, [(3, 4)])
+        self.assertEqual(self.db[(3, 4)], "b")
+
+    def test_popitem_key_dumps_only(self):
+        self.db.close()
+        Rdict.destroy(self.path)
+        self.db = Rdict(self.path)
+        # the keys read back are the dumped bytes, which do not dump to themselves
+        self.db.set_key_dumps(lambda key: str(key).encode())
+        self.db["a"] = 1
+        self.db["b"] = 2
+        self.assertEqual(self.db.popitem(), (b"b", 2))
+        self.assertEqual(self.db.popitem(), (b"a", 1))
+        self.assertRaises(KeyError, self.db.popitem)
+
+    def test_raw_mode_bypass(self):
+        self.db.close()
+        Rdict.destroy(self.path)
+        self.db = Rdict(self.path, Options(raw_mode=True))
+        self.db.set_key_dumps(pack_tuple)
+        self.db[b"raw"] = b"value"
+        self.assertEqual(list(self.db.keys()), [b"raw"])
+
+    def tearDown(self):
+        self.db.close()
+        del self.db
+        gc.collect()
+        Rdict.destroy(self.path)
+        if os.path.exists(self.sst_path):
+            os.remove(self.sst_path)
+
+
 if __name__ == "__main__":
     unittest.main()